@@ -0,0 +1,88 @@
+//! Commands against a long invoice history: loading state.toml, resolving
+//! an invoice reference and the scans behind `list` and `digest`.
+//!
+//! Run with `cargo bench --bench history`.
+
+use std::fmt::Write;
+
+use chrono::NaiveDate;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use invoice::config::{load_state, save_state};
+use invoice::invoice::{monthly_progress, receivables_digest};
+use invoice::{Config, State};
+
+const INVOICES: usize = 5_000;
+
+/// state.toml for `INVOICES` invoices over the years before 2026, most of
+/// them paid
+fn state_toml() -> String {
+    let mut toml = String::from("[counter]\nlast_number = 5000\nlast_year = 2025\n");
+    for i in 0..INVOICES {
+        let date = NaiveDate::from_ymd_opt(2012, 1, 1).unwrap() + chrono::Duration::days(i as i64);
+        let _ = write!(
+            toml,
+            "\n[[history]]\nnumber = \"INV-{}-{:04}\"\nclient = \"client-{}\"\n\
+             date = \"{date}\"\ntotal = {}.0\nfile = \"INV-{}-{:04}.pdf\"\n\
+             items = [\"consulting:{}\", \"support:1\"]\n",
+            date.format("%Y"),
+            i + 1,
+            i % 40,
+            1000 + i % 700,
+            date.format("%Y"),
+            i + 1,
+            1 + i % 12,
+        );
+        if i % 5 != 0 {
+            let _ = write!(
+                toml,
+                "\n[[history.payments]]\namount = {}.0\ndate = \"{}\"\n",
+                1000 + i % 700,
+                date + chrono::Duration::days(20),
+            );
+        }
+    }
+    toml
+}
+
+fn config() -> Config {
+    toml::from_str(invoice::config::CONFIG_TEMPLATE).expect("template config parses")
+}
+
+fn bench_history(c: &mut Criterion) {
+    let toml = state_toml();
+    let state: State = toml::from_str(&toml).expect("generated state parses");
+    let dir = tempfile::tempdir().expect("temp dir");
+    save_state(dir.path(), &state).expect("state saved");
+
+    c.bench_function("parse state.toml", |b| {
+        b.iter(|| toml::from_str::<State>(black_box(&toml)).unwrap())
+    });
+    c.bench_function("load_state unchanged", |b| {
+        b.iter(|| load_state(black_box(dir.path())).unwrap())
+    });
+    c.bench_function("save_state", |b| {
+        b.iter(|| save_state(dir.path(), black_box(&state)).unwrap())
+    });
+
+    c.bench_function("entry by number, newest", |b| {
+        b.iter(|| state.entry(black_box("INV-2025-5000")).unwrap())
+    });
+    c.bench_function("entry by number, oldest", |b| {
+        b.iter(|| state.entry(black_box("INV-2012-0001")).unwrap())
+    });
+    c.bench_function("entry by list index", |b| {
+        b.iter(|| state.entry_at(black_box(INVOICES)).unwrap())
+    });
+
+    let config = config();
+    let today = NaiveDate::from_ymd_opt(2025, 9, 1).unwrap();
+    c.bench_function("monthly goal progress", |b| {
+        b.iter(|| monthly_progress(black_box(&state.history), 10_000.0, today))
+    });
+    c.bench_function("receivables digest", |b| {
+        b.iter(|| receivables_digest(black_box(&state.history), &config, today, 7))
+    });
+}
+
+criterion_group!(benches, bench_history);
+criterion_main!(benches);
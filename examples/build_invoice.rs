@@ -0,0 +1,52 @@
+//! Build an invoice in code and render it, without a config directory.
+//!
+//! Run with `cargo run --example build_invoice [output.pdf]` (needs Typst).
+
+use chrono::NaiveDate;
+use invoice::config::{ServicePeriod, SymbolPosition};
+use invoice::{generate_pdf_from_data, Client, Company, InvoiceBuilder};
+
+fn main() -> invoice::Result<()> {
+    let company = Company {
+        name: "Acme Consulting GmbH".into(),
+        address: "Hauptstraße 1".into(),
+        city: "Berlin".into(),
+        zip: "10115".into(),
+        country: "Germany".into(),
+        email: "billing@acme.example".into(),
+        ..Default::default()
+    };
+    let client = Client {
+        name: "Globex Corporation".into(),
+        email: "ap@globex.example".into(),
+        address: "42 Market Street".into(),
+        city: "Springfield".into(),
+        state: "OR".into(),
+        zip: "97477".into(),
+        ..Default::default()
+    };
+
+    let data = InvoiceBuilder::new("ACME-2026-0042", company, client)
+        .date(NaiveDate::from_ymd_opt(2026, 2, 2).expect("valid date"))
+        .due_days(14)
+        .period(ServicePeriod::month(2026, 1).expect("valid month"))
+        .currency("€", SymbolPosition::Suffix, true)
+        .item("Technical Consulting", 12.0, "hour", 120.0)
+        .item("Architecture Review", 1.0, "day", 950.0)
+        .discount("Early payment discount", 50.0)
+        .tax_rate(0.19)
+        .build()?;
+
+    println!(
+        "{}: {:.2} due {} (subtotal {:.2}, tax {:.2})",
+        data.number, data.total, data.due_date, data.subtotal, data.tax_amount
+    );
+
+    let output = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| format!("{}.pdf", data.number));
+    generate_pdf_from_data(&data, output.as_ref())?;
+    println!("Saved {output}");
+
+    Ok(())
+}
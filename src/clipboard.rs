@@ -0,0 +1,60 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::error::{InvoiceError, Result};
+
+/// Clipboard commands to try, in order, for the current platform
+fn clipboard_commands() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") {
+        vec![("pbcopy", &[])]
+    } else if cfg!(target_os = "windows") {
+        vec![("clip", &[])]
+    } else {
+        let mut commands: Vec<(&'static str, &'static [&'static str])> = vec![];
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            commands.push(("wl-copy", &[]));
+        }
+        commands.push(("xclip", &["-selection", "clipboard"]));
+        commands.push(("xsel", &["--clipboard", "--input"]));
+        commands
+    }
+}
+
+/// Put text on the system clipboard using the first available clipboard tool.
+/// Returns the name of the tool that was used.
+pub fn copy_text(text: &str) -> Result<&'static str> {
+    let mut tried = Vec::new();
+
+    for (program, args) in clipboard_commands() {
+        let mut child = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(_) => {
+                tried.push(program);
+                continue;
+            }
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+
+        let status = child.wait()?;
+        if status.success() {
+            return Ok(program);
+        }
+        return Err(InvoiceError::Clipboard(format!(
+            "'{program}' exited with {status}"
+        )));
+    }
+
+    Err(InvoiceError::Clipboard(format!(
+        "no clipboard tool found (tried {})",
+        tried.join(", ")
+    )))
+}
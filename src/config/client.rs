@@ -1,6 +1,11 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+use crate::pdf::OutputFormat;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Client {
     pub name: String,
     #[serde(default)]
@@ -12,4 +17,257 @@ pub struct Client {
     pub zip: String,
     #[serde(default)]
     pub country: Option<String>,
+    /// The client's VAT or tax ID, printed under their address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_id: Option<String>,
+    /// Typst cover letter prepended to this client's invoices (relative to config dir)
+    #[serde(default)]
+    pub cover_letter: Option<String>,
+    /// How this client wants to receive invoices
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery: Option<Delivery>,
+    /// Formats produced for this client when none are given on the command line
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub formats: Vec<OutputFormat>,
+    /// Document language as an ISO 639-1 code (e.g., "de"), used by templates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// The client's own reference scheme shown on their invoices next to
+    /// the internal number (e.g., "ACME-{year}-{seq:03}", where {seq}
+    /// counts this client's invoices in the year; {number} is the internal number)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_number_format: Option<String>,
+    /// Overrides of the `[invoice]` settings for this client's invoices
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency_symbol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax_rate: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taxes: Option<BTreeMap<String, f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_days: Option<u32>,
+    /// Custom fields printed on every invoice for this client (e.g., a
+    /// cost center their AP department requires); `--field` adds to them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+    /// Hours a monthly retainer covers; `invoice true-up` bills the rest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retainer: Option<Retainer>,
+}
+
+impl Client {
+    /// One-line postal address
+    pub fn postal_address(&self) -> String {
+        let mut address = format!(
+            "{}, {}, {} {}",
+            self.address, self.city, self.state, self.zip
+        );
+        if let Some(country) = &self.country {
+            address.push_str(&format!(", {country}"));
+        }
+        address
+    }
+
+    /// Whether this client's overrides drop tax from their invoices
+    pub fn tax_exempt(&self) -> bool {
+        match &self.taxes {
+            Some(taxes) => taxes.values().all(|&rate| rate == 0.0),
+            None => self.tax_rate == Some(0.0),
+        }
+    }
+
+    /// Details this client's invoices require that the record lacks, one
+    /// checklist line each. Name and postal address are always required;
+    /// a client billed without tax (a zero `tax_rate` or `taxes` override,
+    /// as under reverse charge) needs the tax ID and country that justify
+    /// it, and the delivery channel needs its contact details.
+    pub fn missing_fields(&self) -> Vec<String> {
+        let blank = |value: &str| value.trim().is_empty();
+        let mut missing = Vec::new();
+        for (field, value) in [
+            ("name", &self.name),
+            ("address", &self.address),
+            ("city", &self.city),
+        ] {
+            if blank(value) {
+                missing.push(format!("{field}: required on every invoice"));
+            }
+        }
+        if self.tax_exempt() {
+            if self.tax_id.as_deref().is_none_or(blank) {
+                missing.push("tax_id: required when billing without tax".to_string());
+            }
+            if self.country.as_deref().is_none_or(blank) {
+                missing.push("country: required when billing without tax".to_string());
+            }
+        }
+        match self.delivery {
+            Some(Delivery::Email) if !self.email.contains('@') => {
+                missing.push("email: required for email delivery".to_string());
+            }
+            Some(Delivery::Postal) if blank(&self.zip) => {
+                missing.push("zip: required for postal delivery".to_string());
+            }
+            _ => {}
+        }
+        missing
+    }
+
+    /// Like `missing_fields`, for messages sent to this client (e.g.,
+    /// reminders): an email address is required whatever the delivery channel
+    pub fn missing_fields_to_send(&self) -> Vec<String> {
+        let mut missing = self.missing_fields();
+        if !self.email.contains('@') && !missing.iter().any(|m| m.starts_with("email:")) {
+            missing.push("email: required to send".to_string());
+        }
+        missing
+    }
+}
+
+/// Check that `email` looks like a deliverable address (one `@`, a dotted
+/// domain, no spaces), returning why not otherwise
+pub fn validate_email(email: &str) -> Result<(), String> {
+    let Some((local, domain)) = email.split_once('@') else {
+        return Err("An email address needs an @".to_string());
+    };
+    if local.is_empty() || domain.contains('@') || email.contains(char::is_whitespace) {
+        return Err(format!("'{email}' is not an email address"));
+    }
+    let labels: Vec<&str> = domain.split('.').collect();
+    if labels.len() < 2 || labels.iter().any(|label| label.is_empty()) {
+        return Err(format!("'{domain}' is not a domain name"));
+    }
+    Ok(())
+}
+
+/// Check that `country` is a name or an ISO 3166 code: letters, spaces,
+/// dots and hyphens only
+pub fn validate_country(country: &str) -> Result<(), String> {
+    let valid = country.chars().filter(|c| c.is_alphabetic()).count() >= 2
+        && country
+            .chars()
+            .all(|c| c.is_alphabetic() || matches!(c, ' ' | '.' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("'{country}' is not a country name or code"))
+    }
+}
+
+/// Check `zip` against the postal code format of `country` when it is one
+/// with a well-known format, or else that it is a plausible code
+pub fn validate_zip(zip: &str, country: Option<&str>) -> Result<(), String> {
+    let digits = |s: &str, n: usize| s.len() == n && s.chars().all(|c| c.is_ascii_digit());
+    let country = country.map(|c| c.trim().to_uppercase());
+    let (valid, expected) = match country.as_deref() {
+        Some("US" | "USA" | "UNITED STATES" | "UNITED STATES OF AMERICA") => (
+            match zip.split_once('-') {
+                Some((zip5, plus4)) => digits(zip5, 5) && digits(plus4, 4),
+                None => digits(zip, 5),
+            },
+            "12345 or 12345-6789",
+        ),
+        Some("BR" | "BRA" | "BRAZIL" | "BRASIL") => (
+            match zip.split_once('-') {
+                Some((first, last)) => digits(first, 5) && digits(last, 3),
+                None => digits(zip, 8),
+            },
+            "12345-678",
+        ),
+        Some("CA" | "CAN" | "CANADA") => {
+            let compact: Vec<char> = zip.chars().filter(|c| *c != ' ').collect();
+            (
+                compact.len() == 6
+                    && compact.iter().enumerate().all(|(i, c)| {
+                        if i.is_multiple_of(2) {
+                            c.is_ascii_alphabetic()
+                        } else {
+                            c.is_ascii_digit()
+                        }
+                    }),
+                "A1A 1A1",
+            )
+        }
+        Some(
+            "DE" | "DEU" | "GERMANY" | "FR" | "FRA" | "FRANCE" | "ES" | "ESP" | "SPAIN" | "IT"
+            | "ITA" | "ITALY",
+        ) => (digits(zip, 5), "12345"),
+        Some("NL" | "NLD" | "NETHERLANDS") => {
+            let compact: String = zip.chars().filter(|c| *c != ' ').collect();
+            (
+                compact.len() == 6
+                    && compact.is_ascii()
+                    && digits(&compact[..4], 4)
+                    && compact[4..].chars().all(|c| c.is_ascii_alphabetic()),
+                "1234 AB",
+            )
+        }
+        _ => (
+            (2..=10).contains(&zip.len())
+                && zip
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-')),
+            "letters, digits, spaces and hyphens",
+        ),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{zip}' is not a postal code (expected {expected})"
+        ))
+    }
+}
+
+/// Check that `tax_id` has the shape of a VAT number: a two-letter country
+/// prefix followed by 2 to 13 letters and digits (e.g., DE123456789)
+pub fn validate_tax_id(tax_id: &str) -> Result<(), String> {
+    let compact: String = tax_id
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '.' | '-'))
+        .collect();
+    let (prefix, rest) = compact.split_at(compact.char_indices().nth(2).map_or(0, |(i, _)| i));
+    let valid = prefix.len() == 2
+        && prefix.chars().all(|c| c.is_ascii_uppercase())
+        && (2..=13).contains(&rest.len())
+        && rest.chars().all(|c| c.is_ascii_alphanumeric())
+        && rest.chars().any(|c| c.is_ascii_digit());
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{tax_id}' is not a VAT number (expected a country prefix and digits, e.g., DE123456789)"
+        ))
+    }
+}
+
+/// A retainer with included hours, billed separately each month. Hours
+/// tracked beyond `included_hours` are billed as `overage_item`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Retainer {
+    pub included_hours: f64,
+    /// Catalog item priced at the agreed overage rate
+    pub overage_item: String,
+}
+
+/// Preferred delivery channel for a client's invoices
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Delivery {
+    Email,
+    Postal,
+    /// Don't deliver (e.g., the client downloads from a portal)
+    None,
+}
+
+impl fmt::Display for Delivery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Delivery::Email => write!(f, "email"),
+            Delivery::Postal => write!(f, "postal"),
+            Delivery::None => write!(f, "none"),
+        }
+    }
 }
@@ -1,13 +1,68 @@
+use chrono::{Datelike, Months, NaiveDate, Weekday};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use super::client::Client;
+use super::schedule::ScheduleEntry;
+use super::state::DEFAULT_SERIES;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
     pub company: Company,
     pub invoice: InvoiceSettings,
     pub pdf: PdfSettings,
+    #[serde(default)]
+    pub display: DisplaySettings,
+    #[serde(default)]
+    pub payment: PaymentSettings,
+    #[serde(default)]
+    pub dunning: DunningSettings,
+    /// Late interest on overdue invoices; `invoice interest` needs this section
+    #[serde(default)]
+    pub interest: Option<InterestSettings>,
+    /// Extra numbering series (e.g., retainer, credit), each with its own counter
+    #[serde(default)]
+    pub series: BTreeMap<String, SeriesSettings>,
+    /// Revenue targets shown by `status` and `list`
+    #[serde(default)]
+    pub goals: Option<GoalSettings>,
+    /// Fixed-price projects that items.toml entries can bill against
+    #[serde(default)]
+    pub projects: BTreeMap<String, ProjectSettings>,
+    /// Alternate payees (e.g., a factoring company) selected with `generate --payee`
+    #[serde(default)]
+    pub payees: BTreeMap<String, PayeeSettings>,
+    /// Saved settings for `invoice import-time`
+    #[serde(default)]
+    pub import: ImportSettings,
+    /// How `invoice digest --email` sends mail
+    #[serde(default)]
+    pub email: EmailSettings,
+    /// Commands `invoice schedule` runs or installs in crontab
+    #[serde(default)]
+    pub schedule: BTreeMap<String, ScheduleEntry>,
+    #[serde(default)]
+    pub access: AccessSettings,
+    /// Conversions shown by `list`, `status` and reports, and the rates
+    /// foreign-currency items are priced at
+    #[serde(default)]
+    pub currency: CurrencySettings,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+impl Config {
+    /// Number format for a series; `default` falls back to `[invoice] number_format`
+    pub fn number_format(&self, series: &str) -> Option<&str> {
+        match self.series.get(series) {
+            Some(settings) => Some(&settings.number_format),
+            None if series == DEFAULT_SERIES => Some(&self.invoice.number_format),
+            None => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Company {
     pub name: String,
     pub address: String,
@@ -22,17 +77,511 @@ pub struct Company {
     pub tax_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct InvoiceSettings {
     pub number_format: String,
     pub currency: String,
     pub currency_symbol: String,
+    /// Put the symbol before ("prefix", default) or after ("suffix") amounts
+    #[serde(default)]
+    pub symbol_position: SymbolPosition,
+    /// Separate the symbol from the amount with a space (e.g., "1234.56 €")
+    #[serde(default)]
+    pub symbol_space: bool,
     pub due_days: u32,
     #[serde(default)]
     pub tax_rate: f64,
+    /// Named taxes by fractional rate (e.g., GST = 0.05, PST = 0.07), each
+    /// charged on the taxable lines and listed separately; replaces `tax_rate`
+    #[serde(default)]
+    pub taxes: BTreeMap<String, f64>,
+    /// Move due dates that land on a weekend or holiday to the next business day
+    #[serde(default)]
+    pub business_day_due_dates: bool,
+    /// Dates that are not business days, for `business_day_due_dates`
+    #[serde(default)]
+    pub holidays: Vec<NaiveDate>,
+}
+
+impl InvoiceSettings {
+    /// These settings with a client's currency, tax and payment terms applied
+    pub fn for_client(&self, client: &Client) -> InvoiceSettings {
+        let mut settings = self.clone();
+        if let Some(currency) = &client.currency {
+            settings.currency = currency.clone();
+        }
+        if let Some(symbol) = &client.currency_symbol {
+            settings.currency_symbol = symbol.clone();
+        }
+        if let Some(rate) = client.tax_rate {
+            settings.tax_rate = rate;
+            settings.taxes.clear();
+        }
+        if let Some(taxes) = &client.taxes {
+            settings.taxes = taxes.clone();
+        }
+        if let Some(days) = client.due_days {
+            settings.due_days = days;
+        }
+        settings
+    }
+
+    /// The taxes charged, as names and fractional rates: `taxes`, or else a
+    /// single "Tax" at `tax_rate`. Zero rates are left out.
+    pub fn tax_rates(&self) -> Vec<(String, f64)> {
+        let rates = if self.taxes.is_empty() {
+            vec![("Tax".to_string(), self.tax_rate)]
+        } else {
+            self.taxes.clone().into_iter().collect()
+        };
+        rates.into_iter().filter(|(_, rate)| *rate != 0.0).collect()
+    }
+
+    /// Due date of an invoice issued on `date`
+    pub fn due_date(&self, date: NaiveDate) -> NaiveDate {
+        self.due_date_in(date, self.due_days)
+    }
+
+    /// Due date of an invoice issued on `date` with `due_days` terms
+    pub fn due_date_in(&self, date: NaiveDate, due_days: u32) -> NaiveDate {
+        let mut due = date
+            .checked_add_signed(chrono::Duration::days(due_days.into()))
+            .unwrap_or(date);
+        if self.business_day_due_dates {
+            while matches!(due.weekday(), Weekday::Sat | Weekday::Sun)
+                || self.holidays.contains(&due)
+            {
+                let Some(next) = due.succ_opt() else { break };
+                due = next;
+            }
+        }
+        due
+    }
+
+    /// Attach the currency symbol to an already formatted amount
+    pub fn with_symbol(&self, amount: &str) -> String {
+        let space = if self.symbol_space { " " } else { "" };
+        match self.symbol_position {
+            SymbolPosition::Prefix => format!("{}{space}{amount}", self.currency_symbol),
+            SymbolPosition::Suffix => format!("{amount}{space}{}", self.currency_symbol),
+        }
+    }
+
+    /// Amount with two decimals and the currency symbol (e.g., "$12.50")
+    pub fn money(&self, amount: f64) -> String {
+        self.with_symbol(&format!("{amount:.2}"))
+    }
+}
+
+/// Where the currency symbol goes relative to an amount
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolPosition {
+    #[default]
+    Prefix,
+    Suffix,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PdfSettings {
     pub output_dir: String,
+    /// Always render reproducible PDFs (same as passing --deterministic)
+    #[serde(default)]
+    pub deterministic: bool,
+    /// Extra directories every generated PDF is also copied to (e.g.,
+    /// "~/Dropbox/Invoices/{year}"); {year}, {month} and {client} come
+    /// from the invoice, and relative paths are under the config directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub copy_to: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct DisplaySettings {
+    /// Command used to open generated files instead of the system opener
+    #[serde(default)]
+    pub pdf_viewer: Option<String>,
+    /// Most invoices `list` shows without --limit or --all
+    #[serde(default)]
+    pub default_list_limit: Option<usize>,
+    /// How far back `list` looks without --limit or --all (e.g., "12m")
+    #[serde(default)]
+    pub default_list_window: Option<ListWindow>,
+}
+
+/// A span of time back from today, written as a count and a unit:
+/// "30d", "8w", "12m" or "2y"
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum ListWindow {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+}
+
+impl ListWindow {
+    /// First date inside the window ending on `today`
+    pub fn start(self, today: NaiveDate) -> NaiveDate {
+        let months = |n: u32| {
+            today
+                .checked_sub_months(Months::new(n))
+                .unwrap_or(NaiveDate::MIN)
+        };
+        match self {
+            ListWindow::Days(n) => today - chrono::Duration::days(n.into()),
+            ListWindow::Weeks(n) => today - chrono::Duration::weeks(n.into()),
+            ListWindow::Months(n) => months(n),
+            ListWindow::Years(n) => months(n.saturating_mul(12)),
+        }
+    }
+}
+
+impl fmt::Display for ListWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListWindow::Days(n) => write!(f, "{n}d"),
+            ListWindow::Weeks(n) => write!(f, "{n}w"),
+            ListWindow::Months(n) => write!(f, "{n}m"),
+            ListWindow::Years(n) => write!(f, "{n}y"),
+        }
+    }
+}
+
+impl FromStr for ListWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let invalid = || format!("invalid window '{s}' (expected e.g. 30d, 8w, 12m or 2y)");
+        let unit = s.chars().last().ok_or_else(invalid)?;
+        let count: u32 = s[..s.len() - unit.len_utf8()]
+            .parse()
+            .map_err(|_| invalid())?;
+        match unit {
+            'd' => Ok(ListWindow::Days(count)),
+            'w' => Ok(ListWindow::Weeks(count)),
+            'm' => Ok(ListWindow::Months(count)),
+            'y' => Ok(ListWindow::Years(count)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for ListWindow {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ListWindow> for String {
+    fn from(window: ListWindow) -> String {
+        window.to_string()
+    }
+}
+
+/// Who may change the data in this config dir
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct AccessSettings {
+    /// Refuse every command that would change state, config or invoices,
+    /// as `--read-only` does (e.g., for a bookkeeper's copy)
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// Where exchange rates come from and which currency totals are converted to
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CurrencySettings {
+    /// Currency `list`, `status` and reports convert outstanding amounts to;
+    /// nothing is converted when it matches the invoices' currency
+    #[serde(default = "default_display_currency")]
+    pub display: String,
+    #[serde(default)]
+    pub provider: RateProvider,
+    /// Rates for the `fixed` provider as units of the second currency per
+    /// unit of the first (e.g., "USD/BRL" = 5.4); the inverse is implied
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rates: BTreeMap<String, f64>,
+    /// How long a fetched rate is reused from state.toml
+    #[serde(default = "default_cache_hours")]
+    pub cache_hours: u32,
+}
+
+impl Default for CurrencySettings {
+    fn default() -> Self {
+        Self {
+            display: default_display_currency(),
+            provider: RateProvider::default(),
+            rates: BTreeMap::new(),
+            cache_hours: default_cache_hours(),
+        }
+    }
+}
+
+fn default_display_currency() -> String {
+    "BRL".to_string()
+}
+
+fn default_cache_hours() -> u32 {
+    12
+}
+
+/// Source of exchange rates
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateProvider {
+    /// European Central Bank reference rates from the Frankfurter API
+    #[default]
+    Frankfurter,
+    /// The rates listed under `[currency.rates]`
+    Fixed,
+    /// No rates: conversions are skipped and foreign items need `--rate`
+    None,
+}
+
+/// How clients can pay, included in shareable invoice summaries
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct PaymentSettings {
+    #[serde(default)]
+    pub link: Option<String>,
+    #[serde(default)]
+    pub bank_details: Option<String>,
+}
+
+/// Someone other than the company who collects payment for an invoice,
+/// such as a factoring company the receivable was sold to
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct PayeeSettings {
+    pub name: String,
+    /// Printed above the remittance details (e.g., a notice of assignment)
+    #[serde(default)]
+    pub notice: Option<String>,
+    #[serde(default)]
+    pub link: Option<String>,
+    #[serde(default)]
+    pub bank_details: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SeriesSettings {
+    pub number_format: String,
+}
+
+/// Outgoing mail, handed to a sendmail-compatible command
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EmailSettings {
+    /// Command that reads a full message (headers included) on stdin
+    #[serde(default = "default_mail_command")]
+    pub command: String,
+    /// Sender address; defaults to the company email
+    #[serde(default)]
+    pub from: Option<String>,
+}
+
+impl Default for EmailSettings {
+    fn default() -> Self {
+        Self {
+            command: default_mail_command(),
+            from: None,
+        }
+    }
+}
+
+fn default_mail_command() -> String {
+    "sendmail -t".to_string()
+}
+
+/// Reminder escalation for overdue invoices
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DunningSettings {
+    /// Escalation levels in order; level N is `levels[N - 1]`
+    #[serde(default = "default_dunning_levels")]
+    pub levels: Vec<DunningLevel>,
+}
+
+impl Default for DunningSettings {
+    fn default() -> Self {
+        Self {
+            levels: default_dunning_levels(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DunningLevel {
+    /// Days past the due date before this level applies
+    pub days: u32,
+    /// Message template; supports {client}, {number}, {amount}, {due_date},
+    /// {days_overdue}, {level} and {interest}
+    pub message: String,
+}
+
+fn default_dunning_levels() -> Vec<DunningLevel> {
+    vec![
+        DunningLevel {
+            days: 1,
+            message: "Hi {client}, a friendly reminder that invoice {number} for {amount} \
+                      was due on {due_date}. If you've already paid, please disregard this message."
+                .to_string(),
+        },
+        DunningLevel {
+            days: 14,
+            message: "Hi {client}, invoice {number} for {amount} is now {days_overdue} days \
+                      overdue (due {due_date}). Please arrange payment at your earliest convenience."
+                .to_string(),
+        },
+        DunningLevel {
+            days: 30,
+            message: "Hi {client}, invoice {number} for {amount} remains unpaid {days_overdue} \
+                      days after its due date of {due_date}. Please settle it within 7 days or \
+                      contact us to discuss."
+                .to_string(),
+        },
+    ]
+}
+
+/// Revenue targets
+#[derive(Debug, Deserialize, Serialize)]
+pub struct GoalSettings {
+    /// Amount to invoice each calendar month
+    pub monthly_revenue: f64,
+}
+
+/// A fixed-price engagement tracked by `invoice projects`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProjectSettings {
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Agreed total for the project
+    pub fixed_price: f64,
+}
+
+/// Timesheet import settings
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct ImportSettings {
+    /// Named column mappings, e.g. `[import.profiles.clockify]`
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ImportProfile>,
+}
+
+/// How to read a timesheet CSV export. Unset fields fall back to the
+/// command-line flags, then to the defaults noted below.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ImportProfile {
+    /// Catalog item the hours are billed as
+    #[serde(default)]
+    pub item: Option<String>,
+    /// Column with the entry date (default: "Date")
+    #[serde(default)]
+    pub date_column: Option<String>,
+    /// chrono format of the date column (default: "%Y-%m-%d")
+    #[serde(default)]
+    pub date_format: Option<String>,
+    /// Column with the time spent, as decimal hours or h:mm[:ss] (default: "Hours")
+    #[serde(default)]
+    pub hours_column: Option<String>,
+    /// Column whose values become separate invoice lines; without it all
+    /// hours go on one line
+    #[serde(default)]
+    pub description_column: Option<String>,
+    /// Round each entry to a multiple of this many minutes
+    #[serde(default)]
+    pub round_minutes: Option<u32>,
+    /// Direction of `round_minutes` (default: up)
+    #[serde(default)]
+    pub rounding: Option<Rounding>,
+    /// Field separator (default: ',')
+    #[serde(default)]
+    pub delimiter: Option<char>,
+}
+
+impl ImportProfile {
+    /// Fields set in `overrides` replace the ones in this profile
+    pub fn merged(self, overrides: ImportProfile) -> ImportProfile {
+        ImportProfile {
+            item: overrides.item.or(self.item),
+            date_column: overrides.date_column.or(self.date_column),
+            date_format: overrides.date_format.or(self.date_format),
+            hours_column: overrides.hours_column.or(self.hours_column),
+            description_column: overrides.description_column.or(self.description_column),
+            round_minutes: overrides.round_minutes.or(self.round_minutes),
+            rounding: overrides.rounding.or(self.rounding),
+            delimiter: overrides.delimiter.or(self.delimiter),
+        }
+    }
+}
+
+/// Direction used when rounding imported time entries
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Rounding {
+    #[default]
+    Up,
+    Nearest,
+    Down,
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "up" => Ok(Rounding::Up),
+            "nearest" => Ok(Rounding::Nearest),
+            "down" => Ok(Rounding::Down),
+            other => Err(format!(
+                "unknown rounding '{other}' (expected up, nearest or down)"
+            )),
+        }
+    }
+}
+
+/// Statutory late interest on overdue balances
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InterestSettings {
+    /// Annual rate as a fraction (e.g., 0.08 for 8%)
+    pub annual_rate: f64,
+    #[serde(default)]
+    pub day_count: DayCount,
+    /// Append the accrued interest to reminder messages
+    #[serde(default)]
+    pub include_in_reminders: bool,
+}
+
+/// Day-count convention used to turn days late into a fraction of a year
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DayCount {
+    #[default]
+    #[serde(rename = "actual/365")]
+    Actual365,
+    #[serde(rename = "actual/360")]
+    Actual360,
+    #[serde(rename = "30/360")]
+    Thirty360,
+}
+
+impl fmt::Display for DayCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DayCount::Actual365 => write!(f, "actual/365"),
+            DayCount::Actual360 => write!(f, "actual/360"),
+            DayCount::Thirty360 => write!(f, "30/360"),
+        }
+    }
+}
+
+impl FromStr for DayCount {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "actual/365" | "act/365" => Ok(DayCount::Actual365),
+            "actual/360" | "act/360" => Ok(DayCount::Actual360),
+            "30/360" => Ok(DayCount::Thirty360),
+            other => Err(format!(
+                "unknown day count '{other}' (expected actual/365, actual/360 or 30/360)"
+            )),
+        }
+    }
 }
@@ -0,0 +1,24 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// An engagement from contracts.toml whose invoices count against its value
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Contract {
+    /// Client id from clients.toml
+    pub client: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Total contracted amount, before tax
+    pub value: f64,
+    pub start: NaiveDate,
+    /// Last day of the engagement; open-ended when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<NaiveDate>,
+}
+
+impl Contract {
+    /// Whether `date` falls within the contract's term
+    pub fn is_active(&self, date: NaiveDate) -> bool {
+        date >= self.start && self.end.is_none_or(|end| date <= end)
+    }
+}
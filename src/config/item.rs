@@ -1,3 +1,5 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -5,4 +7,80 @@ pub struct Item {
     pub description: String,
     pub rate: f64,
     pub unit: String,
+    /// Project in config.toml whose fixed price this item bills against
+    /// (typically for `unit = "flat"` milestones)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// What the line bills for; invoices group lines by kind
+    #[serde(default)]
+    pub kind: ItemKind,
+    /// What one unit costs you to deliver, for `invoice margins`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+    /// ISO 4217 code `rate` and `cost` are priced in when it isn't the
+    /// invoice's (e.g., "EUR" for a subcontractor billed in euros)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// Whether the invoice's taxes apply; false for exempt items
+    #[serde(default = "taxable", skip_serializing_if = "is_taxable")]
+    pub taxable: bool,
+    /// Billing cycle of a subscription-style item (hosting billed monthly)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurring: Option<Recurrence>,
+    /// Charge a recurring item by the days of its service period when that
+    /// covers only part of a cycle, such as a start in mid-month
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub prorate: bool,
+}
+
+fn taxable() -> bool {
+    true
+}
+
+fn is_taxable(taxable: &bool) -> bool {
+    *taxable
+}
+
+/// How often a recurring item is billed. Weeks run Monday to Sunday and
+/// months are calendar months.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Recurrence {
+    Weekly,
+    Monthly,
+}
+
+/// Accounting category of a line item, so reimbursed expenses aren't
+/// mistaken for service revenue
+#[derive(
+    Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemKind {
+    #[default]
+    Service,
+    Product,
+    /// Reimbursable costs passed through to the client
+    Expense,
+}
+
+impl ItemKind {
+    /// Group heading on invoices
+    pub fn label(self) -> &'static str {
+        match self {
+            ItemKind::Service => "Services",
+            ItemKind::Product => "Products",
+            ItemKind::Expense => "Reimbursable Expenses",
+        }
+    }
+}
+
+impl fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemKind::Service => write!(f, "service"),
+            ItemKind::Product => write!(f, "product"),
+            ItemKind::Expense => write!(f, "expense"),
+        }
+    }
 }
@@ -1,19 +1,37 @@
 mod client;
 mod company;
+mod contract;
 mod item;
+mod schedule;
 pub mod state;
+mod workspace;
 
-pub use client::Client;
-pub use company::{Company, Config};
-pub use item::Item;
-pub use state::{HistoryEntry, State};
+pub use client::{
+    validate_country, validate_email, validate_tax_id, validate_zip, Client, Delivery, Retainer,
+};
+pub use company::{
+    AccessSettings, Company, Config, CurrencySettings, DayCount, DunningLevel, EmailSettings,
+    GoalSettings, ImportProfile, ImportSettings, InterestSettings, InvoiceSettings, ListWindow,
+    PayeeSettings, ProjectSettings, RateProvider, Rounding, SymbolPosition,
+};
+pub use contract::Contract;
+pub use item::{Item, ItemKind, Recurrence};
+pub use schedule::{CronSchedule, ScheduleEntry};
+pub use state::{
+    CachedRate, Credit, Deposit, Discount, FollowUp, HistoryEntry, Installment, Note, PaymentPlan,
+    Reminder, ReportEntry, Retention, Revision, ServicePeriod, Standing, State, Void,
+    DEFAULT_SERIES,
+};
+pub use workspace::Workspace;
 
 use crate::error::{InvoiceError, Result};
 use directories::ProjectDirs;
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::SystemTime;
 
 /// Global configuration from ~/.config/invoicing.toml
 #[derive(Debug, Deserialize, Default)]
@@ -41,10 +59,33 @@ pub fn load_global_config() -> GlobalConfig {
     GlobalConfig::default()
 }
 
+/// Name of the per-project workspace directory found by discovery
+pub const WORKSPACE_DIR_NAME: &str = ".invoice";
+
+/// Walk up from `start` looking for a `.invoice/` workspace directory, like
+/// git discovers `.git/`. The home directory's `~/.invoice` is the legacy
+/// default location rather than a workspace, so it is skipped.
+pub fn discover_workspace(start: &Path) -> Option<PathBuf> {
+    let home_default = dirs_home().map(|h| h.join(WORKSPACE_DIR_NAME));
+    start
+        .ancestors()
+        .map(|dir| dir.join(WORKSPACE_DIR_NAME))
+        .find(|candidate| candidate.is_dir() && Some(candidate) != home_default.as_ref())
+}
+
 /// Get the config directory path
-/// Priority: 1) CLI flag (-C), 2) ~/.config/invoicing.toml, 3) XDG/default
+/// Priority: 1) CLI flag (-C), 2) `.invoice/` in the current or a parent
+/// directory, 3) ~/.config/invoicing.toml, 4) XDG/default
 pub fn config_dir() -> Result<PathBuf> {
-    // Check global config first
+    // Per-project workspace wins over machine-wide settings
+    if let Some(dir) = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| discover_workspace(&cwd))
+    {
+        return Ok(dir);
+    }
+
+    // Check global config next
     let global = load_global_config();
     if let Some(dir) = global.config_dir {
         return Ok(expand_path(&dir));
@@ -72,9 +113,9 @@ fn dirs_home() -> Option<PathBuf> {
 
 /// Expand ~ in paths
 pub fn expand_path(path: &str) -> PathBuf {
-    if path.starts_with("~/") {
+    if let Some(rest) = path.strip_prefix("~/") {
         if let Some(home) = dirs_home() {
-            return home.join(&path[2..]);
+            return home.join(rest);
         }
     }
     PathBuf::from(path)
@@ -91,7 +132,7 @@ pub fn resolve_output_dir(output_dir: &str, cfg_dir: &Path) -> PathBuf {
 }
 
 /// Load the main config.toml
-pub fn load_config(config_dir: &PathBuf) -> Result<Config> {
+pub fn load_config(config_dir: &Path) -> Result<Config> {
     let path = config_dir.join("config.toml");
     if !path.exists() {
         return Err(InvoiceError::ConfigFileNotFound(path));
@@ -100,8 +141,37 @@ pub fn load_config(config_dir: &PathBuf) -> Result<Config> {
     toml::from_str(&content).map_err(|e| InvoiceError::ConfigParse { path, source: e })
 }
 
+/// `client` as the `[id]` table clients.toml stores it in
+pub fn client_entry(id: &str, client: &Client) -> Result<String> {
+    toml::to_string(&BTreeMap::from([(id, client)])).map_err(|e| {
+        InvoiceError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            e.to_string(),
+        ))
+    })
+}
+
+/// Add `client` to the end of clients.toml as `[id]`, leaving the rest of
+/// the file (comments included) as it is
+pub fn append_client(config_dir: &Path, id: &str, client: &Client) -> Result<()> {
+    if load_clients(config_dir)?.contains_key(id) {
+        return Err(InvoiceError::ClientExists(id.to_string()));
+    }
+    let entry = client_entry(id, client)?;
+
+    let path = config_dir.join("clients.toml");
+    let mut content = fs::read_to_string(&path)?;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push('\n');
+    content.push_str(&entry);
+    fs::write(&path, content)?;
+    Ok(())
+}
+
 /// Load clients.toml as a HashMap
-pub fn load_clients(config_dir: &PathBuf) -> Result<HashMap<String, Client>> {
+pub fn load_clients(config_dir: &Path) -> Result<HashMap<String, Client>> {
     let path = config_dir.join("clients.toml");
     if !path.exists() {
         return Err(InvoiceError::ConfigFileNotFound(path));
@@ -111,7 +181,7 @@ pub fn load_clients(config_dir: &PathBuf) -> Result<HashMap<String, Client>> {
 }
 
 /// Load items.toml as a HashMap
-pub fn load_items(config_dir: &PathBuf) -> Result<HashMap<String, Item>> {
+pub fn load_items(config_dir: &Path) -> Result<HashMap<String, Item>> {
     let path = config_dir.join("items.toml");
     if !path.exists() {
         return Err(InvoiceError::ConfigFileNotFound(path));
@@ -120,18 +190,60 @@ pub fn load_items(config_dir: &PathBuf) -> Result<HashMap<String, Item>> {
     toml::from_str(&content).map_err(|e| InvoiceError::ConfigParse { path, source: e })
 }
 
+/// Load contracts.toml, sorted by id; empty when the file doesn't exist
+pub fn load_contracts(config_dir: &Path) -> Result<BTreeMap<String, Contract>> {
+    let path = config_dir.join("contracts.toml");
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = fs::read_to_string(&path)?;
+    toml::from_str(&content).map_err(|e| InvoiceError::ConfigParse { path, source: e })
+}
+
 /// Load state.toml (creates default if missing)
-pub fn load_state(config_dir: &PathBuf) -> Result<State> {
+pub fn load_state(config_dir: &Path) -> Result<State> {
     let path = config_dir.join("state.toml");
     if !path.exists() {
         return Ok(State::default());
     }
+    let stamp = file_stamp(&path);
+    if let Some((cached_path, cached_stamp, state)) = &*cached_state() {
+        if *cached_path == path && Some(*cached_stamp) == stamp {
+            return Ok(state.clone());
+        }
+    }
+
     let content = fs::read_to_string(&path)?;
-    toml::from_str(&content).map_err(|e| InvoiceError::ConfigParse { path, source: e })
+    let state: State = toml::from_str(&content).map_err(|e| InvoiceError::ConfigParse {
+        path: path.clone(),
+        source: e,
+    })?;
+    if let Some(stamp) = stamp {
+        *cached_state() = Some((path, stamp, state.clone()));
+    }
+    Ok(state)
+}
+
+/// Modification time and size of a file
+type FileStamp = (SystemTime, u64);
+
+/// The state.toml this process last read or wrote, and its stamp then. A
+/// command often loads state more than once (resolving a reference, then
+/// running), and with a long history parsing dominates, so an unchanged
+/// file is served from here.
+static STATE_CACHE: Mutex<Option<(PathBuf, FileStamp, State)>> = Mutex::new(None);
+
+fn cached_state() -> std::sync::MutexGuard<'static, Option<(PathBuf, FileStamp, State)>> {
+    STATE_CACHE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
 }
 
 /// Save state.toml
-pub fn save_state(config_dir: &PathBuf, state: &State) -> Result<()> {
+pub fn save_state(config_dir: &Path, state: &State) -> Result<()> {
     let path = config_dir.join("state.toml");
     let content = toml::to_string_pretty(state).map_err(|e| {
         InvoiceError::Io(std::io::Error::new(
@@ -139,7 +251,8 @@ pub fn save_state(config_dir: &PathBuf, state: &State) -> Result<()> {
             e.to_string(),
         ))
     })?;
-    fs::write(path, content)?;
+    fs::write(&path, content)?;
+    *cached_state() = file_stamp(&path).map(|stamp| (path, stamp, state.clone()));
     Ok(())
 }
 
@@ -159,11 +272,120 @@ email = "billing@yourcompany.com"
 number_format = "INV-{year}-{seq:04}"  # e.g., INV-2026-0001
 currency = "USD"
 currency_symbol = "$"
+# symbol_position = "suffix"  # "1234.56€" instead of "$1234.56"
+# symbol_space = true         # "1234.56 €"
 due_days = 30
 tax_rate = 0.0  # e.g., 0.0825 for 8.25%
+# business_day_due_dates = true   # due on a weekend or holiday? move it to the next business day
+# holidays = ["2026-12-25", "2027-01-01"]
+
+# Several named taxes instead of tax_rate, each listed on the invoice.
+# Items with taxable = false in items.toml are exempt.
+# [invoice.taxes]
+# GST = 0.05
+# PST = 0.07
 
 [pdf]
 output_dir = "./output"
+# deterministic = true  # Byte-identical PDFs when regenerating unchanged invoices
+# copy_to = ["~/Dropbox/Invoices/{year}"]  # Also copy PDFs here ({year}, {month}, {client})
+
+# Refuse every command that changes invoices, payments or config, like
+# passing --read-only (e.g., in a copy handed to a bookkeeper)
+# [access]
+# read_only = true
+
+# Exchange rates for converted totals in 'list', 'status' and reports, and
+# for items priced in another currency. Fetched rates are cached in state.toml.
+# [currency]
+# display = "BRL"          # Show outstanding amounts converted to this currency
+# provider = "frankfurter" # or "fixed" (the rates below) or "none"
+# cache_hours = 12
+# [currency.rates]
+# "USD/BRL" = 5.4          # 1 USD = 5.4 BRL; BRL/USD is implied
+
+# [display]
+# pdf_viewer = "zathura"  # Overrides the system opener for --open and 'invoice open'
+# default_list_limit = 50     # 'list' shows at most this many invoices unless --all
+# default_list_window = "12m" # ...issued in the last 12 months (also "30d", "8w", "2y")
+
+# Additional numbering series, selected with 'generate --series <name>'
+# [series.retainer]
+# number_format = "RET-{year}-{seq:04}"
+#
+# [series.credit]
+# number_format = "CN-{year}-{seq:04}"
+
+# Reminder escalation used by 'invoice remind' (defaults: 1, 14 and 30 days overdue)
+# [[dunning.levels]]
+# days = 7
+# message = "Hi {client}, invoice {number} for {amount} was due on {due_date}."
+
+# Statutory late interest used by 'invoice interest'
+# [interest]
+# annual_rate = 0.08             # 8% per year
+# day_count = "actual/365"       # or "actual/360", "30/360"
+# include_in_reminders = true    # Append accrued interest to 'invoice remind' messages
+
+# Monthly revenue target shown by 'invoice status' and 'invoice list'
+# [goals]
+# monthly_revenue = 12000
+
+# Fixed-price projects; link items with project = "website" in items.toml
+# and 'invoice projects' shows how much of the price is already invoiced.
+# 'invoice deposit --project website --percent 40' bills an advance that the
+# next invoice for the project deducts.
+# [projects.website]
+# description = "Website redesign"
+# fixed_price = 12000
+
+# Timesheet column mappings for 'invoice import-time --profile clockify'
+# [import.profiles.clockify]
+# item = "consulting"
+# date_column = "Start Date"
+# date_format = "%m/%d/%Y"
+# hours_column = "Duration (decimal)"
+# description_column = "Description"
+# round_minutes = 15
+# rounding = "up"  # up, nearest or down
+
+# Mail for 'invoice digest --email'; the command reads the message on stdin
+# [email]
+# command = "sendmail -t"
+# from = "billing@example.com"  # default: company email
+
+# Commands for 'invoice schedule', which prints them as crontab lines
+# (--crontab) or runs them itself (--run). `cron` takes the usual five
+# fields: minute hour day-of-month month day-of-week.
+# [schedule.digest]
+# cron = "0 8 * * mon"
+# command = "digest --email billing@example.com"
+#
+# [schedule.reminders]
+# cron = "0 9 * * *"
+# command = "notify-due --days 3"
+#
+# [schedule.status]
+# cron = "0 6 * * *"
+# command = "refresh-status"
+
+# [payment]
+# link = "https://pay.example.com/your-company"
+# bank_details = """
+# Bank: Example Bank
+# Account: 000123456
+# """
+
+# Alternate payees, selected with 'generate --payee <name>'. The invoice
+# shows their details instead of [payment] (e.g., a factoring company).
+# [payees.factor1]
+# name = "Example Factoring LLC"
+# notice = "This invoice has been assigned to Example Factoring LLC. Remit payment only to:"
+# link = "https://pay.example-factoring.com/acct/123"
+# bank_details = """
+# Bank: Factor Bank
+# Account: 000987654
+# """
 "#;
 
 /// Template content for clients.toml
@@ -182,6 +404,23 @@ city = "Los Angeles"
 state = "CA"
 zip = "90001"
 # country = "USA"               # optional, defaults to company country
+# tax_id = "DE123456789"        # optional: client's VAT ID, printed under the address
+# cover_letter = "templates/cover.typ"  # optional letter page before the invoice
+# delivery = "email"            # optional: email, postal or none
+# formats = ["pdf", "html"]     # optional: default formats for this client
+# language = "en"               # optional: document language for templates
+# display_number_format = "ACME-{year}-{seq:03}"  # optional: client's own invoice reference
+# Optional overrides of the [invoice] settings in config.toml:
+# currency = "EUR"
+# currency_symbol = "€"
+# tax_rate = 0.19
+# taxes = { GST = 0.05, HST = 0.08 }
+# due_days = 14
+# Optional custom fields shown on every invoice for this client:
+# fields = { "Cost Center" = "1234", "PO Number" = "PO-5678" }
+# Optional retainer: 'invoice true-up' bills tracked hours beyond the
+# included ones as overage_item (an items.toml entry at the overage rate):
+# retainer = { included_hours = 20, overage_item = "consulting" }
 "#;
 
 /// Template content for items.toml
@@ -205,10 +444,36 @@ unit = "hour"
 description = "Project Setup & Configuration"
 rate = 500.00
 unit = "flat"   # fixed price, quantity is typically 1
+# kind = "service"     # or "product", "expense" (reimbursable); invoices group lines by kind
+# project = "website"  # optional: bill against a [projects.website] fixed price
+# cost = 200.00        # optional: your cost per unit, for 'invoice margins'
+# currency = "EUR"     # optional: rate and cost are in EUR; converted when generating
+# taxable = false      # optional: exempt from the invoice's taxes
+#
+# [hosting]
+# description = "Managed Hosting"
+# rate = 300.00
+# unit = "month"
+# recurring = "monthly"  # or "weekly"
+# prorate = true         # bill a partial month by its days (e.g., a start on the 15th)
+"#;
+
+/// Template content for contracts.toml
+pub const CONTRACTS_TEMPLATE: &str = r#"# Contracts (engagements) with a total value. Invoices are linked with
+# 'invoice generate --contract <id>', or automatically when the client has
+# exactly one contract in effect on the invoice date.
+# 'invoice contract status' compares what has been billed with the value.
+#
+# Example:
+# [acme-2026]
+# client = "example-client"
+# description = "2026 platform retainer"  # optional
+# value = 60000.00                        # before tax
+# start = "2026-01-01"
+# end = "2026-12-31"                      # optional: open-ended without it
 "#;
 
 /// Template content for global config (~/.config/invoicing.toml)
-#[allow(dead_code)]
 pub const GLOBAL_CONFIG_TEMPLATE: &str = r#"# Global invoice configuration
 # This file controls where the invoice CLI looks for its data files.
 #
@@ -226,3 +491,51 @@ config_dir = "~/.invoice"
 pub fn global_config_file() -> PathBuf {
     global_config_path().unwrap_or_else(|| PathBuf::from("~/.config/invoicing.toml"))
 }
+
+/// Keys accepted in the global config file
+pub const GLOBAL_CONFIG_KEYS: &[&str] = &["config_dir"];
+
+/// Write GLOBAL_CONFIG_TEMPLATE to ~/.config/invoicing.toml (fails if it exists)
+pub fn init_global_config() -> Result<PathBuf> {
+    let path = global_config_file();
+    if path.exists() {
+        return Err(InvoiceError::AlreadyInitialized(path));
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, GLOBAL_CONFIG_TEMPLATE)?;
+    Ok(path)
+}
+
+/// Set a key in ~/.config/invoicing.toml, creating the file if needed.
+/// Other keys already in the file are preserved.
+pub fn set_global_config_value(key: &str, value: &str) -> Result<PathBuf> {
+    if !GLOBAL_CONFIG_KEYS.contains(&key) {
+        return Err(InvoiceError::UnknownConfigKey(key.to_string()));
+    }
+
+    let path = global_config_file();
+    let mut table: toml::Table = if path.exists() {
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|e| InvoiceError::ConfigParse {
+            path: path.clone(),
+            source: e,
+        })?
+    } else {
+        toml::Table::new()
+    };
+    table.insert(key.to_string(), toml::Value::String(value.to_string()));
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = toml::to_string_pretty(&table).map_err(|e| {
+        InvoiceError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            e.to_string(),
+        ))
+    })?;
+    fs::write(&path, content)?;
+    Ok(path)
+}
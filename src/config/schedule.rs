@@ -0,0 +1,205 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// An `invoice` command to run on a schedule, e.g. `[schedule.digest]`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ScheduleEntry {
+    /// When to run, as a five-field cron expression ("0 8 * * mon")
+    pub cron: CronSchedule,
+    /// Arguments to `invoice`, e.g. "digest --email me@example.com"
+    pub command: String,
+}
+
+impl ScheduleEntry {
+    /// The command split into arguments; double quotes group words
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut arg = String::new();
+        let mut quoted = false;
+        let mut started = false;
+        for c in self.command.chars() {
+            match c {
+                '"' => {
+                    quoted = !quoted;
+                    started = true;
+                }
+                c if c.is_whitespace() && !quoted => {
+                    if started {
+                        args.push(std::mem::take(&mut arg));
+                        started = false;
+                    }
+                }
+                c => {
+                    arg.push(c);
+                    started = true;
+                }
+            }
+        }
+        if started {
+            args.push(arg);
+        }
+        args
+    }
+}
+
+/// Minute, hour, day of month, month and day of week fields of a cron
+/// expression. Each field accepts `*`, numbers, ranges (`1-5`), lists
+/// (`1,15`) and steps (`*/15`); months and weekdays also take names.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronSchedule {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Day of month and weekday were both restricted; either may match
+    either_day: bool,
+}
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl CronSchedule {
+    /// Whether the schedule fires in the minute starting at `at`
+    pub fn matches(&self, at: NaiveDateTime) -> bool {
+        has(self.minutes, at.minute()) && has(self.hours, at.hour()) && self.on_day(at.date())
+    }
+
+    /// First minute after `after` the schedule fires, looking up to five
+    /// years ahead (enough for February 29)
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let mut date = start.date();
+        while date <= start.date() + Duration::days(5 * 366) {
+            if self.on_day(date) {
+                for hour in (0..24).filter(|&h| has(self.hours, h)) {
+                    for minute in (0..60).filter(|&m| has(self.minutes, m)) {
+                        let at = date.and_hms_opt(hour, minute, 0)?;
+                        if at >= start {
+                            return Some(at);
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    fn on_day(&self, date: NaiveDate) -> bool {
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        let day_matches = if self.either_day {
+            day || weekday
+        } else {
+            day && weekday
+        };
+        has(self.months, date.month()) && day_matches
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Bit set of the values a cron field allows
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        let lower = s.to_lowercase();
+        let named = names.iter().position(|n| *n == lower).map(|i| i as u32);
+        let offset = if min == 1 { 1 } else { 0 };
+        named
+            .map(|i| i + offset)
+            .or_else(|| s.parse().ok())
+            .filter(|v| (min..=max).contains(v))
+            .ok_or_else(|| format!("'{s}' is not between {min} and {max}"))
+    };
+
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("'{step}' is not a valid step"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((from, to)) => (value(from)?, value(to)?),
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let v = value(range)?;
+                    (v, v)
+                }
+            },
+        };
+        if from > to {
+            return Err(format!("range '{range}' runs backwards"));
+        }
+        for v in (from..=to).step_by(step as usize) {
+            set |= 1 << v;
+        }
+    }
+    Ok(set)
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "'{s}' needs five fields: minute hour day-of-month month day-of-week"
+            ));
+        };
+        let invalid = |reason: String| format!("invalid cron expression '{s}': {reason}");
+
+        let mut weekdays = parse_field(weekday, 0, 7, &WEEKDAYS).map_err(invalid)?;
+        // Both 0 and 7 mean Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(CronSchedule {
+            source: fields.join(" "),
+            minutes: parse_field(minute, 0, 59, &[]).map_err(invalid)?,
+            hours: parse_field(hour, 0, 23, &[]).map_err(invalid)?,
+            days: parse_field(day, 1, 31, &[]).map_err(invalid)?,
+            months: parse_field(month, 1, 12, &MONTHS).map_err(invalid)?,
+            weekdays,
+            either_day: !day.starts_with('*') && !weekday.starts_with('*'),
+        })
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<CronSchedule> for String {
+    fn from(schedule: CronSchedule) -> String {
+        schedule.to_string()
+    }
+}
@@ -1,21 +1,201 @@
+use std::collections::BTreeMap;
 use std::fmt;
+use std::str::FromStr;
 
-use chrono::{Datelike, NaiveDate};
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Deserializer, Serialize};
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+use crate::money::round_cents;
+
+/// Name of the numbering series that uses `[invoice] number_format` and `counter`
+pub const DEFAULT_SERIES: &str = "default";
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
 pub struct State {
     pub counter: Counter,
+    /// Counters for numbering series other than the default one
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub series: BTreeMap<String, Counter>,
     #[serde(default)]
     pub history: Vec<HistoryEntry>,
+    /// Client credit from overpayments
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub credits: Vec<Credit>,
+    /// Generated client reports, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reports: Vec<ReportEntry>,
+    /// Fetched exchange rates by pair (e.g., "USD/BRL"), reused until they
+    /// are older than `[currency] cache_hours`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rates: BTreeMap<String, CachedRate>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl State {
+    /// Counter for a numbering series (a fresh one if the series hasn't been used yet)
+    pub fn counter_for(&self, series: &str) -> Counter {
+        if series == DEFAULT_SERIES {
+            self.counter
+        } else {
+            self.series.get(series).copied().unwrap_or_default()
+        }
+    }
+
+    /// Mutable counter for a numbering series, created on first use
+    pub fn counter_for_mut(&mut self, series: &str) -> &mut Counter {
+        if series == DEFAULT_SERIES {
+            &mut self.counter
+        } else {
+            self.series.entry(series.to_string()).or_default()
+        }
+    }
+
+    /// The invoice with this number, looking from the newest
+    pub fn entry(&self, number: &str) -> Option<&HistoryEntry> {
+        self.history.iter().rev().find(|e| e.number == number)
+    }
+
+    /// The invoice at a 1-based position counting from the newest, as
+    /// `list` numbers them
+    pub fn entry_at(&self, index: usize) -> Option<&HistoryEntry> {
+        self.history.iter().rev().nth(index.checked_sub(1)?)
+    }
+
+    /// Take back the credit a removed payment of `invoice` created: the
+    /// matching credit is reduced by `amount`, and dropped once used up
+    pub fn revoke_credit(&mut self, invoice: &str, date: NaiveDate, amount: f64) {
+        let Some(idx) = self
+            .credits
+            .iter()
+            .position(|c| c.invoice.as_deref() == Some(invoice) && c.date == date)
+        else {
+            return;
+        };
+        self.credits[idx].amount -= amount;
+        if self.credits[idx].amount < 0.005 {
+            self.credits.remove(idx);
+        }
+    }
+
+    /// Credit balance held for a client
+    pub fn client_credit(&self, client: &str) -> f64 {
+        self.credits
+            .iter()
+            .filter(|c| c.client == client)
+            .fold(0.0, |sum, c| sum + c.amount)
+    }
+
+    /// Put the state in canonical form: invoices ordered by date then number,
+    /// dated records oldest first, and amounts rounded to cents (dropping
+    /// floating-point noise like 0.30000000000000004)
+    pub fn normalize(&mut self) {
+        self.history
+            .sort_by(|a, b| (a.date, &a.number).cmp(&(b.date, &b.number)));
+        for entry in &mut self.history {
+            entry.total = round_cents(entry.total);
+            entry.payments.sort_by_key(|p| p.date);
+            for payment in &mut entry.payments {
+                payment.amount = round_cents(payment.amount);
+            }
+            for revision in &mut entry.revisions {
+                revision.total = round_cents(revision.total);
+            }
+            if let Some(deposit) = &mut entry.deposit {
+                deposit.amount = round_cents(deposit.amount);
+            }
+            if let Some(retention) = &mut entry.retention {
+                retention.amount = round_cents(retention.amount);
+            }
+            entry.tax = entry.tax.map(round_cents);
+            if let Some(plan) = &mut entry.payment_plan {
+                plan.principal = round_cents(plan.principal);
+                for installment in &mut plan.installments {
+                    installment.amount = round_cents(installment.amount);
+                }
+            }
+            entry.reminders.sort_by_key(|r| r.date);
+            entry.followups.sort_by_key(|f| f.date);
+            entry.notes.sort_by_key(|n| n.at);
+        }
+
+        self.credits.sort_by_key(|c| c.date);
+        for credit in &mut self.credits {
+            credit.amount = round_cents(credit.amount);
+        }
+    }
+}
+
+/// An exchange rate and when it was fetched
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct CachedRate {
+    pub rate: f64,
+    pub fetched: NaiveDateTime,
+}
+
+/// A generated client report and the filters it was run with
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ReportEntry {
+    /// Report number in its own sequence (e.g., "RPT-2026-0001")
+    pub number: String,
+    pub client: String,
+    pub date: NaiveDate,
+    /// PDF file name in the output directory
+    pub file: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub net: bool,
+}
+
+impl ReportEntry {
+    /// The filters as a short description (e.g., "from 2026-01-01, unpaid, net")
+    pub fn filters(&self) -> String {
+        let mut filters = Vec::new();
+        if let Some(from) = &self.from {
+            filters.push(format!("from {from}"));
+        }
+        if let Some(to) = &self.to {
+            filters.push(format!("to {to}"));
+        }
+        if let Some(status) = &self.status {
+            filters.push(status.clone());
+        }
+        if let Some(group_by) = &self.group_by {
+            filters.push(format!("by {group_by}"));
+        }
+        if self.net {
+            filters.push("net".to_string());
+        }
+        if filters.is_empty() {
+            "all invoices".to_string()
+        } else {
+            filters.join(", ")
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 pub struct Counter {
     pub last_number: u32,
     pub last_year: u32,
 }
 
+impl Counter {
+    /// Sequence number for the next invoice in `year` (restarts at 1 each year)
+    pub fn next_seq(&self, year: u32) -> u32 {
+        if self.last_year == year {
+            self.last_number + 1
+        } else {
+            1
+        }
+    }
+}
+
 impl Default for Counter {
     fn default() -> Self {
         Self {
@@ -29,14 +209,263 @@ impl Default for Counter {
 pub struct Payment {
     pub amount: f64,
     pub date: NaiveDate,
+    /// Excess of this payment kept as client credit (`add-payment --as-credit`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credit: Option<f64>,
+}
+
+/// Money held for a client, e.g., the excess of an overpaid invoice
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Credit {
+    /// Client identifier from clients.toml
+    pub client: String,
+    pub amount: f64,
+    pub date: NaiveDate,
+    /// Invoice whose overpayment created the credit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invoice: Option<String>,
+}
+
+/// Part of an invoice held back until project completion
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Retention {
+    /// Percentage of the invoice total held back (e.g., 10.0)
+    pub percent: f64,
+    pub amount: f64,
+    /// Project the invoice billed against, if its items link to one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// Invoice that later billed the retained amount
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub released_by: Option<String>,
+}
+
+/// An advance billed on a fixed-price project, deducted from a later invoice
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Deposit {
+    pub project: String,
+    /// Percentage of the project's fixed price (e.g., 40.0)
+    pub percent: f64,
+    /// Amount before tax
+    pub amount: f64,
+    /// Invoice that deducted the deposit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deducted_by: Option<String>,
+}
+
+/// A superseded version of an invoice, kept when an edit changed its total
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Revision {
+    /// 1-based version number of the archived document
+    pub version: u32,
+    /// When the invoice was changed
+    pub changed_at: NaiveDateTime,
+    /// Who made the change ($USER)
+    pub changed_by: String,
+    /// Human-readable summary of what changed
+    pub changes: String,
+    /// Total before the change
+    pub total: f64,
+    /// Item inputs before the change
+    #[serde(default)]
+    pub items: Vec<String>,
+    /// Archived artifact file names (e.g., INV-2026-0001.v1.pdf)
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+/// A payment reminder sent for an overdue invoice
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Reminder {
+    /// Dunning level (1-based) the reminder was sent at
+    pub level: u32,
+    pub date: NaiveDate,
+}
+
+/// An agreed schedule for paying off an invoice in installments
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PaymentPlan {
+    /// When the plan was agreed
+    pub created: NaiveDate,
+    /// Balance the plan covers (what was outstanding when it was agreed)
+    pub principal: f64,
+    pub installments: Vec<Installment>,
+    /// Rendered agreement (e.g., INV-2026-0001.plan.pdf)
+    pub file: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Installment {
+    pub due: NaiveDate,
+    pub amount: f64,
+}
+
+/// A collections contact made about an unpaid invoice
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FollowUp {
+    pub date: NaiveDate,
+    /// Channel used (e.g., phone, email, meeting)
+    pub via: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// When to follow up again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<NaiveDate>,
+}
+
+/// An internal note attached to an invoice (never rendered on documents)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Note {
+    pub at: NaiveDateTime,
+    /// Who wrote the note ($USER)
+    pub author: String,
+    pub text: String,
+}
+
+/// Why and when an issued invoice was voided
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Void {
+    pub date: NaiveDate,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// The dates an invoice's services were rendered, for the client's accrual accounting
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct ServicePeriod {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl ServicePeriod {
+    /// The whole calendar month (e.g., 2026-01 covers Jan 1–31, 2026)
+    pub fn month(year: i32, month: u32) -> Option<Self> {
+        let from = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let to = from
+            .checked_add_months(chrono::Months::new(1))?
+            .pred_opt()?;
+        Some(Self { from, to })
+    }
+}
+
+/// Compact range for display: "Jan 1–31, 2026", "Jan 15 – Feb 14, 2026"
+/// or "Dec 15, 2025 – Jan 14, 2026"
+impl fmt::Display for ServicePeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (from, to) = (self.from, self.to);
+        if from == to {
+            write!(f, "{}", from.format("%b %-d, %Y"))
+        } else if from.year() != to.year() {
+            write!(
+                f,
+                "{} – {}",
+                from.format("%b %-d, %Y"),
+                to.format("%b %-d, %Y")
+            )
+        } else if from.month() != to.month() {
+            write!(f, "{} – {}", from.format("%b %-d"), to.format("%b %-d, %Y"))
+        } else {
+            write!(f, "{}–{}", from.format("%b %-d"), to.format("%-d, %Y"))
+        }
+    }
+}
+
+/// A discount taken off an invoice's subtotal before tax, written as a
+/// percentage ("10%") or a fixed amount ("250")
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub enum Discount {
+    Percent(f64),
+    Amount(f64),
+}
+
+impl Discount {
+    /// What it takes off `subtotal`, never more than the subtotal
+    pub fn amount(self, subtotal: f64) -> f64 {
+        let amount = match self {
+            Discount::Percent(percent) => subtotal * percent / 100.0,
+            Discount::Amount(amount) => amount,
+        };
+        amount.clamp(0.0, subtotal.max(0.0))
+    }
+
+    /// The percentage, or 0 for a fixed amount
+    pub fn percent(self) -> f64 {
+        match self {
+            Discount::Percent(percent) => percent,
+            Discount::Amount(_) => 0.0,
+        }
+    }
+
+    /// Whether it takes nothing off (`edit --discount 0` removes a discount)
+    pub fn is_zero(self) -> bool {
+        match self {
+            Discount::Percent(value) | Discount::Amount(value) => value == 0.0,
+        }
+    }
+}
+
+impl fmt::Display for Discount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Discount::Percent(percent) => write!(f, "{percent}%"),
+            Discount::Amount(amount) => write!(f, "{amount}"),
+        }
+    }
+}
+
+impl FromStr for Discount {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || {
+            format!("invalid discount '{s}' (expected a percentage like 10% or an amount like 250)")
+        };
+        match s.strip_suffix('%') {
+            Some(percent) => {
+                let percent: f64 = percent.trim().parse().map_err(|_| invalid())?;
+                if !(0.0..=100.0).contains(&percent) {
+                    return Err(invalid());
+                }
+                Ok(Discount::Percent(percent))
+            }
+            None => {
+                let amount: f64 = s.parse().map_err(|_| invalid())?;
+                if !amount.is_finite() || amount < 0.0 {
+                    return Err(invalid());
+                }
+                Ok(Discount::Amount(amount))
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for Discount {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Discount> for String {
+    fn from(discount: Discount) -> String {
+        discount.to_string()
+    }
 }
 
 /// Invoice status derived from payment history
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
 pub enum PaymentStatus {
     Unpaid,
     Partial,
     Paid,
+    /// Replaced by a consolidated invoice; nothing is owed on it anymore
+    Superseded,
+    /// Voided after it was issued; nothing is owed on it
+    Void,
 }
 
 impl fmt::Display for PaymentStatus {
@@ -45,10 +474,29 @@ impl fmt::Display for PaymentStatus {
             PaymentStatus::Unpaid => write!(f, "UNPAID"),
             PaymentStatus::Partial => write!(f, "PARTIAL"),
             PaymentStatus::Paid => write!(f, "PAID"),
+            PaymentStatus::Superseded => write!(f, "SUPERSEDED"),
+            PaymentStatus::Void => write!(f, "VOID"),
         }
     }
 }
 
+/// Where an invoice stood on a given day, recorded by `invoice
+/// refresh-status` so scripts reading state.toml needn't work out due
+/// dates and lateness themselves
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Standing {
+    /// Day the status was worked out for
+    pub as_of: NaiveDate,
+    pub status: PaymentStatus,
+    pub due: NaiveDate,
+    /// Unpaid or partly paid past `due`
+    pub overdue: bool,
+    /// Days past `due`: as of `as_of` while anything is owed, or when the
+    /// last payment came in once paid; 0 if on time
+    pub days_late: i64,
+    pub outstanding: f64,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct HistoryEntry {
     pub number: String,
@@ -61,23 +509,180 @@ pub struct HistoryEntry {
     /// Original item inputs (e.g., ["consulting:8", "development:40"])
     #[serde(default)]
     pub items: Vec<String>,
+    /// Every artifact produced for this invoice (e.g., PDF, HTML, JSON).
+    /// `file` remains the primary one; empty for older entries.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+    /// Prior versions archived when edits changed the total
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revisions: Vec<Revision>,
+    /// Reminders sent for this invoice, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reminders: Vec<Reminder>,
+    /// Installment schedule agreed with the client, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_plan: Option<PaymentPlan>,
+    /// Numbering series this invoice was drawn from (None for the default series)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series: Option<String>,
+    /// Issued documents are locked against edits and repricing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+    /// When the invoiced services were rendered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period: Option<ServicePeriod>,
+    /// Internal notes, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<Note>,
+    /// Follow-up contacts about payment, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub followups: Vec<FollowUp>,
+    /// Consolidated invoice that replaced this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub superseded_by: Option<String>,
+    /// Number in the client's own scheme, shown alongside `number`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_number: Option<String>,
+    /// Amount held back from `total` until completion; `total` is what is
+    /// payable now
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<Retention>,
+    /// Invoices whose retention this invoice bills
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub released_retention: Vec<String>,
+    /// Set when this is an advance invoice for a project
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deposit: Option<Deposit>,
+    /// Deposit invoices deducted on this invoice
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deducted_deposits: Vec<String>,
+    /// Tax charged on the invoice; None for entries recorded before it was stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tax: Option<f64>,
+    /// Set when the invoice was voided; it keeps its number but bills nothing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voided: Option<Void>,
+    /// `[payees]` entry collecting payment instead of the company (e.g., a factor)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payee: Option<String>,
+    /// Currency and payment terms the client overrode when it was issued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_days: Option<u32>,
+    /// Invoice-currency value of one unit of each foreign currency its
+    /// items were priced in, so regenerating converts at the same rates
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub exchange_rates: BTreeMap<String, f64>,
+    /// Custom fields printed on the invoice (e.g., "Cost Center" → "1234")
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+    /// Spec file the invoice was generated from, relative to the config
+    /// dir (e.g., "specs/INV-2026-0001.toml"); regenerate rebuilds from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec: Option<String>,
+    /// contracts.toml entry the invoice bills against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+    /// Discount taken off the subtotal before tax
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discount: Option<Discount>,
+    /// Note printed at the bottom of the invoice (e.g., a PO number or
+    /// special terms); unlike `notes`, the client sees it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    /// Status as of the last `invoice refresh-status`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub standing: Option<Standing>,
+    /// The stored PDF was rendered with --deterministic, so rendering the
+    /// same data again reproduces it byte for byte
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deterministic: bool,
 }
 
 impl HistoryEntry {
+    /// Artifact file names, falling back to `file` for older entries
+    pub fn artifacts(&self) -> Vec<&str> {
+        if self.files.is_empty() {
+            vec![self.file.as_str()]
+        } else {
+            self.files.iter().map(String::as_str).collect()
+        }
+    }
+
+    /// Every file in the output dir belonging to this invoice: artifacts,
+    /// archived revisions and the payment plan agreement
+    pub fn owned_files(&self) -> Vec<&str> {
+        let mut files = self.artifacts();
+        files.extend(
+            self.revisions
+                .iter()
+                .flat_map(|r| r.files.iter().map(String::as_str)),
+        );
+        files.extend(self.payment_plan.iter().map(|p| p.file.as_str()));
+        files
+    }
+
+    /// Most recent follow-up contact, if any
+    pub fn last_followup(&self) -> Option<&FollowUp> {
+        self.followups.iter().max_by_key(|f| f.date)
+    }
+
+    /// Highest dunning level reached (0 if no reminder was sent)
+    pub fn dunning_level(&self) -> u32 {
+        self.reminders.iter().map(|r| r.level).max().unwrap_or(0)
+    }
+
     /// Sum of all recorded payments
     pub fn paid_amount(&self) -> f64 {
         self.payments.iter().map(|p| p.amount).sum()
     }
 
-    /// Remaining balance on this invoice
+    /// Whether `total` counts as billed: not superseded (the consolidated
+    /// invoice carries it) and not voided
+    pub fn is_billed(&self) -> bool {
+        self.superseded_by.is_none() && self.voided.is_none()
+    }
+
+    /// Remaining balance on this invoice (zero once superseded, since the
+    /// consolidated invoice carries it, or voided)
     pub fn outstanding(&self) -> f64 {
+        if !self.is_billed() {
+            return 0.0;
+        }
         self.total - self.paid_amount()
     }
 
+    /// Share of `total` (and of each payment) that is revenue rather than
+    /// tax. Tax held back with a retention is left out proportionally;
+    /// entries without a stored tax count in full.
+    pub fn net_ratio(&self) -> f64 {
+        let Some(tax) = self.tax else {
+            return 1.0;
+        };
+        let gross = self.total + self.retention.as_ref().map_or(0.0, |r| r.amount);
+        if gross <= 0.0 {
+            return 1.0;
+        }
+        1.0 - tax / gross
+    }
+
+    /// Whether nothing more is expected on this invoice (paid, superseded or void)
+    pub fn is_settled(&self) -> bool {
+        matches!(
+            self.status(),
+            PaymentStatus::Paid | PaymentStatus::Superseded | PaymentStatus::Void
+        )
+    }
+
     /// Auto-derived payment status
     pub fn status(&self) -> PaymentStatus {
         let paid = self.paid_amount();
-        if paid <= 0.0 {
+        if self.superseded_by.is_some() {
+            PaymentStatus::Superseded
+        } else if self.voided.is_some() {
+            PaymentStatus::Void
+        } else if paid <= 0.0 {
             PaymentStatus::Unpaid
         } else if paid >= self.total {
             PaymentStatus::Paid
@@ -108,6 +713,62 @@ impl<'de> Deserialize<'de> for HistoryEntry {
             payments: Vec<Payment>,
             #[serde(default)]
             items: Vec<String>,
+            #[serde(default)]
+            files: Vec<String>,
+            #[serde(default)]
+            revisions: Vec<Revision>,
+            #[serde(default)]
+            reminders: Vec<Reminder>,
+            #[serde(default)]
+            payment_plan: Option<PaymentPlan>,
+            #[serde(default)]
+            series: Option<String>,
+            #[serde(default)]
+            locked: bool,
+            #[serde(default)]
+            period: Option<ServicePeriod>,
+            #[serde(default)]
+            notes: Vec<Note>,
+            #[serde(default)]
+            followups: Vec<FollowUp>,
+            #[serde(default)]
+            superseded_by: Option<String>,
+            #[serde(default)]
+            display_number: Option<String>,
+            #[serde(default)]
+            retention: Option<Retention>,
+            #[serde(default)]
+            released_retention: Vec<String>,
+            #[serde(default)]
+            deposit: Option<Deposit>,
+            #[serde(default)]
+            deducted_deposits: Vec<String>,
+            #[serde(default)]
+            tax: Option<f64>,
+            #[serde(default)]
+            voided: Option<Void>,
+            #[serde(default)]
+            payee: Option<String>,
+            #[serde(default)]
+            currency: Option<String>,
+            #[serde(default)]
+            due_days: Option<u32>,
+            #[serde(default)]
+            exchange_rates: BTreeMap<String, f64>,
+            #[serde(default)]
+            fields: BTreeMap<String, String>,
+            #[serde(default)]
+            spec: Option<String>,
+            #[serde(default)]
+            contract: Option<String>,
+            #[serde(default)]
+            discount: Option<Discount>,
+            #[serde(default)]
+            memo: Option<String>,
+            #[serde(default)]
+            standing: Option<Standing>,
+            #[serde(default)]
+            deterministic: bool,
         }
 
         let raw = Raw::deserialize(deserializer)?;
@@ -118,6 +779,7 @@ impl<'de> Deserialize<'de> for HistoryEntry {
                 Some(true) => vec![Payment {
                     amount: raw.total,
                     date: raw.date,
+                    credit: None,
                 }],
                 _ => vec![],
             }
@@ -133,6 +795,34 @@ impl<'de> Deserialize<'de> for HistoryEntry {
             file: raw.file,
             payments,
             items: raw.items,
+            files: raw.files,
+            revisions: raw.revisions,
+            reminders: raw.reminders,
+            payment_plan: raw.payment_plan,
+            series: raw.series,
+            locked: raw.locked,
+            period: raw.period,
+            notes: raw.notes,
+            followups: raw.followups,
+            superseded_by: raw.superseded_by,
+            display_number: raw.display_number,
+            retention: raw.retention,
+            released_retention: raw.released_retention,
+            deposit: raw.deposit,
+            deducted_deposits: raw.deducted_deposits,
+            tax: raw.tax,
+            voided: raw.voided,
+            payee: raw.payee,
+            currency: raw.currency,
+            due_days: raw.due_days,
+            exchange_rates: raw.exchange_rates,
+            fields: raw.fields,
+            spec: raw.spec,
+            contract: raw.contract,
+            discount: raw.discount,
+            memo: raw.memo,
+            standing: raw.standing,
+            deterministic: raw.deterministic,
         })
     }
 }
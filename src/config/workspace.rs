@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::{
+    load_clients, load_config, load_items, load_state, resolve_output_dir, save_state, Client,
+    Config, Item, State,
+};
+use crate::error::Result;
+
+/// A config directory loaded once for a whole command. Steps a command
+/// chains (generating then opening, regenerating every invoice) share it,
+/// so each file is read once and every step sees the same data, including
+/// the state changes made by the steps before it.
+#[derive(Debug)]
+pub struct Workspace {
+    pub dir: PathBuf,
+    pub config: Config,
+    pub clients: HashMap<String, Client>,
+    pub items: HashMap<String, Item>,
+    pub state: State,
+}
+
+impl Workspace {
+    /// Load config.toml, clients.toml, items.toml and state.toml from `dir`
+    pub fn load(dir: &Path) -> Result<Self> {
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            config: load_config(dir)?,
+            clients: load_clients(dir)?,
+            items: load_items(dir)?,
+            state: load_state(dir)?,
+        })
+    }
+
+    /// Write the state back to state.toml
+    pub fn save_state(&self) -> Result<()> {
+        save_state(&self.dir, &self.state)
+    }
+
+    /// Directory generated documents are written to
+    pub fn output_dir(&self) -> PathBuf {
+        resolve_output_dir(&self.config.pdf.output_dir, &self.dir)
+    }
+}
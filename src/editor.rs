@@ -0,0 +1,35 @@
+use std::path::Path;
+use std::process::Command;
+
+use crate::error::{InvoiceError, Result};
+
+/// The user's preferred editor command: $VISUAL, then $EDITOR, then `vi`
+pub fn editor_command() -> String {
+    std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Open a file in the user's editor and wait for it to exit.
+/// The editor command may include arguments (e.g., "code --wait").
+pub fn edit_file(path: &Path) -> Result<()> {
+    let editor = editor_command();
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| InvoiceError::Editor(format!("could not launch '{editor}': {e}")))?;
+
+    if !status.success() {
+        return Err(InvoiceError::Editor(format!(
+            "'{editor}' exited with {status}"
+        )));
+    }
+
+    Ok(())
+}
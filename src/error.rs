@@ -19,6 +19,21 @@ pub enum InvoiceError {
     #[error("Client '{0}' not found in clients.toml")]
     ClientNotFound(String),
 
+    #[error("Client '{0}' already exists in clients.toml")]
+    ClientExists(String),
+
+    #[error("Invalid client id '{0}'. Use letters, digits, '-' and '_' only.")]
+    InvalidClientId(String),
+
+    #[error(
+        "Client '{client}' is missing details the invoice requires:\n{}\nComplete the record with 'invoice clients edit'.",
+        .missing.iter().map(|m| format!("  [ ] {m}")).collect::<Vec<_>>().join("\n")
+    )]
+    IncompleteClient {
+        client: String,
+        missing: Vec<String>,
+    },
+
     #[error("Item '{0}' not found in items.toml")]
     ItemNotFound(String),
 
@@ -29,7 +44,7 @@ pub enum InvoiceError {
         reason: String,
     },
 
-    #[error("Invalid item format '{0}'. Expected 'item:quantity' (e.g., 'consulting:8')")]
+    #[error("Invalid item format '{0}'. Expected 'item:quantity' or 'item:description:quantity' (e.g., 'consulting:8', 'design:\"Logo: v2\":3')")]
     InvalidItemFormat(String),
 
     #[error("No items specified. Use --item <name>:<quantity> to add line items.")]
@@ -38,9 +53,19 @@ pub enum InvoiceError {
     #[error("Typst not found. Install it from https://typst.app/ or run: cargo install typst-cli")]
     TypstNotFound,
 
+    #[error("Template not found: {0}")]
+    TemplateNotFound(PathBuf),
+
     #[error("Failed to generate PDF: {0}")]
     PdfGeneration(String),
 
+    #[error("Typst could not compile the {template}:\n{errors}\nTemplate data kept for inspection at {}", data.display())]
+    TypstCompile {
+        template: String,
+        errors: String,
+        data: PathBuf,
+    },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -56,12 +81,95 @@ pub enum InvoiceError {
     #[error("Invoice '{0}' has no stored items (generated before item tracking was added)")]
     NoStoredItems(String),
 
+    #[error("Invoice '{0}' is locked. Pass --force-unlock to modify it.")]
+    InvoiceLocked(String),
+
+    #[error("Invoice '{0}' is already in the trash")]
+    AlreadyInTrash(String),
+
+    #[error(
+        "Invoice '{0}' is not in the trash. Use 'invoice trash list' to see trashed invoices."
+    )]
+    NotInTrash(String),
+
+    #[error("Invoice number '{0}' is already in use")]
+    DuplicateInvoiceNumber(String),
+
+    #[error("Invalid invoice number '{number}': {reason}")]
+    InvalidInvoiceNumber { number: String, reason: String },
+
+    #[error("Invoice {existing} already bills '{client}' for the same items this month. Use --allow-duplicate to generate it anyway.")]
+    DuplicateContent { existing: String, client: String },
+
+    #[error("Numbering series '{0}' not found. Add a [series.{0}] section to config.toml.")]
+    UnknownSeries(String),
+
+    #[error("No late interest rate configured. Add an [interest] section to config.toml or pass --rate.")]
+    InterestNotConfigured,
+
+    #[error("Invoice '{0}' has no outstanding balance")]
+    NothingOutstanding(String),
+
+    #[error("Invoice '{0}' has no payment plan. Pass --installments to create one.")]
+    NoPaymentPlan(String),
+
+    #[error("{0} file problem(s) found. Run 'invoice verify-files --update' to accept the current files.")]
+    VerificationFailed(usize),
+
     #[error("Invoice file not found: {0}")]
     InvoiceFileNotFound(PathBuf),
 
-    #[error("Payment would exceed invoice total (max ${max:.2} remaining)")]
+    #[error("No {format} artifact recorded for {invoice}")]
+    ArtifactNotFound { invoice: String, format: String },
+
+    #[error("Payment would exceed invoice total for {invoice} (max ${max:.2} remaining). Use --as-credit to keep the excess as client credit.")]
     OverPayment { invoice: String, max: f64 },
 
+    #[error("Invoice {invoice} was superseded by {by}. Use {by} instead.")]
+    Superseded { invoice: String, by: String },
+
+    #[error("Invoice '{0}' is void")]
+    Voided(String),
+
+    #[error(
+        "Invoice '{0}' has payments recorded. Remove them with 'remove-payment' before voiding it."
+    )]
+    VoidWithPayments(String),
+
+    #[error("Invoice '{0}' has no unreleased retention")]
+    NoRetentionHeld(String),
+
+    #[error("Project '{0}' not found. Add a [projects.{0}] section to config.toml.")]
+    ProjectNotFound(String),
+
+    #[error("'invoice {0}' would change invoice data, which read-only mode doesn't allow")]
+    ReadOnly(String),
+
+    #[error("Scheduled command '{name}' is invalid: {reason}")]
+    InvalidSchedule { name: String, reason: String },
+
+    #[error("No exchange rate from {from} to {to}. Pass --rate {from}=<rate> to set one.")]
+    NoExchangeRate { from: String, to: String },
+
+    #[error("Report '{0}' not found. Run 'invoice report list' to see generated reports.")]
+    ReportNotFound(String),
+
+    #[error("Contract '{0}' not found in contracts.toml")]
+    ContractNotFound(String),
+
+    #[error("Contract '{contract}' belongs to client '{owner}', not '{client}'")]
+    ContractClientMismatch {
+        contract: String,
+        owner: String,
+        client: String,
+    },
+
+    #[error("Payee '{0}' not found. Add a [payees.{0}] section to config.toml.")]
+    PayeeNotFound(String),
+
+    #[error("Cannot consolidate: {0}")]
+    Consolidate(String),
+
     #[error("No payments recorded for {0}")]
     NoPayments(String),
 
@@ -72,8 +180,66 @@ pub enum InvoiceError {
         count: usize,
     },
 
-    #[error("Payment amount must be greater than zero")]
+    #[error("Payment amount must be a finite number greater than zero")]
     InvalidPaymentAmount,
+
+    #[error("Unknown global config key '{0}'. Supported keys: config_dir")]
+    UnknownConfigKey(String),
+
+    #[error("Note text cannot be empty")]
+    EmptyNote,
+
+    #[error("Could not open viewer: {0}")]
+    Viewer(String),
+
+    #[error("Could not copy to clipboard: {0}")]
+    Clipboard(String),
+
+    #[error("Could not send email: {0}")]
+    Mail(String),
+
+    #[error("Could not show notification: {0}")]
+    Notification(String),
+
+    #[error("Self-update failed: {0}")]
+    Update(String),
+
+    #[error("{failed} of {total} invoice(s) failed")]
+    BatchFailed { failed: usize, total: usize },
+
+    #[error("Editor failed: {0}")]
+    Editor(String),
+
+    #[error("Could not run the invoice picker: {0}")]
+    Picker(String),
+
+    #[error("Invalid invoice draft {path}: {reason}")]
+    InvalidDraft { path: PathBuf, reason: String },
+
+    #[error("Invalid invoice spec: {0}")]
+    InvalidSpec(String),
+
+    #[error(
+        "Import profile '{0}' not found. Add an [import.profiles.{0}] section to config.toml."
+    )]
+    UnknownImportProfile(String),
+
+    #[error(
+        "No item to bill the imported hours as. Pass --item or set item in the import profile."
+    )]
+    ImportItemMissing,
+
+    #[error("Invalid timesheet at line {line}: {reason}")]
+    InvalidTimesheet { line: usize, reason: String },
+
+    #[error("Client '{0}' has no retainer. Add retainer = {{ included_hours = ..., overage_item = \"...\" }} to it in clients.toml.")]
+    NoRetainer(String),
+
+    #[error(
+        "Stopped by --strict:\n{}",
+        .0.iter().map(|c| format!("  - {c}")).collect::<Vec<_>>().join("\n")
+    )]
+    Strict(Vec<String>),
 }
 
 pub type Result<T> = std::result::Result<T, InvoiceError>;
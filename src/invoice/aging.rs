@@ -0,0 +1,141 @@
+//! Accounts receivable aging: what each client still owes, split by how
+//! far past due it is.
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use super::digest::{aging_bucket, AgingBucket};
+use super::dunning::days_overdue;
+use crate::config::{
+    load_clients, load_config, load_state, resolve_output_dir, Client, Company, Config,
+    HistoryEntry, SymbolPosition,
+};
+use crate::error::Result;
+use crate::pdf::generate_aging_pdf;
+
+/// Outstanding balances by days past due. Invoices not yet due count
+/// toward `days_0_30`.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct AgingColumns {
+    pub days_0_30: f64,
+    pub days_31_60: f64,
+    pub days_61_90: f64,
+    pub over_90: f64,
+    pub total: f64,
+}
+
+impl AgingColumns {
+    fn add(&mut self, days_overdue: i64, amount: f64) {
+        let column = match aging_bucket(days_overdue) {
+            AgingBucket::Current | AgingBucket::Days1To30 => &mut self.days_0_30,
+            AgingBucket::Days31To60 => &mut self.days_31_60,
+            AgingBucket::Days61To90 => &mut self.days_61_90,
+            AgingBucket::Over90 => &mut self.over_90,
+        };
+        *column += amount;
+        self.total += amount;
+    }
+
+    /// The columns in order, total last
+    pub fn values(&self) -> [f64; 5] {
+        [
+            self.days_0_30,
+            self.days_31_60,
+            self.days_61_90,
+            self.over_90,
+            self.total,
+        ]
+    }
+}
+
+/// One client's line on the aging report
+#[derive(Debug, Serialize)]
+pub struct AgingRow {
+    pub client_id: String,
+    /// Name from clients.toml, or the identifier for clients no longer there
+    pub client: String,
+    pub invoices: usize,
+    #[serde(flatten)]
+    pub columns: AgingColumns,
+}
+
+/// Complete data for rendering the aging report PDF
+#[derive(Debug, Serialize)]
+pub struct AgingReport {
+    pub date: String,
+    pub company: Company,
+    /// Client owed the most first
+    pub rows: Vec<AgingRow>,
+    pub totals: AgingColumns,
+    pub currency_symbol: String,
+    pub symbol_position: SymbolPosition,
+    pub symbol_space: bool,
+}
+
+/// Outstanding balances per client on `today`, bucketed by days past due
+pub fn aging_report(
+    history: &[HistoryEntry],
+    config: &Config,
+    clients: &HashMap<String, Client>,
+    today: NaiveDate,
+) -> AgingReport {
+    let mut by_client: BTreeMap<&str, AgingRow> = BTreeMap::new();
+    let mut totals = AgingColumns::default();
+    let open = history
+        .iter()
+        .filter(|e| e.date <= today && !e.is_settled() && e.outstanding() > 0.0);
+    for entry in open {
+        let days = days_overdue(entry, config, today);
+        let row = by_client.entry(&entry.client).or_insert_with(|| AgingRow {
+            client_id: entry.client.clone(),
+            client: clients
+                .get(&entry.client)
+                .map_or(&entry.client, |c| &c.name)
+                .clone(),
+            invoices: 0,
+            columns: AgingColumns::default(),
+        });
+        row.invoices += 1;
+        row.columns.add(days, entry.outstanding());
+        totals.add(days, entry.outstanding());
+    }
+
+    let mut rows: Vec<AgingRow> = by_client.into_values().collect();
+    rows.sort_by(|a, b| b.columns.total.total_cmp(&a.columns.total));
+
+    AgingReport {
+        date: today.format("%B %d, %Y").to_string(),
+        company: config.company.clone(),
+        rows,
+        totals,
+        currency_symbol: config.invoice.currency_symbol.clone(),
+        symbol_position: config.invoice.symbol_position,
+        symbol_space: config.invoice.symbol_space,
+    }
+}
+
+/// Render the aging report on `today` to `output_dir/AGING-<date>.pdf`. A
+/// `templates/aging.typ` in the config directory replaces the built-in
+/// template. Returns None when nothing is outstanding.
+pub fn create_aging_report(
+    cfg_dir: &Path,
+    today: NaiveDate,
+) -> Result<Option<(AgingReport, PathBuf)>> {
+    let config = load_config(cfg_dir)?;
+    let clients = load_clients(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+
+    let report = aging_report(&state.history, &config, &clients, today);
+    if report.rows.is_empty() {
+        return Ok(None);
+    }
+
+    let output_dir = resolve_output_dir(&config.pdf.output_dir, cfg_dir);
+    std::fs::create_dir_all(&output_dir)?;
+    let path = output_dir.join(format!("AGING-{}.pdf", today.format("%Y-%m-%d")));
+    generate_aging_pdf(&report, &path, Some(&cfg_dir.join("templates")))?;
+
+    Ok(Some((report, path)))
+}
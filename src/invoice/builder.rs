@@ -0,0 +1,291 @@
+//! Build invoices in code, without a config directory.
+//!
+//! ```no_run
+//! use invoice::{generate_pdf_from_data, Client, Company, InvoiceBuilder};
+//!
+//! let company = Company {
+//!     name: "Acme Consulting".into(),
+//!     ..Default::default()
+//! };
+//! let client = Client {
+//!     name: "Globex".into(),
+//!     email: "ap@globex.example".into(),
+//!     ..Default::default()
+//! };
+//!
+//! let data = InvoiceBuilder::new("INV-2026-0001", company, client)
+//!     .item("Technical Consulting", 8.0, "hour", 150.0)
+//!     .discount("Returning client", 100.0)
+//!     .tax_rate(0.0825)
+//!     .build()?;
+//! generate_pdf_from_data(&data, "INV-2026-0001.pdf".as_ref())?;
+//! # Ok::<(), invoice::InvoiceError>(())
+//! ```
+
+use std::collections::BTreeMap;
+
+use chrono::{Local, NaiveDate};
+
+use super::generator::{
+    compute_totals, group_by_kind, tax_percent, InvoiceData, InvoiceLineItem, Totals,
+};
+use super::retention::retention_amount;
+use crate::config::{
+    Client, Company, Discount, Item, ItemKind, PayeeSettings, ServicePeriod, SymbolPosition,
+};
+use crate::error::{InvoiceError, Result};
+use crate::pdf::SCHEMA_VERSION;
+
+/// Step-by-step construction of [`InvoiceData`], computing totals and dates
+/// the same way `generate` does
+#[derive(Debug, Clone)]
+pub struct InvoiceBuilder {
+    number: String,
+    display_number: Option<String>,
+    company: Company,
+    client: Client,
+    items: Vec<InvoiceLineItem>,
+    date: Option<NaiveDate>,
+    due_days: u32,
+    taxes: Vec<(String, f64)>,
+    retention_percent: f64,
+    discount: Option<Discount>,
+    currency_code: String,
+    currency_symbol: String,
+    symbol_position: SymbolPosition,
+    symbol_space: bool,
+    payment_terms: Option<String>,
+    period: Option<ServicePeriod>,
+    payee: Option<PayeeSettings>,
+    fields: BTreeMap<String, String>,
+    memo: Option<String>,
+}
+
+impl InvoiceBuilder {
+    /// Start an invoice dated today, due in 30 days, in dollars and without tax
+    pub fn new(number: impl Into<String>, company: Company, client: Client) -> Self {
+        Self {
+            number: number.into(),
+            display_number: None,
+            company,
+            client,
+            items: Vec::new(),
+            date: None,
+            due_days: 30,
+            taxes: Vec::new(),
+            retention_percent: 0.0,
+            discount: None,
+            currency_code: "USD".to_string(),
+            currency_symbol: "$".to_string(),
+            symbol_position: SymbolPosition::default(),
+            symbol_space: false,
+            payment_terms: None,
+            period: None,
+            payee: None,
+            fields: BTreeMap::new(),
+            memo: None,
+        }
+    }
+
+    /// Add a service line item
+    pub fn item(self, description: &str, quantity: f64, unit: &str, rate: f64) -> Self {
+        self.line(ItemKind::Service, description, quantity, unit, rate)
+    }
+
+    /// Add a line item of the given kind
+    pub fn line(
+        mut self,
+        kind: ItemKind,
+        description: &str,
+        quantity: f64,
+        unit: &str,
+        rate: f64,
+    ) -> Self {
+        self.items.push(InvoiceLineItem {
+            description: description.to_string(),
+            quantity,
+            unit: unit.to_string(),
+            rate,
+            amount: rate * quantity,
+            kind,
+            original: None,
+            period: None,
+            taxable: true,
+        });
+        self
+    }
+
+    /// Add a reimbursable expense passed through at cost
+    pub fn expense(self, description: &str, amount: f64) -> Self {
+        self.line(ItemKind::Expense, description, 1.0, "expense", amount)
+    }
+
+    /// Add a line item priced from a catalog entry (as in items.toml)
+    pub fn catalog_item(self, item: &Item, quantity: f64) -> Self {
+        let builder = self.line(
+            item.kind,
+            &item.description,
+            quantity,
+            &item.unit,
+            item.rate,
+        );
+        if item.taxable {
+            builder
+        } else {
+            builder.exempt()
+        }
+    }
+
+    /// Exempt the line added last from tax
+    pub fn exempt(mut self) -> Self {
+        if let Some(line) = self.items.last_mut() {
+            line.taxable = false;
+        }
+        self
+    }
+
+    /// Take `amount` off the subtotal, shown as its own negative line
+    pub fn discount(self, description: &str, amount: f64) -> Self {
+        self.item(description, 1.0, "discount", -amount.abs())
+    }
+
+    /// Number in the client's own reference scheme, shown next to `number`
+    pub fn display_number(mut self, display_number: &str) -> Self {
+        self.display_number = Some(display_number.to_string());
+        self
+    }
+
+    /// Invoice date (default: today)
+    pub fn date(mut self, date: NaiveDate) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    /// Days until payment is due (default: 30)
+    pub fn due_days(mut self, days: u32) -> Self {
+        self.due_days = days;
+        self
+    }
+
+    /// Tax rate as a fraction (e.g., 0.0825 for 8.25%), replacing any
+    /// taxes added before
+    pub fn tax_rate(mut self, rate: f64) -> Self {
+        self.taxes = vec![("Tax".to_string(), rate)];
+        self
+    }
+
+    /// Add a named tax, listed separately (e.g., "GST", 0.05)
+    pub fn tax(mut self, name: &str, rate: f64) -> Self {
+        self.taxes.push((name.to_string(), rate));
+        self
+    }
+
+    /// Take a percentage or fixed amount off the subtotal before tax, shown
+    /// in the totals rather than as a line
+    pub fn subtotal_discount(mut self, discount: Discount) -> Self {
+        self.discount = Some(discount);
+        self
+    }
+
+    /// Hold back this percentage of the total until completion (e.g., 10.0)
+    pub fn retention(mut self, percent: f64) -> Self {
+        self.retention_percent = percent;
+        self
+    }
+
+    /// Currency symbol and where it goes (e.g., "€" after the amount, spaced)
+    pub fn currency(mut self, symbol: &str, position: SymbolPosition, space: bool) -> Self {
+        self.currency_symbol = symbol.to_string();
+        self.symbol_position = position;
+        self.symbol_space = space;
+        self
+    }
+
+    /// ISO 4217 code of the amounts (default: "USD")
+    pub fn currency_code(mut self, code: &str) -> Self {
+        self.currency_code = code.to_string();
+        self
+    }
+
+    /// Payment terms line (default: "Net <due days> days")
+    pub fn payment_terms(mut self, terms: &str) -> Self {
+        self.payment_terms = Some(terms.to_string());
+        self
+    }
+
+    /// When the invoiced services were rendered
+    pub fn period(mut self, period: ServicePeriod) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    /// Direct payment to someone other than the company (e.g., a factor)
+    pub fn payee(mut self, payee: PayeeSettings) -> Self {
+        self.payee = Some(payee);
+        self
+    }
+
+    /// Add a custom field (e.g., "Cost Center", "1234")
+    pub fn field(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.insert(name.into(), value.into());
+        self
+    }
+
+    /// Note printed in a Notes section at the bottom (e.g., a PO number)
+    pub fn memo(mut self, memo: &str) -> Self {
+        self.memo = Some(memo.to_string());
+        self
+    }
+
+    /// Compute totals and dates. Fails if no line items were added.
+    pub fn build(self) -> Result<InvoiceData> {
+        if self.items.is_empty() {
+            return Err(InvoiceError::NoItems);
+        }
+
+        let Totals {
+            subtotal,
+            discount,
+            tax_amount,
+            taxes,
+            rounding_adjustment,
+            total,
+        } = compute_totals(&self.items, self.discount, &self.taxes);
+
+        let date = self.date.unwrap_or_else(|| Local::now().date_naive());
+        let due = date + chrono::Duration::days(self.due_days as i64);
+
+        Ok(InvoiceData {
+            schema_version: SCHEMA_VERSION,
+            number: self.number,
+            display_number: self.display_number,
+            date: date.format("%B %d, %Y").to_string(),
+            due_date: due.format("%B %d, %Y").to_string(),
+            company: self.company,
+            client: self.client,
+            items: group_by_kind(self.items),
+            subtotal,
+            discount_percent: self.discount.map_or(0.0, Discount::percent),
+            discount,
+            tax_percent: tax_percent(&self.taxes),
+            tax_amount,
+            taxes,
+            rounding_adjustment,
+            total,
+            retention_percent: self.retention_percent,
+            retention: retention_amount(total, self.retention_percent),
+            currency: self.currency_code,
+            currency_symbol: self.currency_symbol,
+            symbol_position: self.symbol_position,
+            symbol_space: self.symbol_space,
+            due_days: self.due_days,
+            payment_terms: self
+                .payment_terms
+                .unwrap_or_else(|| format!("Net {} days", self.due_days)),
+            service_period: self.period.map(|p| p.to_string()),
+            payee: self.payee,
+            fields: self.fields,
+            memo: self.memo,
+        })
+    }
+}
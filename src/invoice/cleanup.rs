@@ -0,0 +1,51 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{load_config, load_state, resolve_output_dir};
+use crate::error::Result;
+
+/// Documents that aren't recorded in state but aren't leftovers either:
+/// reports from before report numbering (`REPORT-<client>-<date>.pdf`),
+/// statements (`STATEMENT-<client>-<date>.pdf`) and aging reports
+/// (`AGING-<date>.pdf`)
+const GENERATED_PREFIXES: &[&str] = &["REPORT-", "STATEMENT-", "AGING-"];
+
+/// PDFs in the output directory that no history entry references (old
+/// drafts, renamed files), sorted by name
+pub fn orphaned_pdfs(cfg_dir: &Path) -> Result<Vec<PathBuf>> {
+    let config = load_config(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+    let output_dir = resolve_output_dir(&config.pdf.output_dir, cfg_dir);
+    if !output_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let referenced: BTreeSet<&str> = state
+        .history
+        .iter()
+        .flat_map(|entry| entry.owned_files())
+        .chain(state.reports.iter().map(|report| report.file.as_str()))
+        .collect();
+
+    let mut orphans = Vec::new();
+    for dir_entry in fs::read_dir(&output_dir)? {
+        let path = dir_entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let is_pdf = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+        if path.is_file()
+            && is_pdf
+            && !GENERATED_PREFIXES.iter().any(|p| name.starts_with(p))
+            && !referenced.contains(name)
+        {
+            orphans.push(path);
+        }
+    }
+    orphans.sort();
+
+    Ok(orphans)
+}
@@ -0,0 +1,89 @@
+use super::generator::{generate_invoice, GenerateOptions, GeneratedInvoice};
+use crate::config::{ServicePeriod, Workspace};
+use crate::error::{InvoiceError, Result};
+
+/// Bill several unpaid invoices of one client on a single new invoice.
+///
+/// The new invoice repeats the originals' line items (priced from the
+/// current catalog) and covers the span of their service periods when all
+/// of them have one. The originals stay in history marked as superseded by
+/// it, so they no longer count as outstanding. Returns the new invoice.
+pub fn consolidate_invoices(
+    ws: &mut Workspace,
+    client_id: &str,
+    invoice_numbers: &[String],
+    options: &GenerateOptions,
+) -> Result<GeneratedInvoice> {
+    if invoice_numbers.len() < 2 {
+        return Err(InvoiceError::Consolidate(
+            "select at least two invoices".to_string(),
+        ));
+    }
+
+    let state = &ws.state;
+    let mut items = Vec::new();
+    let mut periods = Vec::new();
+    for number in invoice_numbers {
+        let entry = state
+            .history
+            .iter()
+            .find(|e| &e.number == number)
+            .ok_or_else(|| InvoiceError::InvoiceNotFound(number.clone()))?;
+
+        if entry.client != client_id {
+            return Err(InvoiceError::Consolidate(format!(
+                "{} belongs to '{}', not '{}'",
+                number, entry.client, client_id
+            )));
+        }
+        if let Some(by) = &entry.superseded_by {
+            return Err(InvoiceError::Superseded {
+                invoice: number.clone(),
+                by: by.clone(),
+            });
+        }
+        if entry.voided.is_some() {
+            return Err(InvoiceError::Voided(number.clone()));
+        }
+        if !entry.payments.is_empty() {
+            return Err(InvoiceError::Consolidate(format!(
+                "{number} already has payments; only unpaid invoices can be merged"
+            )));
+        }
+        if entry.items.is_empty() {
+            return Err(InvoiceError::NoStoredItems(number.clone()));
+        }
+
+        items.extend(entry.items.iter().cloned());
+        periods.push(entry.period);
+    }
+
+    let mut options = options.clone();
+    // The originals bill the same items; superseding them is the point
+    options.allow_duplicate = true;
+    if options.period.is_none() {
+        options.period = periods
+            .iter()
+            .copied()
+            .collect::<Option<Vec<ServicePeriod>>>()
+            .and_then(|periods| {
+                Some(ServicePeriod {
+                    from: periods.iter().map(|p| p.from).min()?,
+                    to: periods.iter().map(|p| p.to).max()?,
+                })
+            });
+    }
+
+    let consolidated = generate_invoice(ws, client_id, &items, &options)?;
+    for entry in ws
+        .state
+        .history
+        .iter_mut()
+        .filter(|e| invoice_numbers.contains(&e.number))
+    {
+        entry.superseded_by = Some(consolidated.number.clone());
+    }
+    ws.save_state()?;
+
+    Ok(consolidated)
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::config::{Contract, HistoryEntry};
+
+/// A contract's value against what its linked invoices have billed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractStatus {
+    pub id: String,
+    pub client: String,
+    pub description: Option<String>,
+    pub value: f64,
+    pub start: NaiveDate,
+    pub end: Option<NaiveDate>,
+    /// Billed invoices linked to the contract, before tax
+    pub billed: f64,
+    pub invoices: usize,
+}
+
+impl ContractStatus {
+    /// Amount left to bill; negative once the value is exceeded
+    pub fn remaining(&self) -> f64 {
+        self.value - self.billed
+    }
+
+    /// Whether billing is over the contract value (ignoring sub-cent noise)
+    pub fn is_over(&self) -> bool {
+        self.remaining() < -0.005
+    }
+}
+
+/// What an invoice bills against its contract: its total before tax
+pub fn contract_amount(entry: &HistoryEntry) -> f64 {
+    entry.total - entry.tax.unwrap_or(0.0)
+}
+
+/// Billing status of every contract, sorted by id
+pub fn contract_status(
+    contracts: &BTreeMap<String, Contract>,
+    history: &[HistoryEntry],
+) -> Vec<ContractStatus> {
+    contracts
+        .iter()
+        .map(|(id, contract)| {
+            let linked: Vec<&HistoryEntry> = history
+                .iter()
+                .filter(|e| e.is_billed() && e.contract.as_deref() == Some(id.as_str()))
+                .collect();
+            ContractStatus {
+                id: id.clone(),
+                client: contract.client.clone(),
+                description: contract.description.clone(),
+                value: contract.value,
+                start: contract.start,
+                end: contract.end,
+                billed: linked.iter().map(|e| contract_amount(e)).sum(),
+                invoices: linked.len(),
+            }
+        })
+        .collect()
+}
+
+/// The one contract of `client` in effect on `date`, if exactly one is
+pub fn active_contract<'a>(
+    contracts: &'a BTreeMap<String, Contract>,
+    client: &str,
+    date: NaiveDate,
+) -> Option<&'a str> {
+    let mut active = contracts
+        .iter()
+        .filter(|(_, c)| c.client == client && c.is_active(date));
+    match (active.next(), active.next()) {
+        (Some((id, _)), None) => Some(id),
+        _ => None,
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use super::generator::InvoiceLineItem;
+use crate::config::{HistoryEntry, ItemKind, ProjectSettings};
+use crate::error::{InvoiceError, Result};
+use crate::money::round_cents;
+
+/// An advance to bill on a fixed-price project
+#[derive(Debug, Clone)]
+pub struct DepositRequest {
+    pub project: String,
+    /// Percentage of the project's fixed price (e.g., 40.0)
+    pub percent: f64,
+}
+
+/// Deposit invoices of `client` for `project` not yet deducted from a later
+/// invoice
+pub fn pending_deposits<'a>(
+    history: &'a [HistoryEntry],
+    client: &str,
+    project: &str,
+) -> Vec<&'a HistoryEntry> {
+    history
+        .iter()
+        .filter(|e| e.client == client)
+        .filter(|e| {
+            e.deposit
+                .as_ref()
+                .is_some_and(|d| d.deducted_by.is_none() && d.project == project)
+        })
+        .collect()
+}
+
+/// Deposits to deduct from an invoice billing `billed` (amount per project).
+/// Projects whose pending deposits exceed what this invoice bills for them
+/// are returned separately and left for a later invoice.
+pub(crate) fn deductible_deposits(
+    history: &[HistoryEntry],
+    client: &str,
+    billed: &BTreeMap<String, f64>,
+) -> (Vec<String>, Vec<(String, f64)>) {
+    let mut deducted = Vec::new();
+    let mut deferred = Vec::new();
+    for (project, amount) in billed {
+        let pending = pending_deposits(history, client, project);
+        let deposits: f64 = pending
+            .iter()
+            .filter_map(|e| e.deposit.as_ref())
+            .map(|d| d.amount)
+            .sum();
+        if pending.is_empty() {
+            continue;
+        }
+        if round_cents(deposits) > round_cents(*amount) {
+            deferred.push((project.clone(), deposits));
+        } else {
+            deducted.extend(pending.iter().map(|e| e.number.clone()));
+        }
+    }
+    (deducted, deferred)
+}
+
+/// The single line of an advance invoice
+pub(crate) fn deposit_line(
+    projects: &BTreeMap<String, ProjectSettings>,
+    project: &str,
+    percent: f64,
+    amount: f64,
+) -> InvoiceLineItem {
+    let name = projects
+        .get(project)
+        .and_then(|p| p.description.clone())
+        .unwrap_or_else(|| project.to_string());
+    InvoiceLineItem {
+        description: format!("Deposit ({percent}%): {name}"),
+        quantity: 1.0,
+        unit: "deposit".to_string(),
+        rate: amount,
+        amount,
+        kind: ItemKind::Service,
+        original: None,
+        period: None,
+        taxable: true,
+    }
+}
+
+/// One negative line per deducted deposit invoice
+pub(crate) fn deduction_lines(
+    history: &[HistoryEntry],
+    deducted: &[String],
+) -> Result<Vec<InvoiceLineItem>> {
+    deducted
+        .iter()
+        .map(|number| {
+            let deposit = history
+                .iter()
+                .find(|e| &e.number == number)
+                .ok_or_else(|| InvoiceError::InvoiceNotFound(number.clone()))?
+                .deposit
+                .as_ref()
+                .ok_or_else(|| InvoiceError::InvoiceNotFound(number.clone()))?;
+            Ok(InvoiceLineItem {
+                description: format!(
+                    "Less deposit invoiced on {} ({}% of {})",
+                    number, deposit.percent, deposit.project
+                ),
+                quantity: 1.0,
+                unit: "deposit".to_string(),
+                rate: -deposit.amount,
+                amount: -deposit.amount,
+                kind: ItemKind::Service,
+                original: None,
+                period: None,
+                taxable: true,
+            })
+        })
+        .collect()
+}
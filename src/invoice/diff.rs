@@ -0,0 +1,287 @@
+//! Comparing an issued invoice with what regenerating it would produce now,
+//! to catch config or catalog drift before overwriting the document.
+
+use std::fmt;
+
+use super::generator::{client_render_options, rebuild_invoice_data, InvoiceData, InvoiceLineItem};
+use super::snapshot::invoice_snapshot;
+use crate::config::{InvoiceSettings, Workspace};
+use crate::error::{InvoiceError, Result};
+use crate::money::MoneyFormat;
+use crate::pdf::{render_artifacts, OutputFormat, RenderOptions};
+
+/// How an issued invoice differs from its regenerated version
+#[derive(Debug)]
+pub struct InvoiceDiff {
+    pub number: String,
+    /// Payable total history records for the invoice
+    pub stored_total: f64,
+    /// Payable total regenerating would record
+    pub regenerated_total: f64,
+    /// Line items that differ, by position. Lines are only compared when a
+    /// JSON artifact of the issued invoice was kept.
+    pub lines: Vec<LineChange>,
+    /// The invoice's text as issued and as regenerated, line by line; None
+    /// without a JSON artifact
+    pub text: Option<Vec<DiffLine>>,
+    /// Whether the regenerated PDF is byte for byte the stored one; None
+    /// when no PDF is stored or none could be rendered
+    pub pdf_identical: Option<bool>,
+    /// Both PDFs were rendered with --deterministic, so they only differ
+    /// when the document does; otherwise their creation times differ anyway
+    pub pdf_pinned: bool,
+    /// Invoice settings with the invoice's own currency, for its amounts
+    pub settings: InvoiceSettings,
+    pub warnings: Vec<String>,
+}
+
+impl InvoiceDiff {
+    /// Whether regenerating would leave the invoice as issued
+    pub fn is_unchanged(&self) -> bool {
+        (self.stored_total - self.regenerated_total).abs() < 0.005
+            && self.lines.is_empty()
+            && self
+                .text
+                .as_ref()
+                .is_none_or(|text| text.iter().all(|line| matches!(line, DiffLine::Same(_))))
+            && (!self.pdf_pinned || self.pdf_identical != Some(false))
+    }
+}
+
+/// A line item at `position` (1-based) that was added, removed or changed
+#[derive(Debug)]
+pub struct LineChange {
+    pub position: usize,
+    pub before: Option<InvoiceLineItem>,
+    pub after: Option<InvoiceLineItem>,
+}
+
+/// One line of a text diff
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffLine {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+impl fmt::Display for DiffLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffLine::Same(line) => write!(f, "  {line}"),
+            DiffLine::Removed(line) => write!(f, "- {line}"),
+            DiffLine::Added(line) => write!(f, "+ {line}"),
+        }
+    }
+}
+
+/// Rebuild `invoice_number` the way `regenerate` would, render its PDF to
+/// a temporary directory and compare the result with what was issued.
+/// Nothing in the config directory is changed.
+pub fn diff_invoice(
+    ws: &mut Workspace,
+    invoice_number: &str,
+    render: &RenderOptions,
+) -> Result<InvoiceDiff> {
+    let entry_idx = ws
+        .state
+        .history
+        .iter()
+        .position(|e| e.number == invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.to_string()))?;
+
+    let output_dir = ws.output_dir();
+    let issued = invoice_snapshot(&output_dir, &ws.state.history[entry_idx]);
+    let discount = ws.state.history[entry_idx].discount;
+    let mut warnings = Vec::new();
+    let (data, ..) =
+        rebuild_invoice_data(ws, entry_idx, None, discount, render.strict, &mut warnings)?;
+    let entry = &ws.state.history[entry_idx];
+
+    let mut render = client_render_options(render, &data.client, &ws.config, &ws.dir);
+    render.formats = vec![OutputFormat::Pdf];
+    let stored_pdf = entry
+        .artifacts()
+        .into_iter()
+        .find(|name| OutputFormat::from_file_name(name) == Some(OutputFormat::Pdf))
+        .map(|name| output_dir.join(name))
+        .filter(|path| path.is_file());
+    let pdf_identical = match stored_pdf {
+        Some(stored) => match render_temp_pdf(&data, &render) {
+            Ok(rendered) => Some(rendered == std::fs::read(stored)?),
+            Err(InvoiceError::TypstNotFound) => {
+                warnings.push("Typst not found; the PDF was not compared".to_string());
+                None
+            }
+            Err(e) => return Err(e),
+        },
+        None => None,
+    };
+
+    let mut settings = ws.config.invoice.clone();
+    settings.currency_symbol = data.currency_symbol.clone();
+    settings.symbol_position = data.symbol_position;
+    settings.symbol_space = data.symbol_space;
+
+    Ok(InvoiceDiff {
+        number: invoice_number.to_string(),
+        stored_total: entry.total,
+        regenerated_total: data.total - data.retention,
+        lines: issued
+            .as_ref()
+            .map(|issued| line_changes(&issued.items, &data.items))
+            .unwrap_or_default(),
+        text: issued
+            .as_ref()
+            .map(|issued| diff_lines(&invoice_text(issued), &invoice_text(&data))),
+        pdf_identical,
+        pdf_pinned: entry.deterministic && render.deterministic,
+        settings,
+        warnings,
+    })
+}
+
+/// Render `data` as a PDF in a scratch directory and return its bytes
+fn render_temp_pdf(data: &InvoiceData, render: &RenderOptions) -> Result<Vec<u8>> {
+    let dir = std::env::temp_dir().join(format!(
+        "invoice-diff-{}-{}",
+        std::process::id(),
+        data.number
+    ));
+    std::fs::create_dir_all(&dir)?;
+    let rendered = render_artifacts(data, &dir.join(&data.number), render)
+        .and_then(|paths| Ok(std::fs::read(&paths[0])?));
+    let _ = std::fs::remove_dir_all(&dir);
+    rendered
+}
+
+fn line_changes(before: &[InvoiceLineItem], after: &[InvoiceLineItem]) -> Vec<LineChange> {
+    let same = |a: &InvoiceLineItem, b: &InvoiceLineItem| {
+        a.description == b.description
+            && a.unit == b.unit
+            && a.period == b.period
+            && (a.quantity - b.quantity).abs() < 1e-9
+            && (a.rate - b.rate).abs() < 0.005
+            && (a.amount - b.amount).abs() < 0.005
+    };
+    (0..before.len().max(after.len()))
+        .filter_map(|i| {
+            let (before, after) = (before.get(i), after.get(i));
+            if let (Some(a), Some(b)) = (before, after) {
+                if same(a, b) {
+                    return None;
+                }
+            }
+            Some(LineChange {
+                position: i + 1,
+                before: before.cloned(),
+                after: after.cloned(),
+            })
+        })
+        .collect()
+}
+
+/// The text an invoice document shows, one line per row, for diffing
+fn invoice_text(data: &InvoiceData) -> Vec<String> {
+    let format = MoneyFormat {
+        symbol: &data.currency_symbol,
+        position: data.symbol_position,
+        space: data.symbol_space,
+    };
+    let money = |amount: f64| format.format(amount);
+    let client = &data.client;
+
+    let mut text = vec![format!("Invoice {}", data.number)];
+    text.extend(data.display_number.as_ref().map(|n| format!("No. {n}")));
+    text.push(format!("Date: {}", data.date));
+    text.push(format!("Due Date: {}", data.due_date));
+    text.push(format!("From: {}", data.company.name));
+    text.push(format!("Bill To: {}", client.name));
+    text.extend(client.contact.clone());
+    text.push(client.postal_address());
+    text.push(client.email.clone());
+    text.extend(client.tax_id.as_ref().map(|id| format!("VAT ID: {id}")));
+    text.extend(
+        data.service_period
+            .as_ref()
+            .map(|p| format!("Services Rendered: {p}")),
+    );
+    text.extend(
+        data.fields
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}")),
+    );
+    for (i, item) in data.items.iter().enumerate() {
+        text.push(format!(
+            "{}. {} | {} {} | {} | {}",
+            i + 1,
+            item.description,
+            item.quantity,
+            item.unit_label(),
+            money(item.rate),
+            money(item.amount)
+        ));
+        text.extend(item.period.as_ref().map(|p| format!("   {p}")));
+    }
+    text.push(format!("Subtotal: {}", money(data.subtotal)));
+    if data.discount > 0.0 {
+        text.push(format!("Discount: -{}", money(data.discount)));
+    }
+    for tax in data.tax_lines() {
+        text.push(format!(
+            "{} ({:.2}%): {}",
+            tax.name,
+            tax.percent,
+            money(tax.amount)
+        ));
+    }
+    if data.rounding_adjustment != 0.0 {
+        text.push(format!("Rounding: {}", money(data.rounding_adjustment)));
+    }
+    text.push(format!("Total: {}", money(data.total)));
+    if data.retention > 0.0 {
+        text.push(format!("Retention: -{}", money(data.retention)));
+        text.push(format!(
+            "Amount due: {}",
+            money(data.total - data.retention)
+        ));
+    }
+    text.extend(data.memo.as_ref().map(|memo| format!("Notes: {memo}")));
+    if data.due_days > 0 {
+        text.push(format!("Payment Terms: {}", data.payment_terms));
+    }
+    text.extend(data.payee.as_ref().map(|p| format!("Pay to: {}", p.name)));
+    text
+}
+
+/// Line diff of `before` and `after` by longest common subsequence
+fn diff_lines(before: &[String], after: &[String]) -> Vec<DiffLine> {
+    let (n, m) = (before.len(), after.len());
+    // lcs[i][j]: common lines of before[i..] and after[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if before[i] == after[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && before[i] == after[j] {
+            diff.push(DiffLine::Same(before[i].clone()));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push(DiffLine::Removed(before[i].clone()));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(after[j].clone()));
+            j += 1;
+        }
+    }
+    diff
+}
@@ -0,0 +1,234 @@
+use chrono::NaiveDate;
+use std::fmt;
+
+use super::dunning::days_overdue;
+use super::summary::due_date;
+use crate::config::{Config, HistoryEntry};
+
+/// How far past due an unpaid invoice is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AgingBucket {
+    Current,
+    Days1To30,
+    Days31To60,
+    Days61To90,
+    Over90,
+}
+
+impl fmt::Display for AgingBucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgingBucket::Current => write!(f, "current"),
+            AgingBucket::Days1To30 => write!(f, "1-30 days"),
+            AgingBucket::Days31To60 => write!(f, "31-60 days"),
+            AgingBucket::Days61To90 => write!(f, "61-90 days"),
+            AgingBucket::Over90 => write!(f, "90+ days"),
+        }
+    }
+}
+
+/// Bucket for an invoice `days_overdue` past its due date
+pub fn aging_bucket(days_overdue: i64) -> AgingBucket {
+    match days_overdue {
+        i64::MIN..=0 => AgingBucket::Current,
+        1..=30 => AgingBucket::Days1To30,
+        31..=60 => AgingBucket::Days31To60,
+        61..=90 => AgingBucket::Days61To90,
+        _ => AgingBucket::Over90,
+    }
+}
+
+/// Receivables activity between `from` (exclusive) and `to` (inclusive)
+#[derive(Debug, Clone)]
+pub struct Digest {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub issued: Vec<DigestInvoice>,
+    pub payments: Vec<DigestPayment>,
+    pub aging_changes: Vec<AgingChange>,
+    /// Unpaid invoices due within the next period, soonest first
+    pub upcoming: Vec<DigestInvoice>,
+    /// Balance still owed across all invoices as of `to`
+    pub outstanding: f64,
+    pub open_invoices: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct DigestInvoice {
+    pub number: String,
+    pub client: String,
+    pub date: NaiveDate,
+    pub amount: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct DigestPayment {
+    pub number: String,
+    pub client: String,
+    pub date: NaiveDate,
+    pub amount: f64,
+}
+
+/// An unpaid invoice that moved to an older aging bucket
+#[derive(Debug, Clone)]
+pub struct AgingChange {
+    pub number: String,
+    pub client: String,
+    pub before: AgingBucket,
+    pub after: AgingBucket,
+    pub outstanding: f64,
+}
+
+/// Summarize the `days` up to and including `today`
+pub fn receivables_digest(
+    history: &[HistoryEntry],
+    config: &Config,
+    today: NaiveDate,
+    days: i64,
+) -> Digest {
+    let from = today - chrono::Duration::days(days);
+    let upcoming_until = today + chrono::Duration::days(days);
+    let in_period = |date: NaiveDate| date > from && date <= today;
+    let live = || history.iter().filter(|e| e.is_billed());
+
+    let issued = live()
+        .filter(|e| in_period(e.date))
+        .map(|e| DigestInvoice {
+            number: e.number.clone(),
+            client: e.client.clone(),
+            date: e.date,
+            amount: e.total,
+        })
+        .collect();
+
+    let mut payments: Vec<DigestPayment> = history
+        .iter()
+        .flat_map(|e| e.payments.iter().map(move |p| (e, p)))
+        .filter(|(_, p)| in_period(p.date))
+        .map(|(e, p)| DigestPayment {
+            number: e.number.clone(),
+            client: e.client.clone(),
+            date: p.date,
+            amount: p.amount,
+        })
+        .collect();
+    payments.sort_by_key(|p| p.date);
+
+    let open: Vec<&HistoryEntry> = live()
+        .filter(|e| e.date <= today && !e.is_settled())
+        .collect();
+
+    let aging_changes = open
+        .iter()
+        .filter_map(|e| {
+            let before = aging_bucket(days_overdue(e, config, from));
+            let after = aging_bucket(days_overdue(e, config, today));
+            (after > before).then(|| AgingChange {
+                number: e.number.clone(),
+                client: e.client.clone(),
+                before,
+                after,
+                outstanding: e.outstanding(),
+            })
+        })
+        .collect();
+
+    let mut upcoming: Vec<DigestInvoice> = open
+        .iter()
+        .map(|e| (e, due_date(e, config)))
+        .filter(|(_, due)| *due > today && *due <= upcoming_until)
+        .map(|(e, due)| DigestInvoice {
+            number: e.number.clone(),
+            client: e.client.clone(),
+            date: due,
+            amount: e.outstanding(),
+        })
+        .collect();
+    upcoming.sort_by_key(|i| i.date);
+
+    Digest {
+        from,
+        to: today,
+        issued,
+        payments,
+        aging_changes,
+        upcoming,
+        outstanding: open.iter().map(|e| e.outstanding()).sum(),
+        open_invoices: open.len(),
+    }
+}
+
+/// Plain-text digest, suitable for an email body
+pub fn render_digest(digest: &Digest, config: &Config) -> String {
+    let money = |amount: f64| config.invoice.money(amount);
+    let mut out = format!(
+        "Receivables digest for {} to {}\n",
+        (digest.from + chrono::Duration::days(1)).format("%Y-%m-%d"),
+        digest.to.format("%Y-%m-%d")
+    );
+
+    let issued: f64 = digest.issued.iter().map(|i| i.amount).sum();
+    out.push_str(&format!(
+        "\nIssued ({}): {}\n",
+        digest.issued.len(),
+        money(issued)
+    ));
+    for invoice in &digest.issued {
+        out.push_str(&format!(
+            "  {}  {}  {}  {}\n",
+            invoice.date.format("%Y-%m-%d"),
+            invoice.number,
+            invoice.client,
+            money(invoice.amount)
+        ));
+    }
+
+    let received: f64 = digest.payments.iter().map(|p| p.amount).sum();
+    out.push_str(&format!(
+        "\nPayments received ({}): {}\n",
+        digest.payments.len(),
+        money(received)
+    ));
+    for payment in &digest.payments {
+        out.push_str(&format!(
+            "  {}  {}  {}  {}\n",
+            payment.date.format("%Y-%m-%d"),
+            payment.number,
+            payment.client,
+            money(payment.amount)
+        ));
+    }
+
+    out.push_str(&format!(
+        "\nAging changes ({}):\n",
+        digest.aging_changes.len()
+    ));
+    for change in &digest.aging_changes {
+        out.push_str(&format!(
+            "  {}  {}  {} -> {}  {} outstanding\n",
+            change.number,
+            change.client,
+            change.before,
+            change.after,
+            money(change.outstanding)
+        ));
+    }
+
+    out.push_str(&format!("\nComing due ({}):\n", digest.upcoming.len()));
+    for invoice in &digest.upcoming {
+        out.push_str(&format!(
+            "  {}  {}  {}  {}\n",
+            invoice.date.format("%Y-%m-%d"),
+            invoice.number,
+            invoice.client,
+            money(invoice.amount)
+        ));
+    }
+
+    out.push_str(&format!(
+        "\nOutstanding: {} across {} invoice(s)\n",
+        money(digest.outstanding),
+        digest.open_invoices
+    ));
+    out
+}
@@ -0,0 +1,111 @@
+use chrono::NaiveDate;
+use std::path::Path;
+
+use super::interest::accrued_interest;
+use super::summary::due_date;
+use crate::config::{load_state, save_state, Config, HistoryEntry, Reminder};
+use crate::error::{InvoiceError, Result};
+
+/// Days past the due date as of `today` (negative if not yet due)
+pub fn days_overdue(entry: &HistoryEntry, config: &Config, today: NaiveDate) -> i64 {
+    (today - due_date(entry, config)).num_days()
+}
+
+/// The dunning level (1-based) an unpaid invoice has reached by `today`,
+/// or None if it is paid or not yet past the first threshold
+pub fn dunning_level_due(entry: &HistoryEntry, config: &Config, today: NaiveDate) -> Option<u32> {
+    if entry.is_settled() {
+        return None;
+    }
+
+    let overdue = days_overdue(entry, config, today);
+    if overdue <= 0 {
+        return None;
+    }
+
+    config
+        .dunning
+        .levels
+        .iter()
+        .enumerate()
+        .filter(|(_, level)| overdue >= level.days as i64)
+        .map(|(i, _)| i as u32 + 1)
+        .max()
+}
+
+/// Fill in the message template for a dunning level
+pub fn render_reminder(
+    entry: &HistoryEntry,
+    client_name: &str,
+    config: &Config,
+    level: u32,
+    today: NaiveDate,
+) -> String {
+    let template = config
+        .dunning
+        .levels
+        .get(level.saturating_sub(1) as usize)
+        .map_or("", |l| l.message.as_str());
+
+    let interest = config.interest.as_ref().map(|settings| {
+        accrued_interest(
+            entry,
+            config,
+            settings.annual_rate,
+            settings.day_count,
+            today,
+        )
+    });
+    let money = |amount: f64| config.invoice.money(amount);
+
+    let mut message = template
+        .replace("{client}", client_name)
+        .replace("{number}", &entry.number)
+        .replace("{amount}", &money(entry.outstanding().max(0.0)))
+        .replace(
+            "{interest}",
+            &money(interest.as_ref().map_or(0.0, |i| i.amount)),
+        )
+        .replace(
+            "{due_date}",
+            &due_date(entry, config).format("%Y-%m-%d").to_string(),
+        )
+        .replace(
+            "{days_overdue}",
+            &days_overdue(entry, config, today).to_string(),
+        )
+        .replace("{level}", &level.to_string());
+
+    let include_interest = config
+        .interest
+        .as_ref()
+        .is_some_and(|settings| settings.include_in_reminders);
+    if let Some(interest) = interest.filter(|i| include_interest && i.amount > 0.0) {
+        message.push_str(&format!(
+            "\n\nLate interest accrued to date: {} ({:.2}% per year since {}).",
+            money(interest.amount),
+            interest.annual_rate * 100.0,
+            interest.due_date.format("%Y-%m-%d")
+        ));
+    }
+
+    message
+}
+
+/// Record that a reminder was sent at `level` on `date`
+pub fn record_reminder(
+    cfg_dir: &Path,
+    invoice_number: &str,
+    level: u32,
+    date: NaiveDate,
+) -> Result<()> {
+    let mut state = load_state(cfg_dir)?;
+    let entry = state
+        .history
+        .iter_mut()
+        .find(|e| e.number == invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.to_string()))?;
+
+    entry.reminders.push(Reminder { level, date });
+    save_state(cfg_dir, &state)
+}
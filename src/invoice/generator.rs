@@ -1,71 +1,297 @@
 use chrono::{Datelike, Local, NaiveDate};
-use serde::Serialize;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 
+use super::contracts::{active_contract, contract_status};
+use super::deposit::{deductible_deposits, deduction_lines, deposit_line, DepositRequest};
+use super::item_spec::parse_item_spec;
+use super::manifest::{sha256_hex, update_manifest};
+use super::ordering::{apply_positions, sort_item_inputs, ItemSort};
+use super::projects::{project_amounts, project_budgets};
+use super::proration::{prorate, within_term};
+use super::retention::{release_lines, retention_amount};
+use super::snapshot::{client_or_snapshot, invoice_snapshot};
+use super::spec::{parse_generate_spec, GenerateSpec};
+use super::trash::trash_slot;
 use crate::config::{
-    load_clients, load_config, load_items, load_state, resolve_output_dir, save_state, Client,
-    Company, HistoryEntry,
+    load_config, load_contracts, load_state, resolve_output_dir, save_state, Client, Company,
+    Config, Contract, Deposit, Discount, HistoryEntry, InvoiceSettings, Item, ItemKind, Note,
+    PayeeSettings, Retention, Revision, ServicePeriod, SymbolPosition, Workspace, DEFAULT_SERIES,
 };
+use crate::editor::edit_file;
 use crate::error::{InvoiceError, Result};
-use crate::pdf::generate_pdf;
+use crate::money::round_cents;
+use crate::pdf::{debug_artifacts, render_artifacts, OutputFormat, RenderOptions, SCHEMA_VERSION};
+use crate::rates::Rates;
 
 /// A line item on the invoice
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InvoiceLineItem {
     pub description: String,
     pub quantity: f64,
     pub unit: String,
     pub rate: f64,
     pub amount: f64,
+    #[serde(default)]
+    pub kind: ItemKind,
+    /// Price as quoted, when the item is priced in another currency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original: Option<OriginalPrice>,
+    /// Service period label for this line (e.g., "Jan 1–31, 2026")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period: Option<String>,
+    /// Whether the invoice's taxes apply to this line
+    #[serde(default = "taxable")]
+    pub taxable: bool,
+}
+
+impl InvoiceLineItem {
+    /// The unit, plural unless the quantity is one (e.g., "hours")
+    pub fn unit_label(&self) -> String {
+        if self.quantity == 1.0 {
+            self.unit.clone()
+        } else {
+            format!("{}s", self.unit)
+        }
+    }
+}
+
+fn taxable() -> bool {
+    true
+}
+
+/// One of the taxes charged on an invoice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvoiceTax {
+    /// e.g., "GST"
+    pub name: String,
+    /// e.g., 5.0
+    pub percent: f64,
+    pub amount: f64,
+}
+
+/// A line's rate and amount in the currency its item is priced in, and
+/// the rate they were converted at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OriginalPrice {
+    /// ISO 4217 code (e.g., "EUR")
+    pub currency: String,
+    pub rate: f64,
+    pub amount: f64,
+    /// Invoice-currency value of one unit of `currency`
+    pub exchange_rate: f64,
 }
 
 /// Complete invoice data for PDF generation
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct InvoiceData {
+    /// Version of the data contract templates read (see `pdf::contract`)
+    #[serde(default = "schema_version")]
+    pub schema_version: u32,
     pub number: String,
+    /// Number in the client's reference scheme, shown prominently when set
+    #[serde(default)]
+    pub display_number: Option<String>,
     pub date: String,
     pub due_date: String,
     pub company: Company,
     pub client: Client,
     pub items: Vec<InvoiceLineItem>,
     pub subtotal: f64,
-    pub tax_rate: f64,
+    /// Discount percentage (e.g., 10.0); 0 for a fixed amount or none
+    #[serde(default)]
+    pub discount_percent: f64,
+    /// Amount taken off the subtotal before tax
+    #[serde(default)]
+    pub discount: f64,
+    /// Combined percentage of the taxes (e.g., 8.25); templates can still
+    /// read it as the deprecated `tax_rate`
+    #[serde(alias = "tax_rate")]
+    pub tax_percent: f64,
+    /// Sum of the taxes
     pub tax_amount: f64,
+    /// Each tax charged, in the order they are listed
+    #[serde(default)]
+    pub taxes: Vec<InvoiceTax>,
+    /// Difference between the rounded total and the sum of the displayed
+    /// (cent-rounded) lines; rendered as its own row when non-zero
+    pub rounding_adjustment: f64,
     pub total: f64,
+    /// Percentage of the total held back until completion (0 for none)
+    #[serde(default)]
+    pub retention_percent: f64,
+    /// Amount held back; the client pays `total - retention` now
+    #[serde(default)]
+    pub retention: f64,
+    /// ISO 4217 code of the amounts (e.g., "USD")
+    #[serde(default)]
+    pub currency: String,
     pub currency_symbol: String,
+    #[serde(default)]
+    pub symbol_position: SymbolPosition,
+    #[serde(default)]
+    pub symbol_space: bool,
     pub due_days: u32,
     pub payment_terms: String,
+    /// Service period label (e.g., "Jan 1–31, 2026")
+    #[serde(default)]
+    pub service_period: Option<String>,
+    /// Who to pay instead of the company, with their remittance details
+    #[serde(default)]
+    pub payee: Option<PayeeSettings>,
+    /// Custom fields for the client's AP department (e.g., "Cost Center")
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    /// Note printed in a Notes section at the bottom
+    #[serde(default)]
+    pub memo: Option<String>,
 }
 
-/// Parse item input like "consulting:8" into (item_id, quantity)
-fn parse_item_input(input: &str) -> Result<(&str, f64)> {
-    let parts: Vec<&str> = input.split(':').collect();
-    if parts.len() != 2 {
-        return Err(InvoiceError::InvalidItemFormat(input.to_string()));
+fn schema_version() -> u32 {
+    SCHEMA_VERSION
+}
+
+impl InvoiceData {
+    /// The tax rows to print. Invoices issued before taxes were itemized
+    /// have only `tax_percent`, shown as a single "Tax".
+    pub fn tax_lines(&self) -> Vec<InvoiceTax> {
+        if !self.taxes.is_empty() || self.tax_percent <= 0.0 {
+            return self.taxes.clone();
+        }
+        vec![InvoiceTax {
+            name: "Tax".to_string(),
+            percent: self.tax_percent,
+            amount: self.tax_amount,
+        }]
     }
+}
 
-    let item_id = parts[0];
-    let qty_str = parts[1];
+/// Rates for every foreign currency the items are priced in, converting
+/// into `currency`. Rates in `known` (from `--rate` or the stored invoice)
+/// win; the rest come from the `[currency]` provider.
+fn exchange_rates(
+    inputs: &[String],
+    catalog: &HashMap<String, Item>,
+    currency: &str,
+    known: &BTreeMap<String, f64>,
+    rates: &mut Rates,
+) -> Result<BTreeMap<String, f64>> {
+    let foreign: BTreeSet<String> = inputs
+        .iter()
+        .filter_map(|input| parse_item_spec(input).ok())
+        .filter_map(|spec| catalog.get(&spec.item)?.currency.as_deref())
+        .filter(|code| !code.eq_ignore_ascii_case(currency))
+        .map(str::to_uppercase)
+        .collect();
 
-    let quantity: f64 = qty_str.parse().map_err(|_| InvoiceError::InvalidQuantity {
-        item: item_id.to_string(),
-        qty: qty_str.to_string(),
-        reason: "must be a number".to_string(),
-    })?;
+    foreign
+        .into_iter()
+        .map(|code| {
+            let rate = known
+                .get(&code)
+                .copied()
+                .or_else(|| rates.rate(&code, currency))
+                .ok_or_else(|| InvoiceError::NoExchangeRate {
+                    from: code.clone(),
+                    to: currency.to_string(),
+                })?;
+            Ok((code, rate))
+        })
+        .collect()
+}
 
-    if quantity <= 0.0 {
-        return Err(InvoiceError::InvalidQuantity {
-            item: item_id.to_string(),
-            qty: qty_str.to_string(),
-            reason: "must be greater than 0".to_string(),
-        });
-    }
+/// Build invoice lines from `--item` specs against the item catalog,
+/// converting foreign-currency items with `exchange_rates`. Items set to
+/// prorate are charged for the part of their cycles the line's service
+/// period (or else the invoice's `period`) covers within `contract`'s term.
+fn build_line_items(
+    inputs: &[String],
+    catalog: &HashMap<String, Item>,
+    exchange_rates: &BTreeMap<String, f64>,
+    period: Option<ServicePeriod>,
+    contract: Option<&Contract>,
+) -> Result<Vec<InvoiceLineItem>> {
+    inputs
+        .iter()
+        .map(|input| {
+            let spec = parse_item_spec(input)?;
+            let item = catalog
+                .get(&spec.item)
+                .ok_or_else(|| InvoiceError::ItemNotFound(spec.item.clone()))?;
 
-    Ok((item_id, quantity))
+            let mut description = spec.description.unwrap_or_else(|| item.description.clone());
+            let mut factor = 1.0;
+            let mut line_period = spec.period;
+            let billed = spec.period.or(period);
+            if let (Some(recurrence), Some(billed), true) = (item.recurring, billed, item.prorate) {
+                let covered = within_term(billed, contract);
+                let proration = prorate(recurrence, covered);
+                if proration.factor != 1.0 {
+                    factor = proration.factor;
+                    description = if proration.partial {
+                        format!("{description} (prorated: {})", proration.label)
+                    } else {
+                        format!("{description} ({})", proration.label)
+                    };
+                }
+                if covered != billed {
+                    line_period = Some(covered);
+                }
+            }
+
+            let original = item.currency.as_ref().and_then(|currency| {
+                let currency = currency.to_uppercase();
+                exchange_rates
+                    .get(&currency)
+                    .map(|&exchange_rate| OriginalPrice {
+                        currency,
+                        rate: item.rate,
+                        amount: item.rate * spec.quantity * factor,
+                        exchange_rate,
+                    })
+            });
+            let exchange_rate = original.as_ref().map_or(1.0, |o| o.exchange_rate);
+            let rate = item.rate * exchange_rate;
+            // A prorated quantity is shown to four places; the amount is
+            // the exact share, to the cent
+            let (quantity, amount) = if factor == 1.0 {
+                (spec.quantity, rate * spec.quantity)
+            } else {
+                let quantity = spec.quantity * factor;
+                (
+                    (quantity * 10_000.0).round() / 10_000.0,
+                    round_cents(rate * quantity),
+                )
+            };
+
+            let line = InvoiceLineItem {
+                description,
+                quantity,
+                unit: item.unit.clone(),
+                rate,
+                amount,
+                kind: item.kind,
+                original,
+                period: line_period.map(|p| p.to_string()),
+                taxable: item.taxable,
+            };
+            Ok((line, spec.position))
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(apply_positions)
+        .map(group_by_kind)
+}
+
+/// Order lines by kind (services, products, expenses), keeping the given
+/// order within each kind, so templates can print each group under a heading
+pub(crate) fn group_by_kind(mut items: Vec<InvoiceLineItem>) -> Vec<InvoiceLineItem> {
+    items.sort_by_key(|item| item.kind);
+    items
 }
 
 /// Format invoice number from template
-fn format_invoice_number(format: &str, year: u32, seq: u32) -> String {
+pub(crate) fn format_invoice_number(format: &str, year: u32, seq: u32) -> String {
     format
         .replace("{year}", &year.to_string())
         .replace("{seq:04}", &format!("{:04}", seq))
@@ -73,118 +299,878 @@ fn format_invoice_number(format: &str, year: u32, seq: u32) -> String {
         .replace("{seq:03}", &format!("{:03}", seq))
 }
 
-/// Regenerate an existing invoice from stored data
-pub fn regenerate_invoice(
-    cfg_dir: &PathBuf,
-    invoice_number: &str,
-    new_items: Option<&[String]>,
-) -> Result<PathBuf> {
-    let config = load_config(cfg_dir)?;
-    let clients = load_clients(cfg_dir)?;
-    let items_catalog = load_items(cfg_dir)?;
-    let mut state = load_state(cfg_dir)?;
+/// Check that `number` can name the invoice's files: not empty, no path
+/// separators or `..`, and nothing a file system would refuse
+pub fn validate_invoice_number(number: &str) -> Result<()> {
+    let invalid = |reason: &str| {
+        Err(InvoiceError::InvalidInvoiceNumber {
+            number: number.to_string(),
+            reason: reason.to_string(),
+        })
+    };
+    if number.trim().is_empty() {
+        return invalid("it is empty");
+    }
+    if number.contains("..") || number.starts_with('.') {
+        return invalid("it can't start with '.' or contain '..'");
+    }
+    if let Some(ch) = number.chars().find(|c| {
+        c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+    }) {
+        return invalid(&format!("it contains {ch:?}"));
+    }
+    Ok(())
+}
 
-    // Find the invoice in history
-    let entry_idx = state
-        .history
+/// Recover (year, sequence) from a number produced by `format`, if it matches
+fn parse_invoice_number(format: &str, number: &str) -> Option<(u32, u32)> {
+    let mut rest = number;
+    let mut pattern = format;
+    let mut year = None;
+    let mut seq = None;
+
+    while !pattern.is_empty() {
+        if let Some(after) = pattern.strip_prefix("{year}") {
+            let digits = rest
+                .get(..4)
+                .filter(|d| d.bytes().all(|b| b.is_ascii_digit()))?;
+            year = Some(digits.parse().ok()?);
+            rest = &rest[4..];
+            pattern = after;
+        } else if pattern.starts_with("{seq:") {
+            let end = pattern.find('}')?;
+            let len = rest.bytes().take_while(u8::is_ascii_digit).count();
+            if len == 0 {
+                return None;
+            }
+            seq = Some(rest[..len].parse().ok()?);
+            rest = &rest[len..];
+            pattern = &pattern[end + 1..];
+        } else {
+            let ch = pattern.chars().next()?;
+            rest = rest.strip_prefix(ch)?;
+            pattern = &pattern[ch.len_utf8()..];
+        }
+    }
+
+    if !rest.is_empty() {
+        return None;
+    }
+    Some((year?, seq?))
+}
+
+/// Computed invoice totals
+pub(crate) struct Totals {
+    pub(crate) subtotal: f64,
+    pub(crate) discount: f64,
+    pub(crate) tax_amount: f64,
+    pub(crate) taxes: Vec<InvoiceTax>,
+    pub(crate) rounding_adjustment: f64,
+    pub(crate) total: f64,
+}
+
+/// Compute totals from line items so that the displayed lines always add up
+/// to the displayed total. Each line, the discount and each tax are rounded
+/// to cents as they appear on the PDF; any remaining difference against the
+/// rounded exact total becomes the rounding adjustment. The discount comes
+/// off the subtotal before tax, shared between taxable and exempt lines in
+/// proportion; every tax in `taxes` (names and fractional rates) is charged
+/// on the taxable lines.
+pub(crate) fn compute_totals(
+    line_items: &[InvoiceLineItem],
+    discount: Option<Discount>,
+    taxes: &[(String, f64)],
+) -> Totals {
+    let exact_subtotal: f64 = line_items.iter().map(|i| i.amount).sum();
+    let exact_discount = discount.map_or(0.0, |d| d.amount(exact_subtotal));
+    let taxable_lines: f64 = line_items
         .iter()
-        .position(|e| e.number == invoice_number)
-        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.to_string()))?;
+        .filter(|i| i.taxable)
+        .map(|i| i.amount)
+        .sum();
+    let taxable = if exact_subtotal == 0.0 {
+        taxable_lines
+    } else {
+        taxable_lines - exact_discount * taxable_lines / exact_subtotal
+    };
+    let exact_tax: f64 = taxes.iter().map(|(_, rate)| taxable * rate).sum();
+    let exact_total = exact_subtotal - exact_discount + exact_tax;
+
+    let taxes: Vec<InvoiceTax> = taxes
+        .iter()
+        .map(|(name, rate)| InvoiceTax {
+            name: name.clone(),
+            percent: rate * 100.0,
+            amount: round_cents(taxable * rate),
+        })
+        .collect();
+    let subtotal: f64 = line_items.iter().map(|i| round_cents(i.amount)).sum();
+    let discount = round_cents(exact_discount);
+    let tax_amount = round_cents(taxes.iter().fold(0.0, |sum, t| sum + t.amount));
+    let total = round_cents(exact_total);
+    let rounding_adjustment = round_cents(total - subtotal + discount - tax_amount);
+
+    Totals {
+        subtotal: round_cents(subtotal),
+        discount,
+        tax_amount,
+        taxes,
+        rounding_adjustment,
+        total,
+    }
+}
+
+/// Combined percentage of `taxes` given as fractional rates
+pub(crate) fn tax_percent(taxes: &[(String, f64)]) -> f64 {
+    // Folded from 0.0: summing no taxes would give -0.0
+    taxes.iter().fold(0.0, |sum, (_, rate)| sum + rate * 100.0)
+}
+
+/// Refuse invoice data `--strict` doesn't let through: a client without a
+/// country, lines that need a rounding adjustment to add up, or any of the
+/// `warnings` building it raised (e.g., a placeholder for a deleted client)
+fn strict_check(
+    client_id: &str,
+    data: &InvoiceData,
+    settings: &InvoiceSettings,
+    warnings: &[String],
+) -> Result<()> {
+    let mut concerns = warnings.to_vec();
+    if data
+        .client
+        .country
+        .as_deref()
+        .is_none_or(|c| c.trim().is_empty())
+    {
+        concerns.push(format!("client '{client_id}' has no country"));
+    }
+    if data.rounding_adjustment != 0.0 {
+        concerns.push(format!(
+            "the lines need a rounding adjustment of {} to add up to the total",
+            settings.money(data.rounding_adjustment)
+        ));
+    }
+    if concerns.is_empty() {
+        Ok(())
+    } else {
+        Err(InvoiceError::Strict(concerns))
+    }
+}
+
+/// Fill render options from the client's and config defaults where the caller left them unset
+pub(crate) fn client_render_options(
+    render: &RenderOptions,
+    client: &Client,
+    config: &Config,
+    cfg_dir: &Path,
+) -> RenderOptions {
+    let mut options = render.clone();
+    options.deterministic |= config.pdf.deterministic;
+    if options.formats.is_empty() {
+        options.formats = client.formats.clone();
+    }
+    if options.cover_letter.is_none() {
+        options.cover_letter = client
+            .cover_letter
+            .as_deref()
+            .map(|path| resolve_output_dir(path, cfg_dir));
+    }
+    if options.template_dir.is_none() {
+        options.template_dir = Some(cfg_dir.join("templates"));
+    }
+    options
+}
+
+/// File names of produced artifacts
+fn artifact_names(paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .filter_map(|p| p.file_name().and_then(|n| n.to_str()).map(String::from))
+        .collect()
+}
+
+/// The primary artifact: the PDF when one was produced, otherwise the first
+fn primary_file(files: &[String]) -> String {
+    files
+        .iter()
+        .find(|f| OutputFormat::from_file_name(f) == Some(OutputFormat::Pdf))
+        .or_else(|| files.first())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Path of the primary artifact (see `primary_file`)
+fn primary_path(paths: Vec<PathBuf>) -> PathBuf {
+    let names = artifact_names(&paths);
+    let primary = primary_file(&names);
+    paths
+        .into_iter()
+        .find(|p| p.file_name().and_then(|n| n.to_str()) == Some(primary.as_str()))
+        .unwrap_or_default()
+}
+
+/// Archived name for an artifact version: INV-2026-0001.pdf -> INV-2026-0001.v1.pdf
+fn versioned_file_name(file: &str, version: u32) -> String {
+    let path = Path::new(file);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => format!(
+            "{}.v{}.{}",
+            stem.to_string_lossy(),
+            version,
+            ext.to_string_lossy()
+        ),
+        _ => format!("{file}.v{version}"),
+    }
+}
+
+/// The current user, for revision and note records
+pub(crate) fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Copy the PDFs among `produced` into each `[pdf] copy_to` destination.
+/// The artifacts are already rendered, so a failed copy is only returned
+/// as a warning.
+fn copy_pdfs(
+    produced: &[PathBuf],
+    config: &Config,
+    cfg_dir: &Path,
+    date: NaiveDate,
+    client_id: &str,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let pdfs: Vec<&PathBuf> = produced
+        .iter()
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| OutputFormat::from_file_name(&name.to_string_lossy()))
+                == Some(OutputFormat::Pdf)
+        })
+        .collect();
+    for pattern in &config.pdf.copy_to {
+        let dir = pattern
+            .replace("{year}", &date.format("%Y").to_string())
+            .replace("{month}", &date.format("%m").to_string())
+            .replace("{client}", client_id);
+        let dir = resolve_output_dir(&dir, cfg_dir);
+        for pdf in &pdfs {
+            let Some(name) = pdf.file_name() else {
+                continue;
+            };
+            let copied =
+                std::fs::create_dir_all(&dir).and_then(|()| std::fs::copy(pdf, dir.join(name)));
+            if let Err(e) = copied {
+                warnings.push(format!(
+                    "could not copy {} to {}: {e}",
+                    pdf.display(),
+                    dir.display()
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+/// Move the entry's current artifacts aside as the next version and describe
+/// the change, so an already-sent document is never silently overwritten
+fn archive_revision(
+    output_dir: &Path,
+    entry: &HistoryEntry,
+    new_items: &[String],
+    new_total: f64,
+    settings: &InvoiceSettings,
+) -> Result<Revision> {
+    let version = entry.revisions.len() as u32 + 1;
+
+    let mut files = Vec::new();
+    for name in entry.artifacts() {
+        let current = output_dir.join(name);
+        if current.exists() {
+            let archived = versioned_file_name(name, version);
+            std::fs::rename(&current, output_dir.join(&archived))?;
+            files.push(archived);
+        }
+    }
+
+    let mut changes = format!(
+        "total {} -> {}",
+        settings.money(entry.total),
+        settings.money(new_total)
+    );
+    if entry.items != new_items {
+        changes.push_str(&format!(
+            "; items {} -> {}",
+            entry.items.join(", "),
+            new_items.join(", ")
+        ));
+    }
+
+    Ok(Revision {
+        version,
+        changed_at: Local::now().naive_local(),
+        changed_by: current_user(),
+        changes,
+        total: entry.total,
+        items: entry.items.clone(),
+        files,
+    })
+}
+
+/// Put the files `archive_revision` moved aside back in place, for when
+/// the new version could not be rendered
+fn restore_revision(output_dir: &Path, entry: &HistoryEntry, revision: &Revision) {
+    for name in entry.artifacts() {
+        let archived = versioned_file_name(name, revision.version);
+        if revision.files.contains(&archived) {
+            let _ = std::fs::rename(output_dir.join(&archived), output_dir.join(name));
+        }
+    }
+}
+
+/// Options for generating a new invoice
+#[derive(Debug, Default, Clone)]
+pub struct GenerateOptions {
+    /// Custom output file path (default: output_dir/<number>.pdf)
+    pub output_path: Option<PathBuf>,
+    /// Open the computed invoice JSON in $EDITOR and render whatever is saved
+    pub edit_json: bool,
+    /// Use this invoice number instead of the next one from the counter
+    pub number: Option<String>,
+    /// Numbering series to draw from (default: the `[invoice] number_format` series)
+    pub series: Option<String>,
+    /// When the invoiced services were rendered
+    pub period: Option<ServicePeriod>,
+    /// Generate even if an invoice this month already bills the same
+    /// client for the same items
+    pub allow_duplicate: bool,
+    /// Percentage of the total to hold back until completion (e.g., 10.0)
+    pub retention: Option<f64>,
+    /// Invoices of the same client whose retention this invoice bills
+    pub release_retention: Vec<String>,
+    /// Bill an advance on a fixed-price project instead of (or besides) items
+    pub deposit: Option<DepositRequest>,
+    /// Reorder the items before pinned (`@N`) positions apply
+    pub sort_items: ItemSort,
+    /// `[payees]` entry to direct payment to instead of the company
+    pub payee: Option<String>,
+    /// Exchange rates to use instead of fetching them, e.g. "EUR" → 1.08
+    pub exchange_rates: BTreeMap<String, f64>,
+    /// Discount taken off the subtotal before tax
+    pub discount: Option<Discount>,
+    /// Custom fields added to the client's; an empty value drops one
+    pub fields: BTreeMap<String, String>,
+    /// Invoice date instead of today
+    pub date: Option<NaiveDate>,
+    /// Lines priced outside the item catalog, added after the items
+    pub lines: Vec<InvoiceLineItem>,
+    /// Internal notes to attach, as with `invoice note add`
+    pub notes: Vec<String>,
+    /// Note printed at the bottom of the invoice
+    pub memo: Option<String>,
+    /// contracts.toml entry to bill against (default: the client's only
+    /// contract in effect on the invoice date)
+    pub contract: Option<String>,
+    /// Text of the spec the invoice comes from, kept under `specs/` as its
+    /// record so regenerating rebuilds the same lines
+    pub spec: Option<String>,
+    pub render: RenderOptions,
+}
+
+/// A newly generated invoice, for the caller to report
+#[derive(Debug)]
+pub struct GeneratedInvoice {
+    pub number: String,
+    /// The data the artifacts were rendered from
+    pub data: InvoiceData,
+    /// Primary artifact: the PDF when one was produced
+    pub path: PathBuf,
+    /// Every artifact written, in the order of the requested formats
+    pub files: Vec<PathBuf>,
+    /// Typst sources kept for inspection when rendering with `debug`
+    pub debug_files: Vec<PathBuf>,
+    /// contracts.toml entry the invoice bills against
+    pub contract: Option<String>,
+    /// Deposit invoices deducted from this one
+    pub deducted: Vec<String>,
+    /// Problems that didn't stop the invoice, such as a project invoiced
+    /// past its fixed price
+    pub warnings: Vec<String>,
+}
+
+impl GeneratedInvoice {
+    /// What the client pays now: the total less any retention
+    pub fn amount_due(&self) -> f64 {
+        self.data.total - self.data.retention
+    }
+}
+
+/// A regenerated invoice, for the caller to report
+#[derive(Debug)]
+pub struct RegeneratedInvoice {
+    /// Primary artifact: the PDF when one was produced
+    pub path: PathBuf,
+    /// Problems that didn't stop the invoice, such as a failed `copy_to`
+    pub warnings: Vec<String>,
+}
+
+/// Fingerprint of what an invoice bills: the client, its line items (in any
+/// order) and the service period, or the month it was issued without one
+fn content_hash(
+    client_id: &str,
+    items: &[String],
+    date: NaiveDate,
+    period: Option<&ServicePeriod>,
+) -> String {
+    let mut lines: Vec<String> = items
+        .iter()
+        .map(|input| match parse_item_spec(input) {
+            Ok(spec) => format!(
+                "{}\t{}\t{}",
+                spec.item,
+                spec.description.unwrap_or_default(),
+                spec.quantity
+            ),
+            Err(_) => input.clone(),
+        })
+        .collect();
+    lines.sort();
+
+    let when = match period {
+        Some(period) => format!("{}..{}", period.from, period.to),
+        None => date.format("%Y-%m").to_string(),
+    };
+    let content = format!("{client_id}\n{when}\n{}", lines.join("\n"));
+    sha256_hex(content.as_bytes())
+}
+
+/// Remittance details of the `[payees]` entry an invoice is paid to
+fn payee_settings(config: &Config, payee: Option<&str>) -> Result<Option<PayeeSettings>> {
+    payee
+        .map(|id| {
+            config
+                .payees
+                .get(id)
+                .cloned()
+                .ok_or_else(|| InvoiceError::PayeeNotFound(id.to_string()))
+        })
+        .transpose()
+}
+
+/// The client's custom fields with `--field` values added or, when empty,
+/// removed
+fn custom_fields(client: &Client, fields: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+    let mut merged = client.fields.clone();
+    for (name, value) in fields {
+        if value.is_empty() {
+            merged.remove(name);
+        } else {
+            merged.insert(name.clone(), value.clone());
+        }
+    }
+    merged
+}
+
+/// Keep the spec an invoice was generated from under `specs/`, returning
+/// its path relative to the config dir
+fn store_spec(cfg_dir: &Path, invoice_number: &str, spec: &str) -> Result<String> {
+    let extension = if spec.trim_start().starts_with('{') {
+        "json"
+    } else {
+        "toml"
+    };
+    let relative = format!("specs/{invoice_number}.{extension}");
+    std::fs::create_dir_all(cfg_dir.join("specs"))?;
+    std::fs::write(cfg_dir.join(&relative), spec)?;
+    Ok(relative)
+}
+
+/// Read the spec recorded for an invoice
+fn load_spec(cfg_dir: &Path, relative: &str) -> Result<GenerateSpec> {
+    let path = cfg_dir.join(relative);
+    let input = std::fs::read_to_string(&path)
+        .map_err(|e| InvoiceError::InvalidSpec(format!("{}: {e}", path.display())))?;
+    parse_generate_spec(&input)
+}
+
+/// Write the invoice data as a JSON draft, let the user edit it in $EDITOR,
+/// and read the edited version back
+fn review_draft(invoice_data: InvoiceData) -> Result<InvoiceData> {
+    let draft_path =
+        std::env::temp_dir().join(format!("invoice-draft-{}.json", invoice_data.number));
+    let json = serde_json::to_string_pretty(&invoice_data)
+        .map_err(|e| InvoiceError::PdfGeneration(e.to_string()))?;
+    std::fs::write(&draft_path, json)?;
+
+    edit_file(&draft_path)?;
+
+    let edited = std::fs::read_to_string(&draft_path)?;
+    let data = serde_json::from_str(&edited).map_err(|e| InvoiceError::InvalidDraft {
+        path: draft_path.clone(),
+        reason: e.to_string(),
+    })?;
+    let _ = std::fs::remove_file(&draft_path);
+    Ok(data)
+}
+
+/// The invoice's data as regenerating it renders it: the stored items (or
+/// `new_items`) priced from items.toml at the rates it was issued with.
+/// Also returns the item inputs and exchange rates used. With `strict`, a
+/// rate that has to be fetched must not come from an expired cache.
+pub(crate) fn rebuild_invoice_data(
+    ws: &mut Workspace,
+    entry_idx: usize,
+    new_items: Option<&[String]>,
+    discount: Option<Discount>,
+    strict: bool,
+    warnings: &mut Vec<String>,
+) -> Result<(InvoiceData, Vec<String>, BTreeMap<String, f64>)> {
+    let Workspace {
+        dir,
+        config,
+        clients,
+        items,
+        state,
+    } = ws;
+    let (cfg_dir, config, clients, items_catalog) = (dir.as_path(), &*config, &*clients, &*items);
 
     let entry = &state.history[entry_idx];
+    let invoice_number = entry.number.as_str();
     let client_id = entry.client.clone();
     let original_date = entry.date;
+    let period = entry.period;
+    let display_number = entry.display_number.clone();
+    let payee = payee_settings(config, entry.payee.as_deref())?;
+    let retention_percent = entry.retention.as_ref().map_or(0.0, |r| r.percent);
+    let released = entry.released_retention.clone();
+    let deposit = entry.deposit.clone();
+    let deducted = entry.deducted_deposits.clone();
+    let stored_rates = entry.exchange_rates.clone();
+    let stored_currency = entry.currency.clone();
+    let fields = entry.fields.clone();
+    let memo = entry.memo.clone();
+    let spec = entry
+        .spec
+        .as_deref()
+        .map(|path| load_spec(cfg_dir, path))
+        .transpose()?;
 
-    // Use new items if provided, otherwise use stored items
-    let items_to_use: Vec<String> = match new_items {
-        Some(items) => items.to_vec(),
-        None => {
-            if entry.items.is_empty() {
+    // Use new items if provided, otherwise the spec's or the stored items
+    let items_to_use: Vec<String> = match (new_items, &spec) {
+        (Some(items), _) => items.to_vec(),
+        (None, Some(spec)) => spec.item_inputs(),
+        (None, None) => {
+            if entry.items.is_empty() && released.is_empty() && deposit.is_none() {
                 return Err(InvoiceError::NoStoredItems(invoice_number.to_string()));
             }
             entry.items.clone()
         }
     };
 
-    // Look up client
-    let client = clients
-        .get(&client_id)
-        .ok_or_else(|| InvoiceError::ClientNotFound(client_id.clone()))?
-        .clone();
-
-    // Parse and validate items
-    let mut line_items: Vec<InvoiceLineItem> = Vec::new();
-
-    for input in &items_to_use {
-        let (item_id, quantity) = parse_item_input(input)?;
-
-        let item = items_catalog
-            .get(item_id)
-            .ok_or_else(|| InvoiceError::ItemNotFound(item_id.to_string()))?;
-
-        let amount = item.rate * quantity;
+    // Look up client, falling back to the details it was issued with
+    let output_dir = resolve_output_dir(&config.pdf.output_dir, cfg_dir);
+    let client = client_or_snapshot(clients, &state.history, &output_dir, &client_id, warnings)?;
+    let settings = config.invoice.for_client(&client);
 
-        line_items.push(InvoiceLineItem {
-            description: item.description.clone(),
-            quantity,
-            unit: item.unit.clone(),
-            rate: item.rate,
-            amount,
+    // Lines for items since removed from items.toml come from the invoice
+    // as issued, when it was saved as JSON
+    let items_missing = new_items.is_none()
+        && items_to_use.iter().any(|input| {
+            parse_item_spec(input).is_ok_and(|spec| !items_catalog.contains_key(&spec.item))
         });
-    }
+    let issued_lines = items_missing
+        .then(|| invoice_snapshot(&output_dir, entry))
+        .flatten()
+        .map(|data| data.items);
+
+    // Parse and validate items, converting at the rates the invoice was
+    // issued with unless its currency has changed since
+    let known_rates = if stored_currency == client.currency {
+        stored_rates
+    } else {
+        BTreeMap::new()
+    };
+    let (exchange_rates, line_items) = match issued_lines {
+        Some(lines) => {
+            warnings.push(format!(
+                "{invoice_number} bills items missing from items.toml; \
+                 reusing the lines it was issued with"
+            ));
+            (known_rates, lines)
+        }
+        None => {
+            let exchange_rates = exchange_rates(
+                &items_to_use,
+                items_catalog,
+                &settings.currency,
+                &known_rates,
+                &mut Rates::new(
+                    &config.currency,
+                    &mut state.rates,
+                    Local::now().naive_local(),
+                )
+                .allow_stale(!strict),
+            )?;
+            let contracts = load_contracts(cfg_dir)?;
+            let mut line_items = build_line_items(
+                &items_to_use,
+                items_catalog,
+                &exchange_rates,
+                period,
+                entry.contract.as_ref().and_then(|id| contracts.get(id)),
+            )?;
+            line_items.extend(spec.iter().flat_map(GenerateSpec::lines));
+            line_items.extend(release_lines(&state.history, &released)?);
+            if let Some(deposit) = &deposit {
+                line_items.push(deposit_line(
+                    &config.projects,
+                    &deposit.project,
+                    deposit.percent,
+                    deposit.amount,
+                ));
+            }
+            line_items.extend(deduction_lines(&state.history, &deducted)?);
+            (exchange_rates, group_by_kind(line_items))
+        }
+    };
+    let taxes = if released.is_empty() {
+        settings.tax_rates()
+    } else {
+        Vec::new()
+    };
 
     // Calculate totals
-    let subtotal: f64 = line_items.iter().map(|i| i.amount).sum();
-    let tax_amount = subtotal * config.invoice.tax_rate;
-    let total = subtotal + tax_amount;
+    let Totals {
+        subtotal,
+        discount: discounted,
+        tax_amount,
+        taxes: tax_lines,
+        rounding_adjustment,
+        total,
+    } = compute_totals(&line_items, discount, &taxes);
+    let retained = retention_amount(total, retention_percent);
 
     // Use original date for display
     let invoice_date = original_date.format("%B %d, %Y").to_string();
-    let due_date = original_date
-        .checked_add_signed(chrono::Duration::days(config.invoice.due_days as i64))
-        .unwrap_or(original_date)
+    let due_date = settings
+        .due_date(original_date)
         .format("%B %d, %Y")
         .to_string();
 
     // Build invoice data
     let invoice_data = InvoiceData {
+        schema_version: SCHEMA_VERSION,
         number: invoice_number.to_string(),
+        display_number,
         date: invoice_date,
         due_date,
         company: config.company.clone(),
         client: client.clone(),
         items: line_items,
         subtotal,
-        tax_rate: config.invoice.tax_rate * 100.0,
+        discount_percent: discount.map_or(0.0, Discount::percent),
+        discount: discounted,
+        tax_percent: tax_percent(&taxes),
         tax_amount,
+        taxes: tax_lines,
+        rounding_adjustment,
         total,
-        currency_symbol: config.invoice.currency_symbol.clone(),
-        due_days: config.invoice.due_days,
-        payment_terms: format!("Net {} days", config.invoice.due_days),
+        retention_percent,
+        retention: retained,
+        currency: settings.currency.clone(),
+        currency_symbol: settings.currency_symbol.clone(),
+        symbol_position: settings.symbol_position,
+        symbol_space: settings.symbol_space,
+        due_days: settings.due_days,
+        payment_terms: format!("Net {} days", settings.due_days),
+        service_period: period.map(|p| p.to_string()),
+        payee,
+        fields,
+        memo,
     };
 
-    // Determine output path
+    Ok((invoice_data, items_to_use, exchange_rates))
+}
+
+/// Regenerate an existing invoice from stored data. `new_items`,
+/// `discount` and `memo` replace the stored ones; a zero discount or a
+/// blank memo removes it.
+pub fn regenerate_invoice(
+    ws: &mut Workspace,
+    invoice_number: &str,
+    new_items: Option<&[String]>,
+    discount: Option<Discount>,
+    memo: Option<&str>,
+    render: &RenderOptions,
+    force_unlock: bool,
+) -> Result<RegeneratedInvoice> {
+    // Find the invoice in history
+    let entry_idx = ws
+        .state
+        .history
+        .iter()
+        .position(|e| e.number == invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.to_string()))?;
+
+    let discount = discount
+        .or(ws.state.history[entry_idx].discount)
+        .filter(|d| !d.is_zero());
+    let mut warnings = Vec::new();
+    let (mut invoice_data, items_to_use, exchange_rates) = rebuild_invoice_data(
+        ws,
+        entry_idx,
+        new_items,
+        discount,
+        render.strict,
+        &mut warnings,
+    )?;
+    if let Some(memo) = memo {
+        invoice_data.memo = Some(memo.trim().to_string()).filter(|m| !m.is_empty());
+    }
+    let Workspace {
+        dir, config, state, ..
+    } = ws;
+    let (cfg_dir, config) = (dir.as_path(), &*config);
+    let client = &invoice_data.client;
+    let tax_amount = invoice_data.tax_amount;
+    let retained = invoice_data.retention;
     let output_dir = resolve_output_dir(&config.pdf.output_dir, cfg_dir);
+
     std::fs::create_dir_all(&output_dir)?;
 
-    let pdf_filename = format!("{}.pdf", invoice_number);
-    let pdf_path = output_dir.join(&pdf_filename);
+    // Without explicit formats, reproduce every artifact recorded for the invoice
+    // (rather than the client's current preference)
+    let mut render = render.clone();
+    if render.formats.is_empty() {
+        render.formats = state.history[entry_idx]
+            .artifacts()
+            .iter()
+            .filter_map(|name| OutputFormat::from_file_name(name))
+            .collect();
+    }
+    let render = client_render_options(&render, client, config, cfg_dir);
 
-    // Generate PDF
-    generate_pdf(&invoice_data, &pdf_path)?;
+    // History records what is payable now, net of retention
+    let total = invoice_data.total - retained;
 
-    // Update history entry if items changed
-    if new_items.is_some() {
-        state.history[entry_idx].items = items_to_use;
-        state.history[entry_idx].total = total;
-        save_state(cfg_dir, &state)?;
+    // Locked invoices may be re-rendered as-is, but not edited or repriced
+    let previous = &state.history[entry_idx];
+    let total_changed = (previous.total - total).abs() >= 0.005;
+    let memo_changed = previous.memo != invoice_data.memo;
+    let unlock = previous.locked && (new_items.is_some() || total_changed || memo_changed);
+    if unlock && !force_unlock {
+        return Err(InvoiceError::InvoiceLocked(invoice_number.to_string()));
+    }
+    if render.strict {
+        let settings = config.invoice.for_client(client);
+        strict_check(&previous.client, &invoice_data, &settings, &warnings)?;
     }
 
-    Ok(pdf_path)
+    // Keep the previous version when the total changes
+    let previous = &state.history[entry_idx];
+    let revision = if total_changed {
+        Some(archive_revision(
+            &output_dir,
+            previous,
+            &items_to_use,
+            total,
+            &config.invoice,
+        )?)
+    } else {
+        None
+    };
+
+    // Generate artifacts
+    let base_path = output_dir.join(invoice_number);
+    let produced = match render_artifacts(&invoice_data, &base_path, &render) {
+        Ok(produced) => produced,
+        Err(e) => {
+            if let Some(revision) = &revision {
+                restore_revision(&output_dir, &state.history[entry_idx], revision);
+            }
+            return Err(e);
+        }
+    };
+    let files = artifact_names(&produced);
+    let issued = &state.history[entry_idx];
+    warnings.extend(copy_pdfs(
+        &produced,
+        config,
+        cfg_dir,
+        issued.date,
+        &issued.client,
+    ));
+
+    // Update history entry if items, total, or artifacts changed
+    let entry = &mut state.history[entry_idx];
+    let tax_changed = entry
+        .tax
+        .is_none_or(|tax| (tax - tax_amount).abs() >= 0.005);
+    let terms_changed = entry.currency != client.currency || entry.due_days != client.due_days;
+    if new_items.is_some()
+        || revision.is_some()
+        || entry.artifacts() != files
+        || force_unlock
+        || tax_changed
+        || terms_changed
+        || entry.exchange_rates != exchange_rates
+        || entry.discount != discount
+        || memo_changed
+        || entry.deterministic != render.deterministic
+    {
+        entry.currency = client.currency.clone();
+        entry.due_days = client.due_days;
+        entry.exchange_rates = exchange_rates;
+        entry.discount = discount;
+        entry.memo = invoice_data.memo.clone();
+        entry.deterministic = render.deterministic;
+        entry.items = items_to_use;
+        entry.total = total;
+        entry.tax = Some(tax_amount);
+        if let Some(retention) = &mut entry.retention {
+            retention.amount = retained;
+        }
+        entry.file = primary_file(&files);
+        entry.files = files;
+        entry.revisions.extend(revision);
+        if unlock {
+            entry.locked = false;
+        }
+        save_state(cfg_dir, state)?;
+    }
+    update_manifest(cfg_dir, invoice_number)?;
+
+    Ok(RegeneratedInvoice {
+        path: primary_path(produced),
+        warnings,
+    })
+}
+
+/// The invoice as issued, read from its JSON artifact, or else rebuilt
+/// from history the way regenerating it would, adding to `warnings` when
+/// the client record is gone
+pub fn issued_invoice_data(
+    ws: &mut Workspace,
+    invoice_number: &str,
+    warnings: &mut Vec<String>,
+) -> Result<InvoiceData> {
+    let entry_idx = ws
+        .state
+        .history
+        .iter()
+        .position(|e| e.number == invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.to_string()))?;
+
+    if let Some(data) = invoice_snapshot(&ws.output_dir(), &ws.state.history[entry_idx]) {
+        return Ok(data);
+    }
+    let discount = ws.state.history[entry_idx].discount;
+    rebuild_invoice_data(ws, entry_idx, None, discount, false, warnings).map(|(data, ..)| data)
 }
 
 /// Get the PDF path for an invoice
-pub fn get_invoice_path(cfg_dir: &PathBuf, invoice_number: &str) -> Result<PathBuf> {
+pub fn get_invoice_path(cfg_dir: &Path, invoice_number: &str) -> Result<PathBuf> {
     let config = load_config(cfg_dir)?;
     let state = load_state(cfg_dir)?;
 
@@ -204,118 +1190,439 @@ pub fn get_invoice_path(cfg_dir: &PathBuf, invoice_number: &str) -> Result<PathB
     Ok(pdf_path)
 }
 
-/// Generate a new invoice
+/// Get the path of a specific recorded artifact (e.g., the HTML version)
+pub fn get_invoice_artifact(
+    cfg_dir: &Path,
+    invoice_number: &str,
+    format: OutputFormat,
+) -> Result<PathBuf> {
+    let config = load_config(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+
+    let entry = state
+        .history
+        .iter()
+        .find(|e| e.number == invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.to_string()))?;
+
+    let file = entry
+        .artifacts()
+        .into_iter()
+        .find(|name| OutputFormat::from_file_name(name) == Some(format))
+        .ok_or_else(|| InvoiceError::ArtifactNotFound {
+            invoice: invoice_number.to_string(),
+            format: format.to_string(),
+        })?;
+
+    let output_dir = resolve_output_dir(&config.pdf.output_dir, cfg_dir);
+    let path = output_dir.join(file);
+    if !path.exists() {
+        return Err(InvoiceError::InvoiceFileNotFound(path));
+    }
+
+    Ok(path)
+}
+
+/// Generate a new invoice and record it in the workspace state. Nothing is
+/// printed; the result carries what was produced and any warnings.
 pub fn generate_invoice(
-    cfg_dir: &PathBuf,
+    ws: &mut Workspace,
     client_id: &str,
     items_input: &[String],
-    output_path: Option<PathBuf>,
-) -> Result<()> {
-    // Load all config
-    let config = load_config(cfg_dir)?;
-    let clients = load_clients(cfg_dir)?;
-    let items_catalog = load_items(cfg_dir)?;
-    let mut state = load_state(cfg_dir)?;
+    options: &GenerateOptions,
+) -> Result<GeneratedInvoice> {
+    let Workspace {
+        dir,
+        config,
+        clients,
+        items,
+        state,
+    } = ws;
+    let (cfg_dir, config, items_catalog) = (dir.as_path(), &*config, &*items);
 
     // Look up client
     let client = clients
         .get(client_id)
         .ok_or_else(|| InvoiceError::ClientNotFound(client_id.to_string()))?
         .clone();
+    let missing = client.missing_fields();
+    if !missing.is_empty() {
+        return Err(InvoiceError::IncompleteClient {
+            client: client_id.to_string(),
+            missing,
+        });
+    }
+    let payee = payee_settings(config, options.payee.as_deref())?;
+    let settings = config.invoice.for_client(&client);
 
-    // Parse and validate items
-    let mut line_items: Vec<InvoiceLineItem> = Vec::new();
+    let today = options.date.unwrap_or_else(|| Local::now().date_naive());
 
-    for input in items_input {
-        let (item_id, quantity) = parse_item_input(input)?;
+    // Link the contract this invoice bills against; prorated items are
+    // billed only for the days within its term
+    let contracts = load_contracts(cfg_dir)?;
+    let contract = match &options.contract {
+        Some(id) => {
+            let contract = contracts
+                .get(id)
+                .ok_or_else(|| InvoiceError::ContractNotFound(id.clone()))?;
+            if contract.client != client_id {
+                return Err(InvoiceError::ContractClientMismatch {
+                    contract: id.clone(),
+                    owner: contract.client.clone(),
+                    client: client_id.to_string(),
+                });
+            }
+            Some(id.clone())
+        }
+        None => active_contract(&contracts, client_id, today).map(str::to_string),
+    };
 
-        let item = items_catalog
-            .get(item_id)
-            .ok_or_else(|| InvoiceError::ItemNotFound(item_id.to_string()))?;
+    // Parse and validate items; history keeps them in their final order
+    let items_input = &sort_item_inputs(items_input, items_catalog, options.sort_items)?;
+    let exchange_rates = exchange_rates(
+        items_input,
+        items_catalog,
+        &settings.currency,
+        &options.exchange_rates,
+        &mut Rates::new(
+            &config.currency,
+            &mut state.rates,
+            Local::now().naive_local(),
+        )
+        .allow_stale(!options.render.strict),
+    )?;
+    let mut line_items = build_line_items(
+        items_input,
+        items_catalog,
+        &exchange_rates,
+        options.period,
+        contract.as_ref().and_then(|id| contracts.get(id)),
+    )?;
+    line_items.extend(options.lines.iter().cloned());
 
-        let amount = item.rate * quantity;
+    // Released retention was taxed on the original invoices, so a release
+    // invoice carries no tax of its own
+    for number in &options.release_retention {
+        let held = state.history.iter().find(|e| &e.number == number);
+        match held.and_then(|e| e.retention.as_ref()) {
+            Some(retention)
+                if retention.released_by.is_none()
+                    && held.is_some_and(|e| e.client == client_id) => {}
+            _ => return Err(InvoiceError::NoRetentionHeld(number.clone())),
+        }
+    }
+    line_items.extend(release_lines(&state.history, &options.release_retention)?);
 
-        line_items.push(InvoiceLineItem {
-            description: item.description.clone(),
-            quantity,
-            unit: item.unit.clone(),
-            rate: item.rate,
-            amount,
-        });
+    let deposit = match &options.deposit {
+        Some(request) => {
+            let project = config
+                .projects
+                .get(&request.project)
+                .ok_or_else(|| InvoiceError::ProjectNotFound(request.project.clone()))?;
+            let amount = round_cents(project.fixed_price * request.percent / 100.0);
+            line_items.push(deposit_line(
+                &config.projects,
+                &request.project,
+                request.percent,
+                amount,
+            ));
+            Some(Deposit {
+                project: request.project.clone(),
+                percent: request.percent,
+                amount,
+                deducted_by: None,
+            })
+        }
+        None => None,
+    };
+
+    // Advances already billed for these projects come off this invoice
+    let billed = project_amounts(items_input, items_catalog);
+    let (deducted, deferred) = deductible_deposits(&state.history, client_id, &billed);
+    line_items.extend(deduction_lines(&state.history, &deducted)?);
+    let line_items = group_by_kind(line_items);
+    if line_items.is_empty() {
+        return Err(InvoiceError::NoItems);
     }
+    let taxes = if options.release_retention.is_empty() {
+        settings.tax_rates()
+    } else {
+        Vec::new()
+    };
 
     // Calculate totals
-    let subtotal: f64 = line_items.iter().map(|i| i.amount).sum();
-    let tax_amount = subtotal * config.invoice.tax_rate;
-    let total = subtotal + tax_amount;
+    let discount = options.discount.filter(|d| !d.is_zero());
+    let Totals {
+        subtotal,
+        discount: discounted,
+        tax_amount,
+        taxes: tax_lines,
+        rounding_adjustment,
+        total,
+    } = compute_totals(&line_items, discount, &taxes);
+    let retention_percent = options.retention.unwrap_or(0.0);
 
     // Determine invoice number
-    let today = Local::now();
     let current_year = today.year() as u32;
 
-    let seq = if state.counter.last_year == current_year {
-        state.counter.last_number + 1
-    } else {
-        1 // Reset for new year
+    let series = options.series.as_deref().unwrap_or(DEFAULT_SERIES);
+    let number_format = config
+        .number_format(series)
+        .ok_or_else(|| InvoiceError::UnknownSeries(series.to_string()))?
+        .to_string();
+    let current = state.counter_for(series);
+    let next_seq = current.next_seq(current_year);
+
+    // A manual number only moves the counter forward when it belongs to the sequence
+    let (invoice_number, counter) = match &options.number {
+        Some(number) => {
+            validate_invoice_number(number)?;
+            let counter = parse_invoice_number(&number_format, number)
+                .filter(|&(year, seq)| (year, seq) > (current.last_year, current.last_number));
+            (number.clone(), counter)
+        }
+        None => (
+            format_invoice_number(&number_format, current_year, next_seq),
+            Some((current_year, next_seq)),
+        ),
     };
 
-    let invoice_number = format_invoice_number(&config.invoice.number_format, current_year, seq);
+    if state.history.iter().any(|e| e.number == invoice_number)
+        || trash_slot(cfg_dir, &invoice_number).exists()
+    {
+        return Err(InvoiceError::DuplicateInvoiceNumber(invoice_number));
+    }
+
+    // Voided and superseded invoices no longer bill anything
+    if !options.allow_duplicate && !items_input.is_empty() {
+        let hash = content_hash(client_id, items_input, today, options.period.as_ref());
+        if let Some(existing) = state.history.iter().find(|e| {
+            e.voided.is_none()
+                && e.superseded_by.is_none()
+                && content_hash(&e.client, &e.items, e.date, e.period.as_ref()) == hash
+        }) {
+            return Err(InvoiceError::DuplicateContent {
+                existing: existing.number.clone(),
+                client: client_id.to_string(),
+            });
+        }
+    }
 
     // Calculate dates
     let invoice_date = today.format("%B %d, %Y").to_string();
-    let due_date = today
-        .checked_add_signed(chrono::Duration::days(config.invoice.due_days as i64))
-        .unwrap_or(today)
-        .format("%B %d, %Y")
-        .to_string();
+    let due_date = settings.due_date(today).format("%B %d, %Y").to_string();
 
     // Build invoice data
-    let invoice_data = InvoiceData {
+    let mut invoice_data = InvoiceData {
+        schema_version: SCHEMA_VERSION,
         number: invoice_number.clone(),
+        display_number: client.display_number_format.as_deref().map(|format| {
+            let seq = state
+                .history
+                .iter()
+                .filter(|e| e.client == client_id && e.date.year() as u32 == current_year)
+                .count() as u32
+                + 1;
+            format_invoice_number(format, current_year, seq).replace("{number}", &invoice_number)
+        }),
         date: invoice_date,
         due_date,
         company: config.company.clone(),
         client: client.clone(),
         items: line_items,
         subtotal,
-        tax_rate: config.invoice.tax_rate * 100.0, // Convert to percentage
+        discount_percent: discount.map_or(0.0, Discount::percent),
+        discount: discounted,
+        tax_percent: tax_percent(&taxes),
         tax_amount,
+        taxes: tax_lines,
+        rounding_adjustment,
         total,
-        currency_symbol: config.invoice.currency_symbol.clone(),
-        due_days: config.invoice.due_days,
-        payment_terms: format!("Net {} days", config.invoice.due_days),
+        retention_percent,
+        retention: retention_amount(total, retention_percent),
+        currency: settings.currency.clone(),
+        currency_symbol: settings.currency_symbol.clone(),
+        symbol_position: settings.symbol_position,
+        symbol_space: settings.symbol_space,
+        due_days: settings.due_days,
+        payment_terms: format!("Net {} days", settings.due_days),
+        service_period: options.period.map(|p| p.to_string()),
+        payee,
+        fields: custom_fields(&client, &options.fields),
+        memo: options
+            .memo
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .map(str::to_string),
     };
 
+    // Let the user hand-tweak the computed data before rendering
+    if options.edit_json {
+        invoice_data = review_draft(invoice_data)?;
+    }
+    if options.render.strict {
+        strict_check(client_id, &invoice_data, &settings, &[])?;
+    }
+    let total = invoice_data.total;
+    let retained = invoice_data.retention;
+
     // Determine output path
     let output_dir = resolve_output_dir(&config.pdf.output_dir, cfg_dir);
     std::fs::create_dir_all(&output_dir)?;
 
-    let pdf_filename = format!("{}.pdf", invoice_number);
-    let pdf_path = output_path.unwrap_or_else(|| output_dir.join(&pdf_filename));
+    let base_path = options
+        .output_path
+        .clone()
+        .unwrap_or_else(|| output_dir.join(format!("{}.pdf", invoice_number)));
+
+    // Generate artifacts
+    let render = client_render_options(&options.render, &client, config, cfg_dir);
+    let produced = render_artifacts(&invoice_data, &base_path, &render)?;
+    let mut warnings = copy_pdfs(&produced, config, cfg_dir, today, client_id);
+    let files: Vec<String> = produced
+        .iter()
+        .filter_map(|p| p.extension().and_then(|e| e.to_str()))
+        .map(|ext| format!("{}.{}", invoice_number, ext))
+        .collect();
 
-    // Generate PDF
-    generate_pdf(&invoice_data, &pdf_path)?;
+    let spec = options
+        .spec
+        .as_deref()
+        .map(|source| store_spec(cfg_dir, &invoice_number, source))
+        .transpose()?;
+    let now = Local::now().naive_local();
+    let notes = options
+        .notes
+        .iter()
+        .filter(|text| !text.trim().is_empty())
+        .map(|text| Note {
+            at: now,
+            author: current_user(),
+            text: text.trim().to_string(),
+        })
+        .collect();
 
     // Update state
-    state.counter.last_number = seq;
-    state.counter.last_year = current_year;
+    if let Some((year, seq)) = counter {
+        let counter = state.counter_for_mut(series);
+        counter.last_number = seq;
+        counter.last_year = year;
+    }
     state.history.push(HistoryEntry {
         number: invoice_number.clone(),
         client: client_id.to_string(),
-        date: NaiveDate::from_ymd_opt(today.year(), today.month(), today.day()).unwrap(),
-        total,
-        file: pdf_filename,
+        date: today,
+        total: total - retained,
+        file: primary_file(&files),
         payments: vec![],
         items: items_input.to_vec(),
+        files,
+        revisions: vec![],
+        reminders: vec![],
+        payment_plan: None,
+        series: (series != DEFAULT_SERIES).then(|| series.to_string()),
+        locked: false,
+        period: options.period,
+        notes,
+        followups: Vec::new(),
+        superseded_by: None,
+        display_number: invoice_data.display_number.clone(),
+        retention: (retained > 0.0).then(|| {
+            let projects = project_amounts(items_input, items_catalog);
+            Retention {
+                percent: invoice_data.retention_percent,
+                amount: retained,
+                project: (projects.len() == 1)
+                    .then(|| projects.into_keys().next())
+                    .flatten(),
+                released_by: None,
+            }
+        }),
+        released_retention: options.release_retention.clone(),
+        deposit,
+        deducted_deposits: deducted.clone(),
+        tax: Some(invoice_data.tax_amount),
+        voided: None,
+        payee: options.payee.clone(),
+        currency: client.currency.clone(),
+        due_days: client.due_days,
+        exchange_rates,
+        fields: invoice_data.fields.clone(),
+        spec,
+        contract: contract.clone(),
+        discount,
+        memo: invoice_data.memo.clone(),
+        standing: None,
+        deterministic: render.deterministic,
     });
+    for entry in state
+        .history
+        .iter_mut()
+        .filter(|e| options.release_retention.contains(&e.number))
+    {
+        if let Some(retention) = &mut entry.retention {
+            retention.released_by = Some(invoice_number.clone());
+        }
+    }
+    for entry in state
+        .history
+        .iter_mut()
+        .filter(|e| deducted.contains(&e.number))
+    {
+        if let Some(deposit) = &mut entry.deposit {
+            deposit.deducted_by = Some(invoice_number.clone());
+        }
+    }
 
-    save_state(cfg_dir, &state)?;
+    save_state(cfg_dir, state)?;
+    update_manifest(cfg_dir, &invoice_number)?;
 
-    // Print summary
-    println!("Generated {}", invoice_number);
-    println!("  Client: {}", client.name);
-    println!("  Total:  {}{:.2}", config.invoice.currency_symbol, total);
-    println!("  Saved:  {}", pdf_path.display());
+    for (project, deposits) in &deferred {
+        warnings.push(format!(
+            "deposits of {} for project '{}' exceed what this invoice bills for it; \
+             they will be deducted from a later invoice",
+            config.invoice.money(*deposits),
+            project
+        ));
+    }
 
-    Ok(())
+    for status in contract_status(&contracts, &state.history) {
+        if contract.as_ref() == Some(&status.id) && status.is_over() {
+            warnings.push(format!(
+                "contract '{}' has been billed {}, over its value of {}",
+                status.id,
+                config.invoice.money(status.billed),
+                config.invoice.money(status.value)
+            ));
+        }
+    }
+
+    for budget in project_budgets(&config.projects, items_catalog, &state.history) {
+        if billed.contains_key(&budget.id) && budget.is_over() {
+            warnings.push(format!(
+                "project '{}' has been invoiced {}, over its fixed price of {}",
+                budget.id,
+                config.invoice.money(budget.invoiced),
+                config.invoice.money(budget.fixed_price)
+            ));
+        }
+    }
+
+    let path = primary_path(produced.clone());
+    let debug_files = if render.debug {
+        debug_artifacts(&path, &invoice_number)
+    } else {
+        Vec::new()
+    };
+    Ok(GeneratedInvoice {
+        number: invoice_number,
+        data: invoice_data,
+        path,
+        files: produced,
+        debug_files,
+        contract,
+        deducted,
+        warnings,
+    })
 }
@@ -0,0 +1,67 @@
+use chrono::{Datelike, NaiveDate};
+
+use crate::config::HistoryEntry;
+
+/// Progress toward the monthly revenue target for the month containing `today`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalProgress {
+    pub target: f64,
+    /// Total of invoices dated this month
+    pub invoiced: f64,
+    /// Payments received this month (on any invoice)
+    pub collected: f64,
+    /// Invoiced amount extrapolated to the end of the month at the current run rate
+    pub projected: f64,
+}
+
+impl GoalProgress {
+    /// Share of the target invoiced so far (0.0–1.0+)
+    pub fn invoiced_ratio(&self) -> f64 {
+        if self.target > 0.0 {
+            self.invoiced / self.target
+        } else {
+            0.0
+        }
+    }
+
+    /// Share of the target collected so far (0.0–1.0+)
+    pub fn collected_ratio(&self) -> f64 {
+        if self.target > 0.0 {
+            self.collected / self.target
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Number of days in the month containing `date`
+fn days_in_month(date: NaiveDate) -> u32 {
+    let first = date.with_day(1).unwrap_or(date);
+    first
+        .checked_add_months(chrono::Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .map_or(30, |last| last.day())
+}
+
+/// Measure this month's invoicing and collections against `target`
+pub fn monthly_progress(history: &[HistoryEntry], target: f64, today: NaiveDate) -> GoalProgress {
+    let this_month = |date: NaiveDate| date.year() == today.year() && date.month() == today.month();
+
+    let invoiced = history
+        .iter()
+        .filter(|e| this_month(e.date) && e.is_billed())
+        .fold(0.0, |sum, e| sum + e.total);
+    let collected = history
+        .iter()
+        .flat_map(|e| &e.payments)
+        .filter(|p| this_month(p.date))
+        .fold(0.0, |sum, p| sum + p.amount);
+    let projected = invoiced / today.day() as f64 * days_in_month(today) as f64;
+
+    GoalProgress {
+        target,
+        invoiced,
+        collected,
+        projected,
+    }
+}
@@ -0,0 +1,83 @@
+use chrono::{Datelike, NaiveDate};
+
+use super::summary::due_date;
+use crate::config::{Config, DayCount, HistoryEntry};
+
+/// Late interest accrued on an invoice
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccruedInterest {
+    pub due_date: NaiveDate,
+    /// Days between the due date and the calculation date
+    pub days_late: i64,
+    /// Balance still outstanding on the calculation date
+    pub outstanding: f64,
+    pub annual_rate: f64,
+    pub day_count: DayCount,
+    /// Interest rounded to cents
+    pub amount: f64,
+}
+
+/// Fraction of a year between two dates under a day-count convention
+pub fn year_fraction(from: NaiveDate, to: NaiveDate, day_count: DayCount) -> f64 {
+    match day_count {
+        DayCount::Actual365 => (to - from).num_days() as f64 / 365.0,
+        DayCount::Actual360 => (to - from).num_days() as f64 / 360.0,
+        DayCount::Thirty360 => {
+            let d1 = from.day().min(30) as i64;
+            let d2 = if d1 == 30 {
+                to.day().min(30) as i64
+            } else {
+                to.day() as i64
+            };
+            let days = 360 * (to.year() - from.year()) as i64
+                + 30 * (to.month() as i64 - from.month() as i64)
+                + (d2 - d1);
+            days as f64 / 360.0
+        }
+    }
+}
+
+/// Simple interest from the due date to `today`. Payments made after the due
+/// date reduce the balance from the day they were received.
+pub fn accrued_interest(
+    entry: &HistoryEntry,
+    config: &Config,
+    annual_rate: f64,
+    day_count: DayCount,
+    today: NaiveDate,
+) -> AccruedInterest {
+    let due = due_date(entry, config);
+
+    let mut payments: Vec<_> = entry.payments.iter().collect();
+    payments.sort_by_key(|p| p.date);
+
+    // Anything paid by the due date never accrues interest
+    let mut balance = entry.total
+        - payments
+            .iter()
+            .filter(|p| p.date <= due)
+            .map(|p| p.amount)
+            .sum::<f64>();
+    let mut interest = 0.0;
+    let mut from = due;
+
+    for payment in payments.iter().filter(|p| p.date > due && p.date <= today) {
+        if balance > 0.0 {
+            interest += balance * annual_rate * year_fraction(from, payment.date, day_count);
+        }
+        balance -= payment.amount;
+        from = payment.date;
+    }
+    if balance > 0.0 && today > from {
+        interest += balance * annual_rate * year_fraction(from, today, day_count);
+    }
+
+    AccruedInterest {
+        due_date: due,
+        days_late: (today - due).num_days().max(0),
+        outstanding: balance.max(0.0),
+        annual_rate,
+        day_count,
+        amount: (interest * 100.0).round() / 100.0,
+    }
+}
@@ -0,0 +1,424 @@
+//! Parsing of `--item` specs.
+//!
+//! A spec is `item:quantity` or `item:description:quantity`, where the
+//! description overrides the catalog one for this line. Fields are separated
+//! by `:`; a field can be double-quoted to contain colons
+//! (`design:"Logo: v2":3`), and `\` escapes the next character both inside
+//! and outside quotes (`design:Logo\: v2:3`). The quantity may be a small
+//! expression such as `3x8` or `2.5+1.25`, and may be followed by `@N` to
+//! pin the line to position N on the invoice (`retainer:1@1`) and by
+//! `@FROM..TO` or `@YYYY-MM` for the service period the line covers
+//! (`consulting:8@2026-01-01..2026-01-31`).
+
+use std::fmt;
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::config::ServicePeriod;
+use crate::error::{InvoiceError, Result};
+
+/// A parsed `--item` spec
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ItemSpec {
+    /// Catalog key from items.toml
+    pub item: String,
+    /// Line description replacing the catalog one
+    #[serde(default)]
+    pub description: Option<String>,
+    pub quantity: f64,
+    /// 1-based line position on the invoice; unpinned lines fill the rest
+    #[serde(default)]
+    pub position: Option<usize>,
+    /// Dates this line's service was rendered
+    #[serde(default)]
+    pub period: Option<ServicePeriod>,
+}
+
+/// Formats back to spec syntax, quoting fields where needed, so that
+/// `parse_item_spec` reads the same spec again
+impl fmt::Display for ItemSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", quote_field(&self.item))?;
+        if let Some(description) = &self.description {
+            write!(f, ":{}", quote_field(description))?;
+        }
+        write!(f, ":{}", self.quantity)?;
+        if let Some(position) = self.position {
+            write!(f, "@{position}")?;
+        }
+        if let Some(period) = self.period {
+            write!(f, "@{}..{}", period.from, period.to)?;
+        }
+        Ok(())
+    }
+}
+
+/// Quote a field containing separators, quotes or backslashes
+fn quote_field(field: &str) -> String {
+    if field.contains([':', '"', '\\']) {
+        format!("\"{}\"", field.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Parse an item spec like `consulting:8` or `design:"Logo: v2":3`
+pub fn parse_item_spec(input: &str) -> Result<ItemSpec> {
+    let invalid = || InvoiceError::InvalidItemFormat(input.to_string());
+
+    let mut fields = tokenize(input).ok_or_else(invalid)?;
+    let mut qty = fields.pop().ok_or_else(invalid)?;
+    let mut position = None;
+    let mut period = None;
+    // Dates always contain dashes, which tells a period from a position
+    while let Some((rest, suffix)) = qty.rsplit_once('@') {
+        let suffix = suffix.trim();
+        if suffix.contains('-') {
+            if period.is_some() {
+                return Err(invalid());
+            }
+            period = Some(parse_period(suffix).ok_or_else(invalid)?);
+        } else {
+            if position.is_some() {
+                return Err(invalid());
+            }
+            let pinned = suffix.parse::<usize>().ok().filter(|&p| p > 0);
+            position = Some(pinned.ok_or_else(invalid)?);
+        }
+        qty = rest.to_string();
+    }
+    let (item, description) = match fields.len() {
+        1 => (fields.remove(0), None),
+        2 => {
+            let description = fields.pop();
+            (fields.remove(0), description)
+        }
+        _ => return Err(invalid()),
+    };
+    if item.is_empty() || description.as_deref() == Some("") {
+        return Err(invalid());
+    }
+
+    let quantity = parse_quantity(&item, &qty)?;
+    Ok(ItemSpec {
+        item,
+        description,
+        quantity,
+        position,
+        period,
+    })
+}
+
+/// Parse `2026-01-01..2026-01-31` or a whole month like `2026-01`
+fn parse_period(input: &str) -> Option<ServicePeriod> {
+    let date = |s: &str| NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok();
+    match input.split_once("..") {
+        Some((from, to)) => {
+            let period = ServicePeriod {
+                from: date(from)?,
+                to: date(to)?,
+            };
+            (period.from <= period.to).then_some(period)
+        }
+        None => {
+            let (year, month) = input.split_once('-')?;
+            ServicePeriod::month(year.parse().ok()?, month.parse().ok()?)
+        }
+    }
+}
+
+/// Parse the quantity field of a spec (must be positive). Besides plain
+/// numbers it accepts products and sums such as `3x8` (days × hours) or
+/// `2.5+1.25` (summed sessions); `x` and `*` bind tighter than `+`.
+fn parse_quantity(item: &str, qty: &str) -> Result<f64> {
+    let invalid = |reason: &str| InvoiceError::InvalidQuantity {
+        item: item.to_string(),
+        qty: qty.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let quantity = eval_quantity(qty)
+        .filter(|q| q.is_finite())
+        .ok_or_else(|| invalid("must be a number or an expression like 3x8 or 2.5+1.25"))?;
+    if quantity <= 0.0 {
+        return Err(invalid("must be greater than 0"));
+    }
+
+    Ok(quantity)
+}
+
+/// Evaluate a sum of products of plain numbers
+fn eval_quantity(expr: &str) -> Option<f64> {
+    expr.split('+')
+        .map(|term| {
+            term.split(['x', 'X', '*'])
+                .map(|factor| factor.trim().parse::<f64>().ok())
+                .product::<Option<f64>>()
+        })
+        .sum()
+}
+
+/// Split a spec into its `:`-separated fields, honoring quotes and escapes.
+/// Returns None for an unterminated quote or a trailing backslash.
+fn tokenize(input: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => field.push(chars.next()?),
+            '"' => in_quotes = !in_quotes,
+            ':' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return None;
+    }
+    fields.push(field);
+
+    Some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(item: &str, description: Option<&str>, quantity: f64) -> ItemSpec {
+        ItemSpec {
+            item: item.to_string(),
+            description: description.map(str::to_string),
+            quantity,
+            position: None,
+            period: None,
+        }
+    }
+
+    fn is_format_error(input: &str) -> bool {
+        matches!(parse_item_spec(input), Err(InvoiceError::InvalidItemFormat(s)) if s == input)
+    }
+
+    const NOT_A_NUMBER: &str = "must be a number or an expression like 3x8 or 2.5+1.25";
+
+    fn quantity_error(input: &str) -> Option<String> {
+        match parse_item_spec(input) {
+            Err(InvoiceError::InvalidQuantity { reason, .. }) => Some(reason),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn parses_item_and_quantity() {
+        assert_eq!(
+            parse_item_spec("consulting:8").unwrap(),
+            spec("consulting", None, 8.0)
+        );
+        assert_eq!(
+            parse_item_spec("consulting:2.5").unwrap(),
+            spec("consulting", None, 2.5)
+        );
+        assert_eq!(
+            parse_item_spec("consulting: 3 ").unwrap(),
+            spec("consulting", None, 3.0)
+        );
+    }
+
+    #[test]
+    fn parses_description_override() {
+        assert_eq!(
+            parse_item_spec("design:Logo refresh:3").unwrap(),
+            spec("design", Some("Logo refresh"), 3.0)
+        );
+    }
+
+    #[test]
+    fn quoted_fields_may_contain_colons() {
+        assert_eq!(
+            parse_item_spec(r#"design:"Logo: v2":3"#).unwrap(),
+            spec("design", Some("Logo: v2"), 3.0)
+        );
+        assert_eq!(
+            parse_item_spec(r#""web:design":1"#).unwrap(),
+            spec("web:design", None, 1.0)
+        );
+        // Quotes can cover part of a field
+        assert_eq!(
+            parse_item_spec(r#"design:Logo" (v2: final)":1"#).unwrap(),
+            spec("design", Some("Logo (v2: final)"), 1.0)
+        );
+    }
+
+    #[test]
+    fn backslash_escapes_next_character() {
+        assert_eq!(
+            parse_item_spec(r"design:Logo\: v2:3").unwrap(),
+            spec("design", Some("Logo: v2"), 3.0)
+        );
+        assert_eq!(
+            parse_item_spec(r#"design:"Say \"hi\"":1"#).unwrap(),
+            spec("design", Some(r#"Say "hi""#), 1.0)
+        );
+        assert_eq!(
+            parse_item_spec(r"design:C\:\\temp:1").unwrap(),
+            spec("design", Some(r"C:\temp"), 1.0)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_specs() {
+        for input in [
+            "",
+            "consulting",
+            ":8",
+            "consulting::8",
+            "a:b:c:8",
+            r#"design:"Logo:3"#,
+            r"consulting:8\",
+            // An unescaped colon always splits
+            r"design:C:\temp:1",
+        ] {
+            assert!(
+                is_format_error(input),
+                "expected format error for {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for spec in [
+            spec("consulting", None, 8.0),
+            spec("consulting", None, 2.5),
+            spec("design", Some("Logo refresh"), 3.0),
+            spec("design", Some("Logo: v2"), 1.0),
+            spec("web:design", Some(r#"Say "hi""#), 1.0),
+            spec("design", Some(r"C:\temp"), 0.25),
+        ] {
+            assert_eq!(parse_item_spec(&spec.to_string()).unwrap(), spec);
+        }
+        assert_eq!(
+            spec("design", Some("Logo: v2"), 3.0).to_string(),
+            r#"design:"Logo: v2":3"#
+        );
+    }
+
+    #[test]
+    fn parses_pinned_position() {
+        let pinned = |position| ItemSpec {
+            position: Some(position),
+            ..spec("retainer", None, 1.0)
+        };
+        assert_eq!(parse_item_spec("retainer:1@1").unwrap(), pinned(1));
+        assert_eq!(parse_item_spec("retainer:1 @ 3").unwrap(), pinned(3));
+        assert_eq!(
+            parse_item_spec(r#"design:"a@b":2@2"#).unwrap(),
+            ItemSpec {
+                position: Some(2),
+                ..spec("design", Some("a@b"), 2.0)
+            }
+        );
+        assert_eq!(pinned(1).to_string(), "retainer:1@1");
+        assert_eq!(parse_item_spec(&pinned(4).to_string()).unwrap(), pinned(4));
+        for input in ["retainer:1@", "retainer:1@0", "retainer:1@first"] {
+            assert!(
+                is_format_error(input),
+                "expected format error for {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_service_period() {
+        let period = |from: &str, to: &str| ServicePeriod {
+            from: from.parse().unwrap(),
+            to: to.parse().unwrap(),
+        };
+        let january = ItemSpec {
+            period: Some(period("2026-01-01", "2026-01-31")),
+            ..spec("consulting", None, 8.0)
+        };
+        assert_eq!(
+            parse_item_spec("consulting:8@2026-01-01..2026-01-31").unwrap(),
+            january
+        );
+        assert_eq!(parse_item_spec("consulting:8@2026-01").unwrap(), january);
+        assert_eq!(
+            parse_item_spec("consulting:8@2@2026-01").unwrap(),
+            ItemSpec {
+                position: Some(2),
+                ..january.clone()
+            }
+        );
+        assert_eq!(
+            parse_item_spec("consulting:8@2026-01@2").unwrap().position,
+            Some(2)
+        );
+        assert_eq!(january.to_string(), "consulting:8@2026-01-01..2026-01-31");
+        for input in [
+            "consulting:8@2026-01-31..2026-01-01",
+            "consulting:8@2026-13",
+            "consulting:8@2026-01-01..",
+            "consulting:8@2026-01@2026-02",
+            "consulting:8@1@2",
+        ] {
+            assert!(
+                is_format_error(input),
+                "expected format error for {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn evaluates_quantity_expressions() {
+        let quantity = |input: &str| parse_item_spec(input).unwrap().quantity;
+
+        assert_eq!(quantity("consulting:3x8"), 24.0);
+        assert_eq!(quantity("consulting:3X8"), 24.0);
+        assert_eq!(quantity("consulting:3*8"), 24.0);
+        assert_eq!(quantity("consulting:2.5+1.25"), 3.75);
+        assert_eq!(quantity("consulting:2x4 + 1.5"), 9.5);
+        assert_eq!(quantity("consulting:1+2x3"), 7.0);
+        assert_eq!(quantity("consulting:2x3x4"), 24.0);
+        assert_eq!(quantity(r#"design:"Logo: v2":2x1.5"#), 3.0);
+    }
+
+    #[test]
+    fn rejects_bad_quantities() {
+        assert_eq!(quantity_error("consulting:"), Some(NOT_A_NUMBER.into()));
+        assert_eq!(quantity_error("consulting:abc"), Some(NOT_A_NUMBER.into()));
+        assert_eq!(quantity_error("consulting:NaN"), Some(NOT_A_NUMBER.into()));
+        assert_eq!(quantity_error("consulting:inf"), Some(NOT_A_NUMBER.into()));
+        assert_eq!(
+            quantity_error("consulting:0"),
+            Some("must be greater than 0".into())
+        );
+        assert_eq!(
+            quantity_error("consulting:-2"),
+            Some("must be greater than 0".into())
+        );
+        for input in [
+            "consulting:3x",
+            "consulting:+2",
+            "consulting:2++1",
+            "consulting:3x8h",
+        ] {
+            assert_eq!(
+                quantity_error(input),
+                Some(NOT_A_NUMBER.into()),
+                "{input:?}"
+            );
+        }
+        assert_eq!(
+            quantity_error("consulting:2x0"),
+            Some("must be greater than 0".into())
+        );
+        assert_eq!(
+            quantity_error(r#"design:"Logo":"#),
+            Some(NOT_A_NUMBER.into())
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{load_config, load_state, resolve_output_dir};
+use crate::error::{InvoiceError, Result};
+
+/// Checksum manifest kept in the output directory
+pub const MANIFEST_FILE: &str = "manifest.toml";
+
+/// SHA-256 of every generated file, grouped by invoice number
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub invoices: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// Problems found by `verify_files`
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Files checked against the manifest
+    pub checked: usize,
+    /// (invoice, file) recorded in the manifest but not on disk
+    pub missing: Vec<(String, String)>,
+    /// (invoice, file) whose contents no longer match the recorded checksum
+    pub modified: Vec<(String, String)>,
+    /// Files in the output directory the manifest doesn't know about
+    pub orphaned: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.modified.is_empty() && self.orphaned.is_empty()
+    }
+}
+
+/// Lowercase hex SHA-256 of `bytes`
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    digest(&SHA256, bytes)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn output_dir(cfg_dir: &Path) -> Result<PathBuf> {
+    let config = load_config(cfg_dir)?;
+    Ok(resolve_output_dir(&config.pdf.output_dir, cfg_dir))
+}
+
+fn load_manifest(output_dir: &Path) -> Result<Manifest> {
+    let path = output_dir.join(MANIFEST_FILE);
+    if !path.exists() {
+        return Ok(Manifest::default());
+    }
+    let content = fs::read_to_string(&path)?;
+    toml::from_str(&content).map_err(|e| InvoiceError::ConfigParse { path, source: e })
+}
+
+fn save_manifest(output_dir: &Path, manifest: &Manifest) -> Result<()> {
+    let content = toml::to_string_pretty(manifest).map_err(|e| {
+        InvoiceError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            e.to_string(),
+        ))
+    })?;
+    fs::create_dir_all(output_dir)?;
+    fs::write(output_dir.join(MANIFEST_FILE), content)?;
+    Ok(())
+}
+
+/// Checksums of the invoice's files that exist in the output directory
+fn hash_files<'a>(
+    output_dir: &Path,
+    files: impl IntoIterator<Item = &'a str>,
+) -> Result<BTreeMap<String, String>> {
+    let mut hashes = BTreeMap::new();
+    for name in files {
+        let path = output_dir.join(name);
+        if path.is_file() {
+            hashes.insert(name.to_string(), sha256_hex(&fs::read(&path)?));
+        }
+    }
+    Ok(hashes)
+}
+
+/// Re-record the checksums for one invoice after its files changed.
+/// Invoices no longer in history (e.g., trashed) are dropped from the manifest.
+pub fn update_manifest(cfg_dir: &Path, invoice_number: &str) -> Result<()> {
+    let output_dir = output_dir(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+    let mut manifest = load_manifest(&output_dir)?;
+
+    let hashes = match state.history.iter().find(|e| e.number == invoice_number) {
+        Some(entry) => hash_files(&output_dir, entry.owned_files())?,
+        None => BTreeMap::new(),
+    };
+
+    if hashes.is_empty() {
+        manifest.invoices.remove(invoice_number);
+    } else {
+        manifest.invoices.insert(invoice_number.to_string(), hashes);
+    }
+    save_manifest(&output_dir, &manifest)
+}
+
+/// Rebuild the manifest from the files currently on disk, accepting them as
+/// correct. Returns the number of files recorded.
+pub fn rebuild_manifest(cfg_dir: &Path) -> Result<usize> {
+    let output_dir = output_dir(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+
+    let mut manifest = Manifest::default();
+    for entry in &state.history {
+        let hashes = hash_files(&output_dir, entry.owned_files())?;
+        if !hashes.is_empty() {
+            manifest.invoices.insert(entry.number.clone(), hashes);
+        }
+    }
+
+    let count = manifest.invoices.values().map(BTreeMap::len).sum();
+    save_manifest(&output_dir, &manifest)?;
+    Ok(count)
+}
+
+/// Compare the output directory against the manifest
+pub fn verify_files(cfg_dir: &Path) -> Result<VerifyReport> {
+    let output_dir = output_dir(cfg_dir)?;
+    let manifest = load_manifest(&output_dir)?;
+    let mut report = VerifyReport::default();
+    let mut known = BTreeSet::new();
+
+    for (number, files) in &manifest.invoices {
+        for (name, expected) in files {
+            known.insert(name.as_str());
+            report.checked += 1;
+
+            let path = output_dir.join(name);
+            if !path.is_file() {
+                report.missing.push((number.clone(), name.clone()));
+            } else if sha256_hex(&fs::read(&path)?) != *expected {
+                report.modified.push((number.clone(), name.clone()));
+            }
+        }
+    }
+
+    if output_dir.exists() {
+        for dir_entry in fs::read_dir(&output_dir)? {
+            let dir_entry = dir_entry?;
+            let name = dir_entry.file_name().to_string_lossy().to_string();
+            if dir_entry.path().is_file()
+                && name != MANIFEST_FILE
+                && !name.starts_with('.')
+                && !known.contains(name.as_str())
+            {
+                report.orphaned.push(name);
+            }
+        }
+    }
+    report.orphaned.sort();
+
+    Ok(report)
+}
@@ -0,0 +1,86 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::item_spec::parse_item_spec;
+use crate::config::{HistoryEntry, Item};
+
+/// Revenue against cost for one item or client
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Margin {
+    /// Item or client id
+    pub id: String,
+    pub revenue: f64,
+    /// Cost of the lines whose item has a `cost`
+    pub cost: f64,
+    /// Revenue from lines whose item has no `cost`; left out of the margin
+    pub uncosted: f64,
+}
+
+impl Margin {
+    /// Revenue minus cost over the costed lines; None when nothing was costed
+    pub fn margin(&self) -> Option<f64> {
+        let costed = self.revenue - self.uncosted;
+        (costed.abs() >= 0.005).then_some(costed - self.cost)
+    }
+
+    /// Margin as a percentage of the costed revenue
+    pub fn margin_percent(&self) -> Option<f64> {
+        self.margin()
+            .map(|margin| margin / (self.revenue - self.uncosted) * 100.0)
+    }
+
+    fn add(&mut self, revenue: f64, cost: Option<f64>) {
+        self.revenue += revenue;
+        match cost {
+            Some(cost) => self.cost += cost,
+            None => self.uncosted += revenue,
+        }
+    }
+}
+
+/// Margins per item and per client, each sorted by id
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Margins {
+    pub items: Vec<Margin>,
+    pub clients: Vec<Margin>,
+}
+
+/// Revenue and cost of every billed line in history, priced from the
+/// current catalog and converted at the rates each invoice was issued with.
+/// Lines for items no longer in the catalog are skipped.
+pub fn item_margins(history: &[HistoryEntry], catalog: &HashMap<String, Item>) -> Margins {
+    let mut items: BTreeMap<String, Margin> = BTreeMap::new();
+    let mut clients: BTreeMap<String, Margin> = BTreeMap::new();
+
+    for entry in history.iter().filter(|e| e.is_billed()) {
+        for spec in entry
+            .items
+            .iter()
+            .filter_map(|input| parse_item_spec(input).ok())
+        {
+            let Some(item) = catalog.get(&spec.item) else {
+                continue;
+            };
+            let exchange_rate = item
+                .currency
+                .as_ref()
+                .and_then(|currency| entry.exchange_rates.get(&currency.to_uppercase()))
+                .copied()
+                .unwrap_or(1.0);
+            let revenue = item.rate * spec.quantity * exchange_rate;
+            let cost = item.cost.map(|cost| cost * spec.quantity * exchange_rate);
+            for (map, id) in [(&mut items, &spec.item), (&mut clients, &entry.client)] {
+                map.entry(id.clone())
+                    .or_insert_with(|| Margin {
+                        id: id.clone(),
+                        ..Margin::default()
+                    })
+                    .add(revenue, cost);
+            }
+        }
+    }
+
+    Margins {
+        items: items.into_values().collect(),
+        clients: clients.into_values().collect(),
+    }
+}
@@ -0,0 +1,258 @@
+//! Merging a second copy of state.toml into this one, such as the
+//! conflicted copy a file-sync tool leaves when two machines changed the
+//! state at the same time.
+
+use chrono::NaiveDate;
+
+use crate::config::state::Counter;
+use crate::config::{HistoryEntry, State};
+
+/// What merging another copy changed, and what it couldn't
+#[derive(Debug, Default)]
+pub struct MergeReport {
+    /// Invoices only the other copy had
+    pub added: Vec<String>,
+    /// Invoices that gained payments, reminders, notes, follow-ups or a
+    /// payment plan
+    pub updated: Vec<String>,
+    /// Payments taken from the other copy, as (invoice, date, amount).
+    /// A payment removed here but still in the other copy is among them
+    pub payments_added: Vec<(String, NaiveDate, f64)>,
+    pub credits_added: usize,
+    pub reports_added: usize,
+    /// Differences left for manual resolution; this copy's values are kept
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl MergeReport {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.updated.is_empty()
+            && self.payments_added.is_empty()
+            && self.credits_added == 0
+            && self.reports_added == 0
+            && self.conflicts.is_empty()
+    }
+}
+
+/// A field two copies disagree on
+#[derive(Debug)]
+pub struct MergeConflict {
+    /// Invoice or report number
+    pub number: String,
+    pub field: &'static str,
+    pub ours: String,
+    pub theirs: String,
+}
+
+/// Merge `theirs` into `ours`. Invoices are matched by number: new ones are
+/// added, and records only ever appended to (payments, reminders, notes,
+/// follow-ups) are unioned. Nothing records removals, so a payment removed
+/// on one side comes back; the payments taken are listed in the report for
+/// review. Invoices in `trashed` (this copy's trash) are not re-added but
+/// reported as conflicts. Credits and reports are unioned too, counters
+/// take the later position and cached rates the newer fetch.
+pub fn merge_states(ours: &mut State, theirs: &State, trashed: &[String]) -> MergeReport {
+    let mut report = MergeReport::default();
+
+    for other in &theirs.history {
+        match ours.history.iter_mut().find(|e| e.number == other.number) {
+            Some(entry) => merge_entry(entry, other, &mut report),
+            None if trashed.contains(&other.number) => {
+                report.conflicts.push(MergeConflict {
+                    number: other.number.clone(),
+                    field: "history",
+                    ours: "in the trash".to_string(),
+                    theirs: "issued".to_string(),
+                });
+            }
+            None => {
+                ours.history.push(other.clone());
+                report.added.push(other.number.clone());
+            }
+        }
+    }
+    ours.history
+        .sort_by(|a, b| (a.date, &a.number).cmp(&(b.date, &b.number)));
+
+    ours.counter = later(ours.counter, theirs.counter);
+    for (series, counter) in &theirs.series {
+        let merged = match ours.series.get(series) {
+            Some(&ours) => later(ours, *counter),
+            None => *counter,
+        };
+        ours.series.insert(series.clone(), merged);
+    }
+
+    for credit in &theirs.credits {
+        let known = ours.credits.iter().any(|c| {
+            c.client == credit.client
+                && c.date == credit.date
+                && same_amount(c.amount, credit.amount)
+                && c.invoice == credit.invoice
+        });
+        if !known {
+            ours.credits.push(credit.clone());
+            report.credits_added += 1;
+        }
+    }
+    ours.credits.sort_by_key(|c| c.date);
+
+    for other in &theirs.reports {
+        match ours.reports.iter().find(|r| r.number == other.number) {
+            Some(existing) if existing.file != other.file || existing.client != other.client => {
+                report.conflicts.push(MergeConflict {
+                    number: other.number.clone(),
+                    field: "report",
+                    ours: format!("{} ({})", existing.file, existing.client),
+                    theirs: format!("{} ({})", other.file, other.client),
+                });
+            }
+            Some(_) => {}
+            None => {
+                ours.reports.push(other.clone());
+                report.reports_added += 1;
+            }
+        }
+    }
+    ours.reports.sort_by_key(|r| r.date);
+
+    for (pair, rate) in &theirs.rates {
+        if ours
+            .rates
+            .get(pair)
+            .is_none_or(|r| r.fetched < rate.fetched)
+        {
+            ours.rates.insert(pair.clone(), *rate);
+        }
+    }
+
+    report
+}
+
+fn merge_entry(entry: &mut HistoryEntry, other: &HistoryEntry, report: &mut MergeReport) {
+    let mut conflict = |field, ours: String, theirs: String| {
+        if ours != theirs {
+            report.conflicts.push(MergeConflict {
+                number: entry.number.clone(),
+                field,
+                ours,
+                theirs,
+            });
+        }
+    };
+
+    // The same number issued twice for different invoices: nothing else
+    // from the other copy applies to this one
+    if entry.client != other.client {
+        conflict("client", entry.client.clone(), other.client.clone());
+        return;
+    }
+    conflict("date", entry.date.to_string(), other.date.to_string());
+    if !same_amount(entry.total, other.total) {
+        conflict(
+            "total",
+            format!("{:.2}", entry.total),
+            format!("{:.2}", other.total),
+        );
+    }
+    conflict("items", entry.items.join(", "), other.items.join(", "));
+    conflict(
+        "discount",
+        entry.discount.map(|d| d.to_string()).unwrap_or_default(),
+        other.discount.map(|d| d.to_string()).unwrap_or_default(),
+    );
+    conflict(
+        "memo",
+        entry.memo.clone().unwrap_or_default(),
+        other.memo.clone().unwrap_or_default(),
+    );
+    conflict(
+        "voided",
+        entry.voided.is_some().to_string(),
+        other.voided.is_some().to_string(),
+    );
+    conflict(
+        "superseded_by",
+        entry.superseded_by.clone().unwrap_or_default(),
+        other.superseded_by.clone().unwrap_or_default(),
+    );
+
+    let before = (
+        entry.payments.len(),
+        entry.reminders.len(),
+        entry.notes.len(),
+        entry.followups.len(),
+        entry.payment_plan.is_some(),
+    );
+    for payment in &other.payments {
+        if !entry
+            .payments
+            .iter()
+            .any(|p| p.date == payment.date && same_amount(p.amount, payment.amount))
+        {
+            entry.payments.push(payment.clone());
+            report
+                .payments_added
+                .push((entry.number.clone(), payment.date, payment.amount));
+        }
+    }
+    for reminder in &other.reminders {
+        if !entry
+            .reminders
+            .iter()
+            .any(|r| r.level == reminder.level && r.date == reminder.date)
+        {
+            entry.reminders.push(reminder.clone());
+        }
+    }
+    for note in &other.notes {
+        if !entry
+            .notes
+            .iter()
+            .any(|n| n.at == note.at && n.text == note.text)
+        {
+            entry.notes.push(note.clone());
+        }
+    }
+    for followup in &other.followups {
+        if !entry
+            .followups
+            .iter()
+            .any(|f| f.date == followup.date && f.via == followup.via)
+        {
+            entry.followups.push(followup.clone());
+        }
+    }
+    if entry.payment_plan.is_none() && other.payment_plan.is_some() {
+        entry.payment_plan = other.payment_plan.clone();
+    }
+
+    let after = (
+        entry.payments.len(),
+        entry.reminders.len(),
+        entry.notes.len(),
+        entry.followups.len(),
+        entry.payment_plan.is_some(),
+    );
+    if after != before {
+        entry.payments.sort_by_key(|p| p.date);
+        entry.reminders.sort_by_key(|r| r.date);
+        entry.notes.sort_by_key(|n| n.at);
+        entry.followups.sort_by_key(|f| f.date);
+        report.updated.push(entry.number.clone());
+    }
+}
+
+/// The counter further along
+fn later(a: Counter, b: Counter) -> Counter {
+    if (b.last_year, b.last_number) > (a.last_year, a.last_number) {
+        b
+    } else {
+        a
+    }
+}
+
+fn same_amount(a: f64, b: f64) -> bool {
+    (a - b).abs() < 0.005
+}
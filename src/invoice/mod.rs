@@ -1,5 +1,85 @@
+mod aging;
+mod builder;
+mod cleanup;
+mod consolidate;
+mod contracts;
+mod deposit;
+mod diff;
+mod digest;
+mod dunning;
 mod generator;
+mod goals;
+mod interest;
+mod item_spec;
+mod manifest;
+mod margins;
+mod merge;
+mod notes;
+mod ordering;
+mod overdue;
+mod plan;
+mod projects;
+mod proration;
 mod report;
+mod retainer;
+mod retention;
+mod snapshot;
+mod spec;
+mod standing;
+mod statement;
+mod summary;
+mod timesheet;
+mod trash;
+mod void;
 
-pub use generator::{generate_invoice, get_invoice_path, regenerate_invoice, InvoiceData};
-pub use report::{ReportData, ReportInvoiceRow, ReportPayment};
+pub use aging::{aging_report, create_aging_report, AgingColumns, AgingReport, AgingRow};
+pub use builder::InvoiceBuilder;
+pub use cleanup::orphaned_pdfs;
+pub use consolidate::consolidate_invoices;
+pub use contracts::{active_contract, contract_amount, contract_status, ContractStatus};
+pub use deposit::{pending_deposits, DepositRequest};
+pub use diff::{diff_invoice, DiffLine, InvoiceDiff, LineChange};
+pub use digest::{
+    aging_bucket, receivables_digest, render_digest, AgingBucket, AgingChange, Digest,
+    DigestInvoice, DigestPayment,
+};
+pub use dunning::{days_overdue, dunning_level_due, record_reminder, render_reminder};
+#[cfg(feature = "test-support")]
+pub(crate) use generator::{compute_totals, tax_percent, Totals};
+pub use generator::{
+    generate_invoice, get_invoice_artifact, get_invoice_path, issued_invoice_data,
+    regenerate_invoice, validate_invoice_number, GenerateOptions, GeneratedInvoice, InvoiceData,
+    InvoiceLineItem, InvoiceTax, OriginalPrice, RegeneratedInvoice,
+};
+pub use goals::{monthly_progress, GoalProgress};
+pub use interest::{accrued_interest, year_fraction, AccruedInterest};
+pub use item_spec::{parse_item_spec, ItemSpec};
+pub use manifest::{
+    rebuild_manifest, sha256_hex, update_manifest, verify_files, Manifest, VerifyReport,
+    MANIFEST_FILE,
+};
+pub use margins::{item_margins, Margin, Margins};
+pub use merge::{merge_states, MergeConflict, MergeReport};
+pub use notes::{add_note, record_followup, search_notes};
+pub use ordering::{sort_item_inputs, ItemSort};
+pub use overdue::{aging_totals, overdue_by_client, OverdueClient, OverdueInvoice};
+pub use plan::{
+    create_payment_plan, plan_adherence, schedule_installments, InstallmentStatus, PaymentPlanData,
+    PlanInstallmentRow,
+};
+pub use projects::{project_amounts, project_budgets, ProjectBudget};
+pub use proration::{prorate, within_term, Proration};
+pub use report::{
+    month_groups, next_report_number, ReportData, ReportGroup, ReportGroupBy, ReportInvoiceRow,
+    ReportPayment,
+};
+pub use retainer::{true_up, TrueUp};
+pub use retention::{held_retention, retained_by_project, retention_amount};
+pub use snapshot::{client_or_snapshot, invoice_snapshot};
+pub use spec::{parse_generate_spec, GenerateSpec, SpecDiscount, SpecItem, SpecLine};
+pub use standing::{refresh_standings, standing};
+pub use statement::{create_unpaid_statement, unpaid_statement, StatementData, StatementRow};
+pub use summary::{due_date, render_summary, SummaryFormat};
+pub use timesheet::{read_timesheet, timesheet_items, timesheet_period, TimeEntry};
+pub use trash::{list_trash, purge_trash, restore_invoice, trash_invoice, TrashedInvoice};
+pub use void::void_invoice;
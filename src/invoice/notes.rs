@@ -0,0 +1,56 @@
+use chrono::Local;
+use std::path::Path;
+
+use super::generator::current_user;
+use crate::config::{load_state, save_state, FollowUp, HistoryEntry, Note, State};
+use crate::error::{InvoiceError, Result};
+
+/// Attach a timestamped internal note to an invoice
+pub fn add_note(cfg_dir: &Path, invoice_number: &str, text: &str) -> Result<Note> {
+    if text.trim().is_empty() {
+        return Err(InvoiceError::EmptyNote);
+    }
+
+    let mut state = load_state(cfg_dir)?;
+
+    let entry = state
+        .history
+        .iter_mut()
+        .find(|e| e.number == invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.to_string()))?;
+
+    let note = Note {
+        at: Local::now().naive_local(),
+        author: current_user(),
+        text: text.trim().to_string(),
+    };
+    entry.notes.push(note.clone());
+    save_state(cfg_dir, &state)?;
+
+    Ok(note)
+}
+
+/// Notes whose text contains `query` (case-insensitive), in history order
+pub fn search_notes<'a>(state: &'a State, query: &str) -> Vec<(&'a HistoryEntry, &'a Note)> {
+    let query = query.to_lowercase();
+    state
+        .history
+        .iter()
+        .flat_map(|entry| entry.notes.iter().map(move |note| (entry, note)))
+        .filter(|(_, note)| note.text.to_lowercase().contains(&query))
+        .collect()
+}
+
+/// Record a follow-up contact about an invoice's payment
+pub fn record_followup(cfg_dir: &Path, invoice_number: &str, followup: FollowUp) -> Result<()> {
+    let mut state = load_state(cfg_dir)?;
+
+    let entry = state
+        .history
+        .iter_mut()
+        .find(|e| e.number == invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.to_string()))?;
+    entry.followups.push(followup);
+
+    save_state(cfg_dir, &state)
+}
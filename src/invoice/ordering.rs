@@ -0,0 +1,96 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use super::item_spec::parse_item_spec;
+use crate::config::Item;
+use crate::error::{InvoiceError, Result};
+
+/// Order of the lines on a new invoice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ItemSort {
+    /// As given on the command line
+    #[default]
+    None,
+    /// Largest amount first
+    ByAmount,
+    /// Alphabetically by line description
+    ByDescription,
+}
+
+impl fmt::Display for ItemSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemSort::None => write!(f, "none"),
+            ItemSort::ByAmount => write!(f, "by-amount"),
+            ItemSort::ByDescription => write!(f, "by-description"),
+        }
+    }
+}
+
+impl FromStr for ItemSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "none" => Ok(ItemSort::None),
+            "by-amount" | "amount" => Ok(ItemSort::ByAmount),
+            "by-description" | "description" => Ok(ItemSort::ByDescription),
+            other => Err(format!(
+                "unknown item order '{other}' (expected by-amount, by-description or none)"
+            )),
+        }
+    }
+}
+
+/// Reorder `--item` specs; ties keep their given order. Pinned positions
+/// (`@N`) are applied later, when the lines are built.
+pub fn sort_item_inputs(
+    inputs: &[String],
+    catalog: &HashMap<String, Item>,
+    sort: ItemSort,
+) -> Result<Vec<String>> {
+    if sort == ItemSort::None {
+        return Ok(inputs.to_vec());
+    }
+
+    let mut keyed = inputs
+        .iter()
+        .map(|input| {
+            let spec = parse_item_spec(input)?;
+            let item = catalog
+                .get(&spec.item)
+                .ok_or_else(|| InvoiceError::ItemNotFound(spec.item.clone()))?;
+            let description = spec
+                .description
+                .unwrap_or_else(|| item.description.clone())
+                .to_lowercase();
+            Ok((input.clone(), item.rate * spec.quantity, description))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    match sort {
+        ItemSort::None => {}
+        ItemSort::ByAmount => keyed.sort_by(|a, b| b.1.total_cmp(&a.1)),
+        ItemSort::ByDescription => keyed.sort_by(|a, b| a.2.cmp(&b.2)),
+    }
+    Ok(keyed.into_iter().map(|(input, _, _)| input).collect())
+}
+
+/// Move lines pinned to a 1-based position there; the others keep their
+/// relative order around them. Positions past the end go last.
+pub(crate) fn apply_positions<T>(lines: Vec<(T, Option<usize>)>) -> Vec<T> {
+    let (mut pinned, unpinned): (Vec<_>, Vec<_>) = lines
+        .into_iter()
+        .partition(|(_, position)| position.is_some());
+    pinned.sort_by_key(|(_, position)| *position);
+
+    let mut ordered: Vec<T> = unpinned.into_iter().map(|(line, _)| line).collect();
+    for (line, position) in pinned {
+        let index = position.unwrap_or(1).saturating_sub(1).min(ordered.len());
+        ordered.insert(index, line);
+    }
+    ordered
+}
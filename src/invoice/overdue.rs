@@ -0,0 +1,81 @@
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+use super::digest::{aging_bucket, AgingBucket};
+use super::dunning::days_overdue;
+use super::summary::due_date;
+use crate::config::{Config, HistoryEntry};
+
+/// A client's invoices past their due date
+#[derive(Debug, Clone)]
+pub struct OverdueClient {
+    pub client: String,
+    /// Most overdue first
+    pub invoices: Vec<OverdueInvoice>,
+    pub outstanding: f64,
+}
+
+impl OverdueClient {
+    /// Days the client's oldest overdue invoice is late
+    pub fn max_days(&self) -> i64 {
+        self.invoices.iter().map(|i| i.days).max().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OverdueInvoice {
+    pub number: String,
+    pub due: NaiveDate,
+    pub days: i64,
+    pub bucket: AgingBucket,
+    pub outstanding: f64,
+}
+
+/// Unpaid and partly paid invoices past their due date on `today`, grouped
+/// by client. The client owed the most comes first.
+pub fn overdue_by_client(
+    history: &[HistoryEntry],
+    config: &Config,
+    today: NaiveDate,
+) -> Vec<OverdueClient> {
+    let mut clients: BTreeMap<&str, OverdueClient> = BTreeMap::new();
+    for entry in history.iter().filter(|e| e.is_billed() && !e.is_settled()) {
+        let days = days_overdue(entry, config, today);
+        if days <= 0 {
+            continue;
+        }
+        let client = clients
+            .entry(&entry.client)
+            .or_insert_with(|| OverdueClient {
+                client: entry.client.clone(),
+                invoices: Vec::new(),
+                outstanding: 0.0,
+            });
+        client.outstanding += entry.outstanding();
+        client.invoices.push(OverdueInvoice {
+            number: entry.number.clone(),
+            due: due_date(entry, config),
+            days,
+            bucket: aging_bucket(days),
+            outstanding: entry.outstanding(),
+        });
+    }
+
+    let mut clients: Vec<OverdueClient> = clients.into_values().collect();
+    for client in &mut clients {
+        client.invoices.sort_by_key(|i| std::cmp::Reverse(i.days));
+    }
+    clients.sort_by(|a, b| b.outstanding.total_cmp(&a.outstanding));
+    clients
+}
+
+/// Outstanding balance and invoice count in each aging bucket, oldest last
+pub fn aging_totals(clients: &[OverdueClient]) -> BTreeMap<AgingBucket, (f64, usize)> {
+    let mut totals = BTreeMap::new();
+    for invoice in clients.iter().flat_map(|c| &c.invoices) {
+        let (amount, count) = totals.entry(invoice.bucket).or_insert((0.0, 0));
+        *amount += invoice.outstanding;
+        *count += 1;
+    }
+    totals
+}
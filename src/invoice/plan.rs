@@ -0,0 +1,173 @@
+use chrono::{Local, Months, NaiveDate};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::manifest::update_manifest;
+use super::snapshot::client_or_snapshot;
+use crate::config::{
+    load_clients, load_config, load_state, resolve_output_dir, save_state, Client, Company,
+    HistoryEntry, Installment, PaymentPlan, SymbolPosition,
+};
+use crate::error::{InvoiceError, Result};
+use crate::pdf::generate_payment_plan_pdf;
+
+/// One row of the installment schedule on the agreement
+#[derive(Debug, Serialize)]
+pub struct PlanInstallmentRow {
+    pub index: usize,
+    pub due_date: String,
+    pub amount: f64,
+}
+
+/// Complete data for rendering the payment plan agreement PDF
+#[derive(Debug, Serialize)]
+pub struct PaymentPlanData {
+    pub number: String,
+    pub date: String,
+    pub company: Company,
+    pub client: Client,
+    pub invoice_date: String,
+    pub invoice_total: f64,
+    pub principal: f64,
+    pub installments: Vec<PlanInstallmentRow>,
+    pub currency_symbol: String,
+    pub symbol_position: SymbolPosition,
+    pub symbol_space: bool,
+}
+
+/// Where an installment stands against recorded payments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallmentStatus {
+    Paid,
+    Late,
+    Upcoming,
+}
+
+impl fmt::Display for InstallmentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstallmentStatus::Paid => write!(f, "PAID"),
+            InstallmentStatus::Late => write!(f, "LATE"),
+            InstallmentStatus::Upcoming => write!(f, "UPCOMING"),
+        }
+    }
+}
+
+/// Split `principal` into `count` monthly installments starting on `start`.
+/// Amounts are whole cents; the last installment absorbs the remainder.
+pub fn schedule_installments(principal: f64, count: u32, start: NaiveDate) -> Vec<Installment> {
+    let cents = (principal * 100.0).round() as i64;
+    let base = cents / count as i64;
+    let remainder = cents - base * count as i64;
+
+    (0..count)
+        .map(|i| Installment {
+            due: start.checked_add_months(Months::new(i)).unwrap_or(start),
+            amount: (base + if i + 1 == count { remainder } else { 0 }) as f64 / 100.0,
+        })
+        .collect()
+}
+
+/// Status of each installment, treating payments received since the plan was
+/// agreed as paying installments off in order
+pub fn plan_adherence(
+    entry: &HistoryEntry,
+    plan: &PaymentPlan,
+    today: NaiveDate,
+) -> Vec<InstallmentStatus> {
+    let paid_before_plan = entry.total - plan.principal;
+    let covered = entry.paid_amount() - paid_before_plan;
+
+    let mut cumulative = 0.0;
+    plan.installments
+        .iter()
+        .map(|installment| {
+            cumulative += installment.amount;
+            if covered + 0.005 >= cumulative {
+                InstallmentStatus::Paid
+            } else if installment.due < today {
+                InstallmentStatus::Late
+            } else {
+                InstallmentStatus::Upcoming
+            }
+        })
+        .collect()
+}
+
+/// Agree a payment plan for an invoice's outstanding balance and render the
+/// agreement PDF next to the invoice. Replaces any existing plan.
+pub fn create_payment_plan(
+    cfg_dir: &Path,
+    invoice_number: &str,
+    installments: u32,
+    start: NaiveDate,
+    warnings: &mut Vec<String>,
+) -> Result<(PaymentPlan, PathBuf)> {
+    let config = load_config(cfg_dir)?;
+    let clients = load_clients(cfg_dir)?;
+    let mut state = load_state(cfg_dir)?;
+
+    let entry_idx = state
+        .history
+        .iter()
+        .position(|e| e.number == invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.to_string()))?;
+    let entry = &state.history[entry_idx];
+
+    let principal = (entry.outstanding() * 100.0).round() / 100.0;
+    if principal <= 0.0 {
+        return Err(InvoiceError::NothingOutstanding(invoice_number.to_string()));
+    }
+
+    let output_dir = resolve_output_dir(&config.pdf.output_dir, cfg_dir);
+    let client = client_or_snapshot(
+        &clients,
+        &state.history,
+        &output_dir,
+        &entry.client,
+        warnings,
+    )?;
+
+    let schedule = schedule_installments(principal, installments, start);
+    let today = Local::now().date_naive();
+
+    let data = PaymentPlanData {
+        number: entry.number.clone(),
+        date: today.format("%B %d, %Y").to_string(),
+        company: config.company.clone(),
+        client,
+        invoice_date: entry.date.format("%B %d, %Y").to_string(),
+        invoice_total: entry.total,
+        principal,
+        installments: schedule
+            .iter()
+            .enumerate()
+            .map(|(i, installment)| PlanInstallmentRow {
+                index: i + 1,
+                due_date: installment.due.format("%B %d, %Y").to_string(),
+                amount: installment.amount,
+            })
+            .collect(),
+        currency_symbol: config.invoice.currency_symbol.clone(),
+        symbol_position: config.invoice.symbol_position,
+        symbol_space: config.invoice.symbol_space,
+    };
+
+    std::fs::create_dir_all(&output_dir)?;
+    let file = format!("{}.plan.pdf", entry.number);
+    let path = output_dir.join(&file);
+    generate_payment_plan_pdf(&data, &path)?;
+
+    let plan = PaymentPlan {
+        created: today,
+        principal,
+        installments: schedule,
+        file,
+    };
+    state.history[entry_idx].payment_plan = Some(plan.clone());
+    save_state(cfg_dir, &state)?;
+    update_manifest(cfg_dir, invoice_number)?;
+
+    Ok((plan, path))
+}
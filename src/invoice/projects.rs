@@ -0,0 +1,71 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::item_spec::parse_item_spec;
+use crate::config::{HistoryEntry, Item, ProjectSettings};
+
+/// Agreed fixed price of a project against what its linked items have billed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectBudget {
+    pub id: String,
+    pub description: Option<String>,
+    pub fixed_price: f64,
+    /// Sum of linked line items across all invoices in history
+    pub invoiced: f64,
+}
+
+impl ProjectBudget {
+    /// Amount left to invoice; negative once the fixed price is exceeded
+    pub fn remaining(&self) -> f64 {
+        self.fixed_price - self.invoiced
+    }
+
+    /// Whether cumulative invoicing is over the fixed price (ignoring sub-cent noise)
+    pub fn is_over(&self) -> bool {
+        self.remaining() < -0.005
+    }
+}
+
+/// Amount billed for each project by the given `--item` specs, priced from
+/// the current catalog. Items without a `project` are skipped.
+pub fn project_amounts(
+    inputs: &[String],
+    catalog: &HashMap<String, Item>,
+) -> BTreeMap<String, f64> {
+    let mut amounts = BTreeMap::new();
+    for spec in inputs
+        .iter()
+        .filter_map(|input| parse_item_spec(input).ok())
+    {
+        let Some(item) = catalog.get(&spec.item) else {
+            continue;
+        };
+        if let Some(project) = &item.project {
+            *amounts.entry(project.clone()).or_insert(0.0) += item.rate * spec.quantity;
+        }
+    }
+    amounts
+}
+
+/// Budget status of every configured project, sorted by id
+pub fn project_budgets(
+    projects: &BTreeMap<String, ProjectSettings>,
+    catalog: &HashMap<String, Item>,
+    history: &[HistoryEntry],
+) -> Vec<ProjectBudget> {
+    let mut invoiced: BTreeMap<String, f64> = BTreeMap::new();
+    for entry in history {
+        for (project, amount) in project_amounts(&entry.items, catalog) {
+            *invoiced.entry(project).or_insert(0.0) += amount;
+        }
+    }
+
+    projects
+        .iter()
+        .map(|(id, settings)| ProjectBudget {
+            id: id.clone(),
+            description: settings.description.clone(),
+            fixed_price: settings.fixed_price,
+            invoiced: invoiced.get(id).copied().unwrap_or(0.0),
+        })
+        .collect()
+}
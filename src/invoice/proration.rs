@@ -0,0 +1,100 @@
+//! Prorating recurring items billed for part of a cycle, such as the first
+//! month of a contract that starts mid-month or the last week of one that
+//! ends mid-week.
+
+use chrono::{Datelike, Days, Months, NaiveDate};
+
+use crate::config::{Contract, Recurrence, ServicePeriod};
+
+/// The cycles of a recurring item a service period covers
+#[derive(Debug, Clone, PartialEq)]
+pub struct Proration {
+    /// Cycles covered, e.g. 0.5484 for 17 days of a 31-day month
+    pub factor: f64,
+    /// How `factor` was worked out, e.g. "17/31 days + 1 month"
+    pub label: String,
+    /// Whether a cycle is only partly covered
+    pub partial: bool,
+}
+
+/// Cycles of `recurrence` that `period` covers: one for each whole cycle,
+/// and the share of its days for a partial one
+pub fn prorate(recurrence: Recurrence, period: ServicePeriod) -> Proration {
+    let mut factor = 0.0;
+    let mut parts = Vec::new();
+    let mut whole = 0;
+    let mut start = period.from;
+    while start <= period.to {
+        let (first, last) = cycle(recurrence, start);
+        let days = (last.min(period.to) - start).num_days() + 1;
+        let length = (last - first).num_days() + 1;
+        if days == length {
+            whole += 1;
+            factor += 1.0;
+        } else {
+            if whole > 0 {
+                parts.push(cycles_label(recurrence, whole));
+                whole = 0;
+            }
+            parts.push(format!("{days}/{length} days"));
+            factor += days as f64 / length as f64;
+        }
+        let Some(next) = last.succ_opt() else { break };
+        start = next;
+    }
+    let partial = !parts.is_empty();
+    if whole > 0 {
+        parts.push(cycles_label(recurrence, whole));
+    }
+    Proration {
+        factor,
+        label: parts.join(" + "),
+        partial,
+    }
+}
+
+/// `period` cut down to the days `contract` is in effect, so the first and
+/// last cycles of an engagement are billed from its start and to its end.
+/// A period entirely outside the contract is left as it is.
+pub fn within_term(period: ServicePeriod, contract: Option<&Contract>) -> ServicePeriod {
+    let Some(contract) = contract else {
+        return period;
+    };
+    let from = period.from.max(contract.start);
+    let to = contract.end.map_or(period.to, |end| period.to.min(end));
+    if from <= to {
+        ServicePeriod { from, to }
+    } else {
+        period
+    }
+}
+
+/// First and last day of the cycle containing `date`
+fn cycle(recurrence: Recurrence, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+    match recurrence {
+        Recurrence::Weekly => {
+            let first = date - Days::new(u64::from(date.weekday().num_days_from_monday()));
+            (first, first + Days::new(6))
+        }
+        Recurrence::Monthly => {
+            let first = date.with_day(1).unwrap_or(date);
+            let last = first
+                .checked_add_months(Months::new(1))
+                .and_then(|next| next.pred_opt())
+                .unwrap_or(date);
+            (first, last)
+        }
+    }
+}
+
+fn cycles_label(recurrence: Recurrence, count: u32) -> String {
+    let unit = match recurrence {
+        Recurrence::Weekly => "week",
+        Recurrence::Monthly => "month",
+    };
+    if count == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{count} {unit}s")
+    }
+}
@@ -1,16 +1,55 @@
+use chrono::Datelike;
 use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
 
-use crate::config::{Client, Company};
+use super::generator::format_invoice_number;
+use crate::config::{Client, Company, HistoryEntry, ReportEntry, SymbolPosition};
+use crate::rates::Conversion;
+
+/// Numbering of client reports, a sequence of its own restarting each year
+pub const REPORT_NUMBER_FORMAT: &str = "RPT-{year}-{seq:04}";
+
+/// Number for the next report generated in `year`
+pub fn next_report_number(reports: &[ReportEntry], year: i32) -> String {
+    let seq = reports.iter().filter(|r| r.date.year() == year).count() as u32 + 1;
+    format_invoice_number(REPORT_NUMBER_FORMAT, year as u32, seq)
+}
+
+/// How report rows are grouped under headings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportGroupBy {
+    Month,
+}
+
+impl fmt::Display for ReportGroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportGroupBy::Month => write!(f, "month"),
+        }
+    }
+}
+
+impl FromStr for ReportGroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "month" => Ok(ReportGroupBy::Month),
+            other => Err(format!("unknown grouping '{other}' (expected month)")),
+        }
+    }
+}
 
 /// A single payment line item for display in report detail rows
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ReportPayment {
     pub amount: f64,
     pub date: String,
 }
 
 /// A single row in the invoice report table
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ReportInvoiceRow {
     pub number: String,
     pub date: String,
@@ -19,21 +58,100 @@ pub struct ReportInvoiceRow {
     pub outstanding: f64,
     pub payments: Vec<ReportPayment>,
     pub status: String,
+    /// `[payees]` entry collecting payment, for factored invoices
+    pub payee: Option<String>,
+}
+
+impl ReportInvoiceRow {
+    /// Row for `entry`; with `net`, amounts leave out the tax
+    pub fn from_entry(entry: &HistoryEntry, net: bool) -> Self {
+        let scale = if net { entry.net_ratio() } else { 1.0 };
+        ReportInvoiceRow {
+            number: entry.number.clone(),
+            date: entry.date.format("%B %d, %Y").to_string(),
+            total: entry.total * scale,
+            paid: entry.paid_amount() * scale,
+            outstanding: entry.outstanding() * scale,
+            payments: entry
+                .payments
+                .iter()
+                .map(|p| ReportPayment {
+                    amount: p.amount * scale,
+                    date: p.date.format("%B %d, %Y").to_string(),
+                })
+                .collect(),
+            status: entry.status().to_string(),
+            payee: entry.payee.clone(),
+        }
+    }
+}
+
+/// Rows under one heading, with their subtotals
+#[derive(Debug, Serialize)]
+pub struct ReportGroup {
+    pub label: String,
+    pub rows: Vec<ReportInvoiceRow>,
+    pub total: f64,
+    pub paid: f64,
+    pub outstanding: f64,
+}
+
+/// Group entries by the month they were issued, in date order
+pub fn month_groups(entries: &[&HistoryEntry], net: bool) -> Vec<ReportGroup> {
+    let mut sorted = entries.to_vec();
+    sorted.sort_by_key(|e| e.date);
+
+    let mut groups: Vec<ReportGroup> = Vec::new();
+    for entry in sorted {
+        let label = entry.date.format("%B %Y").to_string();
+        if groups.last().is_none_or(|g| g.label != label) {
+            groups.push(ReportGroup {
+                label,
+                rows: Vec::new(),
+                total: 0.0,
+                paid: 0.0,
+                outstanding: 0.0,
+            });
+        }
+        let Some(group) = groups.last_mut() else {
+            continue;
+        };
+        let row = ReportInvoiceRow::from_entry(entry, net);
+        if entry.is_billed() {
+            group.total += row.total;
+        }
+        group.paid += row.paid;
+        group.rows.push(row);
+        group.outstanding = group.total - group.paid;
+    }
+    groups
 }
 
 /// Complete data for rendering the invoice report PDF
 #[derive(Debug, Serialize)]
 pub struct ReportData {
+    /// Report number (e.g., "RPT-2026-0001")
+    pub number: String,
     pub company: Company,
     pub client: Client,
     pub client_id: String,
     pub rows: Vec<ReportInvoiceRow>,
+    /// Rows grouped under headings (e.g., by month); empty when ungrouped
+    pub groups: Vec<ReportGroup>,
     pub total: f64,
     pub paid: f64,
     pub outstanding: f64,
+    /// Part of `outstanding` owed to alternate payees rather than the company
+    pub factored_outstanding: f64,
+    /// `outstanding` in the `[currency] display` currency, when it differs
+    pub converted_outstanding: Option<Conversion>,
     pub currency_symbol: String,
+    pub symbol_position: SymbolPosition,
+    pub symbol_space: bool,
     pub generated_date: String,
     pub filter_from: Option<String>,
     pub filter_to: Option<String>,
     pub filter_status: Option<String>,
+    /// Amounts exclude the tax charged on each invoice
+    pub net: bool,
 }
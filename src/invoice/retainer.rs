@@ -0,0 +1,63 @@
+//! Retainer true-ups: billing the hours tracked beyond what a monthly
+//! retainer includes.
+
+use std::collections::BTreeMap;
+
+use super::item_spec::ItemSpec;
+use super::timesheet::TimeEntry;
+use crate::config::{Retainer, ServicePeriod};
+
+/// Tracked hours measured against a retainer's allotment
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrueUp {
+    pub included: f64,
+    pub worked: f64,
+    /// Hours beyond `included`; zero when the allotment covered them all
+    pub overage: f64,
+}
+
+impl TrueUp {
+    /// The line billing the overage, None when there is nothing to bill
+    pub fn item_spec(&self, retainer: &Retainer) -> Option<ItemSpec> {
+        (self.overage > 0.0).then(|| ItemSpec {
+            item: retainer.overage_item.clone(),
+            description: Some(format!(
+                "Hours beyond the {} included ({} worked)",
+                self.included, self.worked
+            )),
+            quantity: self.overage,
+            position: None,
+            period: None,
+        })
+    }
+
+    /// Custom fields that print the calculation on the invoice
+    pub fn fields(&self) -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("Included hours".to_string(), self.included.to_string()),
+            ("Hours worked".to_string(), self.worked.to_string()),
+            ("Overage hours".to_string(), self.overage.to_string()),
+        ])
+    }
+}
+
+/// Compare the hours of `entries` within `period` (all of them when None)
+/// with the hours `retainer` includes
+pub fn true_up(
+    retainer: &Retainer,
+    entries: &[TimeEntry],
+    period: Option<ServicePeriod>,
+) -> TrueUp {
+    let worked: f64 = entries
+        .iter()
+        .filter(|e| period.is_none_or(|p| e.date >= p.from && e.date <= p.to))
+        .map(|e| e.hours)
+        .sum();
+    let worked = (worked * 100.0).round() / 100.0;
+    let overage = ((worked - retainer.included_hours).max(0.0) * 100.0).round() / 100.0;
+    TrueUp {
+        included: retainer.included_hours,
+        worked,
+        overage,
+    }
+}
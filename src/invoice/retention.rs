@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+use super::generator::InvoiceLineItem;
+use crate::config::{HistoryEntry, ItemKind};
+use crate::error::{InvoiceError, Result};
+use crate::money::round_cents;
+
+/// Amount held back from `total` at `percent` (e.g., 10.0)
+pub fn retention_amount(total: f64, percent: f64) -> f64 {
+    round_cents(total * percent / 100.0)
+}
+
+/// Invoices of `client` whose retention hasn't been billed yet, optionally
+/// only those for `project`
+pub fn held_retention<'a>(
+    history: &'a [HistoryEntry],
+    client: &str,
+    project: Option<&str>,
+) -> Vec<&'a HistoryEntry> {
+    history
+        .iter()
+        .filter(|e| e.client == client)
+        .filter(|e| {
+            e.retention.as_ref().is_some_and(|r| {
+                r.released_by.is_none() && project.is_none_or(|p| r.project.as_deref() == Some(p))
+            })
+        })
+        .collect()
+}
+
+/// Unreleased retention per project, for invoices linked to one
+pub fn retained_by_project(history: &[HistoryEntry]) -> BTreeMap<String, f64> {
+    let mut retained = BTreeMap::new();
+    for retention in history.iter().filter_map(|e| e.retention.as_ref()) {
+        if let (Some(project), None) = (&retention.project, &retention.released_by) {
+            *retained.entry(project.clone()).or_insert(0.0) += retention.amount;
+        }
+    }
+    retained
+}
+
+/// One line per released invoice billing the amount it held back
+pub(crate) fn release_lines(
+    history: &[HistoryEntry],
+    released: &[String],
+) -> Result<Vec<InvoiceLineItem>> {
+    released
+        .iter()
+        .map(|number| {
+            let retention = history
+                .iter()
+                .find(|e| &e.number == number)
+                .ok_or_else(|| InvoiceError::InvoiceNotFound(number.clone()))?
+                .retention
+                .as_ref()
+                .ok_or_else(|| InvoiceError::NoRetentionHeld(number.clone()))?;
+            Ok(InvoiceLineItem {
+                description: format!(
+                    "Retention release: {} ({}% held)",
+                    number, retention.percent
+                ),
+                quantity: 1.0,
+                unit: "release".to_string(),
+                rate: retention.amount,
+                amount: retention.amount,
+                kind: ItemKind::Service,
+                original: None,
+                period: None,
+                taxable: true,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,68 @@
+//! Fallbacks for history that outlived its clients.toml and items.toml
+//! records. The JSON artifact written for an invoice captures the client
+//! and lines exactly as issued, so it stands in for deleted records; when
+//! there is none, documents are rendered with a placeholder client.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::generator::InvoiceData;
+use crate::config::{Client, HistoryEntry};
+use crate::error::{InvoiceError, Result};
+use crate::pdf::OutputFormat;
+
+/// The invoice as issued, read back from its JSON artifact if it has one
+pub fn invoice_snapshot(output_dir: &Path, entry: &HistoryEntry) -> Option<InvoiceData> {
+    entry
+        .artifacts()
+        .into_iter()
+        .filter(|name| OutputFormat::from_file_name(name) == Some(OutputFormat::Json))
+        .find_map(|name| {
+            let json = std::fs::read_to_string(output_dir.join(name)).ok()?;
+            serde_json::from_str(&json).ok()
+        })
+}
+
+/// The client's clients.toml record. If it has been removed, the details
+/// saved with the client's newest invoice that has a JSON artifact, or
+/// else a placeholder carrying the currency and terms history recorded;
+/// either way a warning added to `warnings` says which was used. A client
+/// no invoice was ever issued to is not found.
+pub fn client_or_snapshot(
+    clients: &HashMap<String, Client>,
+    history: &[HistoryEntry],
+    output_dir: &Path,
+    client_id: &str,
+    warnings: &mut Vec<String>,
+) -> Result<Client> {
+    if let Some(client) = clients.get(client_id) {
+        return Ok(client.clone());
+    }
+
+    let mut entries = history.iter().rev().filter(|e| e.client == client_id);
+    let latest = entries
+        .clone()
+        .next()
+        .ok_or_else(|| InvoiceError::ClientNotFound(client_id.to_string()))?;
+    if let Some((number, data)) =
+        entries.find_map(|e| invoice_snapshot(output_dir, e).map(|data| (&e.number, data)))
+    {
+        warnings.push(format!(
+            "client '{client_id}' is missing from clients.toml; \
+             using the details saved with {number}"
+        ));
+        return Ok(data.client);
+    }
+
+    warnings.push(format!(
+        "client '{client_id}' is missing from clients.toml and no saved \
+         details were found; rendering with a placeholder. Add it back to \
+         clients.toml to restore its name and address."
+    ));
+    Ok(Client {
+        name: format!("Client record missing ({client_id})"),
+        currency: latest.currency.clone(),
+        due_days: latest.due_days,
+        ..Client::default()
+    })
+}
@@ -0,0 +1,174 @@
+//! Whole-invoice specifications for `generate --stdin` and `generate --spec`.
+//!
+//! A spec is JSON or TOML with the same fields as the `generate` flags, plus
+//! lines that aren't in the item catalog, discounts and notes:
+//!
+//! ```toml
+//! client = "acme"
+//! date = "2026-02-01"
+//! items = [
+//!     "consulting:8",
+//!     { item = "design", description = "Logo: v2", quantity = 3 },
+//!     { description = "Travel to Denver", quantity = 1, rate = 420.0, unit = "trip", kind = "expense" },
+//! ]
+//! discounts = [{ description = "Returning client", amount = 100.0 }]
+//! notes = ["Approved by Jane on the kickoff call"]
+//! period = { from = "2026-01-01", to = "2026-01-31" }
+//! formats = ["pdf", "html"]
+//! fields = { "Cost Center" = "1234" }
+//! ```
+//!
+//! With `--spec`, a copy of the file is kept under `specs/` in the config
+//! dir and `regenerate` rebuilds the invoice from it.
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use super::generator::InvoiceLineItem;
+use super::item_spec::ItemSpec;
+use super::ordering::ItemSort;
+use crate::config::{ItemKind, ServicePeriod};
+use crate::error::{InvoiceError, Result};
+use crate::pdf::OutputFormat;
+
+/// Everything needed to generate one invoice
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenerateSpec {
+    /// Client identifier from clients.toml
+    pub client: String,
+    pub items: Vec<SpecItem>,
+    /// Use this invoice number instead of the next one from the counter
+    #[serde(default)]
+    pub number: Option<String>,
+    /// Numbering series from config.toml
+    #[serde(default)]
+    pub series: Option<String>,
+    #[serde(default)]
+    pub period: Option<ServicePeriod>,
+    #[serde(default)]
+    pub formats: Vec<OutputFormat>,
+    /// Generate even if an invoice this month bills the same items
+    #[serde(default)]
+    pub allow_duplicate: bool,
+    /// Line order: "by-amount", "by-description" or "none"
+    #[serde(default)]
+    pub sort_items: ItemSort,
+    /// `[payees]` entry to direct payment to
+    #[serde(default)]
+    pub payee: Option<String>,
+    /// Custom fields, e.g. `fields = { "Cost Center" = "1234" }`
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    /// Invoice date (default: today)
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+    /// Amounts taken off, each shown as its own negative line
+    #[serde(default)]
+    pub discounts: Vec<SpecDiscount>,
+    /// Internal notes attached to the invoice, as with `invoice note add`
+    #[serde(default)]
+    pub notes: Vec<String>,
+    /// contracts.toml entry to bill against
+    #[serde(default)]
+    pub contract: Option<String>,
+}
+
+/// A line item, either in `--item` syntax or spelled out
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SpecItem {
+    Spec(String),
+    Item(ItemSpec),
+    Line(SpecLine),
+}
+
+/// A line priced in the spec itself rather than from items.toml
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpecLine {
+    pub description: String,
+    pub quantity: f64,
+    pub rate: f64,
+    #[serde(default = "default_unit")]
+    pub unit: String,
+    #[serde(default)]
+    pub kind: ItemKind,
+    #[serde(default)]
+    pub period: Option<ServicePeriod>,
+    /// Whether the invoice's taxes apply to this line
+    #[serde(default = "taxable")]
+    pub taxable: bool,
+}
+
+fn taxable() -> bool {
+    true
+}
+
+fn default_unit() -> String {
+    "unit".to_string()
+}
+
+/// A fixed amount off the invoice
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpecDiscount {
+    pub description: String,
+    pub amount: f64,
+}
+
+impl GenerateSpec {
+    /// Catalog items in `--item` syntax, as stored in history
+    pub fn item_inputs(&self) -> Vec<String> {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                SpecItem::Spec(spec) => Some(spec.clone()),
+                SpecItem::Item(item) => Some(item.to_string()),
+                SpecItem::Line(_) => None,
+            })
+            .collect()
+    }
+
+    /// Invoice lines for the items priced in the spec, then the discounts
+    pub fn lines(&self) -> Vec<InvoiceLineItem> {
+        let lines = self.items.iter().filter_map(|item| match item {
+            SpecItem::Line(line) => Some(InvoiceLineItem {
+                description: line.description.clone(),
+                quantity: line.quantity,
+                unit: line.unit.clone(),
+                rate: line.rate,
+                amount: line.rate * line.quantity,
+                kind: line.kind,
+                original: None,
+                period: line.period.map(|p| p.to_string()),
+                taxable: line.taxable,
+            }),
+            _ => None,
+        });
+        let discounts = self.discounts.iter().map(|discount| InvoiceLineItem {
+            description: discount.description.clone(),
+            quantity: 1.0,
+            unit: "discount".to_string(),
+            rate: -discount.amount.abs(),
+            amount: -discount.amount.abs(),
+            kind: ItemKind::Service,
+            original: None,
+            period: None,
+            taxable: true,
+        });
+        lines.chain(discounts).collect()
+    }
+}
+
+/// Parse a spec as JSON when it starts with `{`, TOML otherwise
+pub fn parse_generate_spec(input: &str) -> Result<GenerateSpec> {
+    let result = if input.trim_start().starts_with('{') {
+        serde_json::from_str(input).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(input).map_err(|e| e.to_string())
+    };
+    result.map_err(InvoiceError::InvalidSpec)
+}
@@ -0,0 +1,39 @@
+use chrono::NaiveDate;
+
+use super::summary::due_date;
+use crate::config::state::PaymentStatus;
+use crate::config::{Config, HistoryEntry, Standing};
+use crate::money::round_cents;
+
+/// Where `entry` stands on `today`: its payment status, due date, whether
+/// it is overdue and by how many days it is (or was paid) late
+pub fn standing(entry: &HistoryEntry, config: &Config, today: NaiveDate) -> Standing {
+    let status = entry.status();
+    let due = due_date(entry, config);
+    let owed = matches!(status, PaymentStatus::Unpaid | PaymentStatus::Partial);
+    let days_late = match status {
+        _ if owed => (today - due).num_days(),
+        PaymentStatus::Paid => entry
+            .payments
+            .iter()
+            .map(|p| p.date)
+            .max()
+            .map_or(0, |paid| (paid - due).num_days()),
+        _ => 0,
+    };
+    Standing {
+        as_of: today,
+        status,
+        due,
+        overdue: owed && today > due,
+        days_late: days_late.max(0),
+        outstanding: round_cents(entry.outstanding().max(0.0)),
+    }
+}
+
+/// Record each invoice's standing on `today` in its history entry
+pub fn refresh_standings(history: &mut [HistoryEntry], config: &Config, today: NaiveDate) {
+    for entry in history {
+        entry.standing = Some(standing(entry, config, today));
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use super::dunning::days_overdue;
+use super::snapshot::client_or_snapshot;
+use super::summary::due_date;
+use crate::config::{
+    load_clients, load_config, load_state, resolve_output_dir, Client, Company, Config,
+    HistoryEntry, SymbolPosition,
+};
+use crate::error::Result;
+use crate::pdf::generate_statement_pdf;
+
+/// One unpaid invoice on the statement
+#[derive(Debug, Serialize)]
+pub struct StatementRow {
+    pub number: String,
+    pub date: String,
+    pub due_date: String,
+    /// Days past due; zero or negative when not yet due
+    pub days_overdue: i64,
+    pub total: f64,
+    pub paid: f64,
+    pub outstanding: f64,
+    /// Who collects this invoice when it isn't the company (e.g., a factor)
+    pub payee: Option<String>,
+}
+
+/// Complete data for rendering the unpaid-invoices statement PDF
+#[derive(Debug, Serialize)]
+pub struct StatementData {
+    pub date: String,
+    pub company: Company,
+    pub client: Client,
+    pub rows: Vec<StatementRow>,
+    pub total_due: f64,
+    pub payment_link: Option<String>,
+    pub bank_details: Option<String>,
+    pub currency_symbol: String,
+    pub symbol_position: SymbolPosition,
+    pub symbol_space: bool,
+}
+
+/// Unpaid and partially paid invoices of `client_id`, oldest due date first
+pub fn unpaid_statement(
+    history: &[HistoryEntry],
+    config: &Config,
+    client_id: &str,
+    client: Client,
+    today: NaiveDate,
+) -> StatementData {
+    let mut open: Vec<&HistoryEntry> = history
+        .iter()
+        .filter(|e| e.client == client_id && !e.is_settled())
+        .collect();
+    open.sort_by_key(|e| (due_date(e, config), e.number.clone()));
+
+    let rows: Vec<StatementRow> = open
+        .iter()
+        .map(|e| StatementRow {
+            number: e.number.clone(),
+            date: e.date.format("%b %d, %Y").to_string(),
+            due_date: due_date(e, config).format("%b %d, %Y").to_string(),
+            days_overdue: days_overdue(e, config, today),
+            total: e.total,
+            paid: e.paid_amount(),
+            outstanding: e.outstanding(),
+            payee: e.payee.as_deref().map(|id| {
+                config
+                    .payees
+                    .get(id)
+                    .map_or(id, |p| p.name.as_str())
+                    .to_string()
+            }),
+        })
+        .collect();
+
+    StatementData {
+        date: today.format("%B %d, %Y").to_string(),
+        company: config.company.clone(),
+        client,
+        total_due: rows.iter().map(|r| r.outstanding).sum(),
+        rows,
+        payment_link: config.payment.link.clone(),
+        bank_details: config.payment.bank_details.clone(),
+        currency_symbol: config.invoice.currency_symbol.clone(),
+        symbol_position: config.invoice.symbol_position,
+        symbol_space: config.invoice.symbol_space,
+    }
+}
+
+/// Render the statement for a client to `output_dir/STATEMENT-<client>-<date>.pdf`.
+/// Returns None when the client has nothing unpaid.
+pub fn create_unpaid_statement(
+    cfg_dir: &Path,
+    client_id: &str,
+    warnings: &mut Vec<String>,
+) -> Result<Option<(StatementData, PathBuf)>> {
+    let config = load_config(cfg_dir)?;
+    let clients = load_clients(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+
+    let output_dir = resolve_output_dir(&config.pdf.output_dir, cfg_dir);
+    let client = client_or_snapshot(&clients, &state.history, &output_dir, client_id, warnings)?;
+
+    let today = Local::now().date_naive();
+    let data = unpaid_statement(&state.history, &config, client_id, client, today);
+    if data.rows.is_empty() {
+        return Ok(None);
+    }
+
+    std::fs::create_dir_all(&output_dir)?;
+    let path = output_dir.join(format!(
+        "STATEMENT-{}-{}.pdf",
+        client_id,
+        today.format("%Y-%m-%d")
+    ));
+    generate_statement_pdf(&data, &path)?;
+
+    Ok(Some((data, path)))
+}
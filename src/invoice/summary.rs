@@ -0,0 +1,151 @@
+use chrono::NaiveDate;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::config::{Config, HistoryEntry};
+use crate::pdf::escape_html;
+
+/// Markup used for a shareable invoice summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SummaryFormat {
+    #[default]
+    Text,
+    Markdown,
+    Html,
+}
+
+impl fmt::Display for SummaryFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SummaryFormat::Text => write!(f, "text"),
+            SummaryFormat::Markdown => write!(f, "md"),
+            SummaryFormat::Html => write!(f, "html"),
+        }
+    }
+}
+
+impl FromStr for SummaryFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" | "txt" => Ok(SummaryFormat::Text),
+            "md" | "markdown" => Ok(SummaryFormat::Markdown),
+            "html" => Ok(SummaryFormat::Html),
+            other => Err(format!(
+                "unknown summary format '{other}' (expected text, md or html)"
+            )),
+        }
+    }
+}
+
+/// Due date for an invoice, using the payment terms it was issued with
+pub fn due_date(entry: &HistoryEntry, config: &Config) -> NaiveDate {
+    let due_days = entry.due_days.unwrap_or(config.invoice.due_days);
+    config.invoice.due_date_in(entry.date, due_days)
+}
+
+/// Render a short summary of an invoice (number, amount due, due date and
+/// payment details) for pasting into messages to the client
+pub fn render_summary(
+    entry: &HistoryEntry,
+    client_name: &str,
+    config: &Config,
+    format: SummaryFormat,
+) -> String {
+    let amount = config.invoice.money(entry.outstanding().max(0.0));
+    let due = due_date(entry, config).format("%Y-%m-%d").to_string();
+    // Factored invoices are paid to the payee, never to the company
+    let payee = entry.payee.as_deref().and_then(|id| config.payees.get(id));
+    let (link, bank_details) = match payee {
+        Some(payee) => (payee.link.as_deref(), payee.bank_details.as_deref()),
+        None => (
+            config.payment.link.as_deref(),
+            config.payment.bank_details.as_deref(),
+        ),
+    };
+    let link = link.map(str::trim);
+    let bank: Vec<&str> = bank_details
+        .map(|details| {
+            details
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut out = String::new();
+    match format {
+        SummaryFormat::Text => {
+            out.push_str(&format!("Invoice {} for {}\n", entry.number, client_name));
+            out.push_str(&format!("Amount due: {}\n", amount));
+            out.push_str(&format!("Due date: {}\n", due));
+            if let Some(payee) = payee {
+                out.push_str(&format!("Pay to: {}\n", payee.name));
+            }
+            if let Some(link) = link {
+                out.push_str(&format!("Pay online: {}\n", link));
+            }
+            if !bank.is_empty() {
+                out.push_str("Bank details:\n");
+                for line in &bank {
+                    out.push_str(&format!("  {}\n", line));
+                }
+            }
+        }
+        SummaryFormat::Markdown => {
+            out.push_str(&format!(
+                "**Invoice {}** for {}\n\n",
+                entry.number, client_name
+            ));
+            out.push_str(&format!("- **Amount due:** {}\n", amount));
+            out.push_str(&format!("- **Due date:** {}\n", due));
+            if let Some(payee) = payee {
+                out.push_str(&format!("- **Pay to:** {}\n", payee.name));
+            }
+            if let Some(link) = link {
+                out.push_str(&format!("- **Pay online:** <{}>\n", link));
+            }
+            if !bank.is_empty() {
+                out.push_str("\n**Bank details**\n\n");
+                for line in &bank {
+                    out.push_str(&format!("    {}\n", line));
+                }
+            }
+        }
+        SummaryFormat::Html => {
+            out.push_str(&format!(
+                "<p><strong>Invoice {}</strong> for {}</p>\n<ul>\n",
+                escape_html(&entry.number),
+                escape_html(client_name)
+            ));
+            out.push_str(&format!(
+                "  <li><strong>Amount due:</strong> {}</li>\n",
+                escape_html(&amount)
+            ));
+            out.push_str(&format!("  <li><strong>Due date:</strong> {}</li>\n", due));
+            if let Some(payee) = payee {
+                out.push_str(&format!(
+                    "  <li><strong>Pay to:</strong> {}</li>\n",
+                    escape_html(&payee.name)
+                ));
+            }
+            if let Some(link) = link {
+                let link = escape_html(link);
+                out.push_str(&format!(
+                    "  <li><strong>Pay online:</strong> <a href=\"{link}\">{link}</a></li>\n"
+                ));
+            }
+            out.push_str("</ul>\n");
+            if !bank.is_empty() {
+                let lines: Vec<String> = bank.iter().map(|l| escape_html(l)).collect();
+                out.push_str(&format!(
+                    "<p><strong>Bank details</strong><br>\n{}</p>\n",
+                    lines.join("<br>\n")
+                ));
+            }
+        }
+    }
+    out
+}
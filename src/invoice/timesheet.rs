@@ -0,0 +1,165 @@
+//! Reading time-tracker CSV exports into invoice lines.
+
+use chrono::NaiveDate;
+
+use super::item_spec::ItemSpec;
+use crate::config::{ImportProfile, Rounding, ServicePeriod};
+use crate::error::{InvoiceError, Result};
+
+/// One row of a timesheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeEntry {
+    pub date: NaiveDate,
+    /// Hours after rounding
+    pub hours: f64,
+    pub description: Option<String>,
+}
+
+/// Parse a CSV timesheet whose first row holds the column names
+pub fn read_timesheet(source: &str, profile: &ImportProfile) -> Result<Vec<TimeEntry>> {
+    let delimiter = profile.delimiter.unwrap_or(',');
+    let date_format = profile.date_format.as_deref().unwrap_or("%Y-%m-%d");
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    let header = match lines.next() {
+        Some((_, line)) => split_record(line, delimiter),
+        None => return Ok(Vec::new()),
+    };
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+            .ok_or_else(|| InvoiceError::InvalidTimesheet {
+                line: 1,
+                reason: format!("no '{name}' column"),
+            })
+    };
+    let date_col = column(profile.date_column.as_deref().unwrap_or("Date"))?;
+    let hours_col = column(profile.hours_column.as_deref().unwrap_or("Hours"))?;
+    let description_col = profile
+        .description_column
+        .as_deref()
+        .map(column)
+        .transpose()?;
+
+    let mut entries = Vec::new();
+    for (i, line) in lines {
+        let invalid = |reason: String| InvoiceError::InvalidTimesheet {
+            line: i + 1,
+            reason,
+        };
+        let fields = split_record(line, delimiter);
+        let field = |col: usize| fields.get(col).map(|f| f.trim()).unwrap_or("");
+
+        let date = NaiveDate::parse_from_str(field(date_col), date_format).map_err(|_| {
+            invalid(format!(
+                "date '{}' doesn't match '{date_format}'",
+                field(date_col)
+            ))
+        })?;
+        let hours = parse_hours(field(hours_col))
+            .ok_or_else(|| invalid(format!("'{}' is not a duration", field(hours_col))))?;
+        let description = description_col
+            .map(field)
+            .filter(|d| !d.is_empty())
+            .map(str::to_string);
+
+        entries.push(TimeEntry {
+            date,
+            hours: round_hours(hours, profile.round_minutes, profile.rounding),
+            description,
+        });
+    }
+    Ok(entries)
+}
+
+/// Item specs billing the entries as `item`, one line per description in
+/// order of first appearance
+pub fn timesheet_items(entries: &[TimeEntry], item: &str) -> Vec<ItemSpec> {
+    let mut specs: Vec<ItemSpec> = Vec::new();
+    for entry in entries {
+        match specs
+            .iter_mut()
+            .find(|s| s.description == entry.description)
+        {
+            Some(spec) => spec.quantity += entry.hours,
+            None => specs.push(ItemSpec {
+                item: item.to_string(),
+                description: entry.description.clone(),
+                quantity: entry.hours,
+                position: None,
+                period: None,
+            }),
+        }
+    }
+    for spec in &mut specs {
+        spec.quantity = (spec.quantity * 100.0).round() / 100.0;
+    }
+    specs.retain(|spec| spec.quantity > 0.0);
+    specs
+}
+
+/// The dates the entries span
+pub fn timesheet_period(entries: &[TimeEntry]) -> Option<ServicePeriod> {
+    let from = entries.iter().map(|e| e.date).min()?;
+    let to = entries.iter().map(|e| e.date).max()?;
+    Some(ServicePeriod { from, to })
+}
+
+/// Decimal hours (`1.5`, `1,5`) or a duration (`1:30`, `01:30:00`)
+fn parse_hours(value: &str) -> Option<f64> {
+    if value.contains(':') {
+        let mut parts = value.split(':').map(|p| p.parse::<u32>().ok());
+        let hours = parts.next()??;
+        let minutes = parts.next()??;
+        let seconds = parts.next().unwrap_or(Some(0))?;
+        if parts.next().is_some() || minutes >= 60 || seconds >= 60 {
+            return None;
+        }
+        return Some(hours as f64 + minutes as f64 / 60.0 + seconds as f64 / 3600.0);
+    }
+    value
+        .replace(',', ".")
+        .parse::<f64>()
+        .ok()
+        .filter(|h| h.is_finite() && *h >= 0.0)
+}
+
+fn round_hours(hours: f64, round_minutes: Option<u32>, rounding: Option<Rounding>) -> f64 {
+    let Some(step) = round_minutes.filter(|&m| m > 0).map(f64::from) else {
+        return hours;
+    };
+    // Trim float noise so 0.25h doesn't round up to the next 15 minutes
+    let steps = ((hours * 60.0 / step) * 1e6).round() / 1e6;
+    let steps = match rounding.unwrap_or_default() {
+        Rounding::Up => steps.ceil(),
+        Rounding::Nearest => steps.round(),
+        Rounding::Down => steps.floor(),
+    };
+    steps * step / 60.0
+}
+
+/// Split a CSV record, honoring double-quoted fields with `""` escapes
+fn split_record(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
@@ -0,0 +1,203 @@
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::generator::format_invoice_number;
+use super::manifest::update_manifest;
+use crate::config::{
+    load_config, load_state, resolve_output_dir, save_state, Config, HistoryEntry, State,
+    DEFAULT_SERIES,
+};
+use crate::error::{InvoiceError, Result};
+
+/// Directory inside the config dir holding removed invoices
+pub const TRASH_DIR: &str = ".trash";
+
+/// An invoice moved to the trash along with its artifacts
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrashedInvoice {
+    pub deleted_at: NaiveDateTime,
+    pub entry: HistoryEntry,
+    /// The invoice was the latest of its series, so its number was given
+    /// back to the counter for the next invoice
+    #[serde(default)]
+    pub number_released: bool,
+}
+
+fn trash_root(cfg_dir: &Path) -> PathBuf {
+    cfg_dir.join(TRASH_DIR)
+}
+
+pub(crate) fn trash_slot(cfg_dir: &Path, invoice_number: &str) -> PathBuf {
+    trash_root(cfg_dir).join(invoice_number)
+}
+
+/// Move a file, falling back to copy + remove across filesystems
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// Whether `entry` carries the sequence number `seq` of its series' counter
+fn is_counter_number(state: &State, config: &Config, entry: &HistoryEntry, seq: u32) -> bool {
+    let series = entry.series.as_deref().unwrap_or(DEFAULT_SERIES);
+    let Some(format) = config.number_format(series) else {
+        return false;
+    };
+    let counter = state.counter_for(series);
+    seq > 0 && format_invoice_number(format, counter.last_year, seq) == entry.number
+}
+
+/// Remove an invoice from history, moving its entry and artifacts to `.trash/<number>/`.
+/// When it was the latest invoice of its series, the counter steps back so
+/// the next invoice reuses the number; earlier numbers are never reused.
+pub fn trash_invoice(cfg_dir: &Path, invoice_number: &str) -> Result<TrashedInvoice> {
+    let config = load_config(cfg_dir)?;
+    let mut state = load_state(cfg_dir)?;
+
+    let idx = state
+        .history
+        .iter()
+        .position(|e| e.number == invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.to_string()))?;
+
+    let slot = trash_slot(cfg_dir, invoice_number);
+    if slot.exists() {
+        return Err(InvoiceError::AlreadyInTrash(invoice_number.to_string()));
+    }
+    fs::create_dir_all(&slot)?;
+
+    // Move artifacts (and archived revisions) alongside the entry
+    let entry = state.history.remove(idx);
+    let series = entry.series.as_deref().unwrap_or(DEFAULT_SERIES);
+    let last = state.counter_for(series).last_number;
+    let number_released = is_counter_number(&state, &config, &entry, last);
+    if number_released {
+        state.counter_for_mut(series).last_number -= 1;
+    }
+    let output_dir = resolve_output_dir(&config.pdf.output_dir, cfg_dir);
+    for name in entry.owned_files() {
+        let path = output_dir.join(name);
+        if path.exists() {
+            move_file(&path, &slot.join(name))?;
+        }
+    }
+
+    let trashed = TrashedInvoice {
+        deleted_at: Local::now().naive_local(),
+        entry,
+        number_released,
+    };
+    let content = toml::to_string_pretty(&trashed).map_err(|e| {
+        InvoiceError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            e.to_string(),
+        ))
+    })?;
+    fs::write(slot.join("entry.toml"), content)?;
+
+    save_state(cfg_dir, &state)?;
+    update_manifest(cfg_dir, invoice_number)?;
+    Ok(trashed)
+}
+
+/// List trashed invoices, oldest deletion first
+pub fn list_trash(cfg_dir: &Path) -> Result<Vec<TrashedInvoice>> {
+    let root = trash_root(cfg_dir);
+    if !root.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut trashed = Vec::new();
+    for dir in fs::read_dir(&root)? {
+        let path = dir?.path().join("entry.toml");
+        if !path.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        let item: TrashedInvoice =
+            toml::from_str(&content).map_err(|e| InvoiceError::ConfigParse { path, source: e })?;
+        trashed.push(item);
+    }
+    trashed.sort_by_key(|t| t.deleted_at);
+    Ok(trashed)
+}
+
+/// Put a trashed invoice back into history (in date order) and restore its files
+pub fn restore_invoice(cfg_dir: &Path, invoice_number: &str) -> Result<HistoryEntry> {
+    let config = load_config(cfg_dir)?;
+    let mut state = load_state(cfg_dir)?;
+
+    let slot = trash_slot(cfg_dir, invoice_number);
+    let path = slot.join("entry.toml");
+    if !path.exists() {
+        return Err(InvoiceError::NotInTrash(invoice_number.to_string()));
+    }
+    if state.history.iter().any(|e| e.number == invoice_number) {
+        return Err(InvoiceError::DuplicateInvoiceNumber(
+            invoice_number.to_string(),
+        ));
+    }
+
+    let content = fs::read_to_string(&path)?;
+    let trashed: TrashedInvoice =
+        toml::from_str(&content).map_err(|e| InvoiceError::ConfigParse { path, source: e })?;
+
+    let output_dir = resolve_output_dir(&config.pdf.output_dir, cfg_dir);
+    fs::create_dir_all(&output_dir)?;
+    for file in fs::read_dir(&slot)? {
+        let file = file?.path();
+        if file.file_name().and_then(|n| n.to_str()) == Some("entry.toml") {
+            continue;
+        }
+        if let Some(name) = file.file_name() {
+            move_file(&file, &output_dir.join(name))?;
+        }
+    }
+
+    // Take the number back if the counter would otherwise issue it again
+    let entry = trashed.entry;
+    let series = entry.series.as_deref().unwrap_or(DEFAULT_SERIES);
+    let last = state.counter_for(series).last_number;
+    if is_counter_number(&state, &config, &entry, last + 1) {
+        state.counter_for_mut(series).last_number += 1;
+    }
+    let position = state
+        .history
+        .iter()
+        .position(|e| e.date > entry.date)
+        .unwrap_or(state.history.len());
+    state.history.insert(position, entry.clone());
+    save_state(cfg_dir, &state)?;
+    update_manifest(cfg_dir, invoice_number)?;
+
+    fs::remove_dir_all(&slot)?;
+    Ok(entry)
+}
+
+/// Permanently delete one trashed invoice, or everything when `invoice_number` is None.
+/// Returns the number of invoices purged.
+pub fn purge_trash(cfg_dir: &Path, invoice_number: Option<&str>) -> Result<usize> {
+    match invoice_number {
+        Some(number) => {
+            let slot = trash_slot(cfg_dir, number);
+            if !slot.join("entry.toml").exists() {
+                return Err(InvoiceError::NotInTrash(number.to_string()));
+            }
+            fs::remove_dir_all(slot)?;
+            Ok(1)
+        }
+        None => {
+            let count = list_trash(cfg_dir)?.len();
+            let root = trash_root(cfg_dir);
+            if root.exists() {
+                fs::remove_dir_all(root)?;
+            }
+            Ok(count)
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use chrono::Local;
+use std::fs;
+use std::path::Path;
+
+use super::manifest::update_manifest;
+use crate::config::{load_config, load_state, resolve_output_dir, save_state, HistoryEntry, Void};
+use crate::error::{InvoiceError, Result};
+
+/// Mark an issued invoice as void. It stays in history under its number,
+/// which is never reused, but no longer counts as billed. With
+/// `remove_files`, its artifacts are deleted from the output dir.
+pub fn void_invoice(
+    cfg_dir: &Path,
+    invoice_number: &str,
+    reason: Option<String>,
+    remove_files: bool,
+) -> Result<HistoryEntry> {
+    let config = load_config(cfg_dir)?;
+    let mut state = load_state(cfg_dir)?;
+
+    let entry = state
+        .history
+        .iter_mut()
+        .find(|e| e.number == invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.to_string()))?;
+    if entry.voided.is_some() {
+        return Err(InvoiceError::Voided(invoice_number.to_string()));
+    }
+    if let Some(by) = &entry.superseded_by {
+        return Err(InvoiceError::Superseded {
+            invoice: invoice_number.to_string(),
+            by: by.clone(),
+        });
+    }
+    if !entry.payments.is_empty() {
+        return Err(InvoiceError::VoidWithPayments(invoice_number.to_string()));
+    }
+
+    entry.voided = Some(Void {
+        date: Local::now().date_naive(),
+        reason,
+    });
+    if remove_files {
+        let output_dir = resolve_output_dir(&config.pdf.output_dir, cfg_dir);
+        for name in entry.artifacts() {
+            let path = output_dir.join(name);
+            if path.exists() {
+                fs::remove_file(&path)?;
+            }
+        }
+    }
+
+    let voided = entry.clone();
+    save_state(cfg_dir, &state)?;
+    if remove_files {
+        update_manifest(cfg_dir, invoice_number)?;
+    }
+    Ok(voided)
+}
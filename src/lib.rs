@@ -1,8 +1,25 @@
+pub mod clipboard;
 pub mod config;
+pub mod editor;
 pub mod error;
 pub mod invoice;
+pub mod mail;
+pub mod money;
+#[cfg(feature = "notify")]
+pub mod notify;
 pub mod pdf;
+pub mod picker;
+pub mod rates;
+#[cfg(feature = "test-support")]
+pub mod testing;
+#[cfg(feature = "network")]
+pub mod update;
+pub mod vat;
+pub mod viewer;
 
-pub use config::{Client, Company, Config, GlobalConfig, HistoryEntry, Item, State};
+pub use config::{Client, Company, Config, GlobalConfig, HistoryEntry, Item, State, Workspace};
 pub use error::{InvoiceError, Result};
-pub use invoice::{generate_invoice, InvoiceData};
+pub use invoice::{
+    generate_invoice, GenerateOptions, GeneratedInvoice, InvoiceBuilder, InvoiceData,
+};
+pub use pdf::generate_pdf_from_data;
@@ -0,0 +1,40 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::error::{InvoiceError, Result};
+
+/// A plain-text email message with the headers `sendmail -t` reads
+pub fn compose_message(from: &str, to: &str, subject: &str, body: &str) -> String {
+    format!(
+        "From: {from}\nTo: {to}\nSubject: {subject}\nMIME-Version: 1.0\n\
+         Content-Type: text/plain; charset=utf-8\n\n{body}"
+    )
+}
+
+/// Pipe a composed message to a sendmail-compatible command.
+/// The command may include arguments (e.g., "sendmail -t").
+pub fn send_message(command: &str, message: &str) -> Result<()> {
+    let mut parts = command.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| InvoiceError::Mail("no mail command configured".to_string()))?;
+
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| InvoiceError::Mail(format!("could not launch '{command}': {e}")))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(message.as_bytes())?;
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(InvoiceError::Mail(format!(
+            "'{command}' exited with {status}"
+        )));
+    }
+    Ok(())
+}
@@ -1,34 +1,74 @@
-mod config;
-mod error;
-mod invoice;
-mod pdf;
-
-use chrono::Datelike;
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use chrono::{Datelike, Timelike};
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use tabled::{settings::Style, Table, Tabled};
 
-use crate::config::{
-    config_dir, global_config_file, load_clients, load_config, load_global_config, load_items,
-    load_state, save_state,
-    state::{Payment, PaymentStatus},
-    CLIENTS_TEMPLATE, CONFIG_TEMPLATE, ITEMS_TEMPLATE,
+use invoice::clipboard;
+use invoice::config::{
+    self, config_dir, global_config_file, load_clients, load_config, load_contracts,
+    load_global_config, load_items, load_state, save_state,
+    state::{Credit, FollowUp, Payment, PaymentStatus, ReportEntry, ServicePeriod},
+    DayCount, Delivery, Discount, HistoryEntry, ImportProfile, RateProvider, Rounding, Standing,
+    State, Workspace, CLIENTS_TEMPLATE, CONFIG_TEMPLATE, CONTRACTS_TEMPLATE, DEFAULT_SERIES,
+    ITEMS_TEMPLATE,
 };
-use crate::error::{InvoiceError, Result};
-use crate::invoice::{
-    generate_invoice, get_invoice_path, regenerate_invoice, ReportData, ReportInvoiceRow,
-    ReportPayment,
+use invoice::editor;
+use invoice::error::{InvoiceError, Result};
+use invoice::invoice::{
+    accrued_interest, add_note, aging_totals, client_or_snapshot, consolidate_invoices,
+    contract_status, create_aging_report, create_payment_plan, create_unpaid_statement,
+    days_overdue, diff_invoice, dunning_level_due, generate_invoice, get_invoice_artifact,
+    get_invoice_path, held_retention, issued_invoice_data, item_margins, list_trash, merge_states,
+    month_groups, monthly_progress, next_report_number, orphaned_pdfs, overdue_by_client,
+    parse_generate_spec, parse_item_spec, plan_adherence, project_budgets, purge_trash,
+    read_timesheet, rebuild_manifest, receivables_digest, record_followup, record_reminder,
+    refresh_standings, regenerate_invoice, render_digest, render_reminder, render_summary,
+    restore_invoice, retained_by_project, search_notes, timesheet_items, timesheet_period,
+    trash_invoice, true_up, validate_invoice_number, verify_files, void_invoice, AgingColumns,
+    DepositRequest, DiffLine, GenerateOptions, GeneratedInvoice, GoalProgress, InstallmentStatus,
+    InvoiceLineItem, ItemSort, ItemSpec, Margin, ReportData, ReportGroupBy, ReportInvoiceRow,
+    SummaryFormat, TimeEntry,
 };
-use crate::pdf::generate_report_pdf;
+use invoice::mail;
+use invoice::money::{format_amount, format_whole, MoneyFormat};
+#[cfg(feature = "notify")]
+use invoice::notify;
+use invoice::pdf::{
+    check_template, debug_artifacts, generate_report_pdf, typst_version, OutputFormat,
+    RenderOptions, SCHEMA_VERSION,
+};
+use invoice::picker;
+use invoice::rates::{self, RateSource, Rates};
+#[cfg(feature = "network")]
+use invoice::update::{self, UpdateStatus};
+use invoice::viewer;
+
+mod progress;
+
+use progress::Progress;
 
 #[derive(Parser)]
 #[command(name = "invoice")]
 #[command(version, about = "Minimal CLI invoicing system", long_about = None)]
 struct Cli {
-    /// Path to config directory (default: ~/.invoice or XDG config)
+    /// Path to config directory (default: nearest .invoice/ in a parent dir, then ~/.invoice or XDG config)
     #[arg(short = 'C', long, global = true)]
     config_dir: Option<PathBuf>,
 
+    /// Refuse commands that change invoices, payments or config; viewing
+    /// and exporting still work (also set by [access] read_only)
+    #[arg(long, global = true)]
+    read_only: bool,
+
+    /// Fail instead of issuing questionable invoices: a client without a
+    /// country, a rounding adjustment, an exchange rate that can't be
+    /// fetched fresh, or template warnings
+    #[arg(long, global = true)]
+    strict: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -39,81 +79,523 @@ enum Commands {
     Init,
 
     /// Generate a new invoice
-    Generate {
-        /// Client identifier from clients.toml
-        #[arg(short, long)]
-        client: String,
+    Generate(Box<GenerateArgs>),
 
-        /// Line items in format "item:quantity" (can be repeated)
-        #[arg(short, long, value_name = "ITEM:QTY")]
-        item: Vec<String>,
+    /// Manage the invoice number counter
+    Counter {
+        #[command(subcommand)]
+        action: CounterAction,
+    },
 
-        /// Custom output file path (default: output_dir/INV-XXXX.pdf)
-        #[arg(short, long)]
-        output: Option<PathBuf>,
+    /// List configured clients
+    #[command(alias = "client")]
+    Clients {
+        #[command(subcommand)]
+        action: Option<ClientsAction>,
+    },
+
+    /// List available line items
+    Items {
+        #[command(subcommand)]
+        action: Option<FileAction>,
+    },
+
+    /// Compare fixed-price projects with what has been invoiced against them
+    Projects,
+
+    /// Revenue, cost and margin per item and per client, from item costs in items.toml
+    Margins,
+
+    /// Track contracts from contracts.toml against what has been billed
+    Contract {
+        #[command(subcommand)]
+        action: ContractAction,
+    },
+
+    /// Summarize the week's receivables: invoices issued, payments received,
+    /// aging changes and upcoming due dates. Use --email from cron.
+    Digest {
+        /// Send the digest to this address instead of printing it
+        #[arg(long, value_name = "ADDRESS")]
+        email: Option<String>,
+
+        /// Length of the period in days
+        #[arg(long, default_value_t = 7)]
+        days: i64,
+
+        /// Last day of the period, YYYY-MM-DD (default: today)
+        #[arg(long)]
+        date: Option<String>,
+    },
+
+    /// Overdue invoices grouped by client, owed the most first, with aging
+    /// totals: who to chase this week
+    Overdue {
+        /// Day to count days overdue to, YYYY-MM-DD (default: today)
+        #[arg(long)]
+        date: Option<String>,
+    },
+
+    /// Accounts receivable aging: each client's outstanding balance in 0-30,
+    /// 31-60, 61-90 and 90+ days past due columns, printed and saved as a PDF
+    Aging {
+        /// Day to age balances to, YYYY-MM-DD (default: today)
+        #[arg(long)]
+        date: Option<String>,
 
         /// Open generated PDF with system default viewer
         #[arg(long)]
         open: bool,
     },
 
-    /// List configured clients
-    Clients,
+    /// Record each invoice's status, due date, overdue flag and days late in
+    /// state.toml, for scripts that read it. Add it to [schedule] to keep
+    /// them current.
+    RefreshStatus {
+        /// Day to work the status out for, YYYY-MM-DD (default: today)
+        #[arg(long)]
+        date: Option<String>,
 
-    /// List available line items
-    Items,
+        /// Print the refreshed statuses as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List the [schedule] commands from config.toml, print them as crontab
+    /// lines, or keep running and start each one when it is due
+    Schedule {
+        /// Print crontab lines to install instead of the list
+        #[arg(long, conflicts_with = "run")]
+        crontab: bool,
+
+        /// Stay in the foreground, running each command when it is due
+        #[arg(long)]
+        run: bool,
+    },
+
+    /// Manage config.toml and the global config (~/.config/invoicing.toml)
+    Config {
+        /// Write a commented global config template to ~/.config/invoicing.toml
+        #[arg(long)]
+        init_global: bool,
+
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
 
     /// Show invoice status and next number
     Status {
-        /// Show global config information
+        /// Show global config information and check Typst, templates,
+        /// the output directory and the exchange-rate provider
         #[arg(short, long)]
         verbose: bool,
     },
 
     /// List generated invoices
     List {
-        /// Number of invoices to show (default: all)
-        #[arg(short, long)]
+        /// Number of invoices to show (default: [display] default_list_limit
+        /// and default_list_window, or all)
+        #[arg(short, long, conflicts_with = "all")]
         limit: Option<usize>,
+
+        /// Show every invoice, ignoring the configured defaults
+        #[arg(long)]
+        all: bool,
+
+        /// Show amounts without the tax charged on each invoice
+        #[arg(long, conflicts_with = "overdue")]
+        net: bool,
+
+        /// Only unpaid invoices past their due date, with follow-up status
+        #[arg(long)]
+        overdue: bool,
+
+        /// Tab-separated rows without borders, headers or footer, for
+        /// pipelines: index, number, date, total, status, client and
+        /// outstanding. Columns keep this order; new ones are only appended.
+        #[arg(long, conflicts_with = "overdue")]
+        plain: bool,
     },
 
+    /// Show invoices as a board: UNPAID / PARTIAL / OVERDUE / PAID (this month)
+    Board,
+
     /// Edit an existing invoice's line items
     Edit {
-        /// Invoice number or index from 'list' (e.g., 1 or INV-2025-0001)
-        invoice: String,
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2025-0001);
+        /// choose it with the picker when omitted
+        invoice: Option<String>,
 
-        /// New line items in format "item:quantity" (replaces existing items)
+        /// New line items as "item:quantity" or "item:description:quantity" (replaces existing items)
         #[arg(short, long, value_name = "ITEM:QTY")]
         item: Vec<String>,
+
+        /// New discount off the subtotal: a percentage (10%) or amount (250); 0 removes it
+        #[arg(long, value_name = "DISCOUNT")]
+        discount: Option<Discount>,
+
+        /// New note printed at the bottom of the invoice; "" removes it
+        #[arg(long, value_name = "TEXT")]
+        note: Option<String>,
+
+        /// Unlock a locked invoice and apply the edit anyway
+        #[arg(long)]
+        force_unlock: bool,
     },
 
     /// Open an invoice PDF
     Open {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2025-0001);
+        /// choose it with the picker when omitted
+        invoice: Option<String>,
+
+        /// Which recorded artifact to open (pdf, html, json; default: primary)
+        #[arg(long)]
+        format: Option<OutputFormat>,
+    },
+
+    /// Print an invoice in the terminal: client, lines, totals and payments
+    Show {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2025-0001);
+        /// choose it with the picker when omitted
+        invoice: Option<String>,
+    },
+
+    /// Choose an invoice with a fuzzy finder (fzf, or $INVOICE_PICKER),
+    /// previewing each, and print its number for other commands
+    /// (e.g., invoice add-payment $(invoice pick) 500)
+    Pick,
+
+    /// Print a payment reminder at the dunning level an overdue invoice has reached
+    Remind {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2025-0001)
+        invoice: String,
+
+        /// Show the reminder without recording it as sent
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Create a one-page PDF of a client's unpaid invoices, for attaching to reminders
+    Dun {
+        /// Client identifier from clients.toml
+        client: String,
+
+        /// Open the statement with system default viewer
+        #[arg(long)]
+        open: bool,
+    },
+
+    /// Show desktop notifications for invoices due soon or overdue (for cron/login items)
+    #[cfg(feature = "notify")]
+    NotifyDue {
+        /// Also notify about invoices due within this many days
+        #[arg(long, default_value_t = 3)]
+        days: i64,
+    },
+
+    /// Calculate statutory late interest on an overdue invoice
+    Interest {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2025-0001)
+        invoice: String,
+
+        /// Annual rate as a fraction, overriding [interest] annual_rate (e.g., 0.08)
+        #[arg(long)]
+        rate: Option<f64>,
+
+        /// Day-count convention: actual/365, actual/360 or 30/360
+        #[arg(long)]
+        day_count: Option<DayCount>,
+    },
+
+    /// Agree an installment plan for an invoice, or show how it is being followed
+    PaymentPlan {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2025-0001)
+        invoice: String,
+
+        /// Number of monthly installments (creates or replaces the plan)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        installments: Option<u32>,
+
+        /// First installment due date in YYYY-MM-DD format (default: today)
+        #[arg(long, requires = "installments")]
+        start: Option<String>,
+
+        /// Open the generated agreement
+        #[arg(long, requires = "installments")]
+        open: bool,
+    },
+
+    /// Check generated files against the checksum manifest
+    VerifyFiles {
+        /// Accept the files on disk and rebuild the manifest from them
+        #[arg(long)]
+        update: bool,
+    },
+
+    /// Show version information (--verbose adds build details for bug reports)
+    Version {
+        /// Include git commit, build date, features and Typst version
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Update this binary to the latest GitHub release
+    #[cfg(feature = "network")]
+    SelfUpdate {
+        /// Only check whether a newer release is available
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Copy a shareable invoice summary to the clipboard
+    Copy {
         /// Invoice number or index from 'list' (e.g., 1 or INV-2025-0001)
         invoice: String,
+
+        /// Summary markup: text, md or html
+        #[arg(long, default_value_t = SummaryFormat::Text)]
+        format: SummaryFormat,
+    },
+
+    /// Replace several unpaid invoices of a client with one consolidated invoice
+    Consolidate {
+        /// Client identifier from clients.toml
+        #[arg(short, long)]
+        client: String,
+
+        /// Invoices to merge, as numbers or indexes from 'list' (e.g., 3,4,5)
+        #[arg(long, value_delimiter = ',', required = true, value_name = "INVOICES")]
+        invoices: Vec<String>,
+
+        /// Open the consolidated PDF with system default viewer
+        #[arg(long)]
+        open: bool,
+    },
+
+    /// Generate an invoice from a time-tracker CSV export
+    ImportTime {
+        /// CSV file whose first row names the columns
+        file: PathBuf,
+
+        /// Client identifier from clients.toml
+        #[arg(short, long)]
+        client: String,
+
+        /// Catalog item to bill the hours as
+        #[arg(short, long)]
+        item: Option<String>,
+
+        /// Column whose values become separate invoice lines
+        #[arg(long)]
+        description_column: Option<String>,
+
+        #[command(flatten)]
+        timesheet: TimesheetArgs,
+
+        /// Print the imported line items without generating an invoice
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Open the invoice with system default viewer
+        #[arg(long)]
+        open: bool,
+    },
+
+    /// Bill a retainer client for the tracked hours beyond the ones their
+    /// retainer includes, at the overage item's rate
+    TrueUp {
+        /// Time-tracker CSV export whose first row names the columns
+        file: PathBuf,
+
+        /// Client identifier from clients.toml; needs a retainer
+        #[arg(short, long)]
+        client: String,
+
+        /// Only count hours tracked in this month (default: the whole file)
+        #[arg(long, value_name = "YYYY-MM")]
+        period: Option<String>,
+
+        #[command(flatten)]
+        timesheet: TimesheetArgs,
+
+        /// Print the hours and overage without generating an invoice
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Open the invoice with system default viewer
+        #[arg(long)]
+        open: bool,
+    },
+
+    /// Bill an advance on a fixed-price project; later invoices for the
+    /// project deduct it
+    Deposit {
+        /// Client identifier from clients.toml
+        #[arg(short, long)]
+        client: String,
+
+        /// Project id from the [projects] section of config.toml
+        #[arg(long)]
+        project: String,
+
+        /// Share of the project's fixed price to bill (e.g., 40%)
+        #[arg(long, value_parser = parse_percent)]
+        percent: f64,
+
+        /// Open the deposit invoice with system default viewer
+        #[arg(long)]
+        open: bool,
+    },
+
+    /// Bill the retention held back on a client's invoices
+    ReleaseRetention {
+        /// Client identifier from clients.toml
+        #[arg(short, long)]
+        client: String,
+
+        /// Only release retention held for this project
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Open the release invoice with system default viewer
+        #[arg(long)]
+        open: bool,
     },
 
     /// Regenerate an invoice PDF from stored data
     Regenerate {
         /// Invoice number or index from 'list' (e.g., 1 or INV-2025-0001)
-        invoice: String,
+        #[arg(required_unless_present = "all")]
+        invoice: Option<String>,
+
+        /// Regenerate every invoice in history
+        #[arg(long, conflicts_with_all = ["invoice", "open"])]
+        all: bool,
 
         /// Open regenerated PDF with system default viewer
         #[arg(long)]
         open: bool,
+
+        /// Typst cover letter to prepend (relative paths resolve against the config dir)
+        #[arg(long, value_name = "TEMPLATE")]
+        cover_letter: Option<PathBuf>,
+
+        /// Output formats to produce, comma-separated (default: those recorded)
+        #[arg(long, alias = "format", value_delimiter = ',', value_name = "FORMATS")]
+        formats: Vec<OutputFormat>,
+
+        /// Pin PDF metadata so unchanged invoices render byte-identical files
+        #[arg(long)]
+        deterministic: bool,
+
+        /// Keep the generated .typ and .json in output_dir/debug/<number>/ for manual compiling
+        #[arg(long)]
+        debug: bool,
+
+        /// Unlock a locked invoice even if regenerating would change its total
+        #[arg(long)]
+        force_unlock: bool,
+    },
+
+    /// Show what regenerating an invoice would change: total, line items,
+    /// document text and PDF, without touching the stored files
+    Diff {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2026-0001);
+        /// choose it with the picker when omitted
+        invoice: Option<String>,
+
+        /// Render with pinned PDF metadata, for invoices generated with --deterministic
+        #[arg(long)]
+        deterministic: bool,
+    },
+
+    /// Lock an issued invoice against edits and repricing
+    Lock {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2026-0001)
+        invoice: String,
+    },
+
+    /// Log payment follow-ups (calls, emails) on unpaid invoices
+    Followup {
+        #[command(subcommand)]
+        action: FollowupAction,
+    },
+
+    /// Internal notes on invoices (never rendered on documents)
+    Note {
+        #[command(subcommand)]
+        action: NoteAction,
+    },
+
+    /// Move invoices to the trash, or list, restore and purge trashed ones
+    Trash {
+        #[command(subcommand)]
+        action: TrashAction,
+    },
+
+    /// Void an issued invoice: it keeps its number but no longer counts as
+    /// billed. To remove a mistaken invoice entirely, use 'trash add'.
+    Void {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2026-0001)
+        invoice: String,
+
+        /// Why the invoice was voided, kept in history
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Also delete the invoice's PDF and other artifacts
+        #[arg(long)]
+        remove_files: bool,
+    },
+
+    /// Rewrite state.toml in canonical order with rounded amounts
+    Compact,
+
+    /// Merge another copy of state.toml (e.g., a sync conflict copy) into this one
+    MergeState {
+        /// The other state file
+        file: PathBuf,
+
+        /// Show what would be merged without changing state.toml
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Delete PDFs in the output directory that no invoice references
+    CleanOutput {
+        /// Only list the files that would be deleted
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Work with custom Typst templates (e.g., cover letters)
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
     },
 
     /// Record a payment against an invoice
     AddPayment {
         /// Invoice number or index from 'list' (e.g., 1 or INV-2026-0001)
-        invoice: String,
+        #[arg(required_unless_present = "bulk")]
+        invoice: Option<String>,
 
         /// Payment amount
-        amount: f64,
+        #[arg(required_unless_present = "bulk")]
+        amount: Option<f64>,
+
+        /// Several payments at once, comma-separated (e.g., "INV-2026-0001=500,INV-2026-0002=750")
+        #[arg(long, value_name = "INVOICE=AMOUNT,...", conflicts_with_all = ["invoice", "amount"])]
+        bulk: Option<String>,
 
         /// Payment date (default: today)
         #[arg(long)]
         date: Option<String>,
+
+        /// Keep any amount beyond the balance due as credit for the client
+        #[arg(long)]
+        as_credit: bool,
     },
 
     /// Remove a payment from an invoice
@@ -132,11 +614,15 @@ enum Commands {
         invoice: String,
     },
 
-    /// Generate a PDF report of invoices for a client
+    /// Generate a numbered PDF report of invoices for a client (e.g., RPT-2026-0001)
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Report {
+        #[command(subcommand)]
+        action: Option<ReportAction>,
+
         /// Client identifier from clients.toml
-        #[arg(short, long)]
-        client: String,
+        #[arg(short, long, required = true)]
+        client: Option<String>,
 
         /// Filter invoices from this date (YYYY-MM-DD)
         #[arg(long)]
@@ -150,68 +636,762 @@ enum Commands {
         #[arg(long)]
         status: Option<String>,
 
+        /// Group invoices under headings with subtotals (month)
+        #[arg(long, value_name = "PERIOD")]
+        group_by: Option<ReportGroupBy>,
+
+        /// Leave the tax charged on each invoice out of every amount
+        #[arg(long)]
+        net: bool,
+
         /// Open generated PDF with system default viewer
         #[arg(long)]
         open: bool,
     },
 }
 
-fn main() {
-    if let Err(e) = run() {
-        eprintln!("Error: {e}");
-        std::process::exit(1);
-    }
-}
-
-fn run() -> Result<()> {
-    let cli = Cli::parse();
+// Boxed in `Commands` since it takes far more room than any other command
+#[derive(Args)]
+struct GenerateArgs {
+    /// Client identifier from clients.toml
+    #[arg(short, long, required_unless_present_any = ["stdin", "spec", "interactive"])]
+    client: Option<String>,
+
+    /// Line items as "item:quantity" or "item:description:quantity"; quote
+    /// fields containing ':' (e.g., 'design:"Logo: v2":3'). Quantities can
+    /// be expressions like 3x8 or 2.5+1.25. Add the service period a line
+    /// covers after its quantity (e.g., consulting:8@2026-01-01..2026-01-31
+    /// or consulting:8@2026-01). Can be repeated.
+    #[arg(short, long, value_name = "ITEM:QTY")]
+    item: Vec<String>,
+
+    /// Custom output file path (default: output_dir/INV-XXXX.pdf)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
 
-    // Determine config directory
-    let cfg_dir = match cli.config_dir {
-        Some(p) => p,
-        None => config_dir()?,
-    };
+    /// Open generated PDF with system default viewer
+    #[arg(long)]
+    open: bool,
 
-    match cli.command {
-        Commands::Init => cmd_init(&cfg_dir),
-        Commands::Generate {
-            client,
-            item,
-            output,
-            open,
-        } => cmd_generate(&cfg_dir, &client, &item, output, open),
-        Commands::Clients => cmd_clients(&cfg_dir),
-        Commands::Items => cmd_items(&cfg_dir),
-        Commands::Status { verbose } => cmd_status(&cfg_dir, verbose),
-        Commands::List { limit } => cmd_invoices(&cfg_dir, limit),
-        Commands::Edit { invoice, item } => cmd_edit(&cfg_dir, &invoice, &item),
-        Commands::Open { invoice } => cmd_open(&cfg_dir, &invoice),
-        Commands::Regenerate { invoice, open } => cmd_regenerate(&cfg_dir, &invoice, open),
-        Commands::AddPayment {
-            invoice,
-            amount,
-            date,
-        } => cmd_add_payment(&cfg_dir, &invoice, amount, date),
-        Commands::RemovePayment { invoice, index } => {
-            cmd_remove_payment(&cfg_dir, &invoice, index)
-        }
-        Commands::Payments { invoice } => cmd_payments(&cfg_dir, &invoice),
-        Commands::Report {
-            client,
-            from,
-            to,
-            status,
-            open,
-        } => cmd_report(&cfg_dir, &client, from, to, status, open),
-    }
+    /// Typst cover letter to prepend (relative paths resolve against the config dir)
+    #[arg(long, value_name = "TEMPLATE")]
+    cover_letter: Option<PathBuf>,
+
+    /// Review the computed invoice JSON in $EDITOR before rendering
+    #[arg(long)]
+    edit_json: bool,
+
+    /// Output formats to produce, comma-separated (pdf, html, json)
+    #[arg(long, alias = "format", value_delimiter = ',', value_name = "FORMATS")]
+    formats: Vec<OutputFormat>,
+
+    /// Pin PDF metadata so unchanged invoices render byte-identical files
+    #[arg(long)]
+    deterministic: bool,
+
+    /// Keep the generated .typ and .json in output_dir/debug/<number>/ for manual compiling
+    #[arg(long)]
+    debug: bool,
+
+    /// Use a specific invoice number (e.g., INV-2026-0100) instead of the counter
+    #[arg(long)]
+    number: Option<String>,
+
+    /// Numbering series from config.toml (e.g., retainer, credit)
+    #[arg(long)]
+    series: Option<String>,
+
+    /// Service period as a calendar month (e.g., 2026-01)
+    #[arg(long, value_name = "YYYY-MM", conflicts_with_all = ["period_from", "period_to"])]
+    period: Option<String>,
+
+    /// First day of the service period (with --period-to)
+    #[arg(long, value_name = "YYYY-MM-DD", requires = "period_to")]
+    period_from: Option<String>,
+
+    /// Last day of the service period (with --period-from)
+    #[arg(long, value_name = "YYYY-MM-DD", requires = "period_from")]
+    period_to: Option<String>,
+
+    /// Generate even if an invoice this month bills the same client for the same items
+    #[arg(long)]
+    allow_duplicate: bool,
+
+    /// Line order: by-amount, by-description or none (as given). Pin a line
+    /// with an @N suffix on its quantity (e.g., retainer:1@1).
+    #[arg(long, value_name = "ORDER", default_value_t = ItemSort::None)]
+    sort_items: ItemSort,
+
+    /// Hold back part of the total until completion (e.g., 10%); bill it later
+    /// with 'release-retention'
+    #[arg(long, value_name = "PERCENT", value_parser = parse_percent)]
+    retention: Option<f64>,
+
+    /// Take a percentage (10%) or fixed amount (250) off the subtotal before tax
+    #[arg(long, value_name = "DISCOUNT")]
+    discount: Option<Discount>,
+
+    /// Note printed in a Notes section at the bottom of the invoice
+    /// (e.g., a PO number, special terms or a thank-you)
+    #[arg(long, value_name = "TEXT")]
+    note: Option<String>,
+
+    /// Direct payment to a [payees] entry from config.toml (e.g., a factoring company)
+    #[arg(long, value_name = "PAYEE")]
+    payee: Option<String>,
+
+    /// Convert items priced in CURRENCY at this rate instead of the one
+    /// from the [currency] provider (e.g., --rate EUR=1.08); repeatable
+    #[arg(long = "rate", value_name = "CURRENCY=RATE", value_parser = parse_exchange_rate)]
+    rates: Vec<(String, f64)>,
+
+    /// Print a custom field on the invoice (e.g., --field "Cost Center=1234");
+    /// repeatable, and an empty value drops one of the client's fields
+    #[arg(long = "field", value_name = "NAME=VALUE", value_parser = parse_custom_field)]
+    fields: Vec<(String, String)>,
+
+    /// Bill against a contracts.toml entry (default: the client's only
+    /// contract in effect today)
+    #[arg(long, value_name = "CONTRACT")]
+    contract: Option<String>,
+
+    /// Read the client, items and other settings as a JSON or TOML spec from stdin
+    #[arg(long, conflicts_with_all = ["client", "item", "number", "series", "period", "period_from", "period_to"])]
+    stdin: bool,
+
+    /// Generate from a JSON or TOML spec file; a copy is kept under specs/
+    /// and 'regenerate' rebuilds the invoice from it
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["stdin", "client", "item", "number", "series", "period", "period_from", "period_to"])]
+    spec: Option<PathBuf>,
+
+    /// Choose the client (unless --client is given) and items with the
+    /// fuzzy finder, entering each quantity, then confirm before generating
+    #[arg(long, conflicts_with_all = ["stdin", "spec"])]
+    interactive: bool,
 }
 
-/// Initialize config directory with template files
-fn cmd_init(cfg_dir: &PathBuf) -> Result<()> {
+#[derive(Subcommand)]
+enum ReportAction {
+    /// List generated reports, newest first
+    List,
+    /// Open a generated report
+    Open {
+        /// Report number or index from 'report list' (e.g., 1 or RPT-2026-0001)
+        report: String,
+    },
+}
+
+/// Actions on a single config file (clients.toml, items.toml)
+#[derive(Subcommand)]
+enum FileAction {
+    /// Open the file in $EDITOR and validate it on save
+    Edit,
+}
+
+/// How to read a timesheet export, for `import-time` and `true-up`
+#[derive(Args)]
+struct TimesheetArgs {
+    /// Saved column mapping from [import.profiles.<NAME>] in config.toml;
+    /// the other timesheet flags override its settings
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Column with the entry date (default: Date)
+    #[arg(long)]
+    date_column: Option<String>,
+
+    /// Format of the date column (default: %Y-%m-%d)
+    #[arg(long)]
+    date_format: Option<String>,
+
+    /// Column with decimal hours or h:mm durations (default: Hours)
+    #[arg(long)]
+    hours_column: Option<String>,
+
+    /// Round each entry to a multiple of this many minutes
+    #[arg(long, value_name = "MINUTES")]
+    round_minutes: Option<u32>,
+
+    /// Rounding direction: up, nearest or down (default: up)
+    #[arg(long)]
+    rounding: Option<Rounding>,
+
+    /// Field separator (default: ',')
+    #[arg(long)]
+    delimiter: Option<char>,
+}
+
+#[derive(Subcommand)]
+enum ClientsAction {
+    /// Open the file in $EDITOR and validate it on save
+    Edit,
+    /// Add a client to clients.toml
+    New {
+        /// Identifier to bill the client by (e.g., acme)
+        id: String,
+        /// Answer a prompt for each field instead of filling in an entry in $EDITOR
+        #[arg(long)]
+        interactive: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CounterAction {
+    /// Continue numbering from a given sequence (e.g., when migrating mid-year)
+    Set {
+        /// Year the sequence belongs to (default: current year)
+        #[arg(long)]
+        year: Option<u32>,
+
+        /// Sequence number the next invoice should use
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        next: u32,
+
+        /// Numbering series to set (default: the main invoice series)
+        #[arg(long)]
+        series: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ContractAction {
+    /// Show billed-to-date against each contract's value
+    Status {
+        /// Only this contract
+        contract: Option<String>,
+    },
+    /// Open contracts.toml in $EDITOR and validate it on save
+    Edit,
+}
+
+#[derive(Subcommand)]
+enum FollowupAction {
+    /// Record a follow-up contact
+    Add {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2026-0001)
+        invoice: String,
+
+        /// How the client was contacted (e.g., phone, email, meeting)
+        #[arg(long)]
+        via: String,
+
+        /// What was agreed (e.g., "promised payment Friday")
+        #[arg(long)]
+        note: Option<String>,
+
+        /// Date of the next planned follow-up (YYYY-MM-DD)
+        #[arg(long)]
+        next: Option<String>,
+
+        /// Date of the contact (default: today)
+        #[arg(long)]
+        date: Option<String>,
+    },
+
+    /// Show an invoice's follow-ups, oldest first
+    List {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2026-0001)
+        invoice: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum NoteAction {
+    /// Attach a note to an invoice (e.g., "Client disputed line 2")
+    Add {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2026-0001)
+        invoice: String,
+
+        /// Note text
+        text: String,
+    },
+
+    /// Show an invoice's notes, oldest first
+    List {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2026-0001)
+        invoice: String,
+    },
+
+    /// Find notes containing some text (case-insensitive)
+    Search {
+        /// Text to look for
+        query: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrashAction {
+    /// Move an invoice and its files to the trash
+    Add {
+        /// Invoice number or index from 'list' (e.g., 1 or INV-2026-0001)
+        invoice: String,
+    },
+
+    /// List trashed invoices
+    List,
+
+    /// Restore a trashed invoice back into history
+    Restore {
+        /// Invoice number (e.g., INV-2026-0001)
+        invoice: String,
+    },
+
+    /// Permanently delete trashed invoices
+    Purge {
+        /// Invoice number to purge (purges everything with --all)
+        #[arg(required_unless_present = "all")]
+        invoice: Option<String>,
+
+        /// Purge every trashed invoice
+        #[arg(long, conflicts_with = "invoice")]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateAction {
+    /// Warn about deprecated or unknown invoice data fields a template reads
+    Check {
+        /// Path to the .typ template
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Open config.toml in $EDITOR and validate it on save
+    Edit,
+
+    /// Set a key in the global config (e.g., config_dir ~/Invoices)
+    SetGlobal {
+        /// Key to set (config_dir)
+        key: String,
+
+        /// New value
+        value: String,
+    },
+
+    /// Show the global config and the effective config directory
+    Show,
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let cli = Cli::parse();
+
+    // Determine config directory
+    let cfg_dir = match cli.config_dir {
+        Some(p) => p,
+        None => config_dir()?,
+    };
+
+    // A broken config.toml shouldn't lock out the commands that report it
+    let locked = cfg_dir.join("config.toml").exists()
+        && load_config(&cfg_dir).is_ok_and(|config| config.access.read_only);
+    let read_only = cli.read_only || locked;
+    if read_only {
+        if let Some(name) = mutating_command(&cli.command) {
+            return Err(InvoiceError::ReadOnly(name.to_string()));
+        }
+    }
+
+    match cli.command {
+        Commands::Init => cmd_init(&cfg_dir),
+        Commands::Generate(args) => {
+            let GenerateArgs {
+                client,
+                item,
+                output,
+                open,
+                cover_letter,
+                edit_json,
+                formats,
+                deterministic,
+                debug,
+                number,
+                series,
+                period,
+                period_from,
+                period_to,
+                allow_duplicate,
+                sort_items,
+                retention,
+                discount,
+                note,
+                payee,
+                rates,
+                fields,
+                contract,
+                stdin,
+                spec,
+                interactive,
+            } = *args;
+            let mut options = GenerateOptions {
+                output_path: output,
+                edit_json,
+                number,
+                series,
+                period: parse_service_period(period, period_from, period_to)?,
+                allow_duplicate,
+                retention,
+                release_retention: Vec::new(),
+                deposit: None,
+                sort_items,
+                payee,
+                exchange_rates: rates.into_iter().collect(),
+                discount,
+                fields: fields.into_iter().collect(),
+                date: None,
+                lines: Vec::new(),
+                notes: Vec::new(),
+                memo: note,
+                contract,
+                spec: None,
+                render: render_options(
+                    &cfg_dir,
+                    cover_letter,
+                    formats,
+                    deterministic,
+                    debug,
+                    cli.strict,
+                ),
+            };
+            let source = match spec {
+                Some(path) => Some(std::fs::read_to_string(path)?),
+                None if stdin => Some(std::io::read_to_string(std::io::stdin())?),
+                None => None,
+            };
+            if let Some(source) = source {
+                let spec = parse_generate_spec(&source)?;
+                options.spec = Some(source);
+                options.date = spec.date;
+                options.lines = spec.lines();
+                options.notes = spec.notes.clone();
+                if spec.contract.is_some() {
+                    options.contract = spec.contract.clone();
+                }
+                options.number = spec.number.clone();
+                options.series = spec.series.clone();
+                options.period = spec.period;
+                if spec.payee.is_some() {
+                    options.payee = spec.payee.clone();
+                }
+                for (name, value) in &spec.fields {
+                    options
+                        .fields
+                        .entry(name.clone())
+                        .or_insert_with(|| value.clone());
+                }
+                options.allow_duplicate |= spec.allow_duplicate;
+                if spec.sort_items != ItemSort::None {
+                    options.sort_items = spec.sort_items;
+                }
+                if options.render.formats.is_empty() {
+                    options.render.formats = spec.formats.clone();
+                }
+                cmd_generate(&cfg_dir, &spec.client, &spec.item_inputs(), open, &options)
+            } else if interactive {
+                match ask_generate(&cfg_dir, client, item)? {
+                    Some((client, item)) => cmd_generate(&cfg_dir, &client, &item, open, &options),
+                    None => {
+                        println!("Nothing generated.");
+                        Ok(())
+                    }
+                }
+            } else {
+                let Some(client) = client else {
+                    unreachable!("clap requires --client without --stdin, --spec or --interactive")
+                };
+                cmd_generate(&cfg_dir, &client, &item, open, &options)
+            }
+        }
+        Commands::Counter {
+            action: CounterAction::Set { year, next, series },
+        } => cmd_counter_set(&cfg_dir, year, next, series.as_deref()),
+        Commands::Clients { action: None } => cmd_clients(&cfg_dir),
+        Commands::Clients {
+            action: Some(ClientsAction::Edit),
+        } => cmd_edit_file(&cfg_dir, "clients.toml", |dir| {
+            load_clients(dir).map(|_| ())
+        }),
+        Commands::Clients {
+            action: Some(ClientsAction::New { id, interactive }),
+        } => cmd_client_new(&cfg_dir, &id, interactive),
+        Commands::Items { action: None } => cmd_items(&cfg_dir),
+        Commands::Items {
+            action: Some(FileAction::Edit),
+        } => cmd_edit_file(&cfg_dir, "items.toml", |dir| load_items(dir).map(|_| ())),
+        Commands::Projects => cmd_projects(&cfg_dir),
+        Commands::Margins => cmd_margins(&cfg_dir),
+        Commands::Contract {
+            action: ContractAction::Status { contract },
+        } => cmd_contract_status(&cfg_dir, contract.as_deref()),
+        Commands::Contract {
+            action: ContractAction::Edit,
+        } => cmd_edit_file(&cfg_dir, "contracts.toml", |dir| {
+            load_contracts(dir).map(|_| ())
+        }),
+        Commands::Digest { email, days, date } => {
+            cmd_digest(&cfg_dir, email.as_deref(), days, date)
+        }
+        Commands::Aging { date, open } => cmd_aging(&cfg_dir, date, open),
+        Commands::Overdue { date } => cmd_overdue_clients(&cfg_dir, date),
+        Commands::RefreshStatus { date, json } => cmd_refresh_status(&cfg_dir, date, json),
+        Commands::Schedule { crontab, run } => cmd_schedule(&cfg_dir, crontab, run, cli.read_only),
+        Commands::Config {
+            init_global,
+            action,
+        } => match action {
+            _ if init_global => cmd_init_global(),
+            Some(ConfigAction::Edit) => {
+                cmd_edit_file(&cfg_dir, "config.toml", |dir| load_config(dir).map(|_| ()))
+            }
+            Some(ConfigAction::SetGlobal { key, value }) => cmd_set_global(&key, &value),
+            Some(ConfigAction::Show) | None => cmd_config_show(&cfg_dir),
+        },
+        Commands::Status { verbose } => cmd_status(&cfg_dir, verbose, read_only),
+        Commands::Board => cmd_board(&cfg_dir),
+        Commands::List {
+            limit,
+            all,
+            net,
+            overdue,
+            plain,
+        } => {
+            if overdue {
+                cmd_overdue(&cfg_dir, limit)
+            } else {
+                cmd_invoices(&cfg_dir, limit, all, net, plain, read_only)
+            }
+        }
+        Commands::Edit {
+            invoice,
+            item,
+            discount,
+            note,
+            force_unlock,
+        } => match invoice_or_pick(&cfg_dir, invoice)? {
+            Some(invoice) => cmd_edit(
+                &cfg_dir,
+                &invoice,
+                &item,
+                discount,
+                note.as_deref(),
+                force_unlock,
+                cli.strict,
+            ),
+            None => Ok(()),
+        },
+        Commands::Open { invoice, format } => match invoice_or_pick(&cfg_dir, invoice)? {
+            Some(invoice) => cmd_open(&cfg_dir, &invoice, format),
+            None => Ok(()),
+        },
+        Commands::Show { invoice } => match invoice_or_pick(&cfg_dir, invoice)? {
+            Some(invoice) => cmd_show(&cfg_dir, &invoice),
+            None => Ok(()),
+        },
+        Commands::Pick => match pick_invoice(&cfg_dir)? {
+            Some(number) => {
+                println!("{number}");
+                Ok(())
+            }
+            None => Ok(()),
+        },
+        Commands::Remind { invoice, dry_run } => cmd_remind(&cfg_dir, &invoice, dry_run),
+        Commands::Dun { client, open } => cmd_dun(&cfg_dir, &client, open),
+        #[cfg(feature = "notify")]
+        Commands::NotifyDue { days } => cmd_notify_due(&cfg_dir, days),
+        Commands::Interest {
+            invoice,
+            rate,
+            day_count,
+        } => cmd_interest(&cfg_dir, &invoice, rate, day_count),
+        Commands::PaymentPlan {
+            invoice,
+            installments,
+            start,
+            open,
+        } => cmd_payment_plan(&cfg_dir, &invoice, installments, start, open),
+        Commands::VerifyFiles { update } => cmd_verify_files(&cfg_dir, update),
+        Commands::Version { verbose } => cmd_version(verbose),
+        #[cfg(feature = "network")]
+        Commands::SelfUpdate { check } => cmd_self_update(check),
+        Commands::Copy { invoice, format } => cmd_copy(&cfg_dir, &invoice, format),
+        Commands::Consolidate {
+            client,
+            invoices,
+            open,
+        } => cmd_consolidate(&cfg_dir, &client, &invoices, open, cli.strict),
+        Commands::ImportTime {
+            file,
+            client,
+            item,
+            description_column,
+            timesheet,
+            dry_run,
+            open,
+        } => {
+            let profile = import_profile(&cfg_dir, timesheet, item, description_column)?;
+            cmd_import_time(
+                &cfg_dir, &client, &file, &profile, dry_run, open, cli.strict,
+            )
+        }
+        Commands::TrueUp {
+            file,
+            client,
+            period,
+            timesheet,
+            dry_run,
+            open,
+        } => {
+            let profile = import_profile(&cfg_dir, timesheet, None, None)?;
+            let entries = read_timesheet(&std::fs::read_to_string(file)?, &profile)?;
+            let period = parse_service_period(period, None, None)?;
+            cmd_true_up(
+                &cfg_dir, &client, &entries, period, dry_run, open, cli.strict,
+            )
+        }
+        Commands::Deposit {
+            client,
+            project,
+            percent,
+            open,
+        } => cmd_deposit(&cfg_dir, &client, project, percent, open, cli.strict),
+        Commands::ReleaseRetention {
+            client,
+            project,
+            open,
+        } => cmd_release_retention(&cfg_dir, &client, project.as_deref(), open, cli.strict),
+        Commands::Regenerate {
+            invoice,
+            all: _,
+            open,
+            cover_letter,
+            formats,
+            deterministic,
+            debug,
+            force_unlock,
+        } => {
+            let render = render_options(
+                &cfg_dir,
+                cover_letter,
+                formats,
+                deterministic,
+                debug,
+                cli.strict,
+            );
+            match invoice {
+                Some(invoice) => cmd_regenerate(&cfg_dir, &invoice, open, &render, force_unlock),
+                None => cmd_regenerate_all(&cfg_dir, &render, force_unlock),
+            }
+        }
+        Commands::Diff {
+            invoice,
+            deterministic,
+        } => match invoice_or_pick(&cfg_dir, invoice)? {
+            Some(invoice) => {
+                let render =
+                    render_options(&cfg_dir, None, Vec::new(), deterministic, false, cli.strict);
+                cmd_diff(&cfg_dir, &invoice, &render)
+            }
+            None => Ok(()),
+        },
+        Commands::Lock { invoice } => cmd_lock(&cfg_dir, &invoice),
+        Commands::Followup { action } => cmd_followup(&cfg_dir, action),
+        Commands::Note { action } => cmd_note(&cfg_dir, action),
+        Commands::Trash { action } => cmd_trash(&cfg_dir, action),
+        Commands::Void {
+            invoice,
+            reason,
+            remove_files,
+        } => cmd_void(&cfg_dir, &invoice, reason, remove_files),
+        Commands::Compact => cmd_compact(&cfg_dir),
+        Commands::MergeState { file, dry_run } => cmd_merge_state(&cfg_dir, &file, dry_run),
+        Commands::CleanOutput { dry_run } => cmd_clean_output(&cfg_dir, dry_run),
+        Commands::Template {
+            action: TemplateAction::Check { path },
+        } => cmd_template_check(&path),
+        Commands::AddPayment {
+            invoice,
+            amount,
+            bulk,
+            date,
+            as_credit,
+        } => {
+            let payments = match (bulk, invoice, amount) {
+                (Some(bulk), _, _) => parse_bulk_payments(&bulk)?,
+                (None, Some(invoice), Some(amount)) => vec![(invoice, amount)],
+                _ => unreachable!("clap requires an invoice and amount without --bulk"),
+            };
+            cmd_add_payment(&cfg_dir, &payments, date, as_credit)
+        }
+        Commands::RemovePayment { invoice, index } => cmd_remove_payment(&cfg_dir, &invoice, index),
+        Commands::Payments { invoice } => cmd_payments(&cfg_dir, &invoice),
+        Commands::Report {
+            action: Some(ReportAction::List),
+            ..
+        } => cmd_report_list(&cfg_dir),
+        Commands::Report {
+            action: Some(ReportAction::Open { report }),
+            ..
+        } => cmd_report_open(&cfg_dir, &report),
+        Commands::Report {
+            action: None,
+            client,
+            from,
+            to,
+            status,
+            group_by,
+            net,
+            open,
+        } => {
+            let Some(client) = client else {
+                unreachable!("clap requires --client without a subcommand")
+            };
+            cmd_report(
+                &cfg_dir,
+                &client,
+                ReportFilter { from, to, status },
+                group_by,
+                net,
+                open,
+            )
+        }
+    }
+}
+
+/// Build render options from CLI flags
+fn render_options(
+    cfg_dir: &Path,
+    cover_letter: Option<PathBuf>,
+    formats: Vec<OutputFormat>,
+    deterministic: bool,
+    debug: bool,
+    strict: bool,
+) -> RenderOptions {
+    RenderOptions {
+        cover_letter: cover_letter
+            .map(|p| config::resolve_output_dir(&p.to_string_lossy(), cfg_dir)),
+        formats,
+        deterministic,
+        debug,
+        template_dir: Some(cfg_dir.join("templates")),
+        strict,
+    }
+}
+
+/// Initialize config directory with template files
+fn cmd_init(cfg_dir: &Path) -> Result<()> {
     use std::fs;
 
     if cfg_dir.exists() {
-        return Err(InvoiceError::AlreadyInitialized(cfg_dir.clone()));
+        return Err(InvoiceError::AlreadyInitialized(cfg_dir.to_path_buf()));
     }
 
     // Create directories
@@ -223,6 +1403,7 @@ fn cmd_init(cfg_dir: &PathBuf) -> Result<()> {
     fs::write(cfg_dir.join("config.toml"), CONFIG_TEMPLATE)?;
     fs::write(cfg_dir.join("clients.toml"), CLIENTS_TEMPLATE)?;
     fs::write(cfg_dir.join("items.toml"), ITEMS_TEMPLATE)?;
+    fs::write(cfg_dir.join("contracts.toml"), CONTRACTS_TEMPLATE)?;
 
     println!("Initialized invoice config at: {}", cfg_dir.display());
     println!();
@@ -255,6 +1436,8 @@ struct ClientRow {
     name: String,
     #[tabled(rename = "EMAIL")]
     email: String,
+    #[tabled(rename = "CREDIT")]
+    credit: String,
 }
 
 #[derive(Tabled)]
@@ -267,6 +1450,72 @@ struct ItemRow {
     rate: String,
     #[tabled(rename = "UNIT")]
     unit: String,
+    #[tabled(rename = "KIND")]
+    kind: String,
+    #[tabled(rename = "COST")]
+    cost: String,
+}
+
+#[derive(Tabled)]
+struct MarginRow {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "REVENUE")]
+    revenue: String,
+    #[tabled(rename = "COST")]
+    cost: String,
+    #[tabled(rename = "MARGIN")]
+    margin: String,
+    #[tabled(rename = "MARGIN %")]
+    margin_percent: String,
+}
+
+#[derive(Tabled)]
+struct ReportRow {
+    #[tabled(rename = "#")]
+    index: usize,
+    #[tabled(rename = "NUMBER")]
+    number: String,
+    #[tabled(rename = "CLIENT")]
+    client: String,
+    #[tabled(rename = "DATE")]
+    date: String,
+    #[tabled(rename = "FILTERS")]
+    filters: String,
+    #[tabled(rename = "FILE")]
+    file: String,
+}
+
+#[derive(Tabled)]
+struct ContractRow {
+    #[tabled(rename = "CONTRACT")]
+    id: String,
+    #[tabled(rename = "CLIENT")]
+    client: String,
+    #[tabled(rename = "TERM")]
+    term: String,
+    #[tabled(rename = "VALUE")]
+    value: String,
+    #[tabled(rename = "BILLED")]
+    billed: String,
+    #[tabled(rename = "REMAINING")]
+    remaining: String,
+}
+
+#[derive(Tabled)]
+struct ProjectRow {
+    #[tabled(rename = "PROJECT")]
+    id: String,
+    #[tabled(rename = "DESCRIPTION")]
+    description: String,
+    #[tabled(rename = "FIXED PRICE")]
+    fixed_price: String,
+    #[tabled(rename = "INVOICED")]
+    invoiced: String,
+    #[tabled(rename = "REMAINING")]
+    remaining: String,
+    #[tabled(rename = "RETAINED")]
+    retained: String,
 }
 
 #[derive(Tabled)]
@@ -286,38 +1535,43 @@ struct InvoiceRow {
 }
 
 #[derive(Tabled)]
-struct PaymentRow {
+struct InstallmentRow {
     #[tabled(rename = "#")]
     index: usize,
-    #[tabled(rename = "DATE")]
-    date: String,
+    #[tabled(rename = "DUE")]
+    due: String,
     #[tabled(rename = "AMOUNT")]
     amount: String,
+    #[tabled(rename = "STATUS")]
+    status: String,
 }
 
-fn format_whole_money(value: f64, currency_symbol: &str) -> String {
-    let rounded = value.round() as i64;
-    let grouped = format_grouped_int(rounded);
-    format!("{}{:>6}", currency_symbol, grouped)
+#[derive(Tabled)]
+struct LineRow {
+    #[tabled(rename = "#")]
+    index: usize,
+    #[tabled(rename = "DESCRIPTION")]
+    description: String,
+    #[tabled(rename = "QTY")]
+    quantity: String,
+    #[tabled(rename = "RATE")]
+    rate: String,
+    #[tabled(rename = "AMOUNT")]
+    amount: String,
 }
 
-fn format_grouped_int(value: i64) -> String {
-    let negative = value < 0;
-    let digits = value.unsigned_abs().to_string();
-    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
-
-    for (i, ch) in digits.chars().rev().enumerate() {
-        if i > 0 && i % 3 == 0 {
-            out.push(',');
-        }
-        out.push(ch);
-    }
+#[derive(Tabled)]
+struct PaymentRow {
+    #[tabled(rename = "#")]
+    index: usize,
+    #[tabled(rename = "DATE")]
+    date: String,
+    #[tabled(rename = "AMOUNT")]
+    amount: String,
+}
 
-    let mut grouped: String = out.chars().rev().collect();
-    if negative {
-        grouped.insert(0, '-');
-    }
-    grouped
+fn format_whole_money(value: f64, settings: &config::InvoiceSettings) -> String {
+    settings.with_symbol(&format!("{:>6}", format_whole(value)))
 }
 
 fn add_financial_footer(table: &str, total: &str, paid: &str, outstanding: &str) -> String {
@@ -392,29 +1646,390 @@ fn add_financial_footer(table: &str, total: &str, paid: &str, outstanding: &str)
     out
 }
 
-/// List configured clients
-fn cmd_clients(cfg_dir: &PathBuf) -> Result<()> {
+/// Name of the command when it would change state, config or invoice
+/// files (other than exports such as reports and statements)
+fn mutating_command(command: &Commands) -> Option<&'static str> {
+    let name = match command {
+        Commands::Init => "init",
+        Commands::Generate(_) => "generate",
+        Commands::Counter { .. } => "counter",
+        Commands::Clients {
+            action: Some(ClientsAction::Edit),
+        } => "clients edit",
+        Commands::Clients {
+            action: Some(ClientsAction::New { .. }),
+        } => "clients new",
+        Commands::Items {
+            action: Some(FileAction::Edit),
+        } => "items edit",
+        Commands::Contract {
+            action: ContractAction::Edit,
+        } => "contract edit",
+        Commands::Config {
+            init_global,
+            action,
+        } => match action {
+            _ if *init_global => "config --init-global",
+            Some(ConfigAction::Edit) => "config edit",
+            Some(ConfigAction::SetGlobal { .. }) => "config set-global",
+            Some(ConfigAction::Show) | None => return None,
+        },
+        Commands::Edit { .. } => "edit",
+        Commands::Remind { dry_run: false, .. } => "remind",
+        Commands::PaymentPlan {
+            installments: Some(_),
+            ..
+        } => "payment-plan",
+        Commands::VerifyFiles { update: true } => "verify-files --update",
+        #[cfg(feature = "network")]
+        Commands::SelfUpdate { check: false } => "self-update",
+        Commands::Consolidate { .. } => "consolidate",
+        Commands::ImportTime { dry_run: false, .. } => "import-time",
+        Commands::TrueUp { dry_run: false, .. } => "true-up",
+        Commands::Deposit { .. } => "deposit",
+        Commands::ReleaseRetention { .. } => "release-retention",
+        Commands::Regenerate { .. } => "regenerate",
+        Commands::Lock { .. } => "lock",
+        Commands::RefreshStatus { .. } => "refresh-status",
+        Commands::Followup {
+            action: FollowupAction::Add { .. },
+        } => "followup add",
+        Commands::Note {
+            action: NoteAction::Add { .. },
+        } => "note add",
+        Commands::Trash { action } => match action {
+            TrashAction::Add { .. } => "trash add",
+            TrashAction::Restore { .. } => "trash restore",
+            TrashAction::Purge { .. } => "trash purge",
+            TrashAction::List => return None,
+        },
+        Commands::Void { .. } => "void",
+        Commands::Report { action: None, .. } => "report",
+        Commands::Compact => "compact",
+        Commands::MergeState { dry_run: false, .. } => "merge-state",
+        Commands::CleanOutput { dry_run: false } => "clean-output",
+        Commands::AddPayment { .. } => "add-payment",
+        Commands::RemovePayment { .. } => "remove-payment",
+        Commands::Clients { action: None }
+        | Commands::Items { action: None }
+        | Commands::Projects
+        | Commands::Margins
+        | Commands::Contract {
+            action: ContractAction::Status { .. },
+        }
+        | Commands::Digest { .. }
+        | Commands::Overdue { .. }
+        | Commands::Aging { .. }
+        | Commands::Schedule { .. }
+        | Commands::Status { .. }
+        | Commands::List { .. }
+        | Commands::Board
+        | Commands::Open { .. }
+        | Commands::Show { .. }
+        | Commands::Diff { .. }
+        | Commands::Pick
+        | Commands::Remind { dry_run: true, .. }
+        | Commands::Dun { .. }
+        | Commands::Interest { .. }
+        | Commands::PaymentPlan {
+            installments: None, ..
+        }
+        | Commands::VerifyFiles { update: false }
+        | Commands::Version { .. }
+        | Commands::Copy { .. }
+        | Commands::ImportTime { dry_run: true, .. }
+        | Commands::TrueUp { dry_run: true, .. }
+        | Commands::Followup {
+            action: FollowupAction::List { .. },
+        }
+        | Commands::Note {
+            action: NoteAction::List { .. } | NoteAction::Search { .. },
+        }
+        | Commands::CleanOutput { dry_run: true }
+        | Commands::MergeState { dry_run: true, .. }
+        | Commands::Template { .. }
+        | Commands::Payments { .. }
+        | Commands::Report {
+            action: Some(ReportAction::List | ReportAction::Open { .. }),
+            ..
+        } => return None,
+        #[cfg(feature = "notify")]
+        Commands::NotifyDue { .. } => return None,
+        #[cfg(feature = "network")]
+        Commands::SelfUpdate { check: true } => return None,
+    };
+    Some(name)
+}
+
+/// Write the global config template
+fn cmd_init_global() -> Result<()> {
+    let path = config::init_global_config()?;
+    println!("Initialized global config at: {}", path.display());
+    Ok(())
+}
+
+/// Set a key in the global config
+fn cmd_set_global(key: &str, value: &str) -> Result<()> {
+    let path = config::set_global_config_value(key, value)?;
+    println!("Set {key} = \"{value}\" in {}", path.display());
+    Ok(())
+}
+
+/// Show global config values and the effective config directory
+fn cmd_config_show(cfg_dir: &Path) -> Result<()> {
+    let global_path = global_config_file();
+    let global = load_global_config();
+
+    if global_path.exists() {
+        println!("Global config:    {}", global_path.display());
+    } else {
+        println!("Global config:    {} (not found)", global_path.display());
+    }
+    println!(
+        "  config_dir:     {}",
+        global.config_dir.as_deref().unwrap_or("(unset)")
+    );
+    println!("Config directory: {}", cfg_dir.display());
+
+    Ok(())
+}
+
+/// Open a config file in $EDITOR, re-parse it on save, and offer to re-open
+/// the editor until it's valid so a broken file is never left behind unknowingly
+fn cmd_edit_file(cfg_dir: &Path, file_name: &str, validate: fn(&Path) -> Result<()>) -> Result<()> {
     if !cfg_dir.exists() {
-        return Err(InvoiceError::ConfigNotFound(cfg_dir.clone()));
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
     }
 
-    let clients = load_clients(cfg_dir)?;
+    let path = cfg_dir.join(file_name);
+    loop {
+        editor::edit_file(&path)?;
 
-    if clients.is_empty() {
-        println!("No clients configured.");
-        println!("Add clients to: {}/clients.toml", cfg_dir.display());
-        return Ok(());
+        match validate(cfg_dir) {
+            Ok(()) => {
+                println!("{} is valid.", path.display());
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                if !confirm("Re-open the editor to fix it? [Y/n] ") {
+                    return Err(e);
+                }
+            }
+        }
     }
+}
 
-    let mut sorted: Vec<_> = clients.iter().collect();
-    sorted.sort_by_key(|(k, _)| *k);
+/// Ask a yes/no question on stdin; empty input means yes, EOF means no
+fn confirm(prompt: &str) -> bool {
+    use std::io::Write;
 
-    let rows: Vec<ClientRow> = sorted
+    eprint!("{prompt}");
+    let _ = std::io::stderr().flush();
+
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(0) | Err(_) => false,
+        Ok(_) => matches!(answer.trim().to_lowercase().as_str(), "" | "y" | "yes"),
+    }
+}
+
+/// Add client `id` to clients.toml, either by asking for each field or by
+/// appending an empty entry and opening it in the editor
+fn cmd_client_new(cfg_dir: &Path, id: &str, interactive: bool) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        return Err(InvoiceError::InvalidClientId(id.to_string()));
+    }
+    if load_clients(cfg_dir)?.contains_key(id) {
+        return Err(InvoiceError::ClientExists(id.to_string()));
+    }
+
+    if !interactive {
+        let client = config::Client {
+            name: id.to_string(),
+            ..Default::default()
+        };
+        config::append_client(cfg_dir, id, &client)?;
+        return cmd_edit_file(cfg_dir, "clients.toml", |dir| load_clients(dir).map(|_| ()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let Some(client) = ask_client(&config) else {
+        println!("Nothing added.");
+        return Ok(());
+    };
+
+    println!("{}", config::client_entry(id, &client)?.trim_end());
+    if !confirm("Add this client to clients.toml? [Y/n] ") {
+        println!("Nothing added.");
+        return Ok(());
+    }
+    config::append_client(cfg_dir, id, &client)?;
+    load_clients(cfg_dir)?;
+    println!(
+        "Added client '{id}' to {}",
+        cfg_dir.join("clients.toml").display()
+    );
+    Ok(())
+}
+
+/// Walk through a client's fields on stdin, validating each answer. Only
+/// defaults that differ from config.toml are kept. None at end of input.
+fn ask_client(config: &config::Config) -> Option<config::Client> {
+    let required = |value: &str| match value {
+        "" => Err("This field is required".to_string()),
+        _ => Ok(()),
+    };
+    let optional = |_: &str| Ok(());
+
+    let name = ask("Name", None, required)?;
+    let contact = ask("Contact person (optional)", None, optional)?;
+    let email = ask("Email", None, config::validate_email)?;
+    let address = ask("Street address", None, required)?;
+    let city = ask("City", None, required)?;
+    let state = ask("State or region (optional)", None, optional)?;
+    let country = ask(
+        "Country",
+        Some(&config.company.country),
+        config::validate_country,
+    )?;
+    let zip = ask("Postal code", None, |zip| {
+        config::validate_zip(zip, Some(&country))
+    })?;
+    let tax_id = ask("VAT or tax ID (optional)", None, |id| match id {
+        "" => Ok(()),
+        id => config::validate_tax_id(id),
+    })?;
+    if !tax_id.is_empty() && confirm(&format!("Check {tax_id} with VIES? [Y/n] ")) {
+        match invoice::vat::check_vat(&tax_id) {
+            Some(check) if check.valid => match check.name {
+                Some(name) => eprintln!("VIES: valid, registered to {name}"),
+                None => eprintln!("VIES: valid"),
+            },
+            Some(_) => eprintln!("Warning: VIES does not know {tax_id}"),
+            None => eprintln!("Warning: could not reach VIES; {tax_id} was not checked"),
+        }
+    }
+
+    let due_days = ask(
+        "Payment due in days",
+        Some(&config.invoice.due_days.to_string()),
+        |days| {
+            days.parse::<u32>()
+                .map(|_| ())
+                .map_err(|_| "Enter a whole number of days".to_string())
+        },
+    )?
+    .parse::<u32>()
+    .ok()?;
+    let currency = ask("Currency", Some(&config.invoice.currency), |code| {
+        if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) {
+            Ok(())
+        } else {
+            Err("Use a three-letter ISO 4217 code (e.g., EUR)".to_string())
+        }
+    })?
+    .to_uppercase();
+    let currency_symbol = if currency == config.invoice.currency {
+        None
+    } else {
+        Some(ask("Currency symbol", Some(&currency), required)?)
+    };
+    let language = ask("Document language (optional, e.g., de)", None, |code| {
+        if code.is_empty() || (code.len() == 2 && code.chars().all(|c| c.is_ascii_lowercase())) {
+            Ok(())
+        } else {
+            Err("Use a two-letter ISO 639-1 code (e.g., de)".to_string())
+        }
+    })?;
+
+    let some = |value: String| (!value.is_empty()).then_some(value);
+    Some(config::Client {
+        name,
+        contact: some(contact),
+        email,
+        address,
+        city,
+        state,
+        zip,
+        country: Some(country),
+        tax_id: some(tax_id),
+        language: some(language),
+        due_days: (due_days != config.invoice.due_days).then_some(due_days),
+        currency: currency_symbol.is_some().then_some(currency),
+        currency_symbol,
+        ..Default::default()
+    })
+}
+
+/// Prompt on stderr until `check` accepts the trimmed answer; an empty
+/// answer takes `default`. None at end of input.
+fn ask(
+    prompt: &str,
+    default: Option<&str>,
+    check: impl Fn(&str) -> std::result::Result<(), String>,
+) -> Option<String> {
+    use std::io::Write;
+
+    loop {
+        match default {
+            Some(default) => eprint!("{prompt} [{default}]: "),
+            None => eprint!("{prompt}: "),
+        }
+        let _ = std::io::stderr().flush();
+
+        let mut answer = String::new();
+        if matches!(std::io::stdin().read_line(&mut answer), Ok(0) | Err(_)) {
+            eprintln!();
+            return None;
+        }
+        let answer = match (answer.trim(), default) {
+            ("", Some(default)) => default.to_string(),
+            (answer, _) => answer.to_string(),
+        };
+        match check(&answer) {
+            Ok(()) => return Some(answer),
+            Err(reason) => eprintln!("{reason}"),
+        }
+    }
+}
+
+/// List configured clients
+fn cmd_clients(cfg_dir: &Path) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let clients = load_clients(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+
+    if clients.is_empty() {
+        println!("No clients configured.");
+        println!("Add clients to: {}/clients.toml", cfg_dir.display());
+        return Ok(());
+    }
+
+    let mut sorted: Vec<_> = clients.iter().collect();
+    sorted.sort_by_key(|(k, _)| *k);
+
+    let rows: Vec<ClientRow> = sorted
         .iter()
         .map(|(id, client)| ClientRow {
             id: id.to_string(),
             name: client.name.clone(),
             email: client.email.clone(),
+            credit: match state.client_credit(id) {
+                credit if credit > 0.0 => config.invoice.money(credit),
+                _ => "-".to_string(),
+            },
         })
         .collect();
 
@@ -425,9 +2040,9 @@ fn cmd_clients(cfg_dir: &PathBuf) -> Result<()> {
 }
 
 /// List available line items
-fn cmd_items(cfg_dir: &PathBuf) -> Result<()> {
+fn cmd_items(cfg_dir: &Path) -> Result<()> {
     if !cfg_dir.exists() {
-        return Err(InvoiceError::ConfigNotFound(cfg_dir.clone()));
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
     }
 
     let config = load_config(cfg_dir)?;
@@ -447,8 +2062,109 @@ fn cmd_items(cfg_dir: &PathBuf) -> Result<()> {
         .map(|(id, item)| ItemRow {
             id: id.to_string(),
             description: item.description.clone(),
-            rate: format!("{:.2}{}", item.rate, config.invoice.currency_symbol),
+            rate: config.invoice.money(item.rate),
             unit: format!("/{}", item.unit),
+            kind: item.kind.to_string(),
+            cost: match item.cost {
+                Some(cost) => config.invoice.money(cost),
+                None => "-".to_string(),
+            },
+        })
+        .collect();
+
+    let table = Table::new(rows).with(Style::rounded()).to_string();
+    println!("{table}");
+
+    Ok(())
+}
+
+/// Fixed price vs invoiced amount for each project in config.toml
+fn cmd_projects(cfg_dir: &Path) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let items = load_items(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+
+    if config.projects.is_empty() {
+        println!("No projects configured.");
+        println!(
+            "Add [projects.<id>] sections to: {}/config.toml",
+            cfg_dir.display()
+        );
+        return Ok(());
+    }
+
+    let budgets = project_budgets(&config.projects, &items, &state.history);
+    let retained = retained_by_project(&state.history);
+    let rows: Vec<ProjectRow> = budgets
+        .iter()
+        .map(|budget| ProjectRow {
+            id: budget.id.clone(),
+            description: budget.description.clone().unwrap_or_default(),
+            fixed_price: config.invoice.money(budget.fixed_price),
+            invoiced: config.invoice.money(budget.invoiced),
+            remaining: if budget.is_over() {
+                format!("{} over", config.invoice.money(-budget.remaining()))
+            } else {
+                config.invoice.money(budget.remaining())
+            },
+            retained: match retained.get(&budget.id) {
+                Some(amount) => config.invoice.money(*amount),
+                None => "-".to_string(),
+            },
+        })
+        .collect();
+
+    let table = Table::new(rows).with(Style::rounded()).to_string();
+    println!("{table}");
+
+    Ok(())
+}
+
+/// Billed-to-date against contract values
+fn cmd_contract_status(cfg_dir: &Path, only: Option<&str>) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let contracts = load_contracts(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+
+    if let Some(id) = only.filter(|id| !contracts.contains_key(*id)) {
+        return Err(InvoiceError::ContractNotFound(id.to_string()));
+    }
+    if contracts.is_empty() {
+        println!("No contracts configured.");
+        println!("Add contracts to: {}/contracts.toml", cfg_dir.display());
+        return Ok(());
+    }
+
+    let rows: Vec<ContractRow> = contract_status(&contracts, &state.history)
+        .into_iter()
+        .filter(|status| only.is_none_or(|id| id == status.id))
+        .map(|status| ContractRow {
+            id: status.id.clone(),
+            client: status.client.clone(),
+            term: match status.end {
+                Some(end) => format!("{} to {}", status.start, end),
+                None => format!("from {}", status.start),
+            },
+            value: config.invoice.money(status.value),
+            billed: format!(
+                "{} ({} invoice{})",
+                config.invoice.money(status.billed),
+                status.invoices,
+                if status.invoices == 1 { "" } else { "s" }
+            ),
+            remaining: if status.is_over() {
+                format!("{} over", config.invoice.money(-status.remaining()))
+            } else {
+                config.invoice.money(status.remaining())
+            },
         })
         .collect();
 
@@ -458,26 +2174,116 @@ fn cmd_items(cfg_dir: &PathBuf) -> Result<()> {
     Ok(())
 }
 
+/// Revenue against item costs across billed invoices, per item and per client
+fn cmd_margins(cfg_dir: &Path) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let items = load_items(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+
+    if items.values().all(|item| item.cost.is_none()) {
+        println!("No item costs configured.");
+        println!(
+            "Add 'cost = <per unit>' to items in: {}/items.toml",
+            cfg_dir.display()
+        );
+        return Ok(());
+    }
+
+    let margins = item_margins(&state.history, &items);
+    if margins.items.is_empty() {
+        println!("No invoiced items yet.");
+        return Ok(());
+    }
+
+    let row = |margin: &Margin| MarginRow {
+        id: margin.id.clone(),
+        revenue: config.invoice.money(margin.revenue),
+        cost: match margin.margin() {
+            Some(_) => config.invoice.money(margin.cost),
+            None => "-".to_string(),
+        },
+        margin: margin
+            .margin()
+            .map_or("-".to_string(), |m| config.invoice.money(m)),
+        margin_percent: margin
+            .margin_percent()
+            .map_or("-".to_string(), |p| format!("{p:.1}%")),
+    };
+    for (heading, rows) in [("By item", &margins.items), ("By client", &margins.clients)] {
+        println!("{heading}:");
+        let table = Table::new(rows.iter().map(row))
+            .with(Style::rounded())
+            .to_string();
+        println!("{table}");
+    }
+
+    let uncosted: f64 = margins.items.iter().map(|m| m.uncosted).sum();
+    if uncosted >= 0.005 {
+        println!(
+            "{} of revenue comes from items without a cost and is left out of the margins",
+            config.invoice.money(uncosted)
+        );
+    }
+
+    Ok(())
+}
+
+/// Set the counter so the next generated invoice uses `next`
+fn cmd_counter_set(
+    cfg_dir: &Path,
+    year: Option<u32>,
+    next: u32,
+    series: Option<&str>,
+) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let mut state = load_state(cfg_dir)?;
+    let year = year.unwrap_or_else(|| chrono::Utc::now().year() as u32);
+    let series = series.unwrap_or(DEFAULT_SERIES);
+    let number_format = config
+        .number_format(series)
+        .ok_or_else(|| InvoiceError::UnknownSeries(series.to_string()))?;
+
+    let next_number = format_invoice_number(number_format, year, next);
+    validate_invoice_number(&next_number)?;
+    if state.history.iter().any(|e| e.number == next_number) {
+        return Err(InvoiceError::DuplicateInvoiceNumber(next_number));
+    }
+
+    let counter = state.counter_for_mut(series);
+    counter.last_year = year;
+    counter.last_number = next - 1;
+    save_state(cfg_dir, &state)?;
+
+    println!("Counter set. Next invoice: {}", next_number);
+    Ok(())
+}
+
 /// Show invoice status
-fn cmd_status(cfg_dir: &PathBuf, show_global: bool) -> Result<()> {
+fn cmd_status(cfg_dir: &Path, show_global: bool, read_only: bool) -> Result<()> {
     if !cfg_dir.exists() {
-        return Err(InvoiceError::ConfigNotFound(cfg_dir.clone()));
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
     }
 
     let config = load_config(cfg_dir)?;
     let clients = load_clients(cfg_dir)?;
     let items = load_items(cfg_dir)?;
-    let state = load_state(cfg_dir)?;
+    let mut state = load_state(cfg_dir)?;
 
     // Calculate next invoice number
     let current_year = chrono::Utc::now().year() as u32;
-    let next_seq = if state.counter.last_year == current_year {
-        state.counter.last_number + 1
-    } else {
-        1 // Reset for new year
-    };
-
-    let next_number = format_invoice_number(&config.invoice.number_format, current_year, next_seq);
+    let next_seq = state.counter.next_seq(current_year);
+    let default_format = config
+        .number_format(DEFAULT_SERIES)
+        .unwrap_or(&config.invoice.number_format);
+    let next_number = format_invoice_number(default_format, current_year, next_seq);
 
     println!("Invoice Status");
     println!("{}", "-".repeat(50));
@@ -498,291 +2304,2873 @@ fn cmd_status(cfg_dir: &PathBuf, show_global: bool) -> Result<()> {
     println!("Clients:          {}", clients.len());
     println!("Items:            {}", items.len());
     println!("Next invoice:     {}", next_number);
+    for (name, settings) in &config.series {
+        if name == DEFAULT_SERIES {
+            continue;
+        }
+        let seq = state.counter_for(name).next_seq(current_year);
+        println!(
+            "  {:<15} {}",
+            format!("{}:", name),
+            format_invoice_number(&settings.number_format, current_year, seq)
+        );
+    }
+
+    if let Some(goals) = &config.goals {
+        let today = chrono::Local::now().date_naive();
+        let progress = monthly_progress(&state.history, goals.monthly_revenue, today);
+        println!();
+        println!("Monthly goal ({}):", today.format("%B %Y"));
+        print_goal_progress(&progress, &config.invoice);
+    }
 
     if !state.history.is_empty() {
         println!();
         println!("Recent invoices:");
         for entry in state.history.iter().rev().take(5) {
             println!(
-                "  {} - {} - {}{:.2}",
-                entry.number, entry.client, config.invoice.currency_symbol, entry.total
+                "  {} - {} - {}",
+                entry.number,
+                entry.client,
+                config.invoice.money(entry.total)
+            );
+        }
+
+        let outstanding: f64 = state.history.iter().map(|e| e.outstanding()).sum();
+        if outstanding > 0.0 {
+            let amounts: Vec<_> = state
+                .history
+                .iter()
+                .map(|e| (invoice_currency(e, &config), e.outstanding()))
+                .collect();
+            let mut rates = Rates::new(
+                &config.currency,
+                &mut state.rates,
+                chrono::Local::now().naive_local(),
             );
+            let converted = rates.convert_all(amounts, &config.currency.display);
+            let updated = rates.updated();
+            println!();
+            match converted {
+                Some(converted) => println!(
+                    "Outstanding:      {} ({} {}; {})",
+                    config.invoice.money(outstanding),
+                    format_whole(converted.amount),
+                    converted.currency,
+                    converted.rates_label()
+                ),
+                None => println!("Outstanding:      {}", config.invoice.money(outstanding)),
+            }
+            if updated && !read_only {
+                save_state(cfg_dir, &state)?;
+            }
+        }
+    }
+
+    if show_global {
+        println!();
+        println!("Health:");
+        for (subsystem, health, detail) in health_checks(cfg_dir, &config, &clients) {
+            println!("  {}  {:<15} {}", health, subsystem, detail);
         }
     }
 
     Ok(())
 }
 
-/// Fetch the current USD→BRL exchange rate from the Frankfurter API.
-/// Returns None on any failure (network, timeout, parse error) so the
-/// caller can silently skip the BRL line.
-fn fetch_usd_to_brl_rate() -> Option<f64> {
-    use std::time::Duration;
-    use ureq::Agent;
+/// Outcome of a `status --verbose` check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Health {
+    /// Working
+    Green,
+    /// Degraded or disabled, but invoices can still be generated
+    Yellow,
+    /// Broken; generating invoices will fail
+    Red,
+}
 
-    let agent: Agent = Agent::config_builder()
-        .timeout_global(Some(Duration::from_secs(3)))
-        .build()
-        .into();
+impl std::fmt::Display for Health {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (label, color) = match self {
+            Health::Green => ("green ", "32"),
+            Health::Yellow => ("yellow", "33"),
+            Health::Red => ("red   ", "31"),
+        };
+        if std::io::stdout().is_terminal() {
+            write!(f, "\x1b[{color}m{label}\x1b[0m")
+        } else {
+            write!(f, "{label}")
+        }
+    }
+}
 
-    let body: String = agent
-        .get("https://api.frankfurter.dev/v1/latest?base=USD&symbols=BRL")
-        .call()
-        .ok()?
-        .body_mut()
-        .read_to_string()
-        .ok()?;
+/// Probe the subsystems invoice generation depends on
+fn health_checks(
+    cfg_dir: &Path,
+    config: &config::Config,
+    clients: &HashMap<String, config::Client>,
+) -> Vec<(&'static str, Health, String)> {
+    let mut checks = Vec::new();
+
+    checks.push(match typst_version() {
+        Some(version) => ("Typst", Health::Green, version),
+        None => (
+            "Typst",
+            Health::Red,
+            "not found on PATH; PDFs can't be generated".to_string(),
+        ),
+    });
+
+    let mut covers: Vec<(&String, PathBuf)> = clients
+        .iter()
+        .filter_map(|(id, client)| {
+            let path = client.cover_letter.as_deref()?;
+            Some((id, config::resolve_output_dir(path, cfg_dir)))
+        })
+        .collect();
+    covers.sort();
+    let missing: Vec<String> = covers
+        .iter()
+        .filter(|(_, path)| !path.exists())
+        .map(|(id, path)| format!("{} ({})", path.display(), id))
+        .collect();
+    checks.push(if !missing.is_empty() {
+        (
+            "Templates",
+            Health::Red,
+            format!("missing cover letter: {}", missing.join(", ")),
+        )
+    } else if covers.is_empty() {
+        ("Templates", Health::Green, "built-in".to_string())
+    } else {
+        (
+            "Templates",
+            Health::Green,
+            format!("built-in, {} cover letter override(s)", covers.len()),
+        )
+    });
 
-    let json: serde_json::Value = serde_json::from_str(&body).ok()?;
-    json["rates"]["BRL"].as_f64()
+    let output_dir = config::resolve_output_dir(&config.pdf.output_dir, cfg_dir);
+    let probe = output_dir.join(".invoice-write-test");
+    let writable = std::fs::create_dir_all(&output_dir)
+        .and_then(|_| std::fs::write(&probe, b""))
+        .and_then(|_| std::fs::remove_file(&probe));
+    checks.push(match writable {
+        Ok(()) => (
+            "Output dir",
+            Health::Green,
+            output_dir.display().to_string(),
+        ),
+        Err(e) => (
+            "Output dir",
+            Health::Red,
+            format!("{} is not writable: {}", output_dir.display(), e),
+        ),
+    });
+
+    let (from, to) = (&config.invoice.currency, &config.currency.display);
+    checks.push(match config.currency.provider {
+        _ if from.eq_ignore_ascii_case(to) => (
+            "Exchange rates",
+            Health::Green,
+            format!("nothing to convert (display currency is {to})"),
+        ),
+        RateProvider::None => (
+            "Exchange rates",
+            Health::Yellow,
+            "disabled ([currency] provider = \"none\")".to_string(),
+        ),
+        RateProvider::Fixed => match rates::FixedRates(&config.currency.rates).rate(from, to) {
+            Some(rate) => (
+                "Exchange rates",
+                Health::Green,
+                format!("fixed (1 {from} = {rate:.2} {to})"),
+            ),
+            None => (
+                "Exchange rates",
+                Health::Yellow,
+                format!("no {from}/{to} rate under [currency.rates]"),
+            ),
+        },
+        RateProvider::Frankfurter if !cfg!(feature = "network") => (
+            "Exchange rates",
+            Health::Yellow,
+            "disabled in this build (no `network` feature)".to_string(),
+        ),
+        RateProvider::Frankfurter => match rates::fetch_rate(from, to) {
+            Some(rate) => (
+                "Exchange rates",
+                Health::Green,
+                format!("Frankfurter reachable (1 {from} = {rate:.2} {to})"),
+            ),
+            None => (
+                "Exchange rates",
+                Health::Yellow,
+                format!("Frankfurter unreachable; {to} totals use cached rates or are skipped"),
+            ),
+        },
+    });
+
+    checks
 }
 
-/// List generated invoices with three-way status (UNPAID / PARTIAL / PAID)
-fn cmd_invoices(cfg_dir: &PathBuf, limit: Option<usize>) -> Result<()> {
+/// Print or email the receivables digest
+fn cmd_digest(cfg_dir: &Path, email: Option<&str>, days: i64, date: Option<String>) -> Result<()> {
     if !cfg_dir.exists() {
-        return Err(InvoiceError::ConfigNotFound(cfg_dir.clone()));
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
     }
 
     let config = load_config(cfg_dir)?;
     let state = load_state(cfg_dir)?;
+    let today = match date {
+        Some(s) => chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+            .map_err(|_| InvoiceError::PdfGeneration(format!("Invalid --date value: '{s}'")))?,
+        None => chrono::Local::now().date_naive(),
+    };
 
-    if state.history.is_empty() {
-        println!("No invoices generated yet.");
+    let digest = receivables_digest(&state.history, &config, today, days);
+    let body = render_digest(&digest, &config);
+    let Some(to) = email else {
+        print!("{body}");
         return Ok(());
+    };
+
+    let from = config
+        .email
+        .from
+        .as_deref()
+        .unwrap_or(&config.company.email);
+    let subject = format!(
+        "{}: receivables digest for {}",
+        config.company.name,
+        today.format("%Y-%m-%d")
+    );
+    let message = mail::compose_message(from, to, &subject, &body);
+    mail::send_message(&config.email.command, &message)?;
+    println!("Sent digest to {to}");
+    Ok(())
+}
+
+/// An invoice's standing as printed by `refresh-status --json`
+#[derive(Serialize)]
+struct StandingRow<'a> {
+    number: &'a str,
+    client: &'a str,
+    total: f64,
+    #[serde(flatten)]
+    standing: &'a Standing,
+}
+
+fn cmd_refresh_status(cfg_dir: &Path, date: Option<String>, json: bool) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
     }
 
-    let invoices: Vec<_> = state.history.iter().rev().enumerate().collect();
-    let invoices = match limit {
-        Some(n) => &invoices[..n.min(invoices.len())],
-        None => &invoices[..],
+    let config = load_config(cfg_dir)?;
+    let mut state = load_state(cfg_dir)?;
+    let today = match date {
+        Some(s) => chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+            .map_err(|_| InvoiceError::PdfGeneration(format!("Invalid --date value: '{s}'")))?,
+        None => chrono::Local::now().date_naive(),
     };
 
-    // Derive status from payment records
-    let rows: Vec<InvoiceRow> = invoices
+    refresh_standings(&mut state.history, &config, today);
+    save_state(cfg_dir, &state)?;
+
+    let standings: Vec<(&HistoryEntry, &Standing)> = state
+        .history
         .iter()
-        .map(|(idx, entry)| InvoiceRow {
-            index: idx + 1,
-            number: entry.number.clone(),
-            date: entry.date.to_string(),
-            total: format_whole_money(entry.total, &config.invoice.currency_symbol),
-            status: entry.status().to_string(),
-            client: entry.client.clone(),
-        })
+        .filter_map(|e| Some((e, e.standing.as_ref()?)))
         .collect();
+    if json {
+        let rows: Vec<StandingRow> = standings
+            .iter()
+            .map(|(entry, standing)| StandingRow {
+                number: &entry.number,
+                client: &entry.client,
+                total: entry.total,
+                standing,
+            })
+            .collect();
+        let json = serde_json::to_string_pretty(&rows)
+            .map_err(|e| InvoiceError::PdfGeneration(e.to_string()))?;
+        println!("{json}");
+        return Ok(());
+    }
 
-    // Financial summary uses actual payment amounts
-    let shown_total: f64 = invoices.iter().map(|(_, entry)| entry.total).sum();
-    let shown_paid: f64 = invoices.iter().map(|(_, entry)| entry.paid_amount()).sum();
-    let shown_outstanding: f64 = shown_total - shown_paid;
+    let overdue = standings.iter().filter(|(_, s)| s.overdue).count();
+    println!(
+        "Refreshed the status of {} invoice(s) as of {}: {} overdue",
+        standings.len(),
+        today.format("%Y-%m-%d"),
+        overdue
+    );
+    Ok(())
+}
 
-    let table = Table::new(rows).with(Style::rounded()).to_string();
-    let total_amount = format_whole_money(shown_total, &config.invoice.currency_symbol);
-    let paid_amount = format_whole_money(shown_paid, &config.invoice.currency_symbol);
-    let outstanding_amount = format_whole_money(shown_outstanding, &config.invoice.currency_symbol);
-    let table = add_financial_footer(&table, &total_amount, &paid_amount, &outstanding_amount);
+#[derive(Tabled)]
+struct ScheduleRow {
+    #[tabled(rename = "NAME")]
+    name: String,
+    #[tabled(rename = "CRON")]
+    cron: String,
+    #[tabled(rename = "COMMAND")]
+    command: String,
+    #[tabled(rename = "NEXT RUN")]
+    next: String,
+}
 
-    println!("{table}");
+/// List, install or run the `[schedule]` commands
+fn cmd_schedule(cfg_dir: &Path, crontab: bool, run: bool, read_only: bool) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    if config.schedule.is_empty() {
+        println!("No scheduled commands.");
+        println!(
+            "Add [schedule.<name>] sections to: {}/config.toml",
+            cfg_dir.display()
+        );
+        return Ok(());
+    }
+
+    // Catch typos now rather than at 3 a.m.
+    for (name, entry) in &config.schedule {
+        let args = entry.args();
+        let invalid = |reason: String| InvoiceError::InvalidSchedule {
+            name: name.clone(),
+            reason,
+        };
+        if args.first().is_some_and(|command| command == "schedule") {
+            return Err(invalid("it can't run 'schedule' itself".to_string()));
+        }
+        Cli::try_parse_from(std::iter::once("invoice".to_string()).chain(args))
+            .map_err(|e| invalid(e.kind().to_string()))?;
+    }
+
+    let exe = std::env::current_exe()?;
+    let flags = if read_only { " --read-only" } else { "" };
+    if crontab {
+        let quote = |s: &str| {
+            if s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "/._-".contains(c))
+            {
+                s.to_string()
+            } else {
+                format!("'{}'", s.replace('\'', r"'\''"))
+            }
+        };
+        println!("# invoice schedule for {}", cfg_dir.display());
+        for (name, entry) in &config.schedule {
+            let line = format!(
+                "{} {} -C {}{flags} {}",
+                entry.cron,
+                quote(&exe.to_string_lossy()),
+                quote(&cfg_dir.to_string_lossy()),
+                entry.command
+            );
+            // cron turns unescaped % into newlines
+            println!("{}  # {name}", line.replace('%', r"\%"));
+        }
+        return Ok(());
+    }
+
+    if !run {
+        let now = chrono::Local::now().naive_local();
+        let rows: Vec<ScheduleRow> = config
+            .schedule
+            .iter()
+            .map(|(name, entry)| ScheduleRow {
+                name: name.clone(),
+                cron: entry.cron.to_string(),
+                command: entry.command.clone(),
+                next: entry
+                    .cron
+                    .next_after(now)
+                    .map_or("never".to_string(), |at| {
+                        at.format("%Y-%m-%d %H:%M").to_string()
+                    }),
+            })
+            .collect();
+        println!("{}", Table::new(rows).with(Style::rounded()));
+        println!("Use --crontab to print crontab lines, or --run to run them here");
+        return Ok(());
+    }
+
+    println!(
+        "Running {} scheduled command(s); press Ctrl-C to stop",
+        config.schedule.len()
+    );
+    // Start with the next whole minute, so a restart doesn't repeat a run
+    let now = chrono::Local::now().naive_local();
+    let mut next = now
+        .with_second(0)
+        .and_then(|at| at.with_nanosecond(0))
+        .unwrap_or(now)
+        + chrono::Duration::minutes(1);
+    loop {
+        // Catch up on minutes missed while a long command was running
+        let now = chrono::Local::now().naive_local();
+        while next <= now {
+            for (name, entry) in config.schedule.iter().filter(|(_, e)| e.cron.matches(next)) {
+                let status = std::process::Command::new(&exe)
+                    .arg("-C")
+                    .arg(cfg_dir)
+                    .args(read_only.then_some("--read-only"))
+                    .args(entry.args())
+                    .status();
+                let outcome = match status {
+                    Ok(status) if status.success() => "done".to_string(),
+                    Ok(status) => format!("failed ({status})"),
+                    Err(e) => format!("failed to start: {e}"),
+                };
+                println!("[{}] {name}: {outcome}", next.format("%Y-%m-%d %H:%M"));
+            }
+            next += chrono::Duration::minutes(1);
+        }
+        let wait = (next - chrono::Local::now().naive_local())
+            .to_std()
+            .unwrap_or_default();
+        std::thread::sleep(wait);
+    }
+}
+
+/// Print invoiced/collected progress toward the monthly target
+fn print_goal_progress(progress: &GoalProgress, settings: &config::InvoiceSettings) {
+    println!(
+        "  Invoiced:  {} {:>3.0}%  {} of {}",
+        progress_bar(progress.invoiced_ratio()),
+        progress.invoiced_ratio() * 100.0,
+        settings.money(progress.invoiced),
+        settings.money(progress.target)
+    );
+    println!(
+        "  Collected: {} {:>3.0}%  {}",
+        progress_bar(progress.collected_ratio()),
+        progress.collected_ratio() * 100.0,
+        settings.money(progress.collected)
+    );
+    println!(
+        "  Projected: {} by month end at the current rate",
+        settings.money(progress.projected)
+    );
+}
+
+/// A 20-cell text bar for a ratio, clamped to full
+fn progress_bar(ratio: f64) -> String {
+    const WIDTH: usize = 20;
+    let filled = ((ratio.clamp(0.0, 1.0) * WIDTH as f64).round() as usize).min(WIDTH);
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(WIDTH - filled))
+}
+
+/// Currency an invoice was issued in
+fn invoice_currency<'a>(entry: &'a config::HistoryEntry, config: &'a config::Config) -> &'a str {
+    entry
+        .currency
+        .as_deref()
+        .unwrap_or(&config.invoice.currency)
+}
+
+/// List generated invoices with three-way status (UNPAID / PARTIAL / PAID)
+fn cmd_invoices(
+    cfg_dir: &Path,
+    limit: Option<usize>,
+    all: bool,
+    net: bool,
+    plain: bool,
+    read_only: bool,
+) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let mut state = load_state(cfg_dir)?;
+
+    if state.history.is_empty() {
+        if !plain {
+            println!("No invoices generated yet.");
+        }
+        return Ok(());
+    }
+
+    // Without --limit or --all, [display] keeps the view to recent invoices
+    let (since, limit) = match (all, limit) {
+        (true, _) => (None, None),
+        (false, Some(n)) => (None, Some(n)),
+        (false, None) => (
+            config
+                .display
+                .default_list_window
+                .map(|window| window.start(chrono::Local::now().date_naive())),
+            config.display.default_list_limit,
+        ),
+    };
+    let invoices: Vec<_> = state
+        .history
+        .iter()
+        .rev()
+        .enumerate()
+        .filter(|(_, entry)| since.is_none_or(|since| entry.date >= since))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    let hidden = state.history.len() - invoices.len();
+    let invoices = &invoices[..];
+    let scale = |entry: &config::HistoryEntry| if net { entry.net_ratio() } else { 1.0 };
+
+    if plain {
+        for (idx, entry) in invoices {
+            println!(
+                "{}\t{}\t{}\t{:.2}\t{}\t{}\t{:.2}",
+                idx + 1,
+                entry.number,
+                entry.date,
+                entry.total * scale(entry),
+                entry.status(),
+                entry.client,
+                entry.outstanding().max(0.0) * scale(entry)
+            );
+        }
+        return Ok(());
+    }
+
+    // Derive status from payment records
+    let rows: Vec<InvoiceRow> = invoices
+        .iter()
+        .map(|(idx, entry)| InvoiceRow {
+            index: idx + 1,
+            number: entry.number.clone(),
+            date: entry.date.to_string(),
+            total: format_whole_money(entry.total * scale(entry), &config.invoice),
+            status: {
+                let status = match entry.dunning_level() {
+                    0 => entry.status().to_string(),
+                    level => format!("{} [L{}]", entry.status(), level),
+                };
+                match &entry.payee {
+                    Some(payee) => format!("{status} → {payee}"),
+                    None => status,
+                }
+            },
+            client: entry.client.clone(),
+        })
+        .collect();
+
+    // Financial summary uses actual payment amounts; superseded invoices are
+    // already counted in their consolidated invoice
+    let shown_total: f64 = invoices
+        .iter()
+        .filter(|(_, entry)| entry.is_billed())
+        .map(|(_, entry)| entry.total * scale(entry))
+        .sum();
+    let shown_paid: f64 = invoices
+        .iter()
+        .map(|(_, entry)| entry.paid_amount() * scale(entry))
+        .sum();
+    let shown_outstanding: f64 = shown_total - shown_paid;
+
+    let table = Table::new(rows).with(Style::rounded()).to_string();
+    let total_amount = format_whole_money(shown_total, &config.invoice);
+    let paid_amount = format_whole_money(shown_paid, &config.invoice);
+    let outstanding_amount = format_whole_money(shown_outstanding, &config.invoice);
+    let table = add_financial_footer(&table, &total_amount, &paid_amount, &outstanding_amount);
+
+    println!("{table}");
+
+    println!();
+    println!("Total: {} invoices", state.history.len());
+    if hidden > 0 {
+        println!("{hidden} older invoice(s) not shown; use --all to list everything");
+    }
+    let factored: f64 = invoices
+        .iter()
+        .filter(|(_, entry)| entry.payee.is_some())
+        .map(|(_, entry)| entry.outstanding() * scale(entry))
+        .sum();
+    if factored > 0.0 {
+        println!(
+            "Factored: {} of the outstanding is owed to other payees",
+            config.invoice.money(factored)
+        );
+    }
+    if net {
+        println!("Amounts exclude tax");
+        warn_untaxed_history(invoices.iter().map(|(_, entry)| *entry));
+    }
+
+    if let Some(goals) = &config.goals {
+        let progress = monthly_progress(
+            &state.history,
+            goals.monthly_revenue,
+            chrono::Local::now().date_naive(),
+        );
+        println!(
+            "Monthly goal: {} {:.0}% invoiced, {:.0}% collected",
+            progress_bar(progress.invoiced_ratio()),
+            progress.invoiced_ratio() * 100.0,
+            progress.collected_ratio() * 100.0
+        );
+    }
+
+    // Show the outstanding balance converted to the [currency] display currency
+    if shown_outstanding > 0.0 {
+        let amounts: Vec<_> = invoices
+            .iter()
+            .map(|(_, entry)| {
+                let billed = if entry.is_billed() { entry.total } else { 0.0 };
+                let outstanding = (billed - entry.paid_amount()) * scale(entry);
+                (invoice_currency(entry, &config), outstanding)
+            })
+            .collect();
+        let mut rates = Rates::new(
+            &config.currency,
+            &mut state.rates,
+            chrono::Local::now().naive_local(),
+        );
+        if let Some(converted) = rates.convert_all(amounts, &config.currency.display) {
+            println!(
+                "Outstanding in {}: {} ({})",
+                converted.currency,
+                format_whole(converted.amount),
+                converted.rates_label()
+            );
+        }
+        if rates.updated() && !read_only {
+            save_state(cfg_dir, &state)?;
+        }
+    }
+
+    println!(
+        "Use index number with open/edit/regenerate/add-payment/remove-payment (e.g., 'invoice open 1')"
+    );
+
+    Ok(())
+}
+
+/// Print open invoices in status columns, plus those paid off this month
+fn cmd_board(cfg_dir: &Path) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+    let today = chrono::Local::now().date_naive();
+
+    if state.history.is_empty() {
+        println!("No invoices generated yet.");
+        return Ok(());
+    }
+
+    // Columns: unpaid, partial, overdue, paid this month
+    let mut columns: [Vec<(&str, f64)>; 4] = Default::default();
+    for entry in state.history.iter().rev() {
+        let column = match entry.status() {
+            PaymentStatus::Superseded | PaymentStatus::Void => continue,
+            PaymentStatus::Paid => {
+                let paid_on = entry.payments.iter().map(|p| p.date).max();
+                match paid_on {
+                    Some(d) if d.year() == today.year() && d.month() == today.month() => 3,
+                    _ => continue,
+                }
+            }
+            _ if days_overdue(entry, &config, today) > 0 => 2,
+            PaymentStatus::Partial => 1,
+            PaymentStatus::Unpaid => 0,
+        };
+        let amount = match column {
+            3 => entry.total,
+            _ => entry.outstanding(),
+        };
+        columns[column].push((entry.number.as_str(), amount));
+    }
+
+    let titles = ["UNPAID", "PARTIAL", "OVERDUE", "PAID (THIS MONTH)"];
+    let mut builder = tabled::builder::Builder::default();
+    builder.push_record(titles.iter().zip(&columns).map(|(title, cards)| {
+        let sum = cards.iter().fold(0.0, |sum, (_, amount)| sum + amount);
+        format!("{} ({})\n{}", title, cards.len(), config.invoice.money(sum))
+    }));
+    let depth = columns.iter().map(Vec::len).max().unwrap_or(0);
+    for i in 0..depth {
+        builder.push_record(columns.iter().map(|cards| {
+            cards.get(i).map_or(String::new(), |(number, amount)| {
+                format!("{} {}", number, config.invoice.money(*amount))
+            })
+        }));
+    }
+
+    println!("{}", builder.build().with(Style::rounded()));
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct OverdueRow {
+    #[tabled(rename = "#")]
+    index: usize,
+    #[tabled(rename = "NUMBER")]
+    number: String,
+    #[tabled(rename = "DUE")]
+    due: String,
+    #[tabled(rename = "DAYS")]
+    days: i64,
+    #[tabled(rename = "OUTSTANDING")]
+    outstanding: String,
+    #[tabled(rename = "CLIENT")]
+    client: String,
+    #[tabled(rename = "LAST FOLLOW-UP")]
+    last_followup: String,
+    #[tabled(rename = "NEXT")]
+    next_followup: String,
+}
+
+/// List overdue invoices with their last and next planned follow-up
+fn cmd_overdue(cfg_dir: &Path, limit: Option<usize>) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+    let today = chrono::Local::now().date_naive();
+
+    // Keep the indexes from the full list so they work with other commands
+    let overdue: Vec<_> = state
+        .history
+        .iter()
+        .rev()
+        .enumerate()
+        .filter(|(_, e)| !e.is_settled() && days_overdue(e, &config, today) > 0)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+
+    if overdue.is_empty() {
+        println!("No overdue invoices.");
+        return Ok(());
+    }
+
+    let rows: Vec<OverdueRow> = overdue
+        .iter()
+        .map(|(idx, entry)| {
+            let last = entry.last_followup();
+            OverdueRow {
+                index: idx + 1,
+                number: entry.number.clone(),
+                due: invoice::invoice::due_date(entry, &config).to_string(),
+                days: days_overdue(entry, &config, today),
+                outstanding: format_whole_money(entry.outstanding(), &config.invoice),
+                client: entry.client.clone(),
+                last_followup: last.map_or("-".to_string(), |f| format!("{} ({})", f.date, f.via)),
+                next_followup: last
+                    .and_then(|f| f.next)
+                    .map_or("-".to_string(), |d| d.to_string()),
+            }
+        })
+        .collect();
+
+    let outstanding: f64 = overdue.iter().map(|(_, e)| e.outstanding()).sum();
+    println!("{}", Table::new(rows).with(Style::rounded()));
+    println!();
+    println!(
+        "Overdue: {} across {} invoice(s)",
+        config.invoice.money(outstanding),
+        overdue.len()
+    );
+
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct OverdueClientRow {
+    #[tabled(rename = "#")]
+    index: usize,
+    #[tabled(rename = "NUMBER")]
+    number: String,
+    #[tabled(rename = "DUE")]
+    due: String,
+    #[tabled(rename = "DAYS")]
+    days: i64,
+    #[tabled(rename = "AGING")]
+    aging: String,
+    #[tabled(rename = "OUTSTANDING")]
+    outstanding: String,
+}
+
+/// Overdue invoices grouped by client, followed by totals per aging bucket
+fn cmd_overdue_clients(cfg_dir: &Path, date: Option<String>) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+    let today = match date {
+        Some(s) => chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+            .map_err(|_| InvoiceError::PdfGeneration(format!("Invalid --date value: '{s}'")))?,
+        None => chrono::Local::now().date_naive(),
+    };
+
+    let clients = overdue_by_client(&state.history, &config, today);
+    if clients.is_empty() {
+        println!("No overdue invoices.");
+        return Ok(());
+    }
+
+    // Indexes from the full list, so they work with other commands
+    let index_of = |number: &str| {
+        state
+            .history
+            .iter()
+            .rev()
+            .position(|e| e.number == number)
+            .map_or(0, |i| i + 1)
+    };
+
+    for client in &clients {
+        println!(
+            "{}: {} overdue across {} invoice(s), oldest {} days",
+            client.client,
+            config.invoice.money(client.outstanding),
+            client.invoices.len(),
+            client.max_days()
+        );
+        let rows: Vec<OverdueClientRow> = client
+            .invoices
+            .iter()
+            .map(|invoice| OverdueClientRow {
+                index: index_of(&invoice.number),
+                number: invoice.number.clone(),
+                due: invoice.due.to_string(),
+                days: invoice.days,
+                aging: invoice.bucket.to_string(),
+                outstanding: format_whole_money(invoice.outstanding, &config.invoice),
+            })
+            .collect();
+        println!("{}", Table::new(rows).with(Style::rounded()));
+        println!();
+    }
+
+    println!("Aging:");
+    for (bucket, (amount, count)) in aging_totals(&clients) {
+        println!(
+            "  {:<11} {} ({} invoice(s))",
+            format!("{bucket}:"),
+            config.invoice.money(amount),
+            count
+        );
+    }
+    let outstanding: f64 = clients.iter().map(|c| c.outstanding).sum();
+    let invoices: usize = clients.iter().map(|c| c.invoices.len()).sum();
+    println!(
+        "Overdue: {} across {} invoice(s) from {} client(s)",
+        config.invoice.money(outstanding),
+        invoices,
+        clients.len()
+    );
+
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct AgingTableRow {
+    #[tabled(rename = "CLIENT")]
+    client: String,
+    #[tabled(rename = "INVOICES")]
+    invoices: String,
+    #[tabled(rename = "0-30")]
+    days_0_30: String,
+    #[tabled(rename = "31-60")]
+    days_31_60: String,
+    #[tabled(rename = "61-90")]
+    days_61_90: String,
+    #[tabled(rename = "90+")]
+    over_90: String,
+    #[tabled(rename = "TOTAL")]
+    total: String,
+}
+
+impl AgingTableRow {
+    fn new(
+        client: String,
+        invoices: usize,
+        columns: &AgingColumns,
+        config: &config::Config,
+    ) -> Self {
+        let [days_0_30, days_31_60, days_61_90, over_90, total] = columns
+            .values()
+            .map(|amount| format_whole_money(amount, &config.invoice));
+        Self {
+            client,
+            invoices: invoices.to_string(),
+            days_0_30,
+            days_31_60,
+            days_61_90,
+            over_90,
+            total,
+        }
+    }
+}
+
+/// Print the aging report and save it as a PDF
+fn cmd_aging(cfg_dir: &Path, date: Option<String>, open: bool) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let today = match date {
+        Some(s) => chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+            .map_err(|_| InvoiceError::PdfGeneration(format!("Invalid --date value: '{s}'")))?,
+        None => chrono::Local::now().date_naive(),
+    };
+
+    let Some((report, path)) = create_aging_report(cfg_dir, today)? else {
+        println!("Nothing outstanding.");
+        return Ok(());
+    };
+
+    let mut rows: Vec<AgingTableRow> = report
+        .rows
+        .iter()
+        .map(|row| AgingTableRow::new(row.client.clone(), row.invoices, &row.columns, &config))
+        .collect();
+    let invoices = report.rows.iter().map(|row| row.invoices).sum();
+    rows.push(AgingTableRow::new(
+        "TOTAL".to_string(),
+        invoices,
+        &report.totals,
+        &config,
+    ));
+
+    println!("Aging as of {}", today.format("%Y-%m-%d"));
+    println!("{}", Table::new(rows).with(Style::rounded()));
+    println!("Saved: {}", path.display());
+
+    if open {
+        open_path(cfg_dir, &path);
+    }
+    Ok(())
+}
+
+/// `invoice` as given, or else the one chosen with the picker; None when
+/// the picker was dismissed
+fn invoice_or_pick(cfg_dir: &Path, invoice: Option<String>) -> Result<Option<String>> {
+    match invoice {
+        Some(invoice) => Ok(Some(invoice)),
+        None => pick_invoice(cfg_dir),
+    }
+}
+
+/// Choose an invoice with the fuzzy finder, newest first, previewing each
+/// with `invoice show`. Returns its number, or None when dismissed.
+fn pick_invoice(cfg_dir: &Path) -> Result<Option<String>> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+    // Keep stdout for the chosen number
+    if state.history.is_empty() {
+        eprintln!("No invoices generated yet.");
+        return Ok(None);
+    }
+
+    let lines: Vec<String> = state
+        .history
+        .iter()
+        .rev()
+        .map(|entry| {
+            format!(
+                "{}\t{}\t{}\t{}\t{}",
+                entry.number,
+                entry.date,
+                config.invoice.money(entry.total),
+                entry.status(),
+                entry.client
+            )
+        })
+        .collect();
+    let exe = std::env::current_exe()?;
+    let preview = format!(
+        "{} -C {} show {{1}}",
+        picker::shell_quote(&exe.to_string_lossy()),
+        picker::shell_quote(&cfg_dir.to_string_lossy())
+    );
+    let chosen = picker::pick(&lines, "invoice> ", Some(&preview))?;
+    Ok(chosen.and_then(|line| line.split('\t').next().map(str::to_string)))
+}
+
+/// Resolve an invoice reference to the actual invoice number.
+/// Accepts either an index (1-based) from 'list' or the full invoice number.
+fn resolve_invoice_number(state: &State, reference: &str) -> Result<String> {
+    // Try to parse as an index first; invoices are listed newest first
+    if let Ok(idx) = reference.parse::<usize>() {
+        return state
+            .entry_at(idx)
+            .map(|e| e.number.clone())
+            .ok_or_else(|| InvoiceError::InvalidInvoiceIndex(reference.to_string()));
+    }
+
+    // Otherwise, treat as invoice number - verify it exists
+    match state.entry(reference) {
+        Some(_) => Ok(reference.to_string()),
+        None => Err(InvoiceError::InvoiceNotFound(reference.to_string())),
+    }
+}
+
+/// Build up `generate --interactive`: pick the client unless one was given,
+/// then pick items and enter quantities until the picker is dismissed,
+/// showing the running total. Returns the client and item specs once
+/// confirmed, or None when cancelled.
+fn ask_generate(
+    cfg_dir: &Path,
+    client: Option<String>,
+    mut items: Vec<String>,
+) -> Result<Option<(String, Vec<String>)>> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let clients = load_clients(cfg_dir)?;
+    let catalog = load_items(cfg_dir)?;
+
+    let client_id = match client {
+        Some(client) => client,
+        None => {
+            let mut lines: Vec<String> = clients
+                .iter()
+                .map(|(id, client)| format!("{id}\t{}\t{}", client.name, client.email))
+                .collect();
+            lines.sort();
+            match picker::pick(&lines, "client> ", None)? {
+                Some(line) => line.split('\t').next().unwrap_or_default().to_string(),
+                None => return Ok(None),
+            }
+        }
+    };
+    let client = clients
+        .get(&client_id)
+        .ok_or_else(|| InvoiceError::ClientNotFound(client_id.clone()))?;
+    eprintln!("Client: {} ({client_id})", client.name);
+
+    let currency = client
+        .currency
+        .as_deref()
+        .unwrap_or(&config.invoice.currency);
+    let format = MoneyFormat {
+        symbol: client
+            .currency_symbol
+            .as_deref()
+            .unwrap_or(&config.invoice.currency_symbol),
+        position: config.invoice.symbol_position,
+        space: config.invoice.symbol_space,
+    };
+    // Items priced in another currency are converted when the invoice is
+    // generated, so they are totaled separately here
+    let mut totals: std::collections::BTreeMap<String, f64> = Default::default();
+    let money = |item_currency: &str, amount: f64| {
+        if item_currency == currency {
+            format.format(amount)
+        } else {
+            format!("{item_currency} {}", format_amount(amount))
+        }
+    };
+    let mut add = |spec: &ItemSpec| {
+        let Some(item) = catalog.get(&spec.item) else {
+            return;
+        };
+        let item_currency = item.currency.as_deref().unwrap_or(currency);
+        let amount = item.rate * spec.quantity;
+        *totals.entry(item_currency.to_string()).or_default() += amount;
+        let running: Vec<String> = totals
+            .iter()
+            .map(|(currency, total)| money(currency, *total))
+            .collect();
+        eprintln!(
+            "  {} × {}: {}  (running total {})",
+            item.description,
+            spec.quantity,
+            money(item_currency, amount),
+            running.join(" + ")
+        );
+    };
+    for input in &items {
+        add(&parse_item_spec(input)?);
+    }
+
+    let mut lines: Vec<String> = catalog
+        .iter()
+        .map(|(id, item)| {
+            let currency = item.currency.as_deref().unwrap_or(currency);
+            format!(
+                "{id}\t{}\t{} / {}",
+                item.description,
+                money(currency, item.rate),
+                item.unit
+            )
+        })
+        .collect();
+    lines.sort();
+    while let Some(line) = picker::pick(&lines, "item> ", None)? {
+        let id = line.split('\t').next().unwrap_or_default().to_string();
+        let Some(quantity) = ask(&format!("Quantity of {id}"), None, |qty| {
+            parse_item_spec(&format!("{id}:{qty}"))
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }) else {
+            return Ok(None);
+        };
+        let spec = parse_item_spec(&format!("{id}:{quantity}"))?;
+        add(&spec);
+        items.push(spec.to_string());
+    }
+
+    if items.is_empty() {
+        return Err(InvoiceError::NoItems);
+    }
+    if !confirm(&format!(
+        "Generate an invoice for {} with {} line(s)? [Y/n] ",
+        client.name,
+        items.len()
+    )) {
+        return Ok(None);
+    }
+    Ok(Some((client_id, items)))
+}
+
+/// Generate a new invoice
+fn cmd_generate(
+    cfg_dir: &Path,
+    client_id: &str,
+    items_input: &[String],
+    open: bool,
+    options: &GenerateOptions,
+) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    if items_input.is_empty() {
+        return Err(InvoiceError::NoItems);
+    }
+
+    let mut ws = Workspace::load(cfg_dir)?;
+    let generated = generate_invoice(&mut ws, client_id, items_input, options)?;
+    print_generated(&generated, &ws.config);
+    if open {
+        open_path(cfg_dir, &generated.path);
+    }
+    Ok(())
+}
+
+/// Summarize a newly generated invoice, then any warnings about it
+fn print_generated(generated: &GeneratedInvoice, config: &config::Config) {
+    let data = &generated.data;
+    let settings = config.invoice.for_client(&data.client);
+    println!("Generated {}", generated.number);
+    println!("  Client: {}", data.client.name);
+    if let Some(display_number) = &data.display_number {
+        println!("  Client ref: {}", display_number);
+    }
+    if let Some(period) = &data.service_period {
+        println!("  Period: {}", period);
+    }
+    if let Some(payee) = &data.payee {
+        println!("  Payee:  {}", payee.name);
+    }
+    if let Some(contract) = &generated.contract {
+        println!("  Contract: {}", contract);
+    }
+    if let Some(memo) = &data.memo {
+        println!("  Note:   {}", memo);
+    }
+    println!("  Total:  {}", settings.money(data.total));
+    if !generated.deducted.is_empty() {
+        println!("  Deducts: {}", generated.deducted.join(", "));
+    }
+    if data.retention > 0.0 {
+        println!(
+            "  Retained: {} until released ({} due now)",
+            settings.money(data.retention),
+            settings.money(generated.amount_due())
+        );
+    }
+    for path in &generated.files {
+        println!("  Saved:  {}", path.display());
+    }
+    for path in &generated.debug_files {
+        println!("  Debug:  {}", path.display());
+    }
+    match data.client.delivery {
+        Some(Delivery::Email) => println!("  Deliver: email to {}", data.client.email),
+        Some(Delivery::Postal) => {
+            println!(
+                "  Deliver: print and mail to {}",
+                data.client.postal_address()
+            )
+        }
+        Some(Delivery::None) | None => {}
+    }
+    print_warnings(&generated.warnings);
+}
+
+fn print_warnings(warnings: &[String]) {
+    for warning in warnings {
+        eprintln!("Warning: {warning}");
+    }
+}
+
+/// Merge invoices into a new one and mark them superseded
+fn cmd_consolidate(
+    cfg_dir: &Path,
+    client_id: &str,
+    refs: &[String],
+    open: bool,
+    strict: bool,
+) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let mut ws = Workspace::load(cfg_dir)?;
+    // Resolve indexes before the new invoice shifts them
+    let numbers = refs
+        .iter()
+        .map(|reference| resolve_invoice_number(&ws.state, reference))
+        .collect::<Result<Vec<_>>>()?;
+
+    let options = GenerateOptions {
+        render: RenderOptions {
+            strict,
+            ..RenderOptions::default()
+        },
+        ..GenerateOptions::default()
+    };
+    let consolidated = consolidate_invoices(&mut ws, client_id, &numbers, &options)?;
+    print_generated(&consolidated, &ws.config);
+    println!("  Supersedes: {}", numbers.join(", "));
+
+    if open {
+        open_path(cfg_dir, &consolidated.path);
+    }
+    Ok(())
+}
+
+/// The `[import.profiles]` entry named by `--profile` (or the defaults)
+/// with the flags given on the command line applied over it
+fn import_profile(
+    cfg_dir: &Path,
+    args: TimesheetArgs,
+    item: Option<String>,
+    description_column: Option<String>,
+) -> Result<ImportProfile> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let overrides = ImportProfile {
+        item,
+        date_column: args.date_column,
+        date_format: args.date_format,
+        hours_column: args.hours_column,
+        description_column,
+        round_minutes: args.round_minutes,
+        rounding: args.rounding,
+        delimiter: args.delimiter,
+    };
+    let config = load_config(cfg_dir)?;
+    let profile = match args.profile.as_deref() {
+        Some(name) => config
+            .import
+            .profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| InvoiceError::UnknownImportProfile(name.to_string()))?,
+        None => ImportProfile::default(),
+    };
+    Ok(profile.merged(overrides))
+}
+
+/// Generate an invoice billing the hours of a timesheet export
+fn cmd_import_time(
+    cfg_dir: &Path,
+    client_id: &str,
+    file: &Path,
+    profile: &ImportProfile,
+    dry_run: bool,
+    open: bool,
+    strict: bool,
+) -> Result<()> {
+    let item = profile
+        .item
+        .clone()
+        .ok_or(InvoiceError::ImportItemMissing)?;
+
+    let entries = read_timesheet(&std::fs::read_to_string(file)?, profile)?;
+    let items: Vec<String> = timesheet_items(&entries, &item)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let hours: f64 = entries.iter().map(|e| e.hours).sum();
+    println!(
+        "Imported {} entries ({:.2} hours) from {}",
+        entries.len(),
+        hours,
+        file.display()
+    );
+
+    if dry_run {
+        for item in &items {
+            println!("  {item}");
+        }
+        return Ok(());
+    }
+
+    let options = GenerateOptions {
+        period: timesheet_period(&entries),
+        render: RenderOptions {
+            strict,
+            ..RenderOptions::default()
+        },
+        ..GenerateOptions::default()
+    };
+    cmd_generate(cfg_dir, client_id, &items, open, &options)
+}
+
+/// Generate an invoice for the hours of a timesheet beyond the client's
+/// retainer, with the included, worked and extra hours printed on it
+fn cmd_true_up(
+    cfg_dir: &Path,
+    client_id: &str,
+    entries: &[TimeEntry],
+    period: Option<ServicePeriod>,
+    dry_run: bool,
+    open: bool,
+    strict: bool,
+) -> Result<()> {
+    let clients = load_clients(cfg_dir)?;
+    let retainer = clients
+        .get(client_id)
+        .ok_or_else(|| InvoiceError::ClientNotFound(client_id.to_string()))?
+        .retainer
+        .clone()
+        .ok_or_else(|| InvoiceError::NoRetainer(client_id.to_string()))?;
+
+    let usage = true_up(&retainer, entries, period);
+    println!(
+        "{} hours tracked, {} included, {} over",
+        usage.worked, usage.included, usage.overage
+    );
+
+    let Some(spec) = usage.item_spec(&retainer) else {
+        println!("Nothing to bill: the retainer covers every hour.");
+        return Ok(());
+    };
+    if dry_run {
+        println!("  {spec}");
+        return Ok(());
+    }
+
+    let options = GenerateOptions {
+        period: period.or_else(|| timesheet_period(entries)),
+        fields: usage.fields(),
+        render: RenderOptions {
+            strict,
+            ..RenderOptions::default()
+        },
+        ..GenerateOptions::default()
+    };
+    cmd_generate(cfg_dir, client_id, &[spec.to_string()], open, &options)
+}
+
+/// Generate an advance invoice for a share of a project's fixed price
+fn cmd_deposit(
+    cfg_dir: &Path,
+    client_id: &str,
+    project: String,
+    percent: f64,
+    open: bool,
+    strict: bool,
+) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let options = GenerateOptions {
+        deposit: Some(DepositRequest { project, percent }),
+        render: RenderOptions {
+            strict,
+            ..RenderOptions::default()
+        },
+        ..GenerateOptions::default()
+    };
+    let mut ws = Workspace::load(cfg_dir)?;
+    let generated = generate_invoice(&mut ws, client_id, &[], &options)?;
+    print_generated(&generated, &ws.config);
+
+    if open {
+        open_path(cfg_dir, &generated.path);
+    }
+    Ok(())
+}
+
+/// Generate an invoice for the unreleased retention of a client (or project)
+fn cmd_release_retention(
+    cfg_dir: &Path,
+    client_id: &str,
+    project: Option<&str>,
+    open: bool,
+    strict: bool,
+) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let mut ws = Workspace::load(cfg_dir)?;
+    let held: Vec<String> = held_retention(&ws.state.history, client_id, project)
+        .iter()
+        .map(|e| e.number.clone())
+        .collect();
+    if held.is_empty() {
+        println!("No retention held for '{client_id}'.");
+        return Ok(());
+    }
+
+    let options = GenerateOptions {
+        release_retention: held.clone(),
+        render: RenderOptions {
+            strict,
+            ..RenderOptions::default()
+        },
+        ..GenerateOptions::default()
+    };
+    let generated = generate_invoice(&mut ws, client_id, &[], &options)?;
+    print_generated(&generated, &ws.config);
+    println!("  Releases: {}", held.join(", "));
+
+    if open {
+        open_path(cfg_dir, &generated.path);
+    }
+    Ok(())
+}
+
+/// Format invoice number from template
+fn format_invoice_number(format: &str, year: u32, seq: u32) -> String {
+    format
+        .replace("{year}", &year.to_string())
+        .replace("{seq:04}", &format!("{:04}", seq))
+        .replace("{seq:05}", &format!("{:05}", seq))
+        .replace("{seq:03}", &format!("{:03}", seq))
+}
+
+/// Edit an existing invoice
+fn cmd_edit(
+    cfg_dir: &Path,
+    invoice_ref: &str,
+    items: &[String],
+    discount: Option<Discount>,
+    note: Option<&str>,
+    force_unlock: bool,
+    strict: bool,
+) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    if items.is_empty() && discount.is_none() && note.is_none() {
+        return Err(InvoiceError::NoItems);
+    }
+
+    let mut ws = Workspace::load(cfg_dir)?;
+    let invoice_number = resolve_invoice_number(&ws.state, invoice_ref)?;
+    let revisions_before = revision_count(&ws.state, &invoice_number);
+    let regenerated = regenerate_invoice(
+        &mut ws,
+        &invoice_number,
+        (!items.is_empty()).then_some(items),
+        discount,
+        note,
+        &RenderOptions {
+            strict,
+            ..RenderOptions::default()
+        },
+        force_unlock,
+    )?;
+
+    print_warnings(&regenerated.warnings);
+    println!("Updated {}", invoice_number);
+    if !items.is_empty() {
+        println!("  Items:  {}", items.join(", "));
+    }
+    if let Some(discount) = discount {
+        if discount.is_zero() {
+            println!("  Discount: removed");
+        } else {
+            println!("  Discount: {discount}");
+        }
+    }
+    if let Some(note) = note {
+        if note.trim().is_empty() {
+            println!("  Note:   removed");
+        } else {
+            println!("  Note:   {}", note.trim());
+        }
+    }
+    println!("  Saved:  {}", regenerated.path.display());
+
+    // Show new total
+    if let Some(entry) = ws.state.entry(&invoice_number) {
+        println!("  Total:  {}", ws.config.invoice.money(entry.total));
+        print_new_revision(entry, revisions_before);
+    }
+
+    Ok(())
+}
+
+/// Number of archived revisions for an invoice
+fn revision_count(state: &config::State, invoice_number: &str) -> usize {
+    state.entry(invoice_number).map_or(0, |e| e.revisions.len())
+}
+
+/// Report the revision archived by the last regeneration, if any
+fn print_new_revision(entry: &config::HistoryEntry, revisions_before: usize) {
+    if let Some(revision) = entry.revisions.get(revisions_before) {
+        println!(
+            "  Kept v{}: {} ({})",
+            revision.version,
+            revision.files.join(", "),
+            revision.changes
+        );
+    }
+}
+
+/// Open an invoice PDF
+fn cmd_open(cfg_dir: &Path, invoice_ref: &str, format: Option<OutputFormat>) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let invoice_number = resolve_invoice_number(&load_state(cfg_dir)?, invoice_ref)?;
+    let pdf_path = match format {
+        Some(format) => get_invoice_artifact(cfg_dir, &invoice_number, format)?,
+        None => get_invoice_path(cfg_dir, &invoice_number)?,
+    };
+
+    if open_path(cfg_dir, &pdf_path) {
+        println!("Opened {}", pdf_path.display());
+    }
+    Ok(())
+}
+
+/// Print an invoice as text: what its PDF shows, plus payments and balance
+fn cmd_show(cfg_dir: &Path, invoice_ref: &str) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let mut ws = Workspace::load(cfg_dir)?;
+    let invoice_number = resolve_invoice_number(&ws.state, invoice_ref)?;
+    let mut warnings = Vec::new();
+    let data = issued_invoice_data(&mut ws, &invoice_number, &mut warnings)?;
+    print_warnings(&warnings);
+    let Workspace { config, state, .. } = &ws;
+    let entry = state
+        .entry(&invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.clone()))?;
+
+    // Amounts in the invoice's own currency
+    let mut settings = config.invoice.clone();
+    settings.currency_symbol = data.currency_symbol.clone();
+    settings.symbol_position = data.symbol_position;
+    settings.symbol_space = data.symbol_space;
+
+    match &data.display_number {
+        Some(display) => println!("Invoice {} ({display})", data.number),
+        None => println!("Invoice {}", data.number),
+    }
+    println!("  Date:   {}", data.date);
+    println!("  Due:    {}", data.due_date);
+    println!("  Status: {}", entry.status());
+
+    let client = &data.client;
+    println!();
+    println!("Bill To:");
+    println!("  {}", client.name);
+    if let Some(contact) = &client.contact {
+        println!("  {contact}");
+    }
+    println!("  {}", client.address);
+    println!("  {}, {} {}", client.city, client.state, client.zip);
+    if let Some(country) = &client.country {
+        println!("  {country}");
+    }
+    println!("  {}", client.email);
+    if let Some(tax_id) = &client.tax_id {
+        println!("  VAT ID: {tax_id}");
+    }
+
+    if data.service_period.is_some() || !data.fields.is_empty() {
+        println!();
+    }
+    if let Some(period) = &data.service_period {
+        println!("Services Rendered: {period}");
+    }
+    for (name, value) in &data.fields {
+        println!("{name}: {value}");
+    }
+
+    let rows: Vec<LineRow> = data
+        .items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            let mut description = item.description.clone();
+            if let Some(period) = &item.period {
+                description.push_str(&format!("\n{period}"));
+            }
+            LineRow {
+                index: idx + 1,
+                description,
+                quantity: format!("{} {}", item.quantity, item.unit_label()),
+                rate: settings.money(item.rate),
+                amount: settings.money(item.amount),
+            }
+        })
+        .collect();
+    println!();
+    println!("{}", Table::new(rows).with(Style::rounded()));
+
+    println!("  Subtotal:    {}", settings.money(data.subtotal));
+    if data.discount > 0.0 {
+        let label = if data.discount_percent > 0.0 {
+            format!("Discount ({:.2}%):", data.discount_percent)
+        } else {
+            "Discount:".to_string()
+        };
+        println!("  {label:<12} -{}", settings.money(data.discount));
+    }
+    for tax in data.tax_lines() {
+        let label = format!("{} ({:.2}%):", tax.name, tax.percent);
+        println!("  {label:<12} {}", settings.money(tax.amount));
+    }
+    if data.rounding_adjustment != 0.0 {
+        println!(
+            "  Rounding:    {}",
+            settings.money(data.rounding_adjustment)
+        );
+    }
+    println!("  Total:       {}", settings.money(data.total));
+    if data.retention > 0.0 {
+        println!(
+            "  Retention ({:.2}%): -{}",
+            data.retention_percent,
+            settings.money(data.retention)
+        );
+    }
+    if let Some(memo) = &data.memo {
+        println!();
+        println!("Memo: {memo}");
+    }
+
+    println!();
+    if entry.payments.is_empty() {
+        println!("Payments: none recorded");
+    } else {
+        println!("Payments:");
+        for payment in &entry.payments {
+            println!("  {}  {}", payment.date, settings.money(payment.amount));
+        }
+    }
+    println!("Outstanding: {}", settings.money(entry.outstanding()));
+
+    if !entry.followups.is_empty() {
+        println!();
+        println!("Follow-ups:");
+        for followup in &entry.followups {
+            let mut line = format!("  {}  {}", followup.date, followup.via);
+            if let Some(note) = &followup.note {
+                line.push_str(&format!("  {note}"));
+            }
+            if let Some(next) = followup.next {
+                line.push_str(&format!("  (next: {next})"));
+            }
+            println!("{line}");
+        }
+    }
+    if !entry.notes.is_empty() {
+        println!();
+        println!("Internal notes (not on the invoice):");
+        for note in &entry.notes {
+            println!(
+                "  {}  {}  {}",
+                note.at.format("%Y-%m-%d %H:%M"),
+                note.author,
+                note.text
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Generate the unpaid-invoices statement for a client
+fn cmd_dun(cfg_dir: &Path, client_id: &str, open: bool) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let mut warnings = Vec::new();
+    let statement = create_unpaid_statement(cfg_dir, client_id, &mut warnings)?;
+    print_warnings(&warnings);
+    let Some((statement, path)) = statement else {
+        println!("No unpaid invoices for '{client_id}'.");
+        return Ok(());
+    };
+
+    let overdue = statement.rows.iter().filter(|r| r.days_overdue > 0).count();
+    println!("Generated statement for '{}'", client_id);
+    println!(
+        "  Invoices:  {} unpaid ({} overdue)",
+        statement.rows.len(),
+        overdue
+    );
+    println!("  Total due: {}", config.invoice.money(statement.total_due));
+    println!("  Saved:     {}", path.display());
+
+    if open {
+        open_path(cfg_dir, &path);
+    }
+    Ok(())
+}
+
+/// Print (and record) the reminder for the dunning level an invoice has reached
+fn cmd_remind(cfg_dir: &Path, invoice_ref: &str, dry_run: bool) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let clients = load_clients(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+    let invoice_number = resolve_invoice_number(&state, invoice_ref)?;
+    let today = chrono::Local::now().date_naive();
+
+    let entry = state
+        .entry(&invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.clone()))?;
+
+    let Some(level) = dunning_level_due(entry, &config, today) else {
+        if let Some(by) = &entry.superseded_by {
+            println!(
+                "{} was superseded by {}; no reminder needed",
+                invoice_number, by
+            );
+        } else if entry.status() == PaymentStatus::Paid {
+            println!("{} is paid; no reminder needed", invoice_number);
+        } else {
+            println!("{} is not overdue; no reminder needed", invoice_number);
+        }
+        return Ok(());
+    };
+
+    if level <= entry.dunning_level() {
+        let sent = entry
+            .reminders
+            .iter()
+            .rev()
+            .find(|r| r.level == entry.dunning_level())
+            .map_or(String::new(), |r| format!(" on {}", r.date));
+        println!(
+            "Level {} reminder for {} already sent{}; next escalation not due yet",
+            entry.dunning_level(),
+            invoice_number,
+            sent
+        );
+        return Ok(());
+    }
+
+    if !dry_run {
+        if let Some(client) = clients.get(&entry.client) {
+            let missing = client.missing_fields_to_send();
+            if !missing.is_empty() {
+                return Err(InvoiceError::IncompleteClient {
+                    client: entry.client.clone(),
+                    missing,
+                });
+            }
+        }
+    }
+
+    let client_name = clients
+        .get(&entry.client)
+        .map_or(entry.client.as_str(), |c| c.name.as_str());
+    let message = render_reminder(entry, client_name, &config, level, today);
+
+    println!(
+        "Level {} reminder for {} ({} days overdue)",
+        level,
+        invoice_number,
+        days_overdue(entry, &config, today)
+    );
+    println!();
+    println!("{}", message);
+
+    if dry_run {
+        println!();
+        println!("(dry run: reminder not recorded)");
+    } else {
+        record_reminder(cfg_dir, &invoice_number, level, today)?;
+    }
+
+    Ok(())
+}
+
+/// Notify about unpaid invoices that are overdue or due within `days`.
+/// Prints nothing and exits successfully when nothing is pending.
+#[cfg(feature = "notify")]
+fn cmd_notify_due(cfg_dir: &Path, days: i64) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let clients = load_clients(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+    let today = chrono::Local::now().date_naive();
+
+    for entry in &state.history {
+        if entry.is_settled() {
+            continue;
+        }
+        let overdue = days_overdue(entry, &config, today);
+        if overdue < -days {
+            continue;
+        }
+
+        let when = match overdue {
+            0 => "due today".to_string(),
+            1 => "1 day overdue".to_string(),
+            d if d > 0 => format!("{d} days overdue"),
+            -1 => "due tomorrow".to_string(),
+            d => format!("due in {} days", -d),
+        };
+        let client_name = clients
+            .get(&entry.client)
+            .map_or(entry.client.as_str(), |c| c.name.as_str());
+
+        notify::send(
+            &format!("{} {}", entry.number, when),
+            &format!(
+                "{} owes {} (due {})",
+                client_name,
+                config.invoice.money(entry.outstanding()),
+                invoice::invoice::due_date(entry, &config)
+            ),
+        )?;
+        println!("Notified: {} {}", entry.number, when);
+    }
+
+    Ok(())
+}
+
+/// Show late interest accrued on an invoice since its due date
+fn cmd_interest(
+    cfg_dir: &Path,
+    invoice_ref: &str,
+    rate: Option<f64>,
+    day_count: Option<DayCount>,
+) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+    let invoice_number = resolve_invoice_number(&state, invoice_ref)?;
+    let today = chrono::Local::now().date_naive();
+
+    let entry = state
+        .entry(&invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.clone()))?;
+
+    let annual_rate = rate
+        .or(config.interest.as_ref().map(|i| i.annual_rate))
+        .ok_or(InvoiceError::InterestNotConfigured)?;
+    let day_count = day_count
+        .or(config.interest.as_ref().map(|i| i.day_count))
+        .unwrap_or_default();
+
+    let interest = accrued_interest(entry, &config, annual_rate, day_count, today);
+    let money = |amount: f64| config.invoice.money(amount);
+
+    println!("Late interest for {}", invoice_number);
+    println!("  Due date:    {}", interest.due_date);
+    println!("  Days late:   {}", interest.days_late);
+    println!("  Outstanding: {}", money(interest.outstanding));
+    println!(
+        "  Rate:        {:.2}% per year ({})",
+        annual_rate * 100.0,
+        day_count
+    );
+    println!("  Interest:    {}", money(interest.amount));
+
+    Ok(())
+}
+
+/// Create a payment plan agreement, or show adherence to the existing one
+fn cmd_payment_plan(
+    cfg_dir: &Path,
+    invoice_ref: &str,
+    installments: Option<u32>,
+    start: Option<String>,
+    open: bool,
+) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let state = load_state(cfg_dir)?;
+    let invoice_number = resolve_invoice_number(&state, invoice_ref)?;
+    let config = load_config(cfg_dir)?;
+    let money = |amount: f64| config.invoice.money(amount);
+    let today = chrono::Local::now().date_naive();
+
+    if let Some(count) = installments {
+        let start = match start {
+            Some(s) => chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d").map_err(|_| {
+                InvoiceError::PdfGeneration(format!("Invalid --start value: '{s}'"))
+            })?,
+            None => today,
+        };
+
+        let mut warnings = Vec::new();
+        let (plan, path) =
+            create_payment_plan(cfg_dir, &invoice_number, count, start, &mut warnings)?;
+        print_warnings(&warnings);
+        println!("Payment plan for {}", invoice_number);
+        println!("  Balance:      {}", money(plan.principal));
+        println!("  Installments: {}", plan.installments.len());
+        for (i, installment) in plan.installments.iter().enumerate() {
+            println!(
+                "    {}. {}  {}",
+                i + 1,
+                installment.due,
+                money(installment.amount)
+            );
+        }
+        println!("  Saved:        {}", path.display());
+
+        if open {
+            open_path(cfg_dir, &path);
+        }
+        return Ok(());
+    }
+
+    let entry = state
+        .entry(&invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.clone()))?;
+    let plan = entry
+        .payment_plan
+        .as_ref()
+        .ok_or_else(|| InvoiceError::NoPaymentPlan(invoice_number.clone()))?;
+
+    let statuses = plan_adherence(entry, plan, today);
+    let rows: Vec<InstallmentRow> = plan
+        .installments
+        .iter()
+        .zip(&statuses)
+        .enumerate()
+        .map(|(i, (installment, status))| InstallmentRow {
+            index: i + 1,
+            due: installment.due.to_string(),
+            amount: money(installment.amount),
+            status: status.to_string(),
+        })
+        .collect();
+
+    println!(
+        "Payment plan for {} (agreed {})",
+        invoice_number, plan.created
+    );
+    let table = Table::new(rows).with(Style::rounded()).to_string();
+    println!("{table}");
+
+    let late = statuses
+        .iter()
+        .filter(|s| **s == InstallmentStatus::Late)
+        .count();
+    if late == 0 {
+        println!("On track");
+    } else {
+        println!("Behind schedule: {} late installment(s)", late);
+    }
+
+    Ok(())
+}
+
+/// Report missing, modified, or orphaned files in the output directory
+fn cmd_verify_files(cfg_dir: &Path, update: bool) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    if update {
+        let count = rebuild_manifest(cfg_dir)?;
+        println!("Manifest rebuilt with {} file(s)", count);
+        return Ok(());
+    }
+
+    let report = verify_files(cfg_dir)?;
+    for (invoice, file) in &report.missing {
+        println!("MISSING   {} ({})", file, invoice);
+    }
+    for (invoice, file) in &report.modified {
+        println!("MODIFIED  {} ({})", file, invoice);
+    }
+    for file in &report.orphaned {
+        println!("ORPHANED  {}", file);
+    }
+
+    if report.is_clean() {
+        println!("All {} file(s) match the manifest", report.checked);
+        return Ok(());
+    }
+
+    Err(InvoiceError::VerificationFailed(
+        report.missing.len() + report.modified.len() + report.orphaned.len(),
+    ))
+}
+
+/// Print version information
+fn cmd_version(verbose: bool) -> Result<()> {
+    println!("invoice {}", env!("CARGO_PKG_VERSION"));
+    if !verbose {
+        return Ok(());
+    }
+
+    let features = match env!("INVOICE_FEATURES") {
+        "" => "none".to_string(),
+        list => list.replace(',', ", "),
+    };
+
+    println!("  Commit:     {}", env!("INVOICE_GIT_HASH"));
+    println!("  Built:      {}", env!("INVOICE_BUILD_DATE"));
+    println!("  Target:     {}", env!("INVOICE_TARGET"));
+    println!("  Features:   {}", features);
+    println!(
+        "  Typst:      {}",
+        typst_version().unwrap_or_else(|| "not found".to_string())
+    );
+    Ok(())
+}
+
+/// Check for and install a newer release
+#[cfg(feature = "network")]
+fn cmd_self_update(check: bool) -> Result<()> {
+    println!(
+        "Current version: {} ({})",
+        env!("CARGO_PKG_VERSION"),
+        update::asset_name()
+    );
+
+    match update::self_update(check)? {
+        UpdateStatus::UpToDate { version } => {
+            println!("Already up to date (latest release: {})", version);
+        }
+        UpdateStatus::Available { version } => {
+            println!("Update available: {}", version);
+            println!("  Install with: invoice self-update");
+        }
+        UpdateStatus::Updated { version, path } => {
+            println!("Updated to {}", version);
+            println!("  Binary: {}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy a shareable invoice summary to the clipboard
+fn cmd_copy(cfg_dir: &Path, invoice_ref: &str, format: SummaryFormat) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let clients = load_clients(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+    let invoice_number = resolve_invoice_number(&state, invoice_ref)?;
+
+    let entry = state
+        .entry(&invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.clone()))?;
+    let client_name = clients
+        .get(&entry.client)
+        .map_or(entry.client.as_str(), |c| c.name.as_str());
+
+    let summary = render_summary(entry, client_name, &config, format);
+
+    match clipboard::copy_text(&summary) {
+        Ok(tool) => {
+            println!("Copied {} summary to clipboard ({})", invoice_number, tool);
+        }
+        Err(e) => {
+            eprintln!("Warning: {e}");
+            print!("{}", summary);
+        }
+    }
+
+    Ok(())
+}
+
+/// Open a file with the configured viewer (or the system opener).
+/// Returns false, after printing the path, if it could not be opened.
+fn open_path(cfg_dir: &Path, path: &Path) -> bool {
+    let viewer = load_config(cfg_dir)
+        .ok()
+        .and_then(|config| config.display.pdf_viewer);
+
+    match viewer::open_file(path, viewer.as_deref()) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("Warning: {e}");
+            println!("File is at: {}", path.display());
+            false
+        }
+    }
+}
+
+/// Compare an invoice with what regenerating it would produce
+fn cmd_diff(cfg_dir: &Path, invoice_ref: &str, render: &RenderOptions) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let mut ws = Workspace::load(cfg_dir)?;
+    let invoice_number = resolve_invoice_number(&ws.state, invoice_ref)?;
+    let diff = diff_invoice(&mut ws, &invoice_number, render)?;
+    print_warnings(&diff.warnings);
+
+    let money = |amount: f64| diff.settings.money(amount);
+    println!("Invoice {}", diff.number);
+    if (diff.stored_total - diff.regenerated_total).abs() < 0.005 {
+        println!("  Total: {} (unchanged)", money(diff.stored_total));
+    } else {
+        println!(
+            "  Total: {} -> {}",
+            money(diff.stored_total),
+            money(diff.regenerated_total)
+        );
+    }
+
+    let describe = |item: &InvoiceLineItem| {
+        format!(
+            "{}, {} {} at {} = {}",
+            item.description,
+            item.quantity,
+            item.unit_label(),
+            money(item.rate),
+            money(item.amount)
+        )
+    };
+    if !diff.lines.is_empty() {
+        println!();
+        println!("Line items:");
+        for change in &diff.lines {
+            println!("  {}.", change.position);
+            if let Some(before) = &change.before {
+                println!("    - {}", describe(before));
+            }
+            if let Some(after) = &change.after {
+                println!("    + {}", describe(after));
+            }
+        }
+    }
+
+    match &diff.text {
+        Some(text) if text.iter().any(|line| !matches!(line, DiffLine::Same(_))) => {
+            println!();
+            println!("Text (- issued, + regenerated):");
+            for line in text {
+                println!("  {line}");
+            }
+        }
+        Some(_) => {}
+        None => {
+            println!();
+            println!("No JSON artifact was kept for this invoice, so its lines and text can't be compared.");
+        }
+    }
+
+    println!();
+    match diff.pdf_identical {
+        Some(true) => println!("PDF: identical to the stored file"),
+        Some(false) if !diff.pdf_pinned => {
+            println!("PDF: differs from the stored file, as expected: not both were rendered");
+            println!("     with --deterministic, so they carry different creation times");
+        }
+        Some(false) => println!("PDF: differs from the stored file"),
+        None => println!("PDF: not compared"),
+    }
+
+    if diff.is_unchanged() {
+        println!("Regenerating would leave {} as issued.", diff.number);
+    } else {
+        println!(
+            "Regenerating would change {}. Run 'invoice regenerate {}' to apply it.",
+            diff.number, diff.number
+        );
+    }
+    Ok(())
+}
+
+/// Regenerate an invoice PDF
+fn cmd_regenerate(
+    cfg_dir: &Path,
+    invoice_ref: &str,
+    open: bool,
+    render: &RenderOptions,
+    force_unlock: bool,
+) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let mut ws = Workspace::load(cfg_dir)?;
+    let invoice_number = resolve_invoice_number(&ws.state, invoice_ref)?;
+    let revisions_before = revision_count(&ws.state, &invoice_number);
+    let regenerated = regenerate_invoice(
+        &mut ws,
+        &invoice_number,
+        None,
+        None,
+        None,
+        render,
+        force_unlock,
+    )?;
+    print_warnings(&regenerated.warnings);
+    if open {
+        open_path(cfg_dir, &regenerated.path);
+    }
+
+    println!("Regenerated {}", invoice_number);
+    println!("  Saved: {}", regenerated.path.display());
+    if render.debug {
+        for path in debug_artifacts(&regenerated.path, &invoice_number) {
+            println!("  Debug: {}", path.display());
+        }
+    }
+
+    if let Some(entry) = ws.state.entry(&invoice_number) {
+        print_new_revision(entry, revisions_before);
+    }
+
+    Ok(())
+}
+
+/// Regenerate every invoice in history, carrying on past failures
+fn cmd_regenerate_all(cfg_dir: &Path, render: &RenderOptions, force_unlock: bool) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let mut ws = Workspace::load(cfg_dir)?;
+    let numbers: Vec<String> = ws.state.history.iter().map(|e| e.number.clone()).collect();
+    if numbers.is_empty() {
+        println!("No invoices to regenerate");
+        return Ok(());
+    }
+
+    let mut progress = Progress::new("regenerate", numbers.len());
+    for number in &numbers {
+        match regenerate_invoice(&mut ws, number, None, None, None, render, force_unlock) {
+            Ok(regenerated) => {
+                print_warnings(&regenerated.warnings);
+                progress.success(number)
+            }
+            Err(e) => progress.failure(number, &e),
+        }
+    }
+    let failed = progress.failed();
+    progress.finish();
+
+    println!(
+        "Regenerated {} of {} invoice(s)",
+        numbers.len() - failed,
+        numbers.len()
+    );
+    if failed > 0 {
+        return Err(InvoiceError::BatchFailed {
+            failed,
+            total: numbers.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Lock an invoice against edits and repricing
+fn cmd_lock(cfg_dir: &Path, invoice_ref: &str) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let mut state = load_state(cfg_dir)?;
+    let invoice_number = resolve_invoice_number(&state, invoice_ref)?;
+
+    let entry = state
+        .history
+        .iter_mut()
+        .find(|e| e.number == invoice_number)
+        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.clone()))?;
+
+    if entry.locked {
+        println!("{} is already locked", invoice_number);
+        return Ok(());
+    }
+
+    entry.locked = true;
+    save_state(cfg_dir, &state)?;
+
+    println!("Locked {}", invoice_number);
+    Ok(())
+}
+
+/// Record and list payment follow-ups
+fn cmd_followup(cfg_dir: &Path, action: FollowupAction) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let parse_date = |flag: &str, s: &str| {
+        chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|_| InvoiceError::PdfGeneration(format!("Invalid {flag} value: '{s}'")))
+    };
+
+    match action {
+        FollowupAction::Add {
+            invoice,
+            via,
+            note,
+            next,
+            date,
+        } => {
+            let invoice_number = resolve_invoice_number(&load_state(cfg_dir)?, &invoice)?;
+            let date = match date {
+                Some(s) => parse_date("--date", &s)?,
+                None => chrono::Local::now().date_naive(),
+            };
+            let next = next.map(|s| parse_date("--next", &s)).transpose()?;
+
+            record_followup(
+                cfg_dir,
+                &invoice_number,
+                FollowUp {
+                    date,
+                    via,
+                    note,
+                    next,
+                },
+            )?;
+            println!("Logged follow-up on {}", invoice_number);
+            if let Some(next) = next {
+                println!("  Next follow-up: {}", next);
+            }
+        }
+        FollowupAction::List { invoice } => {
+            let state = load_state(cfg_dir)?;
+            let invoice_number = resolve_invoice_number(&state, &invoice)?;
+            let entry = state
+                .entry(&invoice_number)
+                .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.clone()))?;
+
+            if entry.followups.is_empty() {
+                println!("No follow-ups for {}", invoice_number);
+                return Ok(());
+            }
+            for followup in &entry.followups {
+                let mut line = format!("{}  {}", followup.date, followup.via);
+                if let Some(note) = &followup.note {
+                    line.push_str(&format!("  {}", note));
+                }
+                if let Some(next) = followup.next {
+                    line.push_str(&format!("  (next: {})", next));
+                }
+                println!("{}", line);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct NoteRow {
+    #[tabled(rename = "INVOICE")]
+    invoice: String,
+    #[tabled(rename = "DATE")]
+    date: String,
+    #[tabled(rename = "AUTHOR")]
+    author: String,
+    #[tabled(rename = "NOTE")]
+    text: String,
+}
+
+/// Add, list and search internal invoice notes
+fn cmd_note(cfg_dir: &Path, action: NoteAction) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    match action {
+        NoteAction::Add { invoice, text } => {
+            let invoice_number = resolve_invoice_number(&load_state(cfg_dir)?, &invoice)?;
+            add_note(cfg_dir, &invoice_number, &text)?;
+            println!("Added note to {}", invoice_number);
+        }
+        NoteAction::List { invoice } => {
+            let state = load_state(cfg_dir)?;
+            let invoice_number = resolve_invoice_number(&state, &invoice)?;
+            let entry = state
+                .entry(&invoice_number)
+                .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.clone()))?;
+
+            if entry.notes.is_empty() {
+                println!("No notes for {}", invoice_number);
+                return Ok(());
+            }
+            for note in &entry.notes {
+                println!(
+                    "{}  {}  {}",
+                    note.at.format("%Y-%m-%d %H:%M"),
+                    note.author,
+                    note.text
+                );
+            }
+        }
+        NoteAction::Search { query } => {
+            let state = load_state(cfg_dir)?;
+            let matches = search_notes(&state, &query);
+            if matches.is_empty() {
+                println!("No notes match '{}'", query);
+                return Ok(());
+            }
+
+            let rows: Vec<NoteRow> = matches
+                .into_iter()
+                .map(|(entry, note)| NoteRow {
+                    invoice: entry.number.clone(),
+                    date: note.at.format("%Y-%m-%d %H:%M").to_string(),
+                    author: note.author.clone(),
+                    text: note.text.clone(),
+                })
+                .collect();
+
+            let table = Table::new(rows).with(Style::rounded()).to_string();
+            println!("{}", table);
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Tabled)]
+struct TrashRow {
+    #[tabled(rename = "NUMBER")]
+    number: String,
+    #[tabled(rename = "CLIENT")]
+    client: String,
+    #[tabled(rename = "DATE")]
+    date: String,
+    #[tabled(rename = "TOTAL")]
+    total: String,
+    #[tabled(rename = "DELETED")]
+    deleted: String,
+}
 
-    println!();
-    println!("Total: {} invoices", state.history.len());
+/// Move invoices to the trash and manage trashed invoices
+fn cmd_trash(cfg_dir: &Path, action: TrashAction) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
 
-    // Show outstanding amount converted to BRL if there's an outstanding balance
-    if shown_outstanding > 0.0 {
-        if let Some(rate) = fetch_usd_to_brl_rate() {
-            let brl_amount = (shown_outstanding * rate).round() as i64;
+    match action {
+        TrashAction::Add { invoice } => {
+            let invoice_number = resolve_invoice_number(&load_state(cfg_dir)?, &invoice)?;
+            let trashed = trash_invoice(cfg_dir, &invoice_number)?;
+            println!("Moved {} to the trash", trashed.entry.number);
+            if trashed.number_released {
+                println!(
+                    "  It was the latest invoice, so the next one reuses {}",
+                    trashed.entry.number
+                );
+            }
             println!(
-                "Outstanding in BRL: R$ {} (1 USD = {:.2} BRL)",
-                format_grouped_int(brl_amount),
-                rate
+                "  Restore with: invoice trash restore {}",
+                trashed.entry.number
             );
         }
+        TrashAction::List => {
+            let trashed = list_trash(cfg_dir)?;
+            if trashed.is_empty() {
+                println!("Trash is empty.");
+                return Ok(());
+            }
+
+            let config = load_config(cfg_dir)?;
+            let rows: Vec<TrashRow> = trashed
+                .iter()
+                .map(|t| TrashRow {
+                    number: t.entry.number.clone(),
+                    client: t.entry.client.clone(),
+                    date: t.entry.date.to_string(),
+                    total: config.invoice.money(t.entry.total),
+                    deleted: t.deleted_at.format("%Y-%m-%d %H:%M").to_string(),
+                })
+                .collect();
+
+            let table = Table::new(rows).with(Style::rounded()).to_string();
+            println!("{}", table);
+        }
+        TrashAction::Restore { invoice } => {
+            let entry = restore_invoice(cfg_dir, &invoice)?;
+            println!("Restored {}", entry.number);
+        }
+        TrashAction::Purge { invoice, all } => {
+            let target = if all { None } else { invoice.as_deref() };
+            let count = purge_trash(cfg_dir, target)?;
+            println!("Purged {} invoice(s) from the trash", count);
+        }
+    }
+
+    Ok(())
+}
+
+/// Void an invoice, keeping it in history
+fn cmd_void(
+    cfg_dir: &Path,
+    invoice_ref: &str,
+    reason: Option<String>,
+    remove_files: bool,
+) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
     }
 
+    let invoice_number = resolve_invoice_number(&load_state(cfg_dir)?, invoice_ref)?;
+    let entry = void_invoice(cfg_dir, &invoice_number, reason, remove_files)?;
+    let config = load_config(cfg_dir)?;
     println!(
-        "Use index number with open/edit/regenerate/add-payment/remove-payment (e.g., 'invoice open 1')"
+        "Voided {} ({}, {})",
+        entry.number,
+        entry.client,
+        config.invoice.money(entry.total)
     );
-
+    if remove_files {
+        println!("  Removed: {}", entry.artifacts().join(", "));
+    }
+    println!("  The number stays in history and won't be reused.");
     Ok(())
 }
 
-/// Resolve an invoice reference to the actual invoice number.
-/// Accepts either an index (1-based) from 'list' or the full invoice number.
-fn resolve_invoice_number(cfg_dir: &PathBuf, reference: &str) -> Result<String> {
-    let state = load_state(cfg_dir)?;
-
-    // Try to parse as an index first
-    if let Ok(idx) = reference.parse::<usize>() {
-        if idx == 0 {
-            return Err(InvoiceError::InvalidInvoiceIndex(reference.to_string()));
-        }
-        // Invoices are displayed in reverse order (newest first), 1-indexed
-        let invoices: Vec<_> = state.history.iter().rev().collect();
-        if idx > invoices.len() {
-            return Err(InvoiceError::InvalidInvoiceIndex(reference.to_string()));
-        }
-        return Ok(invoices[idx - 1].number.clone());
+/// Percentage from "10%" or "10"
+fn parse_percent(input: &str) -> std::result::Result<f64, String> {
+    let value: f64 = input
+        .trim()
+        .trim_end_matches('%')
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{input}' is not a percentage (e.g., 10%)"))?;
+    if !(0.0..100.0).contains(&value) {
+        return Err(format!("'{input}' must be at least 0% and below 100%"));
     }
+    Ok(value)
+}
 
-    // Otherwise, treat as invoice number - verify it exists
-    if state.history.iter().any(|e| e.number == reference) {
-        Ok(reference.to_string())
-    } else {
-        Err(InvoiceError::InvoiceNotFound(reference.to_string()))
+/// Parse `--rate CURRENCY=RATE`, e.g. "EUR=1.08"
+fn parse_exchange_rate(input: &str) -> std::result::Result<(String, f64), String> {
+    let invalid = || format!("'{input}' is not CURRENCY=RATE (e.g., EUR=1.08)");
+    let (currency, rate) = input.split_once('=').ok_or_else(invalid)?;
+    let currency = currency.trim().to_uppercase();
+    let rate: f64 = rate.trim().parse().map_err(|_| invalid())?;
+    if currency.is_empty() || rate <= 0.0 {
+        return Err(invalid());
     }
+    Ok((currency, rate))
 }
 
-/// Generate a new invoice
-fn cmd_generate(
-    cfg_dir: &PathBuf,
-    client_id: &str,
-    items_input: &[String],
-    output: Option<PathBuf>,
-    open: bool,
-) -> Result<()> {
-    if !cfg_dir.exists() {
-        return Err(InvoiceError::ConfigNotFound(cfg_dir.clone()));
+/// Parse `--field NAME=VALUE`, e.g. "Cost Center=1234"
+fn parse_custom_field(input: &str) -> std::result::Result<(String, String), String> {
+    match input.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!(
+            "'{input}' is not NAME=VALUE (e.g., \"Cost Center=1234\")"
+        )),
     }
+}
 
-    if items_input.is_empty() {
-        return Err(InvoiceError::NoItems);
+/// Service period from `--period YYYY-MM` or `--period-from`/`--period-to`
+fn parse_service_period(
+    month: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Option<ServicePeriod>> {
+    let parse_date = |flag: &str, s: &str| {
+        chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|_| InvoiceError::PdfGeneration(format!("Invalid {flag} value: '{s}'")))
+    };
+
+    if let Some(month) = month {
+        return chrono::NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d")
+            .ok()
+            .and_then(|first| ServicePeriod::month(first.year(), first.month()))
+            .map(Some)
+            .ok_or_else(|| {
+                InvoiceError::PdfGeneration(format!("Invalid --period value: '{month}'"))
+            });
     }
 
-    let output_path = output.clone();
-    generate_invoice(cfg_dir, client_id, items_input, output)?;
-    if open {
-        let pdf_path = if let Some(path) = output_path {
-            path
-        } else {
-            let state = load_state(cfg_dir)?;
-            let invoice_number = state
-                .history
-                .last()
-                .map(|entry| entry.number.clone())
-                .ok_or_else(|| InvoiceError::InvoiceNotFound("latest".to_string()))?;
-            get_invoice_path(cfg_dir, &invoice_number)?
-        };
-        open_path(&pdf_path)?;
+    match (from, to) {
+        (Some(from), Some(to)) => {
+            let period = ServicePeriod {
+                from: parse_date("--period-from", &from)?,
+                to: parse_date("--period-to", &to)?,
+            };
+            if period.from > period.to {
+                return Err(InvoiceError::PdfGeneration(format!(
+                    "Invalid service period: {from} is after {to}"
+                )));
+            }
+            Ok(Some(period))
+        }
+        _ => Ok(None),
     }
-    Ok(())
 }
 
-/// Format invoice number from template
-fn format_invoice_number(format: &str, year: u32, seq: u32) -> String {
-    format
-        .replace("{year}", &year.to_string())
-        .replace("{seq:04}", &format!("{:04}", seq))
-        .replace("{seq:05}", &format!("{:05}", seq))
-        .replace("{seq:03}", &format!("{:03}", seq))
+/// Parse `--bulk` input ("INV-2026-0001=500,INV-2026-0002=750")
+fn parse_bulk_payments(input: &str) -> Result<Vec<(String, f64)>> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            pair.split_once('=')
+                .and_then(|(invoice, amount)| {
+                    let amount = amount.trim().parse::<f64>().ok()?;
+                    amount
+                        .is_finite()
+                        .then(|| (invoice.trim().to_string(), amount))
+                })
+                .filter(|(invoice, _)| !invoice.is_empty())
+                .ok_or_else(|| {
+                    InvoiceError::PdfGeneration(format!(
+                        "Invalid --bulk entry: '{pair}' (expected INVOICE=AMOUNT)"
+                    ))
+                })
+        })
+        .collect()
 }
 
-/// Edit an existing invoice
-fn cmd_edit(cfg_dir: &PathBuf, invoice_ref: &str, items: &[String]) -> Result<()> {
+/// Normalize state.toml (see `State::normalize`) and report the size change.
+/// Loading also migrates legacy fields, which are dropped on save.
+fn cmd_compact(cfg_dir: &Path) -> Result<()> {
     if !cfg_dir.exists() {
-        return Err(InvoiceError::ConfigNotFound(cfg_dir.clone()));
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
     }
 
-    if items.is_empty() {
-        return Err(InvoiceError::NoItems);
+    let path = cfg_dir.join("state.toml");
+    if !path.exists() {
+        println!("Nothing to compact: {} does not exist", path.display());
+        return Ok(());
     }
 
-    let invoice_number = resolve_invoice_number(cfg_dir, invoice_ref)?;
-    let config = load_config(cfg_dir)?;
-    let pdf_path = regenerate_invoice(cfg_dir, &invoice_number, Some(items))?;
-
-    println!("Updated {}", invoice_number);
-    println!("  Items:  {}", items.join(", "));
-    println!("  Saved:  {}", pdf_path.display());
+    let before = std::fs::metadata(&path)?.len();
+    let mut state = load_state(cfg_dir)?;
+    state.normalize();
+    save_state(cfg_dir, &state)?;
+    let after = std::fs::metadata(&path)?.len();
+
+    let change = if after < before {
+        format!(
+            "{:.1}% smaller",
+            (before - after) as f64 / before as f64 * 100.0
+        )
+    } else if after > before {
+        format!(
+            "{:.1}% larger",
+            (after - before) as f64 / before as f64 * 100.0
+        )
+    } else {
+        "unchanged".to_string()
+    };
 
-    // Show new total
-    let state = load_state(cfg_dir)?;
-    if let Some(entry) = state.history.iter().find(|e| e.number == invoice_number) {
-        println!(
-            "  Total:  {}{:.2}",
-            config.invoice.currency_symbol, entry.total
-        );
-    }
+    println!("Compacted {}", path.display());
+    println!("  Invoices: {}", state.history.len());
+    println!("  Size:     {} -> {} bytes ({})", before, after, change);
 
     Ok(())
 }
 
-/// Open an invoice PDF
-fn cmd_open(cfg_dir: &PathBuf, invoice_ref: &str) -> Result<()> {
+/// Merge another copy of state.toml into this one, listing the conflicts
+/// that need resolving by hand
+fn cmd_merge_state(cfg_dir: &Path, file: &Path, dry_run: bool) -> Result<()> {
     if !cfg_dir.exists() {
-        return Err(InvoiceError::ConfigNotFound(cfg_dir.clone()));
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let content = std::fs::read_to_string(file)?;
+    let theirs: config::State =
+        toml::from_str(&content).map_err(|e| InvoiceError::ConfigParse {
+            path: file.to_path_buf(),
+            source: e,
+        })?;
+    let mut state = load_state(cfg_dir)?;
+    let trashed: Vec<String> = list_trash(cfg_dir)?
+        .into_iter()
+        .map(|t| t.entry.number)
+        .collect();
+    let report = merge_states(&mut state, &theirs, &trashed);
+
+    if report.is_empty() {
+        println!("Nothing to merge: {} adds nothing", file.display());
+        return Ok(());
     }
 
-    let invoice_number = resolve_invoice_number(cfg_dir, invoice_ref)?;
-    let pdf_path = get_invoice_path(cfg_dir, &invoice_number)?;
+    let numbers = |numbers: &[String]| {
+        if numbers.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", numbers.join(", "))
+        }
+    };
+    println!("Merged {}", file.display());
+    println!(
+        "  Added:     {} invoice(s){}",
+        report.added.len(),
+        numbers(&report.added)
+    );
+    println!(
+        "  Updated:   {} invoice(s){}",
+        report.updated.len(),
+        numbers(&report.updated)
+    );
+    if report.credits_added > 0 {
+        println!("  Credits:   {}", report.credits_added);
+    }
+    if report.reports_added > 0 {
+        println!("  Reports:   {}", report.reports_added);
+    }
+    if !report.payments_added.is_empty() {
+        println!();
+        println!("Payments taken from the other copy (remove-payment any removed here):");
+        for (number, date, amount) in &report.payments_added {
+            println!("  {number} {date} {amount:.2}");
+        }
+    }
+    if !report.conflicts.is_empty() {
+        println!();
+        println!("Conflicts to resolve by hand (this copy's values were kept):");
+        for conflict in &report.conflicts {
+            println!(
+                "  {} {}: '{}' here, '{}' in {}",
+                conflict.number,
+                conflict.field,
+                conflict.ours,
+                conflict.theirs,
+                file.display()
+            );
+        }
+    }
 
-    open_path(&pdf_path)?;
+    if dry_run {
+        println!();
+        println!("(dry run: state.toml not changed)");
+    } else {
+        save_state(cfg_dir, &state)?;
+    }
 
-    println!("Opened {}", pdf_path.display());
     Ok(())
 }
 
-fn open_path(pdf_path: &PathBuf) -> Result<()> {
-    // Open with system default viewer
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .arg(&pdf_path)
-            .spawn()
-            .map_err(|e| InvoiceError::Io(e))?;
+/// List orphaned PDFs in the output directory and delete them once confirmed
+fn cmd_clean_output(cfg_dir: &Path, dry_run: bool) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
     }
 
-    #[cfg(target_os = "linux")]
-    {
-        std::process::Command::new("xdg-open")
-            .arg(&pdf_path)
-            .spawn()
-            .map_err(|e| InvoiceError::Io(e))?;
+    let orphans = orphaned_pdfs(cfg_dir)?;
+    if orphans.is_empty() {
+        println!("No orphaned PDFs in the output directory.");
+        return Ok(());
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        std::process::Command::new("cmd")
-            .args(["/C", "start", "", pdf_path.to_str().unwrap_or("")])
-            .spawn()
-            .map_err(|e| InvoiceError::Io(e))?;
+    println!("PDFs not referenced by any invoice:");
+    for path in &orphans {
+        println!("  {}", path.display());
+    }
+
+    if dry_run {
+        println!("{} file(s) would be deleted (dry run)", orphans.len());
+        return Ok(());
+    }
+    if !confirm(&format!("Delete {} file(s)? [Y/n] ", orphans.len())) {
+        println!("Nothing deleted.");
+        return Ok(());
     }
+
+    for path in &orphans {
+        std::fs::remove_file(path)?;
+    }
+    println!("Deleted {} file(s)", orphans.len());
+
     Ok(())
 }
 
-/// Regenerate an invoice PDF
-fn cmd_regenerate(cfg_dir: &PathBuf, invoice_ref: &str, open: bool) -> Result<()> {
-    if !cfg_dir.exists() {
-        return Err(InvoiceError::ConfigNotFound(cfg_dir.clone()));
-    }
+fn cmd_template_check(path: &Path) -> Result<()> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|_| InvoiceError::TemplateNotFound(path.to_path_buf()))?;
 
-    let invoice_number = resolve_invoice_number(cfg_dir, invoice_ref)?;
-    let pdf_path = regenerate_invoice(cfg_dir, &invoice_number, None)?;
-    if open {
-        open_path(&pdf_path)?;
+    let warnings = check_template(&source);
+    if warnings.is_empty() {
+        println!(
+            "{} is compatible with data schema {SCHEMA_VERSION}",
+            path.display()
+        );
+        return Ok(());
     }
 
-    println!("Regenerated {}", invoice_number);
-    println!("  Saved: {}", pdf_path.display());
+    for warning in &warnings {
+        println!("{}:{}: {}", path.display(), warning.line, warning.message);
+    }
+    println!(
+        "{} warning(s) against data schema {SCHEMA_VERSION}",
+        warnings.len()
+    );
 
     Ok(())
 }
 
-/// Record a payment against an invoice
+/// Record payments against one or more invoices.
+///
+/// Every payment is validated before anything is saved, so a bad entry in a
+/// bulk run leaves the state untouched.
 fn cmd_add_payment(
-    cfg_dir: &PathBuf,
-    invoice_ref: &str,
-    amount: f64,
+    cfg_dir: &Path,
+    payments: &[(String, f64)],
     date_str: Option<String>,
+    as_credit: bool,
 ) -> Result<()> {
     if !cfg_dir.exists() {
-        return Err(InvoiceError::ConfigNotFound(cfg_dir.clone()));
-    }
-
-    // Validate amount
-    if amount <= 0.0 {
-        return Err(InvoiceError::InvalidPaymentAmount);
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
     }
 
-    let invoice_number = resolve_invoice_number(cfg_dir, invoice_ref)?;
     let mut state = load_state(cfg_dir)?;
     let config = load_config(cfg_dir)?;
 
@@ -793,41 +5181,100 @@ fn cmd_add_payment(
         None => chrono::Local::now().date_naive(),
     };
 
-    let entry = state
-        .history
-        .iter_mut()
-        .find(|e| e.number == invoice_number)
-        .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.clone()))?;
+    let mut recorded = Vec::with_capacity(payments.len());
+    for (invoice_ref, amount) in payments {
+        let amount = *amount;
 
-    // Guard against overpayment
-    let remaining = entry.outstanding();
-    if amount > remaining + 0.001 {
-        return Err(InvoiceError::OverPayment {
-            invoice: invoice_number,
-            max: remaining,
-        });
-    }
+        // Validate amount
+        if !(amount.is_finite() && amount > 0.0) {
+            return Err(InvoiceError::InvalidPaymentAmount);
+        }
 
-    entry.payments.push(Payment { amount, date });
-    let new_outstanding = entry.outstanding();
-    let inv_number = entry.number.clone();
+        let invoice_number = resolve_invoice_number(&state, invoice_ref)?;
+        let entry = state
+            .history
+            .iter_mut()
+            .find(|e| e.number == invoice_number)
+            .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.clone()))?;
+
+        if entry.voided.is_some() {
+            return Err(InvoiceError::Voided(invoice_number));
+        }
+        if let Some(by) = &entry.superseded_by {
+            return Err(InvoiceError::Superseded {
+                invoice: invoice_number,
+                by: by.clone(),
+            });
+        }
+
+        // Guard against overpayment (earlier entries in a bulk run count too)
+        let remaining = entry.outstanding().max(0.0);
+        let excess = if amount > remaining + 0.001 {
+            if !as_credit {
+                return Err(InvoiceError::OverPayment {
+                    invoice: invoice_number,
+                    max: remaining,
+                });
+            }
+            amount - remaining
+        } else {
+            0.0
+        };
+
+        let applied = amount - excess;
+        if applied > 0.0 {
+            entry.payments.push(Payment {
+                amount: applied,
+                date,
+                credit: (excess > 0.0).then_some(excess),
+            });
+        }
+        let outstanding = entry.outstanding();
+        if excess > 0.0 {
+            let client = entry.client.clone();
+            state.credits.push(Credit {
+                client,
+                amount: excess,
+                date,
+                invoice: Some(invoice_number.clone()),
+            });
+        }
+        recorded.push((invoice_number, amount, outstanding, excess));
+    }
 
     save_state(cfg_dir, &state)?;
 
     // Print confirmation
-    if new_outstanding <= 0.001 {
-        println!(
-            "Recorded {}{:.2} payment for {} (fully paid)",
-            config.invoice.currency_symbol, amount, inv_number
-        );
-    } else {
+    let money = |amount: f64| config.invoice.money(amount);
+    for (inv_number, amount, new_outstanding, excess) in &recorded {
+        if *excess > 0.0 {
+            println!(
+                "Recorded {} payment for {} (fully paid, {} kept as client credit)",
+                money(*amount),
+                inv_number,
+                money(*excess)
+            );
+        } else if *new_outstanding <= 0.001 {
+            println!(
+                "Recorded {} payment for {} (fully paid)",
+                money(*amount),
+                inv_number
+            );
+        } else {
+            println!(
+                "Recorded {} payment for {} ({} remaining)",
+                money(*amount),
+                inv_number,
+                money(*new_outstanding)
+            );
+        }
+    }
+    if recorded.len() > 1 {
+        let total: f64 = recorded.iter().map(|(_, amount, ..)| amount).sum();
         println!(
-            "Recorded {}{:.2} payment for {} ({}{:.2} remaining)",
-            config.invoice.currency_symbol,
-            amount,
-            inv_number,
-            config.invoice.currency_symbol,
-            new_outstanding
+            "Recorded {} payments totaling {}",
+            recorded.len(),
+            money(total)
         );
     }
 
@@ -835,17 +5282,13 @@ fn cmd_add_payment(
 }
 
 /// Remove a payment from an invoice
-fn cmd_remove_payment(
-    cfg_dir: &PathBuf,
-    invoice_ref: &str,
-    index: Option<usize>,
-) -> Result<()> {
+fn cmd_remove_payment(cfg_dir: &Path, invoice_ref: &str, index: Option<usize>) -> Result<()> {
     if !cfg_dir.exists() {
-        return Err(InvoiceError::ConfigNotFound(cfg_dir.clone()));
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
     }
 
-    let invoice_number = resolve_invoice_number(cfg_dir, invoice_ref)?;
     let mut state = load_state(cfg_dir)?;
+    let invoice_number = resolve_invoice_number(&state, invoice_ref)?;
     let config = load_config(cfg_dir)?;
 
     let entry = state
@@ -875,31 +5318,39 @@ fn cmd_remove_payment(
 
     let removed = entry.payments.remove(remove_idx);
     let inv_number = entry.number.clone();
+    if let Some(credit) = removed.credit {
+        state.revoke_credit(&inv_number, removed.date, credit);
+    }
 
     save_state(cfg_dir, &state)?;
 
     println!(
-        "Removed {}{:.2} payment from {}",
-        config.invoice.currency_symbol, removed.amount, inv_number
+        "Removed {} payment from {}",
+        config.invoice.money(removed.amount),
+        inv_number
     );
+    if let Some(credit) = removed.credit {
+        println!(
+            "  Withdrew the {} client credit it created",
+            config.invoice.money(credit)
+        );
+    }
 
     Ok(())
 }
 
 /// Show payment history for an invoice
-fn cmd_payments(cfg_dir: &PathBuf, invoice_ref: &str) -> Result<()> {
+fn cmd_payments(cfg_dir: &Path, invoice_ref: &str) -> Result<()> {
     if !cfg_dir.exists() {
-        return Err(InvoiceError::ConfigNotFound(cfg_dir.clone()));
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
     }
 
-    let invoice_number = resolve_invoice_number(cfg_dir, invoice_ref)?;
     let state = load_state(cfg_dir)?;
+    let invoice_number = resolve_invoice_number(&state, invoice_ref)?;
     let config = load_config(cfg_dir)?;
 
     let entry = state
-        .history
-        .iter()
-        .find(|e| e.number == invoice_number)
+        .entry(&invoice_number)
         .ok_or_else(|| InvoiceError::InvoiceNotFound(invoice_number.clone()))?;
 
     println!("Payments for {}", invoice_number);
@@ -914,10 +5365,7 @@ fn cmd_payments(cfg_dir: &PathBuf, invoice_ref: &str) -> Result<()> {
             .map(|(idx, p)| PaymentRow {
                 index: idx + 1,
                 date: p.date.to_string(),
-                amount: format!(
-                    "{}{:.2}",
-                    config.invoice.currency_symbol, p.amount
-                ),
+                amount: config.invoice.money(p.amount),
             })
             .collect();
 
@@ -926,11 +5374,9 @@ fn cmd_payments(cfg_dir: &PathBuf, invoice_ref: &str) -> Result<()> {
     }
 
     println!(
-        "Total paid: {}{:.2} / {}{:.2} (Status: {})",
-        config.invoice.currency_symbol,
-        entry.paid_amount(),
-        config.invoice.currency_symbol,
-        entry.total,
+        "Total paid: {} / {} (Status: {})",
+        config.invoice.money(entry.paid_amount()),
+        config.invoice.money(entry.total),
         entry.status()
     );
 
@@ -939,26 +5385,32 @@ fn cmd_payments(cfg_dir: &PathBuf, invoice_ref: &str) -> Result<()> {
 
 /// Generate a PDF report of invoices for a client
 fn cmd_report(
-    cfg_dir: &PathBuf,
+    cfg_dir: &Path,
     client_id: &str,
-    from: Option<String>,
-    to: Option<String>,
-    status: Option<String>,
+    filter: ReportFilter,
+    group_by: Option<ReportGroupBy>,
+    net: bool,
     open: bool,
 ) -> Result<()> {
     if !cfg_dir.exists() {
-        return Err(InvoiceError::ConfigNotFound(cfg_dir.clone()));
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
     }
+    let ReportFilter { from, to, status } = filter;
 
     let config = load_config(cfg_dir)?;
     let clients = load_clients(cfg_dir)?;
-    let state = load_state(cfg_dir)?;
+    let mut state = load_state(cfg_dir)?;
 
-    // Validate client exists
-    let client = clients
-        .get(client_id)
-        .ok_or_else(|| InvoiceError::ClientNotFound(client_id.to_string()))?
-        .clone();
+    let output_dir = config::resolve_output_dir(&config.pdf.output_dir, cfg_dir);
+    let mut warnings = Vec::new();
+    let client = client_or_snapshot(
+        &clients,
+        &state.history,
+        &output_dir,
+        client_id,
+        &mut warnings,
+    )?;
+    print_warnings(&warnings);
 
     // Parse date filters
     let from_date = from
@@ -990,8 +5442,8 @@ fn cmd_report(
         .history
         .iter()
         .filter(|e| e.client == client_id)
-        .filter(|e| from_date.map_or(true, |d| e.date >= d))
-        .filter(|e| to_date.map_or(true, |d| e.date <= d))
+        .filter(|e| from_date.is_none_or(|d| e.date >= d))
+        .filter(|e| to_date.is_none_or(|d| e.date <= d))
         .filter(|e| match status.as_deref() {
             Some("paid") => e.status() == PaymentStatus::Paid,
             Some("unpaid") => e.status() == PaymentStatus::Unpaid,
@@ -1008,89 +5460,208 @@ fn cmd_report(
     // Build report rows with three-way status
     let rows: Vec<ReportInvoiceRow> = filtered
         .iter()
-        .map(|e| ReportInvoiceRow {
-            number: e.number.clone(),
-            date: e.date.format("%B %d, %Y").to_string(),
-            total: e.total,
-            paid: e.paid_amount(),
-            outstanding: e.outstanding(),
-            payments: e
-                .payments
-                .iter()
-                .map(|p| ReportPayment {
-                    amount: p.amount,
-                    date: p.date.format("%B %d, %Y").to_string(),
-                })
-                .collect(),
-            status: e.status().to_string(),
-        })
+        .map(|e| ReportInvoiceRow::from_entry(e, net))
         .collect();
+    let groups = match group_by {
+        Some(ReportGroupBy::Month) => month_groups(&filtered, net),
+        None => Vec::new(),
+    };
 
     // Financial summary uses actual payment amounts
-    let total: f64 = filtered.iter().map(|e| e.total).sum();
-    let paid: f64 = filtered.iter().map(|e| e.paid_amount()).sum();
+    let total: f64 = filtered
+        .iter()
+        .zip(&rows)
+        .filter(|(e, _)| e.is_billed())
+        .map(|(_, row)| row.total)
+        .sum();
+    let paid: f64 = rows.iter().map(|row| row.paid).sum();
     let outstanding = total - paid;
+    let factored_outstanding: f64 = rows
+        .iter()
+        .filter(|row| row.payee.is_some())
+        .map(|row| row.outstanding)
+        .sum();
+
+    let amounts: Vec<_> = filtered
+        .iter()
+        .zip(&rows)
+        .map(|(e, row)| (invoice_currency(e, &config), row.outstanding))
+        .collect();
+    let converted_outstanding = (outstanding > 0.0)
+        .then(|| {
+            Rates::new(
+                &config.currency,
+                &mut state.rates,
+                chrono::Local::now().naive_local(),
+            )
+            .convert_all(amounts, &config.currency.display)
+        })
+        .flatten();
 
-    let today = chrono::Local::now().format("%B %d, %Y").to_string();
+    let today = chrono::Local::now().date_naive();
+    let number = next_report_number(&state.reports, today.year());
 
     let report_data = ReportData {
+        number: number.clone(),
         company: config.company.clone(),
         client,
         client_id: client_id.to_string(),
         rows,
+        groups,
         total,
         paid,
         outstanding,
+        factored_outstanding,
+        converted_outstanding,
         currency_symbol: config.invoice.currency_symbol.clone(),
-        generated_date: today,
+        symbol_position: config.invoice.symbol_position,
+        symbol_space: config.invoice.symbol_space,
+        generated_date: today.format("%B %d, %Y").to_string(),
         filter_from: from.clone(),
         filter_to: to.clone(),
         filter_status: status.clone(),
+        net,
     };
 
     // Determine output path
-    let output_dir = config::resolve_output_dir(&config.pdf.output_dir, cfg_dir);
     std::fs::create_dir_all(&output_dir)?;
 
-    let today_str = chrono::Local::now().format("%Y-%m-%d").to_string();
-    let pdf_filename = format!("REPORT-{}-{}.pdf", client_id, today_str);
+    let pdf_filename = format!("{number}.pdf");
     let pdf_path = output_dir.join(&pdf_filename);
 
     // Generate PDF
-    generate_report_pdf(&report_data, &pdf_path)?;
+    generate_report_pdf(&report_data, &pdf_path, Some(&cfg_dir.join("templates")))?;
 
     // Print summary
     println!("Generated report for '{}'", client_id);
+    println!("  Number:   {}", number);
     println!("  Invoices: {}", filtered.len());
+    for group in &report_data.groups {
+        println!(
+            "    {}: {} ({} invoice(s))",
+            group.label,
+            config.invoice.with_symbol(&format_amount(group.total)),
+            group.rows.len()
+        );
+    }
     println!(
         "  Total:    {}{}",
-        config.invoice.currency_symbol,
-        format_report_amount(total)
+        config.invoice.with_symbol(&format_amount(total)),
+        if net { " (excluding tax)" } else { "" }
     );
+    if factored_outstanding > 0.0 {
+        println!(
+            "  Factored: {} outstanding, owed to other payees",
+            config
+                .invoice
+                .with_symbol(&format_amount(factored_outstanding))
+        );
+    }
+    if let Some(converted) = &report_data.converted_outstanding {
+        println!(
+            "  Outstanding in {}: {} ({})",
+            converted.currency,
+            format_amount(converted.amount),
+            converted.rates_label()
+        );
+    }
     println!("  Saved:    {}", pdf_path.display());
+    if net {
+        warn_untaxed_history(filtered.iter().copied());
+    }
+
+    state.reports.push(ReportEntry {
+        number,
+        client: client_id.to_string(),
+        date: today,
+        file: pdf_filename,
+        from,
+        to,
+        status,
+        group_by: group_by.map(|g| g.to_string()),
+        net,
+    });
+    save_state(cfg_dir, &state)?;
 
     if open {
-        open_path(&pdf_path)?;
+        open_path(cfg_dir, &pdf_path);
+    }
+
+    Ok(())
+}
+
+/// Generated reports, newest first
+fn cmd_report_list(cfg_dir: &Path) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let state = load_state(cfg_dir)?;
+    if state.reports.is_empty() {
+        println!("No reports generated yet.");
+        return Ok(());
     }
 
+    let rows: Vec<ReportRow> = state
+        .reports
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, report)| ReportRow {
+            index: i + 1,
+            number: report.number.clone(),
+            client: report.client.clone(),
+            date: report.date.to_string(),
+            filters: report.filters(),
+            file: report.file.clone(),
+        })
+        .collect();
+
+    let table = Table::new(rows).with(Style::rounded()).to_string();
+    println!("{table}");
+
     Ok(())
 }
 
-/// Format a money amount with two decimal places and thousands separators
-fn format_report_amount(value: f64) -> String {
-    let rounded = format!("{:.2}", value);
-    let parts: Vec<&str> = rounded.split('.').collect();
-    let whole = parts[0];
-    let frac = parts[1];
+/// Open a generated report by number or by index from `report list`
+fn cmd_report_open(cfg_dir: &Path, reference: &str) -> Result<()> {
+    if !cfg_dir.exists() {
+        return Err(InvoiceError::ConfigNotFound(cfg_dir.to_path_buf()));
+    }
+
+    let config = load_config(cfg_dir)?;
+    let state = load_state(cfg_dir)?;
+    let report = match reference.parse::<usize>() {
+        Ok(idx) if idx > 0 => state.reports.iter().rev().nth(idx - 1),
+        Ok(_) => None,
+        Err(_) => state.reports.iter().find(|r| r.number == reference),
+    }
+    .ok_or_else(|| InvoiceError::ReportNotFound(reference.to_string()))?;
+
+    let path = config::resolve_output_dir(&config.pdf.output_dir, cfg_dir).join(&report.file);
+    if !path.exists() {
+        return Err(InvoiceError::InvoiceFileNotFound(path));
+    }
+    if open_path(cfg_dir, &path) {
+        println!("Opened {}", path.display());
+    }
+    Ok(())
+}
 
-    // Group digits in the whole part
-    let negative = whole.starts_with('-');
-    let digits = if negative { &whole[1..] } else { whole };
-    let grouped = format_grouped_int(digits.parse::<i64>().unwrap_or(0));
+/// Which invoices `report` includes
+struct ReportFilter {
+    from: Option<String>,
+    to: Option<String>,
+    status: Option<String>,
+}
 
-    if negative {
-        format!("-{}.{}", grouped, frac)
-    } else {
-        format!("{}.{}", grouped, frac)
+/// Point out invoices whose tax wasn't recorded, so --net counts them in full
+fn warn_untaxed_history<'a>(entries: impl IntoIterator<Item = &'a config::HistoryEntry>) {
+    let unknown = entries.into_iter().filter(|e| e.tax.is_none()).count();
+    if unknown > 0 {
+        eprintln!(
+            "Warning: {unknown} invoice(s) predate stored tax amounts and are counted with tax; \
+             run 'invoice regenerate' on them to record it"
+        );
     }
 }
@@ -0,0 +1,63 @@
+//! Money rounding and formatting. The terminal, HTML invoices and the payloads Typst
+//! templates render all format amounts here, so an amount reads the same
+//! wherever it appears.
+
+use crate::config::SymbolPosition;
+
+/// Round a money amount to whole cents
+pub fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Two decimals with thousands separators (e.g., "1,234.50" or "-100.00")
+pub fn format_amount(amount: f64) -> String {
+    let cents = (amount * 100.0).round() as i64;
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    format!("{sign}{}.{:02}", group_digits(cents / 100), cents % 100)
+}
+
+/// Rounded to a whole number with thousands separators (e.g., "1,235")
+pub fn format_whole(amount: f64) -> String {
+    let whole = amount.round() as i64;
+    let sign = if whole < 0 { "-" } else { "" };
+    format!("{sign}{}", group_digits(whole.unsigned_abs()))
+}
+
+fn group_digits(value: u64) -> String {
+    let digits = value.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+/// How a document writes amounts in its currency
+#[derive(Debug, Clone, Copy)]
+pub struct MoneyFormat<'a> {
+    pub symbol: &'a str,
+    pub position: SymbolPosition,
+    /// A (non-breaking) space between the symbol and the number
+    pub space: bool,
+}
+
+impl MoneyFormat<'_> {
+    /// Attach the symbol to an already formatted number
+    pub fn with_symbol(&self, number: &str) -> String {
+        let space = if self.space { "\u{a0}" } else { "" };
+        match self.position {
+            SymbolPosition::Prefix => format!("{}{space}{number}", self.symbol),
+            SymbolPosition::Suffix => format!("{number}{space}{}", self.symbol),
+        }
+    }
+
+    /// `amount` with two decimals, thousands separators and the symbol
+    /// (e.g., "$1,234.50")
+    pub fn format(&self, amount: f64) -> String {
+        self.with_symbol(&format_amount(amount))
+    }
+}
@@ -0,0 +1,12 @@
+use crate::error::{InvoiceError, Result};
+
+/// Show a desktop notification from the `invoice` app
+pub fn send(summary: &str, body: &str) -> Result<()> {
+    notify_rust::Notification::new()
+        .appname("invoice")
+        .summary(summary)
+        .body(body)
+        .show()
+        .map(|_| ())
+        .map_err(|e| InvoiceError::Notification(e.to_string()))
+}
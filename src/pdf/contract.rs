@@ -0,0 +1,420 @@
+//! The JSON data contract between invoice data and Typst templates.
+//!
+//! Every payload carries `schema_version`. When a field is renamed, the old
+//! name stays in the JSON with the same value (a compatibility shim) so
+//! customized templates such as cover letters keep rendering, and
+//! `invoice template check` points out the deprecated names to update.
+//! The fields are listed in `docs/templates.md`; keep it in step when the
+//! contract changes.
+//!
+//! Next to the raw numbers, each payload carries its amounts already
+//! formatted in `formatted`, so templates print money exactly as the
+//! terminal and the HTML output do instead of formatting it themselves.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::{Client, Company};
+use crate::invoice::{
+    AgingColumns, AgingReport, InvoiceBuilder, InvoiceData, PaymentPlanData, ReportData,
+    ReportInvoiceRow, StatementData,
+};
+use crate::money::{format_amount, MoneyFormat};
+
+/// Version of the data contract given to templates
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A field that was renamed but is still provided for older templates
+#[derive(Debug, Clone, Copy)]
+pub struct DeprecatedField {
+    pub name: &'static str,
+    pub replacement: &'static str,
+    /// Schema version that introduced the replacement
+    pub since: u32,
+}
+
+/// Renamed invoice fields still included in the payload
+pub const DEPRECATED_FIELDS: &[DeprecatedField] = &[DeprecatedField {
+    // A percentage, unlike the fractional `[invoice] tax_rate` in config.toml
+    name: "tax_rate",
+    replacement: "tax_percent",
+    since: 1,
+}];
+
+/// The invoice JSON templates read: the current fields, the formatted
+/// amounts and the deprecated aliases in [`DEPRECATED_FIELDS`]
+#[derive(Debug, Serialize)]
+pub struct TemplatePayload<'a> {
+    #[serde(flatten)]
+    data: &'a InvoiceData,
+    formatted: InvoiceAmounts,
+    tax_rate: f64,
+}
+
+/// Wrap invoice data with its formatted amounts and compatibility shims
+/// for rendering
+pub fn template_payload(invoice_data: &InvoiceData) -> TemplatePayload<'_> {
+    TemplatePayload {
+        data: invoice_data,
+        formatted: InvoiceAmounts::new(invoice_data),
+        tax_rate: invoice_data.tax_percent,
+    }
+}
+
+/// An invoice's amounts as printed, with the currency symbol
+#[derive(Debug, Serialize)]
+pub struct InvoiceAmounts {
+    pub subtotal: String,
+    pub discount: String,
+    pub tax_amount: String,
+    /// One per entry of `taxes`
+    pub taxes: Vec<String>,
+    pub rounding_adjustment: String,
+    pub total: String,
+    pub retention: String,
+    /// `total - retention`, what the client pays now
+    pub amount_due: String,
+    /// One per entry of `items`
+    pub items: Vec<LineAmounts>,
+}
+
+/// A line's amounts as printed
+#[derive(Debug, Serialize)]
+pub struct LineAmounts {
+    pub rate: String,
+    pub amount: String,
+    /// Sum of the amounts up to and including this line, for subtotals
+    /// carried from page to page
+    pub running: String,
+    /// `original.amount` in its own currency, without a symbol
+    pub original: Option<String>,
+}
+
+impl InvoiceAmounts {
+    pub fn new(data: &InvoiceData) -> Self {
+        let money = MoneyFormat {
+            symbol: &data.currency_symbol,
+            position: data.symbol_position,
+            space: data.symbol_space,
+        };
+        let mut running = 0.0;
+        let items = data
+            .items
+            .iter()
+            .map(|item| {
+                running += item.amount;
+                LineAmounts {
+                    rate: money.format(item.rate),
+                    amount: money.format(item.amount),
+                    running: money.format(running),
+                    original: item.original.as_ref().map(|o| format_amount(o.amount)),
+                }
+            })
+            .collect();
+        Self {
+            subtotal: money.format(data.subtotal),
+            discount: money.format(data.discount),
+            tax_amount: money.format(data.tax_amount),
+            taxes: data.taxes.iter().map(|t| money.format(t.amount)).collect(),
+            rounding_adjustment: money.format(data.rounding_adjustment),
+            total: money.format(data.total),
+            retention: money.format(data.retention),
+            amount_due: money.format(data.total - data.retention),
+            items,
+        }
+    }
+}
+
+/// Report data with its amounts formatted
+#[derive(Debug, Serialize)]
+pub struct ReportPayload<'a> {
+    #[serde(flatten)]
+    data: &'a ReportData,
+    formatted: ReportAmounts,
+}
+
+/// A report's amounts as printed, mirroring `rows` and `groups`
+#[derive(Debug, Serialize)]
+pub struct ReportAmounts {
+    pub total: String,
+    pub paid: String,
+    pub outstanding: String,
+    pub factored_outstanding: String,
+    /// `converted_outstanding.amount`, without a symbol
+    pub converted_outstanding: Option<String>,
+    pub rows: Vec<RowAmounts>,
+    pub groups: Vec<GroupAmounts>,
+}
+
+/// An invoice row's amounts as printed, on a report or statement
+#[derive(Debug, Serialize)]
+pub struct RowAmounts {
+    pub total: String,
+    pub paid: String,
+    pub outstanding: String,
+    /// One per payment listed on the row
+    pub payments: Vec<String>,
+}
+
+/// A report group's subtotals and rows as printed
+#[derive(Debug, Serialize)]
+pub struct GroupAmounts {
+    pub total: String,
+    pub paid: String,
+    pub outstanding: String,
+    pub rows: Vec<RowAmounts>,
+}
+
+/// Wrap report data with its formatted amounts for rendering
+pub fn report_payload(report: &ReportData) -> ReportPayload<'_> {
+    let money = MoneyFormat {
+        symbol: &report.currency_symbol,
+        position: report.symbol_position,
+        space: report.symbol_space,
+    };
+    let rows = |rows: &[ReportInvoiceRow]| {
+        rows.iter()
+            .map(|row| RowAmounts {
+                total: money.format(row.total),
+                paid: money.format(row.paid),
+                outstanding: money.format(row.outstanding),
+                payments: row
+                    .payments
+                    .iter()
+                    .map(|p| money.format(p.amount))
+                    .collect(),
+            })
+            .collect()
+    };
+    ReportPayload {
+        data: report,
+        formatted: ReportAmounts {
+            total: money.format(report.total),
+            paid: money.format(report.paid),
+            outstanding: money.format(report.outstanding),
+            factored_outstanding: money.format(report.factored_outstanding),
+            converted_outstanding: report
+                .converted_outstanding
+                .as_ref()
+                .map(|c| format_amount(c.amount)),
+            rows: rows(&report.rows),
+            groups: report
+                .groups
+                .iter()
+                .map(|group| GroupAmounts {
+                    total: money.format(group.total),
+                    paid: money.format(group.paid),
+                    outstanding: money.format(group.outstanding),
+                    rows: rows(&group.rows),
+                })
+                .collect(),
+        },
+    }
+}
+
+/// Payment plan data with its amounts formatted
+#[derive(Debug, Serialize)]
+pub struct PlanPayload<'a> {
+    #[serde(flatten)]
+    data: &'a PaymentPlanData,
+    formatted: PlanAmounts,
+}
+
+/// A payment plan's amounts as printed
+#[derive(Debug, Serialize)]
+pub struct PlanAmounts {
+    pub principal: String,
+    pub invoice_total: String,
+    /// One per entry of `installments`
+    pub installments: Vec<String>,
+}
+
+/// Wrap payment plan data with its formatted amounts for rendering
+pub fn plan_payload(plan: &PaymentPlanData) -> PlanPayload<'_> {
+    let money = MoneyFormat {
+        symbol: &plan.currency_symbol,
+        position: plan.symbol_position,
+        space: plan.symbol_space,
+    };
+    PlanPayload {
+        data: plan,
+        formatted: PlanAmounts {
+            principal: money.format(plan.principal),
+            invoice_total: money.format(plan.invoice_total),
+            installments: plan
+                .installments
+                .iter()
+                .map(|row| money.format(row.amount))
+                .collect(),
+        },
+    }
+}
+
+/// Statement data with its amounts formatted
+#[derive(Debug, Serialize)]
+pub struct StatementPayload<'a> {
+    #[serde(flatten)]
+    data: &'a StatementData,
+    formatted: StatementAmounts,
+}
+
+/// A statement's amounts as printed
+#[derive(Debug, Serialize)]
+pub struct StatementAmounts {
+    pub total_due: String,
+    /// One per entry of `rows`
+    pub rows: Vec<RowAmounts>,
+}
+
+/// Wrap statement data with its formatted amounts for rendering
+pub fn statement_payload(statement: &StatementData) -> StatementPayload<'_> {
+    let money = MoneyFormat {
+        symbol: &statement.currency_symbol,
+        position: statement.symbol_position,
+        space: statement.symbol_space,
+    };
+    StatementPayload {
+        data: statement,
+        formatted: StatementAmounts {
+            total_due: money.format(statement.total_due),
+            rows: statement
+                .rows
+                .iter()
+                .map(|row| RowAmounts {
+                    total: money.format(row.total),
+                    paid: money.format(row.paid),
+                    outstanding: money.format(row.outstanding),
+                    payments: Vec::new(),
+                })
+                .collect(),
+        },
+    }
+}
+
+/// Aging report data with its amounts formatted
+#[derive(Debug, Serialize)]
+pub struct AgingPayload<'a> {
+    #[serde(flatten)]
+    data: &'a AgingReport,
+    formatted: AgingAmounts,
+}
+
+/// An aging report's amounts as printed
+#[derive(Debug, Serialize)]
+pub struct AgingAmounts {
+    pub totals: AgingColumnAmounts,
+    /// One per entry of `rows`
+    pub rows: Vec<AgingColumnAmounts>,
+}
+
+/// A line of aging columns as printed
+#[derive(Debug, Serialize)]
+pub struct AgingColumnAmounts {
+    pub days_0_30: String,
+    pub days_31_60: String,
+    pub days_61_90: String,
+    pub over_90: String,
+    pub total: String,
+}
+
+/// Wrap aging report data with its formatted amounts for rendering
+pub fn aging_payload(report: &AgingReport) -> AgingPayload<'_> {
+    let money = MoneyFormat {
+        symbol: &report.currency_symbol,
+        position: report.symbol_position,
+        space: report.symbol_space,
+    };
+    let columns = |columns: &AgingColumns| AgingColumnAmounts {
+        days_0_30: money.format(columns.days_0_30),
+        days_31_60: money.format(columns.days_31_60),
+        days_61_90: money.format(columns.days_61_90),
+        over_90: money.format(columns.over_90),
+        total: money.format(columns.total),
+    };
+    AgingPayload {
+        data: report,
+        formatted: AgingAmounts {
+            totals: columns(&report.totals),
+            rows: report
+                .rows
+                .iter()
+                .map(|row| columns(&row.columns))
+                .collect(),
+        },
+    }
+}
+
+/// A problem found in a template's use of the data contract
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateWarning {
+    /// 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+/// Look for `data.<field>` and `data.at("<field>")` references to fields
+/// that are deprecated or not part of the invoice data contract
+pub fn check_template(source: &str) -> Vec<TemplateWarning> {
+    let known = contract_fields();
+    let mut warnings = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        for field in referenced_fields(line) {
+            let message =
+                if let Some(deprecated) = DEPRECATED_FIELDS.iter().find(|d| d.name == field) {
+                    format!(
+                        "data.{} is deprecated since schema {}; use data.{}",
+                        deprecated.name, deprecated.since, deprecated.replacement
+                    )
+                } else if !known.iter().any(|k| k == field) {
+                    format!("data.{field} is not part of the invoice data contract")
+                } else {
+                    continue;
+                };
+            warnings.push(TemplateWarning {
+                line: i + 1,
+                message,
+            });
+        }
+    }
+
+    warnings
+}
+
+/// Top-level field names of the current invoice payload
+fn contract_fields() -> Vec<String> {
+    let sample = InvoiceBuilder::new("", Company::default(), Client::default())
+        .item("", 1.0, "", 0.0)
+        .build()
+        .ok()
+        .and_then(|data| serde_json::to_value(template_payload(&data)).ok());
+    match sample {
+        Some(Value::Object(fields)) => fields.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Field names following `data.` or `data.at("` on a line
+fn referenced_fields(line: &str) -> Vec<&str> {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+    let mut fields = Vec::new();
+
+    for (start, _) in line.match_indices("data.") {
+        // Skip longer names that merely end in "data" (e.g., `metadata.`)
+        let preceded = line[..start].chars().next_back().is_some_and(is_ident);
+        if preceded {
+            continue;
+        }
+        let rest = &line[start + "data.".len()..];
+        let rest = rest
+            .strip_prefix("at(\"")
+            .or_else(|| rest.strip_prefix("at(\'"))
+            .unwrap_or(rest);
+        let end = rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len());
+        if end > 0 && &rest[..end] != "at" {
+            fields.push(&rest[..end]);
+        }
+    }
+
+    fields
+}
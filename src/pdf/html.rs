@@ -0,0 +1,320 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::error::{InvoiceError, Result};
+use crate::invoice::InvoiceData;
+use crate::money::{format_amount, MoneyFormat};
+
+/// Escape text for safe inclusion in HTML
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Built-in HTML invoice. A `templates/invoice.html` in the config
+/// directory replaces it; see docs/templates.md for the placeholders.
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="{{lang}}">
+<head>
+<meta charset="utf-8">
+<title>Invoice {{number}}</title>
+<style>
+  body { font-family: Helvetica, Arial, sans-serif; font-size: 14px; max-width: 800px; margin: 2em auto; color: #222; }
+  header, .parties { display: flex; justify-content: space-between; }
+  h1 { margin: 0; }
+  table { border-collapse: collapse; }
+  .items { width: 100%; margin: 2em 0 1em; }
+  .items th { background: #f0f0f0; border-bottom: 1px solid #000; text-align: left; }
+  .items th, .items td { padding: 8px; border-bottom: 1px solid #ccc; }
+  .num { text-align: right; }
+  .totals { margin-left: auto; }
+  .totals td { padding: 4px 8px; }
+  .total td { font-weight: bold; border-top: 1px solid #000; }
+  .fields { margin-top: 1em; }
+  .fields td { padding: 2px 8px 2px 0; }
+  .muted { color: #888; font-size: 12px; }
+  .notes { margin-top: 1.5em; }
+</style>
+</head>
+<body>
+<header>
+  <div>
+    <h2>{{company_name}}</h2>
+    {{company_address}}<br>{{company_city}}, {{company_state}} {{company_zip}}<br>{{company_email}}
+  </div>
+  <div class="num">
+    <h1>INVOICE</h1>
+    {{display_number}}<strong>Invoice #:</strong> {{number}}<br>
+    <strong>Date:</strong> {{date}}<br>
+    <strong>Due Date:</strong> {{due_date}}
+  </div>
+</header>
+<hr>
+<div class="parties">
+  <section>
+    <strong>Bill To:</strong><br>
+    <strong>{{client_name}}</strong><br>
+    {{contact}}{{client_address}}<br>{{client_city}}, {{client_state}} {{client_zip}}{{client_country}}<br>{{client_email}}{{client_tax_id}}
+  </section>
+  <section class="num">{{period}}</section>
+</div>
+{{fields}}
+<table class="items">
+<tr><th>#</th><th>Description</th><th class="num">Qty</th><th class="num">Rate</th><th class="num">Amount</th></tr>
+{{items}}</table>
+<table class="totals">
+{{totals}}</table>
+{{memo}}
+{{terms}}
+{{tax_id}}
+</body>
+</html>
+"#;
+
+/// Render invoice data as a standalone HTML document using `template`
+/// (the built-in one when None)
+pub fn render_html(data: &InvoiceData, template: Option<&str>) -> String {
+    let template = template.unwrap_or(HTML_TEMPLATE);
+    let format = MoneyFormat {
+        symbol: &data.currency_symbol,
+        position: data.symbol_position,
+        space: data.symbol_space,
+    };
+    let money = |amount: f64| escape(&format.format(amount));
+
+    let mut rows = String::new();
+    let grouped = data
+        .items
+        .iter()
+        .any(|item| item.kind != data.items[0].kind);
+    let mut last = None;
+    for (i, item) in data.items.iter().enumerate() {
+        if grouped && last != Some(item.kind) {
+            let _ = writeln!(
+                rows,
+                "<tr class=\"group\"><th colspan=\"5\">{}</th></tr>",
+                item.kind.label()
+            );
+        }
+        last = Some(item.kind);
+        let mut description = escape(&item.description);
+        if let Some(period) = &item.period {
+            let _ = write!(description, "<br><small>{}</small>", escape(period));
+        }
+        if let Some(original) = &item.original {
+            let _ = write!(
+                description,
+                "<br><small>{} {} at {}</small>",
+                escape(&original.currency),
+                format_amount(original.amount),
+                original.exchange_rate
+            );
+        }
+        let _ = writeln!(
+            rows,
+            "<tr><td>{}</td><td>{}</td><td class=\"num\">{} {}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            i + 1,
+            description,
+            item.quantity,
+            escape(&item.unit_label()),
+            money(item.rate),
+            money(item.amount),
+        );
+    }
+
+    let mut totals = format!(
+        "<tr><td>Subtotal:</td><td class=\"num\">{}</td></tr>\n",
+        money(data.subtotal)
+    );
+    if data.discount > 0.0 {
+        let label = if data.discount_percent > 0.0 {
+            format!("Discount ({:.2}%)", data.discount_percent)
+        } else {
+            "Discount".to_string()
+        };
+        let _ = writeln!(
+            totals,
+            "<tr><td>{label}:</td><td class=\"num\">-{}</td></tr>",
+            money(data.discount)
+        );
+    }
+    for tax in data.tax_lines() {
+        let _ = writeln!(
+            totals,
+            "<tr><td>{} ({:.2}%):</td><td class=\"num\">{}</td></tr>",
+            escape(&tax.name),
+            tax.percent,
+            money(tax.amount)
+        );
+    }
+    if data.rounding_adjustment != 0.0 {
+        let _ = writeln!(
+            totals,
+            "<tr><td>Rounding adjustment:</td><td class=\"num\">{}</td></tr>",
+            money(data.rounding_adjustment)
+        );
+    }
+    let _ = writeln!(
+        totals,
+        "<tr class=\"total\"><td>Total:</td><td class=\"num\">{}</td></tr>",
+        money(data.total)
+    );
+    if data.retention > 0.0 {
+        let _ = writeln!(
+            totals,
+            "<tr><td>Retention ({:.2}%):</td><td class=\"num\">-{}</td></tr>",
+            data.retention_percent,
+            money(data.retention)
+        );
+        let _ = writeln!(
+            totals,
+            "<tr class=\"total\"><td>Amount due:</td><td class=\"num\">{}</td></tr>",
+            money(data.total - data.retention)
+        );
+    }
+
+    let company = &data.company;
+    let client = &data.client;
+    let contact = client
+        .contact
+        .as_deref()
+        .map(|c| format!("{}<br>", escape(c)))
+        .unwrap_or_default();
+    let terms = if data.due_days > 0 {
+        format!(
+            "<p><strong>Payment Terms:</strong> {}</p>",
+            escape(&data.payment_terms)
+        )
+    } else {
+        String::new()
+    };
+    let period = data
+        .service_period
+        .as_deref()
+        .map(|p| format!("<strong>Services Rendered:</strong><br>{}", escape(p)))
+        .unwrap_or_default();
+    let display_number = data
+        .display_number
+        .as_deref()
+        .map(|n| format!("<h2>No. {}</h2>\n    ", escape(n)))
+        .unwrap_or_default();
+    let fields = if data.fields.is_empty() {
+        String::new()
+    } else {
+        let rows: String = data
+            .fields
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "<tr><td><strong>{}:</strong></td><td>{}</td></tr>\n",
+                    escape(name),
+                    escape(value)
+                )
+            })
+            .collect();
+        format!("<table class=\"fields\">\n{rows}</table>")
+    };
+    let tax_id = company
+        .tax_id
+        .as_deref()
+        .map(|t| format!("<p class=\"muted\">Tax ID: {}</p>", escape(t)))
+        .unwrap_or_default();
+    let client_country = client
+        .country
+        .as_deref()
+        .map(|c| format!("<br>{}", escape(c)))
+        .unwrap_or_default();
+    let client_tax_id = client
+        .tax_id
+        .as_deref()
+        .map(|t| format!("<br>VAT ID: {}", escape(t)))
+        .unwrap_or_default();
+    let memo = data
+        .memo
+        .as_deref()
+        .map(|m| {
+            format!(
+                "<section class=\"notes\"><strong>Notes</strong><br>{}</section>",
+                escape(m).replace('\n', "<br>")
+            )
+        })
+        .unwrap_or_default();
+
+    let values = [
+        ("lang", escape(client.language.as_deref().unwrap_or("en"))),
+        ("number", escape(&data.number)),
+        ("display_number", display_number),
+        ("date", escape(&data.date)),
+        ("due_date", escape(&data.due_date)),
+        ("company_name", escape(&company.name)),
+        ("company_address", escape(&company.address)),
+        ("company_city", escape(&company.city)),
+        ("company_state", escape(&company.state)),
+        ("company_zip", escape(&company.zip)),
+        ("company_email", escape(&company.email)),
+        ("client_name", escape(&client.name)),
+        ("contact", contact),
+        ("client_address", escape(&client.address)),
+        ("client_city", escape(&client.city)),
+        ("client_state", escape(&client.state)),
+        ("client_zip", escape(&client.zip)),
+        ("client_country", client_country),
+        ("client_email", escape(&client.email)),
+        ("client_tax_id", client_tax_id),
+        ("period", period),
+        ("fields", fields),
+        ("items", rows),
+        ("totals", totals),
+        ("total", money(data.total)),
+        ("amount_due", money(data.total - data.retention)),
+        ("memo", memo),
+        ("terms", terms),
+        ("tax_id", tax_id),
+    ];
+    fill(template, &values)
+}
+
+/// Replace each `{{name}}` in `template` with its value. Unknown names are
+/// left as they are, so a typo shows up in the output.
+fn fill(template: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+        let name = after[..end].trim();
+        match values.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => out.push_str(value),
+            None => out.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Write invoice data as a standalone HTML file. An `invoice.html` in
+/// `template_dir` replaces the built-in template.
+pub fn generate_html(
+    invoice_data: &InvoiceData,
+    output_path: &Path,
+    template_dir: Option<&Path>,
+) -> Result<()> {
+    let custom = template_dir
+        .map(|dir| dir.join("invoice.html"))
+        .filter(|path| path.is_file());
+    let template = match custom {
+        Some(path) => {
+            Some(std::fs::read_to_string(&path).map_err(|_| InvoiceError::TemplateNotFound(path))?)
+        }
+        None => None,
+    };
+    std::fs::write(output_path, render_html(invoice_data, template.as_deref()))?;
+    Ok(())
+}
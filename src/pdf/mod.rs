@@ -1,3 +1,19 @@
+mod contract;
+mod html;
+mod output;
 mod typst;
 
-pub use typst::{generate_pdf, generate_report_pdf};
+pub use contract::{
+    aging_payload, check_template, plan_payload, report_payload, statement_payload,
+    template_payload, AgingAmounts, AgingColumnAmounts, AgingPayload, DeprecatedField,
+    GroupAmounts, InvoiceAmounts, LineAmounts, PlanAmounts, PlanPayload, ReportAmounts,
+    ReportPayload, RowAmounts, StatementAmounts, StatementPayload, TemplatePayload,
+    TemplateWarning, DEPRECATED_FIELDS, SCHEMA_VERSION,
+};
+pub(crate) use html::escape as escape_html;
+pub use output::{render_artifacts, OutputFormat};
+pub use typst::{
+    debug_artifacts, debug_dir, generate_aging_pdf, generate_payment_plan_pdf, generate_pdf,
+    generate_pdf_from_data, generate_report_pdf, generate_statement_pdf, typst_version,
+    RenderOptions,
+};
@@ -0,0 +1,106 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{InvoiceError, Result};
+use crate::invoice::InvoiceData;
+
+use super::html::generate_html;
+use super::typst::{generate_pdf, RenderOptions};
+
+/// An artifact format that can be produced from `InvoiceData`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Pdf,
+    Html,
+    Json,
+}
+
+impl OutputFormat {
+    /// File extension used for this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Pdf => "pdf",
+            OutputFormat::Html => "html",
+            OutputFormat::Json => "json",
+        }
+    }
+
+    /// Determine the format of a stored artifact from its file name
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let ext = Path::new(name).extension()?.to_str()?;
+        ext.parse().ok()
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "pdf" => Ok(OutputFormat::Pdf),
+            "html" => Ok(OutputFormat::Html),
+            "json" => Ok(OutputFormat::Json),
+            other => Err(format!(
+                "unknown format '{other}' (expected pdf, html, or json)"
+            )),
+        }
+    }
+}
+
+impl Serialize for OutputFormat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.extension())
+    }
+}
+
+impl<'de> Deserialize<'de> for OutputFormat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Render every requested format from the same invoice data. Each artifact
+/// is written next to `base_path` with the extension swapped for its format.
+/// An empty format list means PDF only.
+pub fn render_artifacts(
+    invoice_data: &InvoiceData,
+    base_path: &Path,
+    options: &RenderOptions,
+) -> Result<Vec<PathBuf>> {
+    let formats = if options.formats.is_empty() {
+        vec![OutputFormat::Pdf]
+    } else {
+        options.formats.clone()
+    };
+
+    let mut produced = Vec::new();
+    for format in formats {
+        let path = base_path.with_extension(format.extension());
+        match format {
+            OutputFormat::Pdf => generate_pdf(invoice_data, &path, options)?,
+            OutputFormat::Html => {
+                generate_html(invoice_data, &path, options.template_dir.as_deref())?
+            }
+            OutputFormat::Json => {
+                let json = serde_json::to_string_pretty(invoice_data)
+                    .map_err(|e| InvoiceError::PdfGeneration(e.to_string()))?;
+                std::fs::write(&path, json)?;
+            }
+        }
+        if !produced.contains(&path) {
+            produced.push(path);
+        }
+    }
+
+    Ok(produced)
+}
@@ -1,27 +1,228 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::error::{InvoiceError, Result};
-use crate::invoice::{InvoiceData, ReportData};
+use crate::invoice::{AgingReport, InvoiceData, PaymentPlanData, ReportData, StatementData};
+
+use super::{
+    aging_payload, check_template, plan_payload, report_payload, statement_payload,
+    template_payload, OutputFormat,
+};
 
 /// Embedded Typst template for invoice generation
-/// Uses a placeholder that gets replaced with the actual JSON file path
+/// Uses a placeholder that gets replaced with the actual JSON file path.
+/// The theme, header, fields and footer come from [`INVOICE_PARTIALS`].
 const INVOICE_TEMPLATE: &str = r##"// Invoice Template
-// Data is loaded from JSON file
+// Data is loaded from JSON file. The theme, header, fields and footer are partials;
+// a file of the same name in templates/ replaces the built-in one.
+
+#import "theme.typ": theme
+#import "header.typ": header
+#import "fields.typ": fields
+#import "footer.typ": footer
 
 #let data = json("DATA_JSON_PATH")
 
-#set page(
-  paper: "us-letter",
-  margin: (top: 1in, bottom: 1in, left: 1in, right: 1in),
-)
+#show: theme
 
-#set text(font: "Helvetica", size: 10pt)
+#header(data)
+
+#v(1.5em)
+
+#fields(data)
+
+// Line items. Long invoices are split into one table per page: each page
+// repeats the header, continuation pages are marked, and the running
+// subtotal is carried forward from page to page.
+#let first-page-rows = 16
+#let page-rows = 26
+
+// Table lines (cells and the running subtotal), under a heading per kind
+// when the invoice mixes kinds. Amounts come formatted in data.formatted.
+#let lines = {
+  let kind-of(item) = item.at("kind", default: "service")
+  let labels = (service: "Services", product: "Products", expense: "Reimbursable Expenses")
+  let grouped = data.items.map(kind-of).dedup().len() > 1
+  let lines = ()
+  let last = none
+  let running = none
+  for (i, item) in data.items.enumerate() {
+    let amounts = data.formatted.items.at(i)
+    if grouped and kind-of(item) != last {
+      lines.push((cells: (table.cell(colspan: 5)[*#labels.at(kind-of(item))*],), running: running))
+    }
+    last = kind-of(item)
+    running = amounts.running
+    lines.push((
+      cells: (
+        str(i + 1),
+        {
+          item.description
+          let period = item.at("period", default: none)
+          if period != none {
+            linebreak()
+            text(size: 8pt, fill: gray)[#period]
+          }
+          // Items priced in another currency note the quoted amount and rate
+          let original = item.at("original", default: none)
+          if original != none {
+            linebreak()
+            text(size: 8pt, fill: gray)[#original.currency #amounts.original at #original.exchange_rate]
+          }
+        },
+        [#item.quantity #if item.quantity == 1 { item.unit } else { item.unit + "s" }],
+        [#amounts.rate],
+        [#amounts.amount],
+      ),
+      running: running,
+    ))
+  }
+  lines
+}
+
+#let pages = {
+  let pages = ()
+  let rest = lines
+  let size = first-page-rows
+  while rest.len() > 0 {
+    let n = calc.min(size, rest.len())
+    pages.push(rest.slice(0, n))
+    rest = rest.slice(n)
+    size = page-rows
+  }
+  pages
+}
+
+// Running subtotal at the end of each page
+#let carried = pages.map(chunk => chunk.last().running)
+
+#for (p, chunk) in pages.enumerate() {
+  if p > 0 {
+    pagebreak()
+    align(right, text(size: 9pt, fill: gray)[Invoice #data.number (continued)])
+  }
+  table(
+    columns: (auto, 1fr, auto, auto, auto),
+    align: (center, left, right, right, right),
+    stroke: (x, y) => if y == 0 { (bottom: 1pt + black) } else if y > 0 { (bottom: 0.5pt + gray) },
+    inset: 8pt,
+    fill: (x, y) => if y == 0 { luma(240) } else { none },
+
+    // Repeats if a page's rows still overflow (e.g., very long descriptions)
+    table.header([*\#*], [*Description*], [*Qty*], [*Rate*], [*Amount*]),
+
+    ..if p > 0 {
+      (table.cell(colspan: 4, align: right)[_Carried forward_], [_#carried.at(p - 1)_])
+    } else {
+      ()
+    },
+
+    ..chunk.map(row => row.cells).flatten(),
+
+    ..if p < pages.len() - 1 {
+      (
+        table.cell(colspan: 4, align: right)[_Subtotal, continued on next page_],
+        [_#carried.at(p)_],
+      )
+    } else {
+      ()
+    },
+  )
+}
+
+#v(1em)
+
+// Totals, kept together so they never split across pages
+#block(breakable: false, width: 100%)[#align(right)[
+  #table(
+    columns: (auto, auto),
+    stroke: none,
+    align: (right, right),
+    inset: 6pt,
+
+    [Subtotal:], [#data.formatted.subtotal],
+
+    ..if data.at("discount", default: 0) > 0 {
+      if data.discount_percent > 0 {
+        ([Discount (#str(calc.round(data.discount_percent, digits: 2))%):], [-#data.formatted.discount])
+      } else {
+        ([Discount:], [-#data.formatted.discount])
+      }
+    } else {
+      ()
+    },
+
+    // Each tax on its own row; older data has only the combined tax
+    ..if data.at("taxes", default: ()).len() > 0 {
+      data.taxes.zip(data.formatted.taxes).map(((tax, amount)) => (
+        [#tax.name (#str(calc.round(tax.percent, digits: 2))%):], [#amount],
+      )).flatten()
+    } else if data.tax_percent > 0 {
+      ([Tax (#str(calc.round(data.tax_percent, digits: 2))%):], [#data.formatted.tax_amount])
+    } else {
+      ()
+    },
+
+    ..if data.rounding_adjustment != 0 {
+      ([Rounding adjustment:], [#data.formatted.rounding_adjustment])
+    } else {
+      ()
+    },
+
+    table.hline(stroke: 1pt),
+    [*Total:*], [*#data.formatted.total*],
+
+    ..if data.at("retention", default: 0) > 0 {
+      (
+        [Retention (#str(calc.round(data.retention_percent, digits: 2))%):], [-#data.formatted.retention],
+        [*Amount due:*], [*#data.formatted.amount_due*],
+      )
+    } else {
+      ()
+    },
+  )
+]]
+
+// Free-form note from `generate --note`
+#if data.at("memo", default: none) != none [
+  #v(1.5em)
+  *Notes* \
+  #data.memo
+]
+
+#v(2em)
+
+#footer(data)
+"##;
+
+/// Page setup and fonts shared by the invoice partials
+const THEME_PARTIAL: &str = r##"// Invoice theme: page setup and fonts. Apply it with `#show: theme`.
+
+#let data = json("DATA_JSON_PATH")
+
+#let theme(body) = {
+  set page(
+    paper: "us-letter",
+    margin: (top: 1in, bottom: 1in, left: 1in, right: 1in),
+  )
+  set text(font: "Helvetica", size: 10pt)
+  set text(lang: data.client.at("language", default: "en"))
+  body
+}
 
+// Deprecated: amounts come formatted in data.formatted. These remain for
+// custom templates that still import them.
 #let fmt-int(digits) = {
-  let len = digits.len()
+  // Keep a leading minus out of the grouping ("-100", not "-,100")
+  let clusters = digits.clusters()
   let out = ""
-  for (i, digit) in digits.clusters().enumerate() {
+  if clusters.len() > 0 and clusters.first() in ("-", "\u{2212}") {
+    out = clusters.first()
+    clusters = clusters.slice(1)
+  }
+  let len = clusters.len()
+  for (i, digit) in clusters.enumerate() {
     if i > 0 and calc.rem(len - i, 3) == 0 {
       out += ","
     }
@@ -30,125 +231,442 @@ const INVOICE_TEMPLATE: &str = r##"// Invoice Template
   out
 }
 
-#let fmt-currency(amount) = {
+#let with-symbol(number) = {
+  let space = if data.at("symbol_space", default: false) { "\u{a0}" } else { "" }
+  if data.at("symbol_position", default: "prefix") == "suffix" {
+    number + space + data.currency_symbol
+  } else {
+    data.currency_symbol + space + number
+  }
+}
+
+#let fmt-number(amount) = {
   let parts = str(calc.round(amount, digits: 2)).split(".")
   let whole = fmt-int(parts.at(0))
   let frac = if parts.len() > 1 { parts.at(1) } else { "00" }
   let frac2 = if frac.len() == 1 { frac + "0" } else { frac }
-  data.currency_symbol + whole + "." + frac2
+  whole + "." + frac2
 }
 
-// Header with company info and invoice details
-#grid(
-  columns: (1fr, 1fr),
-  align: (left, right),
-  [
-    #text(size: 18pt, weight: "bold")[#data.company.name]
-    #v(0.3em)
-    #data.company.address \
-    #data.company.city, #data.company.state #data.company.zip \
-    #data.company.email
-    #if data.company.phone != none [
-      \ #data.company.phone
+#let fmt-currency(amount) = with-symbol(fmt-number(amount))
+"##;
+
+/// Company details, invoice number and dates, and the Bill To block
+const HEADER_PARTIAL: &str = r##"// Invoice header: company, invoice details and the client
+
+#let header(data) = [
+  // Header with company info and invoice details
+  #grid(
+    columns: (1fr, 1fr),
+    align: (left, right),
+    [
+      #text(size: 18pt, weight: "bold")[#data.company.name]
+      #v(0.3em)
+      #data.company.address \
+      #data.company.city, #data.company.state #data.company.zip \
+      #data.company.email
+      #if data.company.phone != none [
+        \ #data.company.phone
+      ]
+    ],
+    [
+      #text(size: 24pt, weight: "bold")[INVOICE]
+      #if data.at("display_number", default: none) != none [
+        \ #text(size: 14pt, weight: "bold")[No. #data.display_number]
+      ]
+      #v(0.5em)
+      #table(
+        columns: (auto, auto),
+        stroke: none,
+        align: (right, left),
+        inset: 2pt,
+        [*Invoice \#:*], [#data.number],
+        [*Date:*], [#data.date],
+        [*Due Date:*], [#data.due_date],
+      )
     ]
-  ],
-  [
-    #text(size: 24pt, weight: "bold")[INVOICE]
-    #v(0.5em)
-    #table(
+  )
+
+  #v(1em)
+  #line(length: 100%, stroke: 0.5pt + gray)
+  #v(1em)
+
+  // Bill To section
+  #grid(
+    columns: (1fr, 1fr),
+    [
+      #text(weight: "bold", size: 11pt)[Bill To:]
+      #v(0.3em)
+      #text(weight: "bold")[#data.client.name]
+      #if data.client.contact != none [
+        \ #data.client.contact
+      ]
+      \ #data.client.address
+      \ #data.client.city, #data.client.state #data.client.zip
+      #if data.client.country != none [
+        \ #data.client.country
+      ]
+      \ #data.client.email
+      #if data.client.at("tax_id", default: none) != none [
+        \ VAT ID: #data.client.tax_id
+      ]
+    ],
+    [
+      #if data.service_period != none [
+        #text(weight: "bold", size: 11pt)[Services Rendered:]
+        #v(0.3em)
+        #data.service_period
+      ]
+    ]
+  )
+]
+"##;
+
+/// Custom fields between the client details and the line items
+const FIELDS_PARTIAL: &str = r##"// Custom fields: the client's `fields` and `generate --field` values
+
+#let fields(data) = {
+  let fields = data.at("fields", default: (:))
+  if fields.len() > 0 {
+    table(
       columns: (auto, auto),
       stroke: none,
-      align: (right, left),
       inset: 2pt,
-      [*Invoice \#:*], [#data.number],
-      [*Date:*], [#data.date],
-      [*Due Date:*], [#data.due_date],
+      ..fields.pairs().map(((name, value)) => ([*#name:*], [#value])).flatten(),
     )
-  ]
-)
+    v(1em)
+  }
+}
+"##;
 
-#v(1em)
-#line(length: 100%, stroke: 0.5pt + gray)
-#v(1em)
+/// Payment terms and tax ID below the totals
+const FOOTER_PARTIAL: &str = r##"// Invoice footer: payment terms, alternate payee and tax ID
 
-// Bill To section
-#grid(
-  columns: (1fr, 1fr),
-  [
-    #text(weight: "bold", size: 11pt)[Bill To:]
-    #v(0.3em)
-    #text(weight: "bold")[#data.client.name]
-    #if data.client.contact != none [
-      \ #data.client.contact
+#let footer(data) = [
+  // Payment terms (only show if due_days > 0)
+  #if data.due_days > 0 [
+    #text(weight: "bold")[Payment Terms:] #data.payment_terms
+  ]
+
+  // Remittance details when someone else collects payment (e.g., a factor)
+  #let payee = data.at("payee", default: none)
+  #if payee != none [
+    #v(0.5em)
+    #block(stroke: 0.5pt + gray, inset: 8pt, width: 100%)[
+      #if payee.notice != none [#payee.notice \ ]
+      #text(weight: "bold")[Remit payment to:] #payee.name
+      #if payee.link != none [
+        \ Online: #link(payee.link)
+      ]
+      #if payee.bank_details != none [
+        \ #payee.bank_details.split("\n").join(linebreak())
+      ]
     ]
-    \ #data.client.address
-    \ #data.client.city, #data.client.state #data.client.zip
-    \ #data.client.email
-  ],
-  []
-)
+  ]
 
-#v(1.5em)
+  #if data.company.tax_id != none [
+    #v(0.5em)
+    #text(size: 9pt, fill: gray)[Tax ID: #data.company.tax_id]
+  ]
+]
+"##;
 
-// Line items table
-#table(
-  columns: (auto, 1fr, auto, auto, auto),
-  align: (center, left, right, right, right),
-  stroke: (x, y) => if y == 0 { (bottom: 1pt + black) } else if y > 0 { (bottom: 0.5pt + gray) },
-  inset: 8pt,
-  fill: (x, y) => if y == 0 { luma(240) } else { none },
+/// Partials the built-in invoice template imports; a file of the same name
+/// in the templates directory replaces each one
+const INVOICE_PARTIALS: &[(&str, &str)] = &[
+    ("theme.typ", THEME_PARTIAL),
+    ("header.typ", HEADER_PARTIAL),
+    ("fields.typ", FIELDS_PARTIAL),
+    ("footer.typ", FOOTER_PARTIAL),
+];
 
-  // Header
-  [*\#*], [*Description*], [*Qty*], [*Rate*], [*Amount*],
-
-  // Items
-  ..data.items.enumerate().map(((i, item)) => (
-    str(i + 1),
-    item.description,
-    [#item.quantity #if item.quantity == 1 { item.unit } else { item.unit + "s" }],
-    [#fmt-currency(item.rate)],
-    [#fmt-currency(item.amount)],
-  )).flatten()
-)
+/// Render-time choices that don't belong in the invoice data itself
+#[derive(Debug, Default, Clone)]
+pub struct RenderOptions {
+    /// Typst file rendered as a letter page before the invoice. It receives
+    /// the same data contract (`json("DATA_JSON_PATH")`) as the invoice template.
+    pub cover_letter: Option<PathBuf>,
+    /// Artifacts to produce (default: PDF only)
+    pub formats: Vec<OutputFormat>,
+    /// Pin the PDF creation date to the invoice date so unchanged data
+    /// always renders a byte-identical file
+    pub deterministic: bool,
+    /// Keep the generated Typst sources and data.json in a `debug/<number>/`
+    /// directory next to the output, whether or not compilation succeeds
+    pub debug: bool,
+    /// Directory whose `invoice.typ` and partials (`theme.typ`,
+    /// `header.typ`, `fields.typ`, `footer.typ`), when present, replace the built-in ones
+    /// (normally the config dir's `templates/`)
+    pub template_dir: Option<PathBuf>,
+    /// Fail instead of rendering with questionable input: custom templates
+    /// reading deprecated or unknown fields, Typst warnings, and (checked
+    /// by the generator) a missing client country or a rounding adjustment
+    pub strict: bool,
+}
 
-#v(1em)
+/// Fresh temp directory for one compile, so concurrent renders in the same
+/// process (e.g., parallel tests) don't overwrite each other's data.json
+fn scratch_dir() -> Result<PathBuf> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "invoice-cli-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
 
-// Totals
-#align(right)[
-  #table(
-    columns: (auto, auto),
-    stroke: none,
-    align: (right, right),
-    inset: 6pt,
+/// Per-invoice directory that `--debug` renders leave their sources in
+pub fn debug_dir(output_path: &Path, invoice_number: &str) -> PathBuf {
+    output_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("debug")
+        .join(invoice_number)
+}
 
-    [Subtotal:], [#fmt-currency(data.subtotal)],
+/// Files kept by a `--debug` render of `output_path`, sorted by name
+pub fn debug_artifacts(output_path: &Path, invoice_number: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(debug_dir(output_path, invoice_number))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    files.sort();
+    files
+}
 
-    ..if data.tax_rate > 0 {
-      ([Tax (#str(calc.round(data.tax_rate, digits: 2))%):], [#fmt-currency(data.tax_amount)])
-    } else {
-      ()
-    },
+/// The template `name` from `template_dir` when one was dropped there,
+/// otherwise `built_in`, along with a label for compile errors
+fn select_template(
+    template_dir: Option<&Path>,
+    name: &str,
+    built_in: &str,
+    kind: &str,
+) -> Result<(String, String)> {
+    let custom = template_dir
+        .map(|dir| dir.join(name))
+        .filter(|path| path.is_file());
+    let Some(path) = custom else {
+        return Ok((built_in.to_string(), format!("built-in {kind} template")));
+    };
+    let source =
+        std::fs::read_to_string(&path).map_err(|_| InvoiceError::TemplateNotFound(path.clone()))?;
+    Ok((source, format!("custom {kind} template {}", path.display())))
+}
 
-    table.hline(stroke: 1pt),
-    [*Total:*], [*#fmt-currency(data.total)*],
-  )
-]
+/// Write the built-in `partials` to the scratch directory, then the files
+/// in `template_dir` over them, so a custom `footer.typ` replaces just the
+/// footer and a logo can be referenced by file name. Returns labels for
+/// the partials, for compile errors.
+fn stage_templates(
+    template_dir: Option<&Path>,
+    partials: &[(&'static str, &str)],
+    data_file: &str,
+    scratch: &Path,
+) -> Result<Vec<(&'static str, String)>> {
+    let mut labels = Vec::new();
+    for (name, source) in partials {
+        std::fs::write(
+            scratch.join(name),
+            source.replace("DATA_JSON_PATH", data_file),
+        )?;
+        let kind = name.trim_end_matches(".typ");
+        labels.push((*name, format!("built-in {kind} partial")));
+    }
 
-#v(2em)
+    let Some(dir) = template_dir.filter(|dir| dir.is_dir()) else {
+        return Ok(labels);
+    };
+    for entry in std::fs::read_dir(dir)? {
+        let file = entry?.path();
+        let Some(file_name) = file.file_name().filter(|_| file.is_file()) else {
+            continue;
+        };
+        if file.extension().is_some_and(|ext| ext == "typ") {
+            let source = std::fs::read_to_string(&file)?;
+            std::fs::write(
+                scratch.join(file_name),
+                source.replace("DATA_JSON_PATH", data_file),
+            )?;
+        } else {
+            std::fs::copy(&file, scratch.join(file_name))?;
+        }
+        if let Some((name, label)) = labels.iter_mut().find(|(name, _)| *name == file_name) {
+            let kind = name.trim_end_matches(".typ");
+            *label = format!("custom {kind} partial {}", file.display());
+        }
+    }
+    Ok(labels)
+}
 
-// Payment terms (only show if due_days > 0)
-#if data.due_days > 0 [
-  #text(weight: "bold")[Payment Terms:] #data.payment_terms
-]
+/// Copy the scratch directory's sources to `dir`, replacing an earlier run's.
+/// They reference each other by relative path, so `typst compile` works on
+/// the copies as-is.
+fn keep_debug_files(scratch: &Path, dir: &Path) -> Result<()> {
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir)?;
+    for entry in std::fs::read_dir(scratch)? {
+        let path = entry?.path();
+        if let Some(name) = path.file_name() {
+            std::fs::copy(&path, dir.join(name))?;
+        }
+    }
+    Ok(())
+}
 
-#if data.company.tax_id != none [
-  #v(0.5em)
-  #text(size: 9pt, fill: gray)[Tax ID: #data.company.tax_id]
-]
+/// Errors listed in a compile failure before the rest are elided
+const MAX_COMPILE_ERRORS: usize = 3;
+
+/// Turn Typst's stderr into an error naming the failing template and the
+/// first few errors with their positions. The scratch directory is left in
+/// place so the data file can be inspected.
+///
+/// `sources` maps file names in the scratch directory to descriptions
+/// (e.g., "cover.typ" to the user's cover letter). Errors without a known
+/// location are blamed on the first entry.
+fn compile_failure(stderr: &[u8], data_path: &Path, sources: &[(&str, String)]) -> InvoiceError {
+    let stderr = String::from_utf8_lossy(stderr);
+    let describe = |file: &str| {
+        sources
+            .iter()
+            .find(|(name, _)| *name == file)
+            .map(|(_, label)| label.clone())
+    };
+
+    let mut errors: Vec<String> = Vec::new();
+    let mut template = None;
+    let mut lines = stderr.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some(message) = line.trim_start().strip_prefix("error:") else {
+            continue;
+        };
+        // The location follows on the next line: "  ┌─ invoice.typ:12:5"
+        let location = lines
+            .peek()
+            .and_then(|next| next.split_once("┌─"))
+            .map(|(_, location)| location.trim().to_string());
+        let entry = match location {
+            Some(location) => {
+                let file = Path::new(location.split(':').next().unwrap_or_default())
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                template = template.or_else(|| describe(&file));
+                let position = location.split_once(':').map_or("", |(_, pos)| pos);
+                format!("  {file}:{position}: {}", message.trim())
+            }
+            None => format!("  {}", message.trim()),
+        };
+        errors.push(entry);
+    }
+
+    if errors.is_empty() {
+        // Not in the usual diagnostic format; show the start of it as-is
+        errors = stderr
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| format!("  {line}"))
+            .collect();
+    }
+    let total = errors.len();
+    errors.truncate(MAX_COMPILE_ERRORS);
+    if total > MAX_COMPILE_ERRORS {
+        errors.push(format!("  ... and {} more", total - MAX_COMPILE_ERRORS));
+    }
+
+    InvoiceError::TypstCompile {
+        template: template
+            .or_else(|| sources.first().map(|(_, label)| label.clone()))
+            .unwrap_or_default(),
+        errors: errors.join("\n"),
+        data: data_path.to_path_buf(),
+    }
+}
+
+/// Data contract problems in the custom templates a render would use: the
+/// `.typ` files in `template_dir` and the cover letter
+fn custom_template_warnings(
+    template_dir: Option<&Path>,
+    cover_letter: Option<&Path>,
+) -> Result<Vec<String>> {
+    let mut templates: Vec<PathBuf> = template_dir
+        .filter(|dir| dir.is_dir())
+        .map(std::fs::read_dir)
+        .transpose()?
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "typ"))
+        .collect();
+    templates.sort();
+    templates.extend(cover_letter.map(Path::to_path_buf));
+
+    let mut warnings = Vec::new();
+    for path in templates {
+        let source = std::fs::read_to_string(&path)
+            .map_err(|_| InvoiceError::TemplateNotFound(path.clone()))?;
+        for warning in check_template(&source) {
+            warnings.push(format!(
+                "{}:{}: {}",
+                path.display(),
+                warning.line,
+                warning.message
+            ));
+        }
+    }
+    Ok(warnings)
+}
+
+/// The warnings in Typst's stderr, with their positions when given
+fn typst_warnings(stderr: &[u8]) -> Vec<String> {
+    let stderr = String::from_utf8_lossy(stderr);
+    let mut warnings = Vec::new();
+    let mut lines = stderr.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some(message) = line.trim_start().strip_prefix("warning:") else {
+            continue;
+        };
+        let location = lines
+            .peek()
+            .and_then(|next| next.split_once("┌─"))
+            .map(|(_, location)| location.trim().to_string());
+        warnings.push(match location {
+            Some(location) => format!("typst: {location}: {}", message.trim()),
+            None => format!("typst: {}", message.trim()),
+        });
+    }
+    warnings
+}
+
+/// Unix timestamp (midnight UTC) of the invoice date, used as the PDF creation date
+fn pinned_timestamp(invoice_data: &InvoiceData) -> i64 {
+    chrono::NaiveDate::parse_from_str(&invoice_data.date, "%B %d, %Y")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map_or(0, |dt| dt.and_utc().timestamp())
+}
+
+/// Wrapper document that prepends the cover letter to the invoice
+const COVER_WRAPPER: &str = r##"#set page(paper: "us-letter")
+#include "cover.typ"
+#pagebreak(weak: true)
+#include "invoice.typ"
 "##;
 
+/// Render invoice data built in code (e.g., with `InvoiceBuilder`) to a PDF
+/// with the default render options. No config directory is involved.
+pub fn generate_pdf_from_data(invoice_data: &InvoiceData, output_path: &Path) -> Result<()> {
+    generate_pdf(invoice_data, output_path, &RenderOptions::default())
+}
+
 /// Generate PDF using Typst CLI
-pub fn generate_pdf(invoice_data: &InvoiceData, output_path: &PathBuf) -> Result<()> {
+pub fn generate_pdf(
+    invoice_data: &InvoiceData,
+    output_path: &Path,
+    options: &RenderOptions,
+) -> Result<()> {
     // Check if typst is available
     let typst_check = Command::new("typst").arg("--version").output();
 
@@ -156,12 +674,22 @@ pub fn generate_pdf(invoice_data: &InvoiceData, output_path: &PathBuf) -> Result
         return Err(InvoiceError::TypstNotFound);
     }
 
+    let template_dir = options.template_dir.as_deref();
+    if options.strict {
+        let warnings = custom_template_warnings(template_dir, options.cover_letter.as_deref())?;
+        if !warnings.is_empty() {
+            return Err(InvoiceError::Strict(warnings));
+        }
+    }
+
     // Create temp directory for template
-    let temp_dir = std::env::temp_dir().join(format!("invoice-cli-{}", std::process::id()));
-    std::fs::create_dir_all(&temp_dir)?;
+    let temp_dir = scratch_dir()?;
+    let partials = stage_templates(template_dir, INVOICE_PARTIALS, "data.json", &temp_dir)?;
+    let (template, template_label) =
+        select_template(template_dir, "invoice.typ", INVOICE_TEMPLATE, "invoice")?;
 
     // Serialize invoice data to JSON
-    let json_data = serde_json::to_string(invoice_data)
+    let json_data = serde_json::to_string(&template_payload(invoice_data))
         .map_err(|e| InvoiceError::PdfGeneration(e.to_string()))?;
 
     // Write JSON to temp file
@@ -169,29 +697,74 @@ pub fn generate_pdf(invoice_data: &InvoiceData, output_path: &PathBuf) -> Result
     std::fs::write(&json_path, &json_data)?;
 
     // Write template with relative JSON path (data.json is in same directory)
-    let template_content = INVOICE_TEMPLATE.replace("DATA_JSON_PATH", "data.json");
+    let template_content = template.replace("DATA_JSON_PATH", "data.json");
     let template_path = temp_dir.join("invoice.typ");
     std::fs::write(&template_path, &template_content)?;
 
+    // With a cover letter, compile a wrapper that includes both documents
+    let main_path = match &options.cover_letter {
+        Some(cover) => {
+            let cover_content = std::fs::read_to_string(cover)
+                .map_err(|_| InvoiceError::TemplateNotFound(cover.clone()))?;
+            let cover_path = temp_dir.join("cover.typ");
+            std::fs::write(
+                &cover_path,
+                cover_content.replace("DATA_JSON_PATH", "data.json"),
+            )?;
+            let wrapper_path = temp_dir.join("main.typ");
+            std::fs::write(&wrapper_path, COVER_WRAPPER)?;
+            wrapper_path
+        }
+        None => template_path.clone(),
+    };
+    let mut sources = vec![("invoice.typ", template_label)];
+    sources.extend(partials);
+    if let Some(cover) = &options.cover_letter {
+        sources.push(("cover.typ", format!("cover letter {}", cover.display())));
+    }
+
     // Run typst compile with root set to temp directory
-    let output = Command::new("typst")
-        .args([
-            "compile",
-            "--root",
-            temp_dir.to_str().unwrap(),
-            template_path.to_str().unwrap(),
-            output_path.to_str().unwrap(),
-        ])
-        .output()?;
+    let mut command = Command::new("typst");
+    command.args([
+        "compile",
+        "--root",
+        temp_dir.to_str().unwrap(),
+        main_path.to_str().unwrap(),
+        output_path.to_str().unwrap(),
+    ]);
+    // Typst reads its --creation-timestamp from SOURCE_DATE_EPOCH
+    if options.deterministic {
+        command.env(
+            "SOURCE_DATE_EPOCH",
+            pinned_timestamp(invoice_data).to_string(),
+        );
+    }
+    let output = command.output()?;
+
+    let mut data_path = json_path;
+    if options.debug {
+        let dir = debug_dir(output_path, &invoice_data.number);
+        keep_debug_files(&temp_dir, &dir)?;
+        data_path = dir.join("data.json");
+    }
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(InvoiceError::PdfGeneration(stderr.to_string()));
+        if options.debug {
+            let _ = std::fs::remove_dir_all(&temp_dir);
+        }
+        return Err(compile_failure(&output.stderr, &data_path, &sources));
     }
 
     // Clean up temp files
-    let _ = std::fs::remove_file(&template_path);
-    let _ = std::fs::remove_file(&json_path);
+    let _ = std::fs::remove_dir_all(&temp_dir);
+
+    if options.strict {
+        let warnings = typst_warnings(&output.stderr);
+        if !warnings.is_empty() {
+            let _ = std::fs::remove_file(output_path);
+            return Err(InvoiceError::Strict(warnings));
+        }
+    }
 
     Ok(())
 }
@@ -209,26 +782,6 @@ const REPORT_TEMPLATE: &str = r##"// Invoice Report Template
 
 #set text(font: "Helvetica", size: 10pt)
 
-#let fmt-int(digits) = {
-  let len = digits.len()
-  let out = ""
-  for (i, digit) in digits.clusters().enumerate() {
-    if i > 0 and calc.rem(len - i, 3) == 0 {
-      out += ","
-    }
-    out += digit
-  }
-  out
-}
-
-#let fmt-currency(amount) = {
-  let parts = str(calc.round(amount, digits: 2)).split(".")
-  let whole = fmt-int(parts.at(0))
-  let frac = if parts.len() > 1 { parts.at(1) } else { "00" }
-  let frac2 = if frac.len() == 1 { frac + "0" } else { frac }
-  data.currency_symbol + whole + "." + frac2
-}
-
 // Header with company info and report title
 #grid(
   columns: (1fr, 1fr),
@@ -245,6 +798,9 @@ const REPORT_TEMPLATE: &str = r##"// Invoice Report Template
   ],
   [
     #text(size: 24pt, weight: "bold")[INVOICE REPORT]
+    #if data.at("number", default: none) != none [
+      \ #text(size: 12pt, weight: "bold")[#data.number]
+    ]
     #v(0.5em)
     #text(size: 10pt, fill: gray)[Generated #data.generated_date]
   ]
@@ -283,11 +839,42 @@ const REPORT_TEMPLATE: &str = r##"// Invoice Report Template
         Status: #data.filter_status
       ]
     ]
+    #if data.at("net", default: false) [
+      #v(0.3em)
+      #text(size: 10pt, fill: gray)[Amounts exclude tax]
+    ]
   ]
 )
 
 #v(1.5em)
 
+// Cells for one invoice, plus a payment detail sub-row for PARTIAL invoices.
+// `amounts` is the row's entry in data.formatted.
+#let row-cells(row, amounts) = {
+  let payee = row.at("payee", default: none)
+  let cells = (
+    if payee != none [#row.number \ #text(size: 8pt, fill: gray)[to #payee]] else { row.number },
+    row.date,
+    [#amounts.total],
+    [#amounts.paid],
+    [#amounts.outstanding],
+    row.status,
+  )
+  if row.payments.len() > 0 and row.status == "PARTIAL" {
+    // Append a detail sub-row spanning all columns
+    let details = row.payments.zip(amounts.payments).map(((p, amount)) =>
+      [#h(1em)#sym.arrow.r #amount on #p.date]
+    ).join(linebreak())
+    (..cells, table.cell(colspan: 6, inset: (left: 24pt, top: 2pt, bottom: 6pt, right: 8pt))[
+      #text(size: 8pt, fill: gray)[#details]
+    ])
+  } else {
+    cells
+  }
+}
+
+#let groups = data.at("groups", default: ())
+
 // Invoice table
 #table(
   columns: (auto, 1fr, auto, auto, auto, auto),
@@ -299,28 +886,20 @@ const REPORT_TEMPLATE: &str = r##"// Invoice Report Template
   // Header
   [*Number*], [*Date*], [*Total*], [*Paid*], [*Outstanding*], [*Status*],
 
-  // Rows with optional payment detail sub-rows for PARTIAL invoices
-  ..data.rows.map(row => {
-    let cells = (
-      row.number,
-      row.date,
-      [#fmt-currency(row.total)],
-      [#fmt-currency(row.paid)],
-      [#fmt-currency(row.outstanding)],
-      row.status,
-    )
-    if row.payments.len() > 0 and row.status == "PARTIAL" {
-      // Append a detail sub-row spanning all columns
-      let details = row.payments.map(p =>
-        [#h(1em)#sym.arrow.r #fmt-currency(p.amount) on #p.date]
-      ).join(linebreak())
-      (..cells, table.cell(colspan: 6, inset: (left: 24pt, top: 2pt, bottom: 6pt, right: 8pt))[
-        #text(size: 8pt, fill: gray)[#details]
-      ])
-    } else {
-      cells
-    }
-  }).flatten()
+  // Grouped: a heading per group, its rows, then a subtotal row
+  ..if groups.len() > 0 {
+    groups.zip(data.formatted.groups).map(((group, amounts)) => (
+      table.cell(colspan: 6, fill: luma(248))[*#group.label*],
+      ..group.rows.zip(amounts.rows).map(((row, a)) => row-cells(row, a)).flatten(),
+      table.cell(colspan: 2, align: right)[_Subtotal #group.label:_],
+      [#emph(amounts.total)],
+      [#emph(amounts.paid)],
+      [#emph(amounts.outstanding)],
+      [],
+    )).flatten()
+  } else {
+    data.rows.zip(data.formatted.rows).map(((row, a)) => row-cells(row, a)).flatten()
+  }
 )
 
 #v(1.5em)
@@ -333,11 +912,27 @@ const REPORT_TEMPLATE: &str = r##"// Invoice Report Template
     align: (right, right),
     inset: 6pt,
 
-    [Total:], [#fmt-currency(data.total)],
-    [Paid:], [#fmt-currency(data.paid)],
+    [#if groups.len() > 0 [Grand total:] else [Total:]], [#data.formatted.total],
+    [Paid:], [#data.formatted.paid],
 
     table.hline(stroke: 1pt),
-    [*Outstanding:*], [*#fmt-currency(data.outstanding)*],
+    [*Outstanding:*], [*#data.formatted.outstanding*],
+    ..if data.at("factored_outstanding", default: 0) > 0 {
+      (
+        text(fill: gray)[of which owed to other payees:],
+        text(fill: gray)[#data.formatted.factored_outstanding],
+      )
+    },
+    ..if data.at("converted_outstanding", default: none) != none {
+      let converted = data.converted_outstanding
+      let rates = converted.rates.pairs().map(((code, rate)) => {
+        "1 " + code + " = " + str(calc.round(rate, digits: 2)) + " " + converted.currency
+      })
+      (
+        text(fill: gray)[in #converted.currency (#rates.join(", ")):],
+        text(fill: gray)[#data.formatted.converted_outstanding],
+      )
+    },
   )
 ]
 
@@ -347,8 +942,13 @@ const REPORT_TEMPLATE: &str = r##"// Invoice Report Template
 ]
 "##;
 
-/// Generate a report PDF using Typst CLI
-pub fn generate_report_pdf(report_data: &ReportData, output_path: &PathBuf) -> Result<()> {
+/// Generate a report PDF using Typst CLI. A `report.typ` in `template_dir`
+/// replaces the built-in report template.
+pub fn generate_report_pdf(
+    report_data: &ReportData,
+    output_path: &Path,
+    template_dir: Option<&Path>,
+) -> Result<()> {
     // Check if typst is available
     let typst_check = Command::new("typst").arg("--version").output();
 
@@ -357,11 +957,13 @@ pub fn generate_report_pdf(report_data: &ReportData, output_path: &PathBuf) -> R
     }
 
     // Create temp directory for template
-    let temp_dir = std::env::temp_dir().join(format!("invoice-cli-{}", std::process::id()));
-    std::fs::create_dir_all(&temp_dir)?;
+    let temp_dir = scratch_dir()?;
+    stage_templates(template_dir, &[], "report_data.json", &temp_dir)?;
+    let (template, template_label) =
+        select_template(template_dir, "report.typ", REPORT_TEMPLATE, "report")?;
 
     // Serialize report data to JSON
-    let json_data = serde_json::to_string(report_data)
+    let json_data = serde_json::to_string(&report_payload(report_data))
         .map_err(|e| InvoiceError::PdfGeneration(e.to_string()))?;
 
     // Write JSON to temp file
@@ -369,7 +971,7 @@ pub fn generate_report_pdf(report_data: &ReportData, output_path: &PathBuf) -> R
     std::fs::write(&json_path, &json_data)?;
 
     // Write template with relative JSON path
-    let template_content = REPORT_TEMPLATE.replace("DATA_JSON_PATH", "report_data.json");
+    let template_content = template.replace("DATA_JSON_PATH", "report_data.json");
     let template_path = temp_dir.join("report.typ");
     std::fs::write(&template_path, &template_content)?;
 
@@ -385,13 +987,406 @@ pub fn generate_report_pdf(report_data: &ReportData, output_path: &PathBuf) -> R
         .output()?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(InvoiceError::PdfGeneration(stderr.to_string()));
+        return Err(compile_failure(
+            &output.stderr,
+            &json_path,
+            &[("report.typ", template_label)],
+        ));
+    }
+
+    // Clean up temp files
+    let _ = std::fs::remove_dir_all(&temp_dir);
+
+    Ok(())
+}
+
+/// Version reported by the installed Typst CLI (e.g., "typst 0.13.1"), if any
+pub fn typst_version() -> Option<String> {
+    let output = Command::new("typst").arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!version.is_empty()).then_some(version)
+}
+
+/// Embedded Typst template for payment plan agreements
+const PAYMENT_PLAN_TEMPLATE: &str = r##"// Payment Plan Agreement Template
+// Data is loaded from JSON file
+
+#let data = json("DATA_JSON_PATH")
+
+#set page(
+  paper: "us-letter",
+  margin: (top: 1in, bottom: 1in, left: 1in, right: 1in),
+)
+
+#set text(font: "Helvetica", size: 10pt)
+
+// Header with company info and document title
+#grid(
+  columns: (1fr, 1fr),
+  align: (left, right),
+  [
+    #text(size: 18pt, weight: "bold")[#data.company.name]
+    #v(0.3em)
+    #data.company.address \
+    #data.company.city, #data.company.state #data.company.zip \
+    #data.company.email
+  ],
+  [
+    #text(size: 20pt, weight: "bold")[PAYMENT PLAN]
+    #v(0.5em)
+    #text(size: 10pt, fill: gray)[Invoice #data.number \ #data.date]
+  ]
+)
+
+#v(1em)
+#line(length: 100%, stroke: 0.5pt + gray)
+#v(1em)
+
+#text(weight: "bold", size: 11pt)[Client:]
+#v(0.3em)
+#text(weight: "bold")[#data.client.name]
+#if data.client.contact != none [
+  \ #data.client.contact
+]
+\ #data.client.address
+\ #data.client.city, #data.client.state #data.client.zip
+
+#v(1.5em)
+
+// Terms
+#data.client.name agrees to pay the outstanding balance of
+*#data.formatted.principal* on invoice #data.number (issued #data.invoice_date,
+total #data.formatted.invoice_total) to #data.company.name in
+#data.installments.len() installments, according to the schedule below.
+
+#v(1em)
+
+#table(
+  columns: (auto, 1fr, auto),
+  align: (left, left, right),
+  stroke: (x, y) => if y == 0 { (bottom: 1pt + black) } else if y > 0 { (bottom: 0.5pt + gray) },
+  inset: 8pt,
+  fill: (x, y) => if y == 0 { luma(240) } else { none },
+
+  [*\#*], [*Due date*], [*Amount*],
+  ..data.installments.zip(data.formatted.installments).map(((row, amount)) => (
+    str(row.index),
+    row.due_date,
+    [#amount],
+  )).flatten(),
+  table.hline(stroke: 1pt),
+  [], [*Total*], [*#data.formatted.principal*],
+)
+
+#v(1em)
+
+Each installment is due on the date shown. Payments are applied to installments
+in order. If an installment remains unpaid past its due date, the full remaining
+balance may become due immediately. Paying ahead of schedule is always allowed.
+
+#v(3em)
+
+#grid(
+  columns: (1fr, 1fr),
+  column-gutter: 2em,
+  [
+    #line(length: 100%, stroke: 0.5pt)
+    #data.company.name
+  ],
+  [
+    #line(length: 100%, stroke: 0.5pt)
+    #data.client.name
+  ],
+)
+"##;
+
+/// Generate a payment plan agreement PDF
+pub fn generate_payment_plan_pdf(plan_data: &PaymentPlanData, output_path: &Path) -> Result<()> {
+    // Check if typst is available
+    let typst_check = Command::new("typst").arg("--version").output();
+
+    if typst_check.is_err() {
+        return Err(InvoiceError::TypstNotFound);
+    }
+
+    // Create temp directory for template
+    let temp_dir = scratch_dir()?;
+
+    // Serialize plan data to JSON
+    let json_data = serde_json::to_string(&plan_payload(plan_data))
+        .map_err(|e| InvoiceError::PdfGeneration(e.to_string()))?;
+
+    let json_path = temp_dir.join("plan_data.json");
+    std::fs::write(&json_path, &json_data)?;
+
+    let template_content = PAYMENT_PLAN_TEMPLATE.replace("DATA_JSON_PATH", "plan_data.json");
+    let template_path = temp_dir.join("plan.typ");
+    std::fs::write(&template_path, &template_content)?;
+
+    let output = Command::new("typst")
+        .args([
+            "compile",
+            "--root",
+            temp_dir.to_str().unwrap(),
+            template_path.to_str().unwrap(),
+            output_path.to_str().unwrap(),
+        ])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(compile_failure(
+            &output.stderr,
+            &json_path,
+            &[("plan.typ", "built-in payment plan template".to_string())],
+        ));
+    }
+
+    // Clean up temp files
+    let _ = std::fs::remove_dir_all(&temp_dir);
+
+    Ok(())
+}
+
+/// Embedded Typst template for the one-page statement of unpaid invoices
+const STATEMENT_TEMPLATE: &str = r##"// Unpaid Invoices Statement Template
+// Data is loaded from JSON file
+
+#let data = json("DATA_JSON_PATH")
+
+#set page(
+  paper: "us-letter",
+  margin: (top: 0.75in, bottom: 0.75in, left: 0.75in, right: 0.75in),
+)
+
+#set text(font: "Helvetica", size: 9pt)
+
+// Header with company info and document title
+#grid(
+  columns: (1fr, 1fr),
+  align: (left, right),
+  [
+    #text(size: 14pt, weight: "bold")[#data.company.name]
+    \ #data.company.email
+  ],
+  [
+    #text(size: 16pt, weight: "bold")[STATEMENT OF UNPAID INVOICES]
+    \ #text(fill: gray)[#data.date]
+  ]
+)
+
+#v(0.5em)
+#line(length: 100%, stroke: 0.5pt + gray)
+#v(0.5em)
+
+*#data.client.name*
+#if data.client.contact != none [ — #data.client.contact]
+
+#v(0.5em)
+
+#table(
+  columns: (auto, auto, auto, 1fr, auto, auto, auto),
+  align: (left, left, left, right, right, right, right),
+  stroke: (x, y) => if y == 0 { (bottom: 1pt + black) } else if y > 0 { (bottom: 0.5pt + gray) },
+  inset: 6pt,
+  fill: (x, y) => if y == 0 { luma(240) } else { none },
+
+  [*Invoice*], [*Date*], [*Due*], [*Days overdue*], [*Total*], [*Paid*], [*Amount due*],
+  ..data.rows.zip(data.formatted.rows).map(((row, amounts)) => (
+    if row.at("payee", default: none) != none [
+      #row.number \ #text(size: 7pt, fill: gray)[pay to #row.payee]
+    ] else { row.number },
+    row.date,
+    row.due_date,
+    if row.days_overdue > 0 {
+      text(fill: rgb("#b00020"))[#row.days_overdue]
+    } else [—],
+    [#amounts.total],
+    [#amounts.paid],
+    [#amounts.outstanding],
+  )).flatten(),
+  table.hline(stroke: 1pt),
+  table.cell(colspan: 6, align: right)[*Total due*], [*#data.formatted.total_due*],
+)
+
+#if data.payment_link != none or data.bank_details != none [
+  #v(1em)
+  #text(weight: "bold")[How to pay]
+  #if data.payment_link != none [
+    \ Online: #link(data.payment_link)
+  ]
+  #if data.bank_details != none [
+    \ #data.bank_details.split("\n").join(linebreak())
+  ]
+]
+"##;
+
+/// Generate the unpaid-invoices statement PDF
+pub fn generate_statement_pdf(statement: &StatementData, output_path: &Path) -> Result<()> {
+    // Check if typst is available
+    let typst_check = Command::new("typst").arg("--version").output();
+
+    if typst_check.is_err() {
+        return Err(InvoiceError::TypstNotFound);
+    }
+
+    // Create temp directory for template
+    let temp_dir = scratch_dir()?;
+
+    let json_data = serde_json::to_string(&statement_payload(statement))
+        .map_err(|e| InvoiceError::PdfGeneration(e.to_string()))?;
+
+    let json_path = temp_dir.join("statement_data.json");
+    std::fs::write(&json_path, &json_data)?;
+
+    let template_content = STATEMENT_TEMPLATE.replace("DATA_JSON_PATH", "statement_data.json");
+    let template_path = temp_dir.join("statement.typ");
+    std::fs::write(&template_path, &template_content)?;
+
+    let output = Command::new("typst")
+        .args([
+            "compile",
+            "--root",
+            temp_dir.to_str().unwrap(),
+            template_path.to_str().unwrap(),
+            output_path.to_str().unwrap(),
+        ])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(compile_failure(
+            &output.stderr,
+            &json_path,
+            &[("statement.typ", "built-in statement template".to_string())],
+        ));
+    }
+
+    // Clean up temp files
+    let _ = std::fs::remove_dir_all(&temp_dir);
+
+    Ok(())
+}
+
+/// Embedded Typst template for the accounts receivable aging report
+const AGING_TEMPLATE: &str = r##"// Accounts Receivable Aging Template
+// Data is loaded from JSON file
+
+#let data = json("DATA_JSON_PATH")
+
+#set page(
+  paper: "us-letter",
+  margin: (top: 0.75in, bottom: 0.75in, left: 0.75in, right: 0.75in),
+)
+
+#set text(font: "Helvetica", size: 9pt)
+
+// Header with company info and document title
+#grid(
+  columns: (1fr, 1fr),
+  align: (left, right),
+  [
+    #text(size: 14pt, weight: "bold")[#data.company.name]
+    \ #data.company.email
+  ],
+  [
+    #text(size: 16pt, weight: "bold")[ACCOUNTS RECEIVABLE AGING]
+    \ #text(fill: gray)[As of #data.date]
+  ]
+)
+
+#v(0.5em)
+#line(length: 100%, stroke: 0.5pt + gray)
+#v(0.5em)
+
+#let late(amount, text-value) = if amount > 0 {
+  text(fill: rgb("#b00020"))[#text-value]
+} else [#text-value]
+
+#table(
+  columns: (1fr, auto, auto, auto, auto, auto, auto),
+  align: (left, right, right, right, right, right, right),
+  stroke: (x, y) => if y == 0 { (bottom: 1pt + black) } else if y > 0 { (bottom: 0.5pt + gray) },
+  inset: 6pt,
+  fill: (x, y) => if y == 0 { luma(240) } else { none },
+
+  [*Client*], [*Invoices*], [*0–30 days*], [*31–60 days*], [*61–90 days*], [*90+ days*], [*Total*],
+  ..data.rows.zip(data.formatted.rows).map(((row, amounts)) => (
+    row.client,
+    str(row.invoices),
+    [#amounts.days_0_30],
+    late(row.days_31_60, amounts.days_31_60),
+    late(row.days_61_90, amounts.days_61_90),
+    late(row.over_90, amounts.over_90),
+    [*#amounts.total*],
+  )).flatten(),
+  table.hline(stroke: 1pt),
+  [*Total*],
+  [*#str(data.rows.map(row => row.invoices).sum())*],
+  [*#data.formatted.totals.days_0_30*],
+  [*#data.formatted.totals.days_31_60*],
+  [*#data.formatted.totals.days_61_90*],
+  [*#data.formatted.totals.over_90*],
+  [*#data.formatted.totals.total*],
+)
+
+#v(0.5em)
+#text(size: 8pt, fill: gray)[
+  Outstanding balances by days past due. Invoices not yet due are counted under 0–30 days.
+]
+"##;
+
+/// Generate the aging report PDF. An `aging.typ` in `template_dir` replaces
+/// the built-in aging template.
+pub fn generate_aging_pdf(
+    report: &AgingReport,
+    output_path: &Path,
+    template_dir: Option<&Path>,
+) -> Result<()> {
+    // Check if typst is available
+    let typst_check = Command::new("typst").arg("--version").output();
+
+    if typst_check.is_err() {
+        return Err(InvoiceError::TypstNotFound);
+    }
+
+    // Create temp directory for template
+    let temp_dir = scratch_dir()?;
+    stage_templates(template_dir, &[], "aging_data.json", &temp_dir)?;
+    let (template, template_label) =
+        select_template(template_dir, "aging.typ", AGING_TEMPLATE, "aging")?;
+
+    let json_data = serde_json::to_string(&aging_payload(report))
+        .map_err(|e| InvoiceError::PdfGeneration(e.to_string()))?;
+
+    let json_path = temp_dir.join("aging_data.json");
+    std::fs::write(&json_path, &json_data)?;
+
+    let template_content = template.replace("DATA_JSON_PATH", "aging_data.json");
+    let template_path = temp_dir.join("aging.typ");
+    std::fs::write(&template_path, &template_content)?;
+
+    let output = Command::new("typst")
+        .args([
+            "compile",
+            "--root",
+            temp_dir.to_str().unwrap(),
+            template_path.to_str().unwrap(),
+            output_path.to_str().unwrap(),
+        ])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(compile_failure(
+            &output.stderr,
+            &json_path,
+            &[("aging.typ", template_label)],
+        ));
     }
 
     // Clean up temp files
-    let _ = std::fs::remove_file(&template_path);
-    let _ = std::fs::remove_file(&json_path);
+    let _ = std::fs::remove_dir_all(&temp_dir);
 
     Ok(())
 }
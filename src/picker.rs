@@ -0,0 +1,69 @@
+//! Choosing interactively from a list (invoices, clients, items) with a
+//! fuzzy finder such as fzf.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::error::{InvoiceError, Result};
+
+/// The fuzzy finder to run: $INVOICE_PICKER, then `fzf`. It may include
+/// arguments and is given fzf's options, so skim (`sk`) works as well.
+pub fn picker_command() -> String {
+    std::env::var("INVOICE_PICKER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "fzf".to_string())
+}
+
+/// Let the user choose one of `lines`, whose tab-separated fields are
+/// searched. `preview` is a shell command run for the highlighted line,
+/// with `{1}` standing for its first field. Returns the chosen line, or
+/// None when the user backs out without choosing.
+pub fn pick(lines: &[String], prompt: &str, preview: Option<&str>) -> Result<Option<String>> {
+    let picker = picker_command();
+    let mut parts = picker.split_whitespace();
+    let program = parts.next().unwrap_or("fzf");
+
+    let mut child = Command::new(program)
+        .args(parts)
+        .args(["--delimiter", "\t", "--prompt", prompt])
+        .args(
+            preview
+                .map(|preview| ["--preview", preview])
+                .into_iter()
+                .flatten(),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| InvoiceError::Picker(format!("could not launch '{picker}': {e}")))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The picker may exit before reading everything, e.g. on Esc
+        let _ = stdin.write_all(lines.join("\n").as_bytes());
+    }
+    let output = child.wait_with_output()?;
+
+    // fzf exits with 1 when nothing matched and 130 when interrupted
+    match output.status.code() {
+        Some(0) => {}
+        Some(1 | 130) => return Ok(None),
+        _ => {
+            return Err(InvoiceError::Picker(format!(
+                "'{picker}' exited with {}",
+                output.status
+            )))
+        }
+    }
+    let chosen = String::from_utf8_lossy(&output.stdout);
+    Ok(chosen
+        .lines()
+        .next()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string))
+}
+
+/// `value` quoted for a POSIX shell, for building preview commands
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
@@ -0,0 +1,106 @@
+//! Progress reporting for commands that work through many invoices.
+//!
+//! On a terminal this draws a progress bar on stderr. Otherwise (pipes, CI,
+//! cron) it writes one logfmt line per item so the output stays greppable:
+//!
+//! ```text
+//! task=regenerate item=INV-2026-0001 status=ok progress=1/3
+//! task=regenerate item=INV-2026-0002 status=error progress=2/3 error="Invoice not found: ..."
+//! ```
+
+use std::fmt::Display;
+use std::io::IsTerminal;
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+pub struct Progress {
+    task: &'static str,
+    total: usize,
+    done: usize,
+    failed: usize,
+    bar: Option<ProgressBar>,
+}
+
+impl Progress {
+    /// Start tracking `total` items for `task` (e.g., "regenerate")
+    pub fn new(task: &'static str, total: usize) -> Self {
+        let bar = std::io::stderr().is_terminal().then(|| {
+            let bar = ProgressBar::new(total as u64);
+            bar.set_style(
+                ProgressStyle::with_template("{prefix} [{bar:30}] {pos}/{len} {msg}")
+                    .expect("valid progress template")
+                    .progress_chars("=> "),
+            );
+            bar.set_prefix(task);
+            bar
+        });
+
+        Self {
+            task,
+            total,
+            done: 0,
+            failed: 0,
+            bar,
+        }
+    }
+
+    /// Record an item that completed
+    pub fn success(&mut self, item: &str) {
+        self.record(item, None);
+    }
+
+    /// Record an item that failed; the error is shown but processing goes on
+    pub fn failure(&mut self, item: &str, error: &dyn Display) {
+        self.failed += 1;
+        self.record(item, Some(error.to_string()));
+    }
+
+    /// Number of items recorded as failed so far
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    fn record(&mut self, item: &str, error: Option<String>) {
+        self.done += 1;
+        match &self.bar {
+            Some(bar) => {
+                if let Some(error) = &error {
+                    bar.println(format!("{item}: {error}"));
+                }
+                bar.set_message(item.to_string());
+                bar.inc(1);
+            }
+            None => {
+                let status = if error.is_some() { "error" } else { "ok" };
+                let mut line = format!(
+                    "task={} item={} status={} progress={}/{}",
+                    self.task,
+                    logfmt_value(item),
+                    status,
+                    self.done,
+                    self.total
+                );
+                if let Some(error) = &error {
+                    line.push_str(&format!(" error={}", logfmt_value(error)));
+                }
+                eprintln!("{line}");
+            }
+        }
+    }
+
+    /// Clear the progress bar (no-op in line mode)
+    pub fn finish(self) {
+        if let Some(bar) = self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+/// Quote a value if it would otherwise break logfmt parsing
+fn logfmt_value(value: &str) -> String {
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '"' || c == '=') {
+        format!("{value:?}")
+    } else {
+        value.to_string()
+    }
+}
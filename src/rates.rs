@@ -0,0 +1,200 @@
+//! Exchange rates: the providers `[currency] provider` selects and the
+//! cache of fetched rates kept in state.toml.
+
+use std::collections::BTreeMap;
+
+use chrono::{Duration, NaiveDateTime};
+use serde::Serialize;
+
+use crate::config::{CachedRate, CurrencySettings, RateProvider};
+
+/// Somewhere to look up exchange rates
+pub trait RateSource {
+    /// Units of `to` for one unit of `from`, or None if unavailable
+    fn rate(&self, from: &str, to: &str) -> Option<f64>;
+}
+
+/// European Central Bank reference rates from the Frankfurter API
+pub struct Frankfurter;
+
+impl RateSource for Frankfurter {
+    fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        fetch_rate(from, to)
+    }
+}
+
+/// Rates listed in config as "FROM/TO" pairs; inverse pairs are implied
+pub struct FixedRates<'a>(pub &'a BTreeMap<String, f64>);
+
+impl RateSource for FixedRates<'_> {
+    fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        let rate = |a: &str, b: &str| {
+            self.0
+                .iter()
+                .find(|(pair, _)| pair.eq_ignore_ascii_case(&pair_key(a, b)))
+                .map(|(_, &rate)| rate)
+                .filter(|&rate| rate > 0.0)
+        };
+        rate(from, to).or_else(|| rate(to, from).map(|rate| 1.0 / rate))
+    }
+}
+
+fn pair_key(from: &str, to: &str) -> String {
+    format!("{}/{}", from.to_uppercase(), to.to_uppercase())
+}
+
+/// Rate lookups through the configured provider. Fetched rates are reused
+/// from `cache` while fresh, stored there when refreshed, and used stale
+/// when the provider can't be reached (unless [`Rates::allow_stale`] is off).
+pub struct Rates<'a> {
+    settings: &'a CurrencySettings,
+    cache: &'a mut BTreeMap<String, CachedRate>,
+    now: NaiveDateTime,
+    updated: bool,
+    allow_stale: bool,
+}
+
+impl<'a> Rates<'a> {
+    pub fn new(
+        settings: &'a CurrencySettings,
+        cache: &'a mut BTreeMap<String, CachedRate>,
+        now: NaiveDateTime,
+    ) -> Self {
+        Self {
+            settings,
+            cache,
+            now,
+            updated: false,
+            allow_stale: true,
+        }
+    }
+
+    /// Whether an expired cached rate stands in when the provider can't be
+    /// reached (default: true)
+    pub fn allow_stale(mut self, allow: bool) -> Self {
+        self.allow_stale = allow;
+        self
+    }
+
+    /// Units of `to` for one unit of `from`
+    pub fn rate(&mut self, from: &str, to: &str) -> Option<f64> {
+        if from.eq_ignore_ascii_case(to) {
+            return Some(1.0);
+        }
+        match self.settings.provider {
+            RateProvider::None => None,
+            RateProvider::Fixed => FixedRates(&self.settings.rates).rate(from, to),
+            RateProvider::Frankfurter => self.cached(&Frankfurter, from, to),
+        }
+    }
+
+    /// `amount` in `from` expressed in `to`
+    pub fn convert(&mut self, amount: f64, from: &str, to: &str) -> Option<f64> {
+        self.rate(from, to).map(|rate| amount * rate)
+    }
+
+    /// The sum of amounts given in various currencies, expressed in `to`.
+    /// None when none of them needs converting or a rate is unavailable.
+    pub fn convert_all<'c>(
+        &mut self,
+        amounts: impl IntoIterator<Item = (&'c str, f64)>,
+        to: &str,
+    ) -> Option<Conversion> {
+        let mut conversion = Conversion {
+            currency: to.to_uppercase(),
+            amount: 0.0,
+            rates: BTreeMap::new(),
+        };
+        for (from, amount) in amounts {
+            let rate = self.rate(from, to)?;
+            if !from.eq_ignore_ascii_case(to) {
+                conversion.rates.insert(from.to_uppercase(), rate);
+            }
+            conversion.amount += amount * rate;
+        }
+        (!conversion.rates.is_empty()).then_some(conversion)
+    }
+
+    /// Whether a fetched rate was added to the cache, which then needs saving
+    pub fn updated(&self) -> bool {
+        self.updated
+    }
+
+    fn cached(&mut self, source: &dyn RateSource, from: &str, to: &str) -> Option<f64> {
+        let (from, to) = (from.to_uppercase(), to.to_uppercase());
+        let key = pair_key(&from, &to);
+        let cached = self.cache.get(&key).copied();
+        let max_age = Duration::hours(i64::from(self.settings.cache_hours));
+        if let Some(cached) = cached.filter(|c| self.now - c.fetched < max_age) {
+            return Some(cached.rate);
+        }
+
+        match source.rate(&from, &to) {
+            Some(rate) => {
+                self.cache.insert(
+                    key,
+                    CachedRate {
+                        rate,
+                        fetched: self.now,
+                    },
+                );
+                self.updated = true;
+                Some(rate)
+            }
+            None => cached.filter(|_| self.allow_stale).map(|c| c.rate),
+        }
+    }
+}
+
+/// An amount converted from one or more currencies
+#[derive(Debug, Clone, Serialize)]
+pub struct Conversion {
+    /// ISO 4217 code the amount is in
+    pub currency: String,
+    pub amount: f64,
+    /// Units of `currency` per unit of each source currency
+    pub rates: BTreeMap<String, f64>,
+}
+
+impl Conversion {
+    /// The rates used, e.g. "1 USD = 5.40 BRL, 1 EUR = 6.12 BRL"
+    pub fn rates_label(&self) -> String {
+        self.rates
+            .iter()
+            .map(|(from, rate)| format!("1 {from} = {rate:.2} {}", self.currency))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Units of `to` for one unit of `from` from Frankfurter. Returns None on
+/// any failure (network, timeout, unknown currency) and always without the
+/// `network` feature, so callers decide whether a missing rate matters.
+#[cfg(feature = "network")]
+pub fn fetch_rate(from: &str, to: &str) -> Option<f64> {
+    use std::time::Duration;
+    use ureq::Agent;
+
+    let agent: Agent = Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(3)))
+        .build()
+        .into();
+
+    let body: String = agent
+        .get(format!(
+            "https://api.frankfurter.dev/v1/latest?base={from}&symbols={to}"
+        ))
+        .call()
+        .ok()?
+        .body_mut()
+        .read_to_string()
+        .ok()?;
+
+    let json: serde_json::Value = serde_json::from_str(&body).ok()?;
+    json["rates"][to].as_f64()
+}
+
+#[cfg(not(feature = "network"))]
+pub fn fetch_rate(_from: &str, _to: &str) -> Option<f64> {
+    None
+}
@@ -0,0 +1,196 @@
+//! Snapshot helpers for testing templates and the data contract they read.
+//!
+//! Enabled with the `test-support` feature. A snapshot is a plain file under
+//! a directory chosen by the test (e.g., `tests/snapshots/invoice_data.snap`).
+//! Run the tests with `UPDATE_SNAPSHOTS=1` to write new or changed snapshots
+//! instead of comparing against them, then review the diff before committing.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::config::{
+    Client, Config, Item, ServicePeriod, CLIENTS_TEMPLATE, CONFIG_TEMPLATE, ITEMS_TEMPLATE,
+};
+use crate::error::{InvoiceError, Result};
+use crate::invoice::{compute_totals, tax_percent, InvoiceData, InvoiceLineItem, Totals};
+use crate::pdf::{generate_pdf, template_payload, RenderOptions, SCHEMA_VERSION};
+
+/// A fixed invoice built from the `init` templates (example client,
+/// 8h consulting and 40h development for January, dated January 15, 2026)
+pub fn sample_invoice() -> InvoiceData {
+    let config: Config = toml::from_str(CONFIG_TEMPLATE).expect("config template parses");
+    let clients: HashMap<String, Client> =
+        toml::from_str(CLIENTS_TEMPLATE).expect("clients template parses");
+    let catalog: HashMap<String, Item> =
+        toml::from_str(ITEMS_TEMPLATE).expect("items template parses");
+
+    let items: Vec<InvoiceLineItem> = [("consulting", 8.0), ("development", 40.0)]
+        .into_iter()
+        .map(|(id, quantity)| {
+            let item = &catalog[id];
+            InvoiceLineItem {
+                description: item.description.clone(),
+                quantity,
+                unit: item.unit.clone(),
+                rate: item.rate,
+                amount: item.rate * quantity,
+                kind: item.kind,
+                original: None,
+                period: None,
+                taxable: item.taxable,
+            }
+        })
+        .collect();
+
+    let Totals {
+        subtotal,
+        discount,
+        tax_amount,
+        taxes,
+        rounding_adjustment,
+        total,
+    } = compute_totals(&items, None, &config.invoice.tax_rates());
+
+    let date = chrono::NaiveDate::from_ymd_opt(2026, 1, 15).expect("valid date");
+    let due = config.invoice.due_date(date);
+
+    InvoiceData {
+        schema_version: SCHEMA_VERSION,
+        number: "INV-2026-0001".to_string(),
+        display_number: None,
+        date: date.format("%B %d, %Y").to_string(),
+        due_date: due.format("%B %d, %Y").to_string(),
+        company: config.company.clone(),
+        client: clients["example-client"].clone(),
+        items,
+        subtotal,
+        discount_percent: 0.0,
+        discount,
+        tax_percent: tax_percent(&config.invoice.tax_rates()),
+        tax_amount,
+        taxes,
+        rounding_adjustment,
+        total,
+        retention_percent: 0.0,
+        retention: 0.0,
+        currency: config.invoice.currency.clone(),
+        currency_symbol: config.invoice.currency_symbol.clone(),
+        symbol_position: config.invoice.symbol_position,
+        symbol_space: config.invoice.symbol_space,
+        due_days: config.invoice.due_days,
+        payment_terms: format!("Net {} days", config.invoice.due_days),
+        service_period: ServicePeriod::month(2026, 1).map(|p| p.to_string()),
+        payee: None,
+        fields: BTreeMap::new(),
+        memo: None,
+    }
+}
+
+/// The JSON the invoice template reads, pretty-printed for readable diffs
+pub fn data_contract(invoice_data: &InvoiceData) -> Result<String> {
+    serde_json::to_string_pretty(&template_payload(invoice_data))
+        .map(|json| json + "\n")
+        .map_err(|e| InvoiceError::PdfGeneration(e.to_string()))
+}
+
+/// Render an invoice to one SVG document per page with Typst.
+/// Rendering is always deterministic so the output is stable across runs.
+pub fn render_svg_pages(
+    invoice_data: &InvoiceData,
+    options: &RenderOptions,
+) -> Result<Vec<String>> {
+    let out_dir = scratch_dir()?;
+    let options = RenderOptions {
+        deterministic: true,
+        ..options.clone()
+    };
+    let result = generate_pdf(invoice_data, &out_dir.join("page-{p}.svg"), &options)
+        .and_then(|()| read_pages(&out_dir));
+    let _ = std::fs::remove_dir_all(&out_dir);
+    result
+}
+
+/// Compare `actual` with the snapshot `<dir>/<name>.snap`, panicking with a
+/// line diff on mismatch. With `UPDATE_SNAPSHOTS` set, writes it instead.
+pub fn assert_snapshot(dir: &Path, name: &str, actual: &str) {
+    let path = dir.join(format!("{name}.snap"));
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(dir).expect("create snapshot dir");
+        std::fs::write(&path, actual).expect("write snapshot");
+        return;
+    }
+
+    let Ok(expected) = std::fs::read_to_string(&path) else {
+        panic!(
+            "Missing snapshot {}; run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        );
+    };
+
+    if expected != actual {
+        panic!(
+            "Snapshot {} does not match:\n{}\nRun with UPDATE_SNAPSHOTS=1 to accept the new output",
+            path.display(),
+            line_diff(&expected, actual)
+        );
+    }
+}
+
+/// Differing lines as `-expected` / `+actual` pairs, with line numbers
+fn line_diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut diff = String::new();
+
+    for i in 0..expected.len().max(actual.len()) {
+        let (old, new) = (expected.get(i), actual.get(i));
+        if old == new {
+            continue;
+        }
+        if let Some(old) = old {
+            diff.push_str(&format!("{:>4} -{}\n", i + 1, old));
+        }
+        if let Some(new) = new {
+            diff.push_str(&format!("{:>4} +{}\n", i + 1, new));
+        }
+    }
+    diff
+}
+
+/// Unique scratch directory for one render
+fn scratch_dir() -> Result<std::path::PathBuf> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "invoice-snapshot-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Read `page-N.svg` files in page order
+fn read_pages(dir: &Path) -> Result<Vec<String>> {
+    let mut pages: Vec<(u32, std::path::PathBuf)> = std::fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let page = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix("page-")?
+                .strip_suffix(".svg")?
+                .parse()
+                .ok()?;
+            Some((page, path))
+        })
+        .collect();
+    pages.sort();
+
+    pages
+        .into_iter()
+        .map(|(_, path)| std::fs::read_to_string(path).map_err(InvoiceError::from))
+        .collect()
+}
@@ -0,0 +1,176 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+use ureq::Agent;
+
+use crate::error::{InvoiceError, Result};
+use crate::invoice::sha256_hex;
+
+/// GitHub API endpoint for the latest release (override with INVOICE_RELEASES_URL)
+const RELEASES_URL: &str = "https://api.github.com/repos/fcoury/invoicing/releases/latest";
+
+/// Largest binary we are willing to download
+const MAX_DOWNLOAD_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Outcome of a self-update run
+#[derive(Debug)]
+pub enum UpdateStatus {
+    /// The running binary is the latest release
+    UpToDate { version: String },
+    /// A newer release exists (reported by `--check`)
+    Available { version: String },
+    /// The running binary was replaced
+    Updated { version: String, path: PathBuf },
+}
+
+/// Release asset name for this platform, e.g. `invoice-x86_64-linux`
+pub fn asset_name() -> String {
+    let ext = if cfg!(windows) { ".exe" } else { "" };
+    format!(
+        "invoice-{}-{}{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        ext
+    )
+}
+
+fn agent() -> Agent {
+    Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(60)))
+        .build()
+        .into()
+}
+
+fn update_error(e: impl std::fmt::Display) -> InvoiceError {
+    InvoiceError::Update(e.to_string())
+}
+
+/// Parse "v1.2.3" / "1.2.3" into comparable numeric parts
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map_while(|p| p.parse().ok())
+        .collect()
+}
+
+fn fetch_latest_release(agent: &Agent) -> Result<Release> {
+    let url = std::env::var("INVOICE_RELEASES_URL").unwrap_or_else(|_| RELEASES_URL.to_string());
+    let body = agent
+        .get(&url)
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", "invoice-cli")
+        .call()
+        .map_err(|e| update_error(format!("could not check for updates: {e}")))?
+        .body_mut()
+        .read_to_string()
+        .map_err(update_error)?;
+
+    serde_json::from_str(&body).map_err(|e| update_error(format!("unexpected release data: {e}")))
+}
+
+fn download(agent: &Agent, url: &str) -> Result<Vec<u8>> {
+    agent
+        .get(url)
+        .header("User-Agent", "invoice-cli")
+        .call()
+        .map_err(|e| update_error(format!("download failed: {e}")))?
+        .body_mut()
+        .with_config()
+        .limit(MAX_DOWNLOAD_BYTES)
+        .read_to_vec()
+        .map_err(update_error)
+}
+
+/// Check the published SHA-256 (`<hex>  <file>` or bare hex) against the download
+fn verify_checksum(bytes: &[u8], checksum_file: &str) -> Result<()> {
+    let expected = checksum_file
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let actual = sha256_hex(bytes);
+
+    if expected != actual {
+        return Err(update_error(format!(
+            "checksum mismatch (expected {expected}, got {actual})"
+        )));
+    }
+    Ok(())
+}
+
+/// Swap the new binary in place of `exe`, keeping the old one until the swap succeeds
+fn replace_executable(exe: &Path, bytes: &[u8]) -> Result<()> {
+    let staged = exe.with_extension("new");
+    let backup = exe.with_extension("old");
+    std::fs::write(&staged, bytes)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    // Renaming the running binary works on every platform, overwriting it doesn't
+    std::fs::rename(exe, &backup)?;
+    if let Err(e) = std::fs::rename(&staged, exe) {
+        let _ = std::fs::rename(&backup, exe);
+        return Err(e.into());
+    }
+    // Windows keeps the running image locked; it's cleaned up on the next update
+    let _ = std::fs::remove_file(&backup);
+    Ok(())
+}
+
+/// Check GitHub for a newer release and, unless `check_only`, download it,
+/// verify its checksum and replace the running executable
+pub fn self_update(check_only: bool) -> Result<UpdateStatus> {
+    let agent = agent();
+    let release = fetch_latest_release(&agent)?;
+    let latest = release.tag_name.trim_start_matches('v').to_string();
+
+    if version_parts(&latest) <= version_parts(env!("CARGO_PKG_VERSION")) {
+        return Ok(UpdateStatus::UpToDate { version: latest });
+    }
+    if check_only {
+        return Ok(UpdateStatus::Available { version: latest });
+    }
+
+    let name = asset_name();
+    let find = |wanted: &str| {
+        release
+            .assets
+            .iter()
+            .find(|a| a.name == wanted)
+            .ok_or_else(|| update_error(format!("release {latest} has no asset '{wanted}'")))
+    };
+    let binary = find(&name)?;
+    let checksum = find(&format!("{name}.sha256"))?;
+
+    let bytes = download(&agent, &binary.browser_download_url)?;
+    let checksum_file = String::from_utf8(download(&agent, &checksum.browser_download_url)?)
+        .map_err(update_error)?;
+    verify_checksum(&bytes, &checksum_file)?;
+
+    let exe = std::env::current_exe()?;
+    replace_executable(&exe, &bytes)?;
+
+    Ok(UpdateStatus::Updated {
+        version: latest,
+        path: exe,
+    })
+}
@@ -0,0 +1,52 @@
+//! Checking EU VAT numbers against the European Commission's VIES service.
+
+/// What VIES says about a VAT number
+#[derive(Debug, Clone, PartialEq)]
+pub struct VatCheck {
+    /// Whether the number is registered for intra-EU trade
+    pub valid: bool,
+    /// Registered name, when the member state shares it
+    pub name: Option<String>,
+}
+
+/// Look `tax_id` (e.g., "DE123456789") up in VIES. Returns None on any
+/// failure (network, timeout, a non-EU prefix) and always without the
+/// `network` feature, so callers decide whether a missing answer matters.
+#[cfg(feature = "network")]
+pub fn check_vat(tax_id: &str) -> Option<VatCheck> {
+    use std::time::Duration;
+    use ureq::Agent;
+
+    let compact: String = tax_id.chars().filter(|c| c.is_alphanumeric()).collect();
+    let (country, number) = compact.split_at(compact.char_indices().nth(2)?.0);
+
+    let agent: Agent = Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(5)))
+        .build()
+        .into();
+
+    let body: String = agent
+        .get(format!(
+            "https://ec.europa.eu/taxation_customs/vies/rest-api/ms/{country}/vat/{number}"
+        ))
+        .call()
+        .ok()?
+        .body_mut()
+        .read_to_string()
+        .ok()?;
+
+    let json: serde_json::Value = serde_json::from_str(&body).ok()?;
+    Some(VatCheck {
+        valid: json["isValid"].as_bool()?,
+        name: json["name"]
+            .as_str()
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && *name != "---")
+            .map(str::to_string),
+    })
+}
+
+#[cfg(not(feature = "network"))]
+pub fn check_vat(_tax_id: &str) -> Option<VatCheck> {
+    None
+}
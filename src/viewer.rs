@@ -0,0 +1,74 @@
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{InvoiceError, Result};
+
+/// How long a custom viewer has to fail before we assume it started fine
+const VIEWER_STARTUP_GRACE: Duration = Duration::from_millis(300);
+
+/// The platform's default opener and the arguments that precede the path
+fn system_opener() -> (&'static str, &'static [&'static str]) {
+    if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else if cfg!(target_os = "windows") {
+        ("cmd", &["/C", "start", ""])
+    } else {
+        ("xdg-open", &[])
+    }
+}
+
+/// Open a file with `viewer` (e.g., "zathura" or "evince --fullscreen"),
+/// falling back to the system opener when no viewer is configured.
+///
+/// System openers hand off and exit, so their exit status is checked. A custom
+/// viewer usually keeps running; it only fails if it can't be spawned or exits
+/// unsuccessfully right after starting.
+pub fn open_file(path: &Path, viewer: Option<&str>) -> Result<()> {
+    match viewer.map(str::trim).filter(|v| !v.is_empty()) {
+        Some(viewer) => open_with_viewer(path, viewer),
+        None => open_with_system(path),
+    }
+}
+
+fn open_with_system(path: &Path) -> Result<()> {
+    let (program, args) = system_opener();
+    let status = Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .map_err(|e| InvoiceError::Viewer(format!("could not launch '{program}': {e}")))?;
+
+    if !status.success() {
+        return Err(InvoiceError::Viewer(format!(
+            "'{program}' exited with {status}"
+        )));
+    }
+    Ok(())
+}
+
+fn open_with_viewer(path: &Path, viewer: &str) -> Result<()> {
+    let mut parts = viewer.split_whitespace();
+    let program = parts.next().unwrap_or(viewer);
+
+    let mut child = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .spawn()
+        .map_err(|e| InvoiceError::Viewer(format!("could not launch '{viewer}': {e}")))?;
+
+    let started = Instant::now();
+    while started.elapsed() < VIEWER_STARTUP_GRACE {
+        if let Some(status) = child.try_wait()? {
+            if !status.success() {
+                return Err(InvoiceError::Viewer(format!(
+                    "'{viewer}' exited with {status}"
+                )));
+            }
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use chrono::NaiveDate;
+use invoice::config::ServicePeriod;
+use invoice::{Client, Company, InvoiceBuilder, InvoiceError};
+
+fn builder() -> InvoiceBuilder {
+    let company = Company {
+        name: "Acme Consulting".into(),
+        ..Default::default()
+    };
+    let client = Client {
+        name: "Globex".into(),
+        email: "ap@globex.example".into(),
+        ..Default::default()
+    };
+    InvoiceBuilder::new("INV-2026-0007", company, client)
+}
+
+#[test]
+fn test_builder_computes_totals_and_dates() {
+    let data = builder()
+        .date(NaiveDate::from_ymd_opt(2026, 3, 10).unwrap())
+        .due_days(15)
+        .period(ServicePeriod::month(2026, 2).unwrap())
+        .item("Technical Consulting", 8.0, "hour", 150.0)
+        .item("Widget", 3.0, "piece", 0.333)
+        .discount("Loyalty discount", 100.0)
+        .tax_rate(0.1)
+        .build()
+        .unwrap();
+
+    assert_eq!(data.number, "INV-2026-0007");
+    assert_eq!(data.date, "March 10, 2026");
+    assert_eq!(data.due_date, "March 25, 2026");
+    assert_eq!(data.payment_terms, "Net 15 days");
+    assert_eq!(data.service_period.as_deref(), Some("Feb 1–28, 2026"));
+
+    // The discount is its own negative line
+    assert_eq!(data.items.len(), 3);
+    assert_eq!(data.items[2].amount, -100.0);
+    assert_eq!(data.subtotal, 1101.0);
+    assert_eq!(data.tax_percent, 10.0);
+    assert_eq!(data.tax_amount, 110.1);
+    assert_eq!(data.rounding_adjustment, 0.0);
+    assert_eq!(data.total, 1211.1);
+}
+
+#[test]
+fn test_builder_requires_items() {
+    assert!(matches!(builder().build(), Err(InvoiceError::NoItems)));
+}
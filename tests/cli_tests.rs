@@ -136,6 +136,50 @@ fn test_status() {
         .stdout(predicate::str::contains("INV-"));
 }
 
+#[test]
+fn test_status_verbose_health_checks() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "init"])
+        .assert()
+        .success();
+
+    let clients = fs::read_to_string(config_path.join("clients.toml")).unwrap();
+    fs::write(
+        config_path.join("clients.toml"),
+        clients.replace(
+            "# cover_letter = \"templates/cover.typ\"",
+            "cover_letter = \"templates/cover.typ\"",
+        ),
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", config_path.to_str().unwrap(), "status", "--verbose"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Health:"))
+        .stdout(predicate::str::is_match(r"green\s+Typst\s+typst 0.0.0 \(fake\)").unwrap())
+        .stdout(
+            predicate::str::is_match(
+                r"red\s+Templates\s+missing cover letter: .*cover.typ \(example-client\)",
+            )
+            .unwrap(),
+        )
+        .stdout(predicate::str::is_match(r"green\s+Output dir").unwrap());
+
+    // The check is only run on request
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Health:").not());
+}
+
 #[test]
 fn test_generate_missing_client() {
     let temp_dir = TempDir::new().unwrap();
@@ -295,7 +339,9 @@ items = ["consulting:2"]
         ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Recorded $1000.00 payment for INV-2026-0001"))
+        .stdout(predicate::str::contains(
+            "Recorded $1000.00 payment for INV-2026-0001",
+        ))
         .stdout(predicate::str::contains("fully paid"));
 
     // List should show PAID and UNPAID
@@ -361,15 +407,12 @@ date = "2026-01-16"
 
     // Remove payment from newest invoice (index 1 = INV-2026-0003)
     invoice_cmd()
-        .args([
-            "-C",
-            config_path.to_str().unwrap(),
-            "remove-payment",
-            "1",
-        ])
+        .args(["-C", config_path.to_str().unwrap(), "remove-payment", "1"])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Removed $300.00 payment from INV-2026-0003"));
+        .stdout(predicate::str::contains(
+            "Removed $300.00 payment from INV-2026-0003",
+        ));
 
     // List with limit 2 should show correct totals
     invoice_cmd()
@@ -423,7 +466,9 @@ items = ["consulting:8"]
         ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Recorded $500.00 payment for INV-2026-0001"))
+        .stdout(predicate::str::contains(
+            "Recorded $500.00 payment for INV-2026-0001",
+        ))
         .stdout(predicate::str::contains("$700.00 remaining"));
 
     // List should show PARTIAL status
@@ -531,7 +576,97 @@ items = ["consulting:4"]
         ])
         .assert()
         .failure()
-        .stderr(predicate::str::contains("Payment would exceed invoice total"));
+        .stderr(predicate::str::contains(
+            "Payment would exceed invoice total",
+        ));
+}
+
+#[test]
+fn test_add_payment_bulk() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 2
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1000.0
+file = "INV-2026-0001.pdf"
+
+[[history]]
+number = "INV-2026-0002"
+client = "example-client"
+date = "2026-01-12"
+total = 750.0
+file = "INV-2026-0002.pdf"
+"#,
+    );
+
+    // One bad entry rejects the whole batch
+    invoice_cmd()
+        .args([
+            "-C",
+            cfg,
+            "add-payment",
+            "--bulk",
+            "INV-2026-0001=500,INV-2026-0002=900",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Payment would exceed invoice total for INV-2026-0002",
+        ));
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(!state.contains("[[history.payments]]"));
+
+    invoice_cmd()
+        .args([
+            "-C",
+            cfg,
+            "add-payment",
+            "--bulk",
+            "INV-2026-0001=500, INV-2026-0002=750",
+            "--date",
+            "2026-02-01",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Recorded $500.00 payment for INV-2026-0001 ($500.00 remaining)",
+        ))
+        .stdout(predicate::str::contains(
+            "Recorded $750.00 payment for INV-2026-0002 (fully paid)",
+        ))
+        .stdout(predicate::str::contains(
+            "Recorded 2 payments totaling $1250.00",
+        ));
+
+    invoice_cmd()
+        .args(["-C", cfg, "add-payment", "--bulk", "INV-2026-0001:500"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --bulk entry"));
+    invoice_cmd()
+        .args(["-C", cfg, "add-payment", "--bulk", "INV-2026-0001=NaN"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --bulk entry"));
+    invoice_cmd()
+        .args(["-C", cfg, "add-payment", "INV-2026-0001", "inf"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "must be a finite number greater than zero",
+        ));
 }
 
 #[test]
@@ -580,16 +715,13 @@ date = "2026-01-20"
         ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Removed $300.00 payment from INV-2026-0001"));
+        .stdout(predicate::str::contains(
+            "Removed $300.00 payment from INV-2026-0001",
+        ));
 
     // Payments command should show only the second payment remaining
     invoice_cmd()
-        .args([
-            "-C",
-            config_path.to_str().unwrap(),
-            "payments",
-            "1",
-        ])
+        .args(["-C", config_path.to_str().unwrap(), "payments", "1"])
         .assert()
         .success()
         .stdout(predicate::str::contains("400.00"))
@@ -648,6 +780,47 @@ date = "2026-01-20"
         .stdout(predicate::str::contains("PARTIAL"));
 }
 
+#[test]
+fn test_show_prints_invoice_details() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 1
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+items = ["consulting:8"]
+
+[[history.payments]]
+amount = 500.0
+date = "2026-01-15"
+"#,
+    );
+
+    invoice_cmd()
+        .args(["-C", cfg, "show", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Invoice INV-2026-0001"))
+        .stdout(predicate::str::contains("Status: PARTIAL"))
+        .stdout(predicate::str::contains("Example Client Inc."))
+        .stdout(predicate::str::contains("Los Angeles, CA 90001"))
+        .stdout(predicate::str::contains("8 hours"))
+        .stdout(predicate::str::contains("Total:       $1200.00"))
+        .stdout(predicate::str::contains("2026-01-15  $500.00"))
+        .stdout(predicate::str::contains("Outstanding: $700.00"));
+}
+
 #[test]
 fn test_legacy_paid_true_migrates_to_payment() {
     let temp_dir = TempDir::new().unwrap();
@@ -685,12 +858,7 @@ paid = true
 
     // Payments command should show the synthesized payment
     invoice_cmd()
-        .args([
-            "-C",
-            config_path.to_str().unwrap(),
-            "payments",
-            "1",
-        ])
+        .args(["-C", config_path.to_str().unwrap(), "payments", "1"])
         .assert()
         .success()
         .stdout(predicate::str::contains("1200.00"))
@@ -859,7 +1027,9 @@ paid = false
         ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("Generated report for 'example-client'"))
+        .stdout(predicate::str::contains(
+            "Generated report for 'example-client'",
+        ))
         .stdout(predicate::str::contains("Invoices: 2"));
 
     // Verify the PDF file was created in the output directory
@@ -870,10 +1040,13 @@ paid = false
         .filter(|e| {
             e.file_name()
                 .to_str()
-                .map_or(false, |n| n.starts_with("REPORT-example-client-") && n.ends_with(".pdf"))
+                .is_some_and(|n| n.starts_with("RPT-") && n.ends_with(".pdf"))
         })
         .collect();
-    assert!(!pdf_files.is_empty(), "Report PDF should exist in output dir");
+    assert!(
+        !pdf_files.is_empty(),
+        "Report PDF should exist in output dir"
+    );
 }
 
 #[test]
@@ -1013,3 +1186,5438 @@ paid = false
         .stdout(predicate::str::contains("Total: 2 invoices"))
         .stdout(predicate::str::contains("Use index number"));
 }
+
+/// Stand-in `typst` binary that writes the JSON data payload to the output
+/// path instead of rendering, so generation can be tested without Typst.
+const FAKE_TYPST: &str = r#"#!/bin/sh
+if [ "$1" = "--version" ]; then
+  echo "typst 0.0.0 (fake)"
+  exit 0
+fi
+root="$3"
+for arg; do out="$arg"; done
+cat "$root"/*.json > "$out"
+if [ -n "$SOURCE_DATE_EPOCH" ]; then echo "SOURCE_DATE_EPOCH=$SOURCE_DATE_EPOCH" >> "$out"; fi
+"#;
+
+/// Install the fake `typst` under `dir` and return a PATH that prefers it
+fn fake_typst_path(dir: &std::path::Path) -> std::ffi::OsString {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin = dir.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let script = bin.join("typst");
+    fs::write(&script, FAKE_TYPST).unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut paths = vec![bin];
+    if let Some(path) = std::env::var_os("PATH") {
+        paths.extend(std::env::split_paths(&path));
+    }
+    std::env::join_paths(paths).unwrap()
+}
+
+#[test]
+fn test_diff_reports_catalog_drift() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+    let year = chrono::Local::now().format("%Y").to_string();
+    let pdf_path = config_path
+        .join("output")
+        .join(format!("INV-{year}-0001.pdf"));
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:8", "--formats", "pdf,json"])
+        .assert()
+        .success();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "diff", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Total: $1200.00 (unchanged)"))
+        .stdout(predicate::str::contains("PDF: identical"))
+        .stdout(predicate::str::contains("would leave"));
+
+    // PDFs rendered without --deterministic differ by their creation time,
+    // which alone doesn't count as a change
+    let mut pdf = fs::read(&pdf_path).unwrap();
+    pdf.extend_from_slice(b"CreationDate");
+    fs::write(&pdf_path, pdf).unwrap();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "diff", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "PDF: differs from the stored file, as expected",
+        ))
+        .stdout(predicate::str::contains("would leave"));
+
+    // A new rate in the catalog would reprice the issued invoice
+    let items_path = config_path.join("items.toml");
+    let items = fs::read_to_string(&items_path).unwrap();
+    fs::write(
+        &items_path,
+        items.replacen("rate = 150.00", "rate = 175.00", 1),
+    )
+    .unwrap();
+    let stored_pdf = fs::read(&pdf_path).unwrap();
+    let state_before = fs::read_to_string(config_path.join("state.toml")).unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "diff", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Total: $1200.00 -> $1400.00"))
+        .stdout(predicate::str::contains(
+            "- Technical Consulting, 8 hours at $150.00 = $1200.00",
+        ))
+        .stdout(predicate::str::contains(
+            "+ Technical Consulting, 8 hours at $175.00 = $1400.00",
+        ))
+        .stdout(predicate::str::contains("- Total: $1,200.00"))
+        .stdout(predicate::str::contains("+ Total: $1,400.00"))
+        .stdout(predicate::str::contains("  Bill To: Example Client Inc."))
+        .stdout(predicate::str::contains(
+            "PDF: differs from the stored file, as expected",
+        ))
+        .stdout(predicate::str::contains("Regenerating would change"));
+
+    // Nothing issued was touched
+    assert_eq!(fs::read(&pdf_path).unwrap(), stored_pdf);
+    assert_eq!(
+        fs::read_to_string(config_path.join("state.toml")).unwrap(),
+        state_before
+    );
+}
+
+#[test]
+fn test_generate_with_line_service_periods() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:8@2026-01-01..2026-01-15"])
+        .args(["--item", "consulting:4@2026-02"])
+        .assert()
+        .success();
+
+    let data = fs::read_to_string(
+        config_path
+            .join("output")
+            .join(format!("INV-{year}-0001.pdf")),
+    )
+    .unwrap();
+    assert!(data.contains(r#""period":"Jan 1–15, 2026""#));
+    assert!(data.contains(r#""period":"Feb 1–28, 2026""#));
+
+    // The period is kept with the item in history, so regenerating keeps it
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("consulting:8@2026-01-01..2026-01-15"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:8@2026-01-31..2026-01-01"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("2026-01-31..2026-01-01"));
+}
+
+#[test]
+fn test_generate_rejects_incomplete_client() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let clients = config_path.join("clients.toml");
+    let mut contents = fs::read_to_string(&clients).unwrap();
+    contents.push_str(
+        r#"
+[eu-client]
+name = "EU Client GmbH"
+email = ""
+address = "Hauptstrasse 1"
+city = "Berlin"
+state = ""
+zip = "10115"
+delivery = "email"
+tax_rate = 0.0
+"#,
+    );
+    fs::write(&clients, &contents).unwrap();
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "generate", "--client", "eu-client"])
+        .args(["--item", "consulting:8"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "[ ] tax_id: required when billing without tax",
+        ))
+        .stderr(predicate::str::contains(
+            "[ ] country: required when billing without tax",
+        ))
+        .stderr(predicate::str::contains(
+            "[ ] email: required for email delivery",
+        ))
+        .stderr(predicate::str::contains(
+            "Complete the record with 'invoice clients edit'.",
+        ));
+    assert!(!config_path
+        .join("output")
+        .join(format!("INV-{year}-0001.pdf"))
+        .exists());
+
+    let contents = contents.replace(
+        "email = \"\"\naddress = \"Hauptstrasse 1\"",
+        "email = \"ap@eu-client.de\"\naddress = \"Hauptstrasse 1\"\ncountry = \"Germany\"\ntax_id = \"DE123456789\"",
+    );
+    fs::write(&clients, contents).unwrap();
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "generate", "--client", "eu-client"])
+        .args(["--item", "consulting:8"])
+        .assert()
+        .success();
+
+    let data = fs::read_to_string(
+        config_path
+            .join("output")
+            .join(format!("INV-{year}-0001.pdf")),
+    )
+    .unwrap();
+    assert!(data.contains(r#""tax_id":"DE123456789""#));
+}
+
+#[test]
+fn test_generate_copies_pdf_to_copy_to_dirs() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let config_file = config_path.join("config.toml");
+    let config = fs::read_to_string(&config_file).unwrap().replace(
+        "output_dir = \"./output\"",
+        "output_dir = \"./output\"\ncopy_to = [\"copies/{year}/{client}\"]",
+    );
+    fs::write(&config_file, config).unwrap();
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:8", "--formats", "pdf,html"])
+        .assert()
+        .success();
+
+    let copies = config_path
+        .join("copies")
+        .join(&year)
+        .join("example-client");
+    assert!(copies.join(format!("INV-{year}-0001.pdf")).exists());
+    assert!(!copies.join(format!("INV-{year}-0001.html")).exists());
+
+    // Regenerating refreshes the copy
+    fs::remove_file(copies.join(format!("INV-{year}-0001.pdf"))).unwrap();
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "regenerate", "1"])
+        .assert()
+        .success();
+    assert!(copies.join(format!("INV-{year}-0001.pdf")).exists());
+}
+
+#[test]
+fn test_generate_and_edit_discount() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+    let pdf = config_path
+        .join("output")
+        .join(format!("INV-{year}-0001.pdf"));
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let config_file = config_path.join("config.toml");
+    let config = fs::read_to_string(&config_file).unwrap();
+    let config = config.replacen("tax_rate = 0.0", "tax_rate = 0.1", 1);
+    fs::write(&config_file, config).unwrap();
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:8", "--discount", "10%"])
+        .assert()
+        .success();
+
+    // $1200 less 10% is $1080, taxed at 10%
+    let data = fs::read_to_string(&pdf).unwrap();
+    assert!(data.contains(r#""subtotal":1200.0"#));
+    assert!(data.contains(r#""discount_percent":10.0"#));
+    assert!(data.contains(r#""discount":120.0"#));
+    assert!(data.contains(r#""tax_amount":108.0"#));
+    assert!(data.contains(r#""total":1188.0"#));
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains(r#"discount = "10%""#));
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "edit", "1", "--discount", "250"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Discount: 250"))
+        .stdout(predicate::str::contains("Total:  $1045.00"));
+    let data = fs::read_to_string(&pdf).unwrap();
+    assert!(data.contains(r#""discount_percent":0.0"#));
+    assert!(data.contains(r#""discount":250.0"#));
+
+    invoice_cmd()
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:8", "--discount", "150%"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid discount '150%'"));
+}
+
+#[test]
+fn test_generate_and_edit_note() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+    let pdf = config_path
+        .join("output")
+        .join(format!("INV-{year}-0001.pdf"));
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:8", "--note", "PO 4521. Thank you!"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Note:   PO 4521. Thank you!"));
+
+    let data = fs::read_to_string(&pdf).unwrap();
+    assert!(data.contains(r#""memo":"PO 4521. Thank you!""#), "{data}");
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains(r#"memo = "PO 4521. Thank you!""#), "{state}");
+
+    invoice_cmd()
+        .args(["-C", cfg, "show", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Memo: PO 4521. Thank you!"));
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "edit", "1", "--note", ""])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Note:   removed"));
+    let data = fs::read_to_string(&pdf).unwrap();
+    assert!(data.contains(r#""memo":null"#), "{data}");
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(!state.contains("memo"), "{state}");
+}
+
+#[test]
+fn test_generate_prorates_recurring_items() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let mut items = fs::read_to_string(config_path.join("items.toml")).unwrap();
+    items.push_str(
+        "\n[hosting]\ndescription = \"Managed Hosting\"\nrate = 310.0\nunit = \"month\"\n\
+         recurring = \"monthly\"\nprorate = true\n\
+         \n[on-call]\ndescription = \"On-call Support\"\nrate = 700.0\nunit = \"week\"\n\
+         recurring = \"weekly\"\nprorate = true\n",
+    );
+    fs::write(config_path.join("items.toml"), items).unwrap();
+    fs::write(
+        config_path.join("contracts.toml"),
+        "[support]\nclient = \"example-client\"\nvalue = 10000.0\nstart = \"2026-01-15\"\n",
+    )
+    .unwrap();
+
+    // The contract starts mid-month, so January's hosting is 17 of 31 days;
+    // on-call covers Wednesday to Sunday of one week and all of the next
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--number", "INV-2026-0001", "--period", "2026-01"])
+        .args([
+            "--item",
+            "hosting:1",
+            "--item",
+            "on-call:1@2026-01-21..2026-02-01",
+        ])
+        .assert()
+        .success();
+
+    let data = fs::read_to_string(config_path.join("output").join("INV-2026-0001.pdf")).unwrap();
+    assert!(
+        data.contains(
+            r#""description":"Managed Hosting (prorated: 17/31 days)","quantity":0.5484"#
+        ),
+        "{data}"
+    );
+    assert!(data.contains(r#""amount":170.0"#), "{data}");
+    assert!(data.contains(r#""period":"Jan 15–31, 2026""#), "{data}");
+    assert!(
+        data.contains(
+            r#""description":"On-call Support (prorated: 5/7 days + 1 week)","quantity":1.7143"#
+        ),
+        "{data}"
+    );
+    assert!(data.contains(r#""amount":1200.0"#), "{data}");
+}
+
+#[test]
+fn test_generate_lists_each_tax_and_skips_exempt_items() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let config_file = config_path.join("config.toml");
+    let config = fs::read_to_string(&config_file).unwrap().replacen(
+        "tax_rate = 0.0",
+        "tax_rate = 0.0\n\n[invoice.taxes]\nGST = 0.05\nPST = 0.07",
+        1,
+    );
+    fs::write(&config_file, config).unwrap();
+    let mut items = fs::read_to_string(config_path.join("items.toml")).unwrap();
+    items.push_str(
+        "\n[travel]\ndescription = \"Travel\"\nrate = 300.0\nunit = \"trip\"\n\
+         kind = \"expense\"\ntaxable = false\n",
+    );
+    fs::write(config_path.join("items.toml"), items).unwrap();
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:8", "--item", "travel:1"])
+        .assert()
+        .success();
+
+    // Both taxes apply to the $1200 of consulting only
+    let data = fs::read_to_string(
+        config_path
+            .join("output")
+            .join(format!("INV-{year}-0001.pdf")),
+    )
+    .unwrap();
+    assert!(data.contains(r#""taxable":false"#), "{data}");
+    assert!(data.contains(r#"{"name":"GST","percent":5.0,"amount":60.0}"#));
+    assert!(data.contains(r#""name":"PST""#));
+    assert!(data.contains(r#""amount":84.0"#));
+    assert!(data.contains(r#""tax_amount":144.0"#));
+    assert!(data.contains(r#""total":1644.0"#));
+
+    invoice_cmd()
+        .args(["-C", cfg, "show", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("GST (5.00%):"))
+        .stdout(predicate::str::contains("PST (7.00%):"));
+}
+
+#[test]
+fn test_strict_refuses_questionable_invoices() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let generate = || {
+        let mut cmd = invoice_cmd();
+        cmd.env("PATH", fake_typst_path(temp_dir.path()))
+            .args([
+                "-C",
+                cfg,
+                "--strict",
+                "generate",
+                "--client",
+                "example-client",
+            ])
+            .args(["--item", "consulting:8"]);
+        cmd
+    };
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    generate()
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Stopped by --strict"))
+        .stderr(predicate::str::contains(
+            "client 'example-client' has no country",
+        ));
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap_or_default();
+    assert!(!state.contains("[[history]]"), "{state}");
+
+    let clients_file = config_path.join("clients.toml");
+    let clients = fs::read_to_string(&clients_file).unwrap().replacen(
+        "zip = \"90001\"",
+        "zip = \"90001\"\ncountry = \"USA\"",
+        1,
+    );
+    fs::write(&clients_file, clients).unwrap();
+    fs::create_dir_all(config_path.join("templates")).unwrap();
+    fs::write(
+        config_path.join("templates").join("footer.typ"),
+        "#let footer(data) = [Tax: #data.tax_rate%]\n",
+    )
+    .unwrap();
+    generate()
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "footer.typ:1: data.tax_rate is deprecated",
+        ));
+
+    fs::remove_file(config_path.join("templates").join("footer.typ")).unwrap();
+    generate().assert().success();
+}
+
+#[test]
+fn test_generate_adds_rounding_adjustment() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "init"])
+        .assert()
+        .success();
+
+    fs::write(
+        config_path.join("items.toml"),
+        r#"[widget]
+description = "Widget"
+rate = 0.333
+unit = "piece"
+"#,
+    )
+    .unwrap();
+
+    let output = temp_dir.path().join("out.pdf");
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            config_path.to_str().unwrap(),
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "widget:1",
+            "--item",
+            "widget:1",
+            "--item",
+            "widget:1",
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Total:  $1.00"));
+
+    // Three displayed lines of $0.33 plus a one-cent adjustment equal the total
+    let data = fs::read_to_string(&output).unwrap();
+    assert!(data.contains(r#""subtotal":0.99"#));
+    assert!(data.contains(r#""rounding_adjustment":0.01"#));
+    assert!(data.contains(r#""total":1.0"#));
+}
+
+#[test]
+fn test_generate_cover_letter_missing_template() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "init"])
+        .assert()
+        .success();
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            config_path.to_str().unwrap(),
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:1",
+            "--cover-letter",
+            "templates/cover.typ",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Template not found"))
+        .stderr(predicate::str::contains("templates/cover.typ"));
+
+    // Once the letter exists in the config dir, generation succeeds
+    fs::write(
+        config_path.join("templates").join("cover.typ"),
+        "#let data = json(\"DATA_JSON_PATH\")\nDear #data.client.name,\n",
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            config_path.to_str().unwrap(),
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:1",
+            "--cover-letter",
+            "templates/cover.typ",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Generated INV-"));
+}
+
+#[test]
+fn test_generate_debug_keeps_template_sources() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "init"])
+        .assert()
+        .success();
+
+    let debug_dir = config_path
+        .join("output")
+        .join("debug")
+        .join(format!("INV-{year}-0001"));
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            config_path.to_str().unwrap(),
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:1",
+            "--debug",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "debug/INV-{year}-0001/data.json"
+        )))
+        .stdout(predicate::str::contains(format!(
+            "debug/INV-{year}-0001/invoice.typ"
+        )));
+
+    let template = fs::read_to_string(debug_dir.join("invoice.typ")).unwrap();
+    assert!(template.contains("json(\"data.json\")"));
+    let data = fs::read_to_string(debug_dir.join("data.json")).unwrap();
+    assert!(data.contains("\"schema_version\":1"));
+}
+
+/// `typst` that fails with two diagnostics in the cover letter
+const FAILING_TYPST: &str = r#"#!/bin/sh
+if [ "$1" = "--version" ]; then
+  echo "typst 0.0.0 (fake)"
+  exit 0
+fi
+cat >&2 <<'EOF'
+error: unknown variable: colour
+  ┌─ cover.typ:2:6
+  │
+2 │ Dear #colour
+  │       ^^^^^^
+
+error: expected expression
+  ┌─ cover.typ:3:1
+  │
+3 │ #
+  │  ^
+EOF
+exit 1
+"#;
+
+#[test]
+fn test_generate_reports_typst_errors_with_context() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "init"])
+        .assert()
+        .success();
+    fs::write(
+        config_path.join("templates").join("cover.typ"),
+        "#let data = json(\"DATA_JSON_PATH\")\nDear #colour\n#\n",
+    )
+    .unwrap();
+
+    let bin = temp_dir.path().join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    fs::write(bin.join("typst"), FAILING_TYPST).unwrap();
+    fs::set_permissions(bin.join("typst"), fs::Permissions::from_mode(0o755)).unwrap();
+    let mut paths = vec![bin];
+    paths.extend(std::env::split_paths(&std::env::var_os("PATH").unwrap()));
+
+    let output = invoice_cmd()
+        .env("PATH", std::env::join_paths(paths).unwrap())
+        .args([
+            "-C",
+            config_path.to_str().unwrap(),
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:1",
+            "--cover-letter",
+            "templates/cover.typ",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Typst could not compile the cover letter",
+        ))
+        .stderr(predicate::str::contains(
+            "cover.typ:2:6: unknown variable: colour",
+        ))
+        .stderr(predicate::str::contains(
+            "cover.typ:3:1: expected expression",
+        ))
+        .get_output()
+        .stderr
+        .clone();
+
+    // The data file is kept so the failing render can be reproduced
+    let stderr = String::from_utf8(output).unwrap();
+    let data_path = stderr
+        .lines()
+        .find_map(|line| line.strip_prefix("Template data kept for inspection at "))
+        .expect("data path in error");
+    let data = fs::read_to_string(data_path).unwrap();
+    assert!(data.contains("\"schema_version\":1"));
+    fs::remove_dir_all(std::path::Path::new(data_path).parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_generate_edit_json_renders_edited_draft() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "init"])
+        .assert()
+        .success();
+
+    // "Editor" that rewrites the line description in place
+    let editor = temp_dir.path().join("editor.sh");
+    fs::write(
+        &editor,
+        "#!/bin/sh\nsed -i 's/Technical Consulting/Architecture Review/' \"$1\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let output = temp_dir.path().join("out.pdf");
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .args([
+            "-C",
+            config_path.to_str().unwrap(),
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:2",
+            "--edit-json",
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    let data = fs::read_to_string(&output).unwrap();
+    assert!(data.contains("Architecture Review"));
+    assert!(!data.contains("Technical Consulting"));
+}
+
+#[test]
+fn test_clients_edit_validates_on_save() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "init"])
+        .assert()
+        .success();
+
+    // A no-op edit leaves a valid file
+    invoice_cmd()
+        .env_remove("VISUAL")
+        .env("EDITOR", "true")
+        .args(["-C", config_path.to_str().unwrap(), "items", "edit"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("items.toml is valid"));
+
+    // An edit that breaks the TOML is reported; without an answer to the
+    // re-open prompt (stdin closed) the command fails
+    let editor = temp_dir.path().join("break.sh");
+    fs::write(&editor, "#!/bin/sh\necho 'broken = [' >> \"$1\"\n").unwrap();
+    fs::set_permissions(&editor, fs::Permissions::from_mode(0o755)).unwrap();
+
+    invoice_cmd()
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .args(["-C", config_path.to_str().unwrap(), "clients", "edit"])
+        .stdin(std::process::Stdio::null())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to parse config file"))
+        .stderr(predicate::str::contains("Re-open the editor"));
+}
+
+#[test]
+fn test_client_new_interactive_validates_answers() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    // A bad email and a zip that isn't German are asked again; the VIES
+    // check is declined
+    let answers = [
+        "Mueller GmbH",
+        "",
+        "ap@mueller",
+        "ap@mueller.de",
+        "Hauptstr. 1",
+        "Berlin",
+        "",
+        "DE",
+        "1011",
+        "10115",
+        "DE123456789",
+        "n",
+        "14",
+        "eur",
+        "€",
+        "de",
+        "y",
+    ];
+    assert_cmd::Command::from_std(invoice_cmd())
+        .args(["-C", cfg, "client", "new", "mueller", "--interactive"])
+        .write_stdin(answers.join("\n") + "\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("'mueller' is not a domain name"))
+        .stderr(predicate::str::contains(
+            "'1011' is not a postal code (expected 12345)",
+        ))
+        .stdout(predicate::str::contains("Added client 'mueller'"));
+
+    let clients = fs::read_to_string(config_path.join("clients.toml")).unwrap();
+    assert!(clients.contains("[mueller]"), "{clients}");
+    assert!(clients.contains(r#"zip = "10115""#), "{clients}");
+    assert!(clients.contains(r#"tax_id = "DE123456789""#), "{clients}");
+    assert!(clients.contains("due_days = 14"), "{clients}");
+    assert!(clients.contains(r#"currency = "EUR""#), "{clients}");
+    assert!(clients.contains(r#"language = "de""#), "{clients}");
+
+    invoice_cmd()
+        .args(["-C", cfg, "clients"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Mueller GmbH"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "clients", "new", "mueller", "--interactive"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Client 'mueller' already exists"));
+}
+
+#[test]
+fn test_global_config_management() {
+    let temp_dir = TempDir::new().unwrap();
+    let home = temp_dir.path();
+    let books = home.join("books");
+
+    invoice_cmd()
+        .env("HOME", home)
+        .args(["config", "--init-global"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Initialized global config"));
+    assert!(home.join(".config").join("invoicing.toml").exists());
+
+    invoice_cmd()
+        .env("HOME", home)
+        .args(["config", "--init-global"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+
+    invoice_cmd()
+        .env("HOME", home)
+        .args([
+            "config",
+            "set-global",
+            "config_dir",
+            books.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    // The global config now drives the config directory without -C
+    invoice_cmd()
+        .env("HOME", home)
+        .args(["config", "show"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Config directory: {}",
+            books.display()
+        )));
+
+    invoice_cmd()
+        .env("HOME", home)
+        .args(["config", "set-global", "output_dir", "x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown global config key"));
+}
+
+#[test]
+fn test_workspace_discovered_from_subdirectory() {
+    let temp_dir = TempDir::new().unwrap();
+    let home = temp_dir.path().join("home");
+    let project = temp_dir.path().join("project");
+    let nested = project.join("src").join("deep");
+    fs::create_dir_all(&home).unwrap();
+    fs::create_dir_all(&nested).unwrap();
+
+    invoice_cmd()
+        .args(["-C", project.join(".invoice").to_str().unwrap(), "init"])
+        .assert()
+        .success();
+
+    // No -C: the project's .invoice/ is found by walking up from the cwd
+    invoice_cmd()
+        .env("HOME", &home)
+        .current_dir(&nested)
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Config directory: {}",
+            project.join(".invoice").display()
+        )));
+}
+
+#[test]
+fn test_generate_multiple_formats_recorded_in_history() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "init"])
+        .assert()
+        .success();
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            config_path.to_str().unwrap(),
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:2",
+            "--formats",
+            "pdf,html,json",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".pdf"))
+        .stdout(predicate::str::contains(".html"))
+        .stdout(predicate::str::contains(".json"));
+
+    let output_dir = config_path.join("output");
+    let mut names: Vec<String> = fs::read_dir(&output_dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|name| name != "manifest.toml")
+        .collect();
+    names.sort();
+    assert_eq!(names.len(), 3, "expected three artifacts, got {names:?}");
+    assert!(names[1].ends_with(".json") && names[2].ends_with(".pdf"));
+
+    let html = fs::read_to_string(output_dir.join(&names[0])).unwrap();
+    assert!(html.contains("<!DOCTYPE html>"));
+    assert!(html.contains("Technical Consulting"));
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("files = ["));
+    assert!(state.contains(&names[0]));
+}
+
+#[test]
+fn test_documents_share_formatted_amounts() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:8", "--item", "development:10"])
+        .args(["--formats", "pdf,html"])
+        .assert()
+        .success();
+
+    // Templates get the amounts formatted as the HTML output prints them
+    let output_dir = config_path.join("output");
+    let data = fs::read_to_string(output_dir.join(format!("INV-{year}-0001.pdf"))).unwrap();
+    assert!(data.contains(r#""total":2450.0"#), "{data}");
+    assert!(data.contains(r#""total":"$2,450.00""#), "{data}");
+    assert!(
+        data.contains(r#""rate":"$125.00","amount":"$1,250.00","running":"$2,450.00""#),
+        "{data}"
+    );
+    let html = fs::read_to_string(output_dir.join(format!("INV-{year}-0001.html"))).unwrap();
+    assert!(html.contains("$1,250.00"), "{html}");
+    assert!(html.contains("$2,450.00"), "{html}");
+}
+
+#[test]
+fn test_html_template_override() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+    let html_path = config_path
+        .join("output")
+        .join(format!("INV-{year}-0001.html"));
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    fs::write(
+        config_path.join("templates").join("invoice.html"),
+        "<p>{{ number }} for {{client_name}}: {{amount_due}} {{unknown}}</p>\n",
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:8", "--format", "html"])
+        .assert()
+        .success();
+    let html = fs::read_to_string(&html_path).unwrap();
+    assert_eq!(
+        html,
+        format!("<p>INV-{year}-0001 for Example Client Inc.: $1,200.00 {{{{unknown}}}}</p>\n")
+    );
+
+    // Without the custom file the built-in template is back
+    fs::remove_file(config_path.join("templates").join("invoice.html")).unwrap();
+    invoice_cmd()
+        .args(["-C", cfg, "regenerate", "1", "--format", "html"])
+        .assert()
+        .success();
+    let html = fs::read_to_string(&html_path).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"), "{html}");
+    assert!(
+        html.contains("<strong>Example Client Inc.</strong>"),
+        "{html}"
+    );
+}
+
+#[test]
+fn test_edit_keeps_previous_version() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "init"])
+        .assert()
+        .success();
+
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 1
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+items = ["consulting:8"]
+"#,
+    );
+    let output_dir = config_path.join("output");
+    fs::write(output_dir.join("INV-2026-0001.pdf"), "original").unwrap();
+
+    // A failed render leaves the current version in place and unarchived
+    let broken = temp_dir.path().join("broken");
+    fs::create_dir_all(&broken).unwrap();
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let script = broken.join("typst");
+        fs::write(
+            &script,
+            "#!/bin/sh\n[ \"$1\" = \"--version\" ] && exit 0\nexit 1\n",
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    invoice_cmd()
+        .env("PATH", &broken)
+        .args([
+            "-C",
+            config_path.to_str().unwrap(),
+            "edit",
+            "1",
+            "--item",
+            "consulting:10",
+        ])
+        .assert()
+        .failure();
+    assert_eq!(
+        fs::read_to_string(output_dir.join("INV-2026-0001.pdf")).unwrap(),
+        "original"
+    );
+    assert!(!output_dir.join("INV-2026-0001.v1.pdf").exists());
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .env("USER", "alice")
+        .args([
+            "-C",
+            config_path.to_str().unwrap(),
+            "edit",
+            "1",
+            "--item",
+            "consulting:10",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Total:  $1500.00"))
+        .stdout(predicate::str::contains("Kept v1: INV-2026-0001.v1.pdf"));
+
+    // The sent version is preserved and the revision is recorded
+    assert_eq!(
+        fs::read_to_string(output_dir.join("INV-2026-0001.v1.pdf")).unwrap(),
+        "original"
+    );
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("[[history.revisions]]"));
+    assert!(state.contains(r#"changed_by = "alice""#));
+    assert!(state.contains("total $1200.00 -> $1500.00; items consulting:8 -> consulting:10"));
+}
+
+#[test]
+fn test_locked_invoice_blocks_edit_until_force_unlock() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "init"])
+        .assert()
+        .success();
+
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 1
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+items = ["consulting:8"]
+"#,
+    );
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "lock", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Locked INV-2026-0001"));
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            config_path.to_str().unwrap(),
+            "edit",
+            "1",
+            "--item",
+            "consulting:10",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is locked"));
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            config_path.to_str().unwrap(),
+            "edit",
+            "1",
+            "--item",
+            "consulting:10",
+            "--force-unlock",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Total:  $1500.00"));
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(!state.contains("locked = true"));
+}
+
+#[test]
+fn test_trash_restore_and_purge() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 2
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+
+[[history]]
+number = "INV-2026-0002"
+client = "example-client"
+date = "2026-02-10"
+total = 800.0
+file = "INV-2026-0002.pdf"
+"#,
+    );
+    let output_dir = config_path.join("output");
+    fs::create_dir_all(&output_dir).unwrap();
+    fs::write(output_dir.join("INV-2026-0001.pdf"), "pdf").unwrap();
+
+    invoice_cmd()
+        .args(["-C", cfg, "trash", "add", "INV-2026-0001"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Moved INV-2026-0001 to the trash"));
+
+    assert!(!output_dir.join("INV-2026-0001.pdf").exists());
+    assert!(config_path
+        .join(".trash/INV-2026-0001/INV-2026-0001.pdf")
+        .exists());
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(!state.contains("INV-2026-0001"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "trash", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("INV-2026-0001"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "trash", "restore", "INV-2026-0001"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored INV-2026-0001"));
+
+    assert!(output_dir.join("INV-2026-0001.pdf").exists());
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.find("INV-2026-0001").unwrap() < state.find("INV-2026-0002").unwrap());
+
+    invoice_cmd()
+        .args(["-C", cfg, "trash", "add", "2"])
+        .assert()
+        .success();
+    invoice_cmd()
+        .args(["-C", cfg, "trash", "purge", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Purged 1 invoice(s)"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "trash", "restore", "INV-2026-0002"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not in the trash"));
+}
+
+#[test]
+fn test_open_falls_back_to_printing_path_when_viewer_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 1
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+"#,
+    );
+    let output_dir = config_path.join("output");
+    fs::create_dir_all(&output_dir).unwrap();
+    fs::write(output_dir.join("INV-2026-0001.pdf"), "pdf").unwrap();
+
+    let config_file = config_path.join("config.toml");
+    let mut config = fs::read_to_string(&config_file).unwrap();
+    config.push_str("\n[display]\npdf_viewer = \"false\"\n");
+    fs::write(&config_file, &config).unwrap();
+
+    invoice_cmd()
+        .args(["-C", cfg, "open", "1"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("'false' exited with"))
+        .stdout(predicate::str::contains("File is at:"))
+        .stdout(predicate::str::contains("INV-2026-0001.pdf"))
+        .stdout(predicate::str::contains("Opened").not());
+
+    config = config.replace(
+        "pdf_viewer = \"false\"",
+        "pdf_viewer = \"no-such-viewer-cmd\"",
+    );
+    fs::write(&config_file, &config).unwrap();
+
+    invoice_cmd()
+        .args(["-C", cfg, "open", "1"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "could not launch 'no-such-viewer-cmd'",
+        ));
+
+    config = config.replace(
+        "pdf_viewer = \"no-such-viewer-cmd\"",
+        "pdf_viewer = \"true\"",
+    );
+    fs::write(&config_file, &config).unwrap();
+
+    invoice_cmd()
+        .args(["-C", cfg, "open", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Opened"));
+}
+
+#[test]
+fn test_copy_summary_to_clipboard() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 1
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+
+[[history.payments]]
+amount = 200.0
+date = "2026-01-15"
+"#,
+    );
+
+    let config_file = config_path.join("config.toml");
+    let mut config = fs::read_to_string(&config_file).unwrap();
+    config.push_str(
+        "\n[payment]\nlink = \"https://pay.example.com/acme\"\nbank_details = \"Bank: Example Bank\\nAccount: 000123\"\n",
+    );
+    fs::write(&config_file, config).unwrap();
+
+    // Fake xclip that records what was copied
+    let bin = temp_dir.path().join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let clip_file = temp_dir.path().join("clipboard.txt");
+    let script = bin.join("xclip");
+    fs::write(
+        &script,
+        format!("#!/bin/sh\n/bin/cat > \"{}\"\n", clip_file.display()),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+    invoice_cmd()
+        .env("PATH", &bin)
+        .env_remove("WAYLAND_DISPLAY")
+        .args(["-C", cfg, "copy", "1", "--format", "md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Copied INV-2026-0001 summary to clipboard (xclip)",
+        ));
+
+    let copied = fs::read_to_string(&clip_file).unwrap();
+    assert!(copied.contains("**Invoice INV-2026-0001** for Example Client Inc."));
+    assert!(copied.contains("- **Amount due:** $1000.00"));
+    assert!(copied.contains("- **Due date:** 2026-02-09"));
+    assert!(copied.contains("<https://pay.example.com/acme>"));
+    assert!(copied.contains("Account: 000123"));
+
+    // Without a clipboard tool the summary is printed instead
+    let empty = temp_dir.path().join("empty");
+    fs::create_dir_all(&empty).unwrap();
+    invoice_cmd()
+        .env("PATH", &empty)
+        .env_remove("WAYLAND_DISPLAY")
+        .args(["-C", cfg, "copy", "1"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("no clipboard tool found"))
+        .stdout(predicate::str::contains(
+            "Invoice INV-2026-0001 for Example Client Inc.",
+        ))
+        .stdout(predicate::str::contains(
+            "Pay online: https://pay.example.com/acme",
+        ));
+}
+
+#[test]
+fn test_counter_set_and_manual_number() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    invoice_cmd()
+        .args([
+            "-C", cfg, "counter", "set", "--year", &year, "--next", "100",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Next invoice: INV-{year}-0100"
+        )));
+
+    invoice_cmd()
+        .args(["-C", cfg, "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("INV-{year}-0100")));
+
+    let generate = |extra: &[&str]| {
+        let mut cmd = invoice_cmd();
+        cmd.env("PATH", fake_typst_path(temp_dir.path())).args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:1",
+            "--allow-duplicate",
+        ]);
+        cmd.args(extra);
+        cmd
+    };
+
+    generate(&[])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Generated INV-{year}-0100"
+        )));
+
+    // A manual number ahead of the sequence moves the counter forward
+    let manual = format!("INV-{year}-0120");
+    generate(&["--number", &manual])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Generated {manual}")));
+
+    generate(&[])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Generated INV-{year}-0121"
+        )));
+
+    // Collisions are rejected, both for manual numbers and for the counter
+    generate(&["--number", &manual])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is already in use"));
+
+    invoice_cmd()
+        .args([
+            "-C", cfg, "counter", "set", "--year", &year, "--next", "120",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is already in use"));
+
+    // Numbers name files, so path characters are rejected
+    generate(&["--number", "../evil"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid invoice number '../evil'"));
+    assert!(!config_path.join("evil.pdf").exists());
+
+    let config_file = config_path.join("config.toml");
+    let config = fs::read_to_string(&config_file).unwrap().replace(
+        "number_format = \"INV-{year}-{seq:04}\"",
+        "number_format = \"INV/{year}/{seq:04}\"",
+    );
+    fs::write(&config_file, config).unwrap();
+    invoice_cmd()
+        .args(["-C", cfg, "counter", "set", "--next", "200"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid invoice number"));
+}
+
+#[test]
+fn test_generate_with_series_uses_its_own_counter() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    let config_file = config_path.join("config.toml");
+    let mut config = fs::read_to_string(&config_file).unwrap();
+    config.push_str("\n[series.retainer]\nnumber_format = \"RET-{year}-{seq:03}\"\n");
+    fs::write(&config_file, config).unwrap();
+
+    let generate = |series: Option<&str>| {
+        let mut cmd = invoice_cmd();
+        cmd.env("PATH", fake_typst_path(temp_dir.path())).args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:1",
+            "--allow-duplicate",
+        ]);
+        if let Some(series) = series {
+            cmd.args(["--series", series]);
+        }
+        cmd
+    };
+
+    generate(None)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Generated INV-{year}-0001"
+        )));
+    generate(Some("retainer"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Generated RET-{year}-001"
+        )));
+    generate(Some("retainer"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Generated RET-{year}-002"
+        )));
+    generate(None)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Generated INV-{year}-0002"
+        )));
+
+    generate(Some("credit"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Numbering series 'credit' not found",
+        ));
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("[series.retainer]"));
+    assert!(state.contains("series = \"retainer\""));
+
+    invoice_cmd()
+        .args(["-C", cfg, "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("INV-{year}-0003")))
+        .stdout(predicate::str::contains(format!("RET-{year}-003")));
+}
+
+#[test]
+fn test_remind_escalates_dunning_levels() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    // Default terms are net 30, so this invoice is 10 days overdue
+    let today = chrono::Local::now().date_naive();
+    let issued = today - chrono::Duration::days(40);
+    write_state(
+        &config_path,
+        &format!(
+            r#"[counter]
+last_number = 1
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "{issued}"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+"#
+        ),
+    );
+
+    invoice_cmd()
+        .args(["-C", cfg, "remind", "1", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Level 1 reminder for INV-2026-0001 (10 days overdue)",
+        ))
+        .stdout(predicate::str::contains("a friendly reminder"))
+        .stdout(predicate::str::contains("$1200.00"));
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(!state.contains("reminders"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "remind", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Level 1 reminder"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "remind", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("already sent"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("UNPAID [L1]"));
+
+    // Custom thresholds and templates from config.toml
+    let config_file = config_path.join("config.toml");
+    let mut config = fs::read_to_string(&config_file).unwrap();
+    config.push_str(
+        r#"
+[[dunning.levels]]
+days = 1
+message = "First notice for {number}"
+
+[[dunning.levels]]
+days = 5
+message = "Second notice: {number} is {days_overdue} days late"
+"#,
+    );
+    fs::write(&config_file, config).unwrap();
+
+    invoice_cmd()
+        .args(["-C", cfg, "remind", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Second notice: INV-2026-0001 is 10 days late",
+        ));
+}
+
+#[test]
+fn test_remind_requires_client_email() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let clients = config_path.join("clients.toml");
+    let contents = fs::read_to_string(&clients)
+        .unwrap()
+        .replace("email = \"jane@example.com\"", "email = \"\"");
+    fs::write(&clients, contents).unwrap();
+
+    let issued = chrono::Local::now().date_naive() - chrono::Duration::days(40);
+    write_state(
+        &config_path,
+        &format!(
+            r#"[counter]
+last_number = 1
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "{issued}"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+"#
+        ),
+    );
+
+    // Previewing works, but a reminder can't be sent without an address
+    invoice_cmd()
+        .args(["-C", cfg, "remind", "1", "--dry-run"])
+        .assert()
+        .success();
+    invoice_cmd()
+        .args(["-C", cfg, "remind", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("[ ] email: required to send"));
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(!state.contains("reminders"));
+}
+
+#[test]
+fn test_interest_accrues_from_due_date() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    // Net 30 terms: due 10 days ago, half paid 5 days ago
+    let today = chrono::Local::now().date_naive();
+    let issued = today - chrono::Duration::days(40);
+    let paid = today - chrono::Duration::days(5);
+    write_state(
+        &config_path,
+        &format!(
+            r#"[counter]
+last_number = 1
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "{issued}"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+
+[[history.payments]]
+amount = 600.0
+date = "{paid}"
+"#
+        ),
+    );
+
+    invoice_cmd()
+        .args(["-C", cfg, "interest", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No late interest rate configured"));
+
+    let config_file = config_path.join("config.toml");
+    let mut config = fs::read_to_string(&config_file).unwrap();
+    config.push_str("\n[interest]\nannual_rate = 0.0365\ninclude_in_reminders = true\n");
+    fs::write(&config_file, config).unwrap();
+
+    // 1200 * 3.65% * 5/365 + 600 * 3.65% * 5/365
+    invoice_cmd()
+        .args(["-C", cfg, "interest", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Days late:   10"))
+        .stdout(predicate::str::contains("Outstanding: $600.00"))
+        .stdout(predicate::str::contains("3.65% per year (actual/365)"))
+        .stdout(predicate::str::contains("Interest:    $0.90"));
+
+    invoice_cmd()
+        .args([
+            "-C",
+            cfg,
+            "interest",
+            "1",
+            "--rate",
+            "0.072",
+            "--day-count",
+            "actual/360",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Interest:    $1.80"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "remind", "1", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Late interest accrued to date: $0.90",
+        ));
+}
+
+#[test]
+fn test_payment_plan_agreement_and_adherence() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 1
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1000.0
+file = "INV-2026-0001.pdf"
+
+[[history.payments]]
+amount = 100.0
+date = "2026-01-20"
+"#,
+    );
+
+    invoice_cmd()
+        .args(["-C", cfg, "payment-plan", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("has no payment plan"));
+
+    let today = chrono::Local::now().date_naive();
+    let start = today - chrono::Months::new(2);
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            cfg,
+            "payment-plan",
+            "1",
+            "--installments",
+            "3",
+            "--start",
+            &start.to_string(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Balance:      $900.00"))
+        .stdout(predicate::str::contains(format!("1. {start}  $300.00")));
+
+    let agreement = fs::read_to_string(config_path.join("output/INV-2026-0001.plan.pdf")).unwrap();
+    assert!(agreement.contains("\"principal\":900.0"));
+    assert!(agreement.contains("\"installments\":[{\"index\":1"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "add-payment", "1", "300"])
+        .assert()
+        .success();
+
+    invoice_cmd()
+        .args(["-C", cfg, "payment-plan", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("PAID"))
+        .stdout(predicate::str::contains("LATE"))
+        .stdout(predicate::str::contains("UPCOMING"))
+        .stdout(predicate::str::contains(
+            "Behind schedule: 1 late installment(s)",
+        ));
+}
+
+/// Serve `body` as JSON to each of the next `count` HTTP requests on a local port
+#[cfg(feature = "network")]
+fn serve_json(body: &'static str, count: usize) -> String {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().take(count) {
+            let mut stream = stream.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    format!("http://{addr}/releases/latest")
+}
+
+#[cfg(feature = "network")]
+#[test]
+fn test_self_update_checks_latest_release() {
+    let url = serve_json(r#"{"tag_name":"v0.0.1","assets":[]}"#, 1);
+    invoice_cmd()
+        .env("INVOICE_RELEASES_URL", url)
+        .args(["self-update", "--check"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Already up to date (latest release: 0.0.1)",
+        ));
+
+    let url = serve_json(r#"{"tag_name":"v999.0.0","assets":[]}"#, 2);
+    invoice_cmd()
+        .env("INVOICE_RELEASES_URL", &url)
+        .args(["self-update", "--check"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Update available: 999.0.0"));
+
+    // Never touches the binary when the platform asset is missing
+    invoice_cmd()
+        .env("INVOICE_RELEASES_URL", &url)
+        .arg("self-update")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "release 999.0.0 has no asset 'invoice-",
+        ));
+}
+
+#[test]
+fn test_version_verbose_shows_build_info() {
+    let temp_dir = TempDir::new().unwrap();
+
+    invoice_cmd()
+        .arg("version")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("invoice "))
+        .stdout(predicate::str::contains("Commit:").not());
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["version", "--verbose"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Commit:"))
+        .stdout(predicate::str::contains("Built:"))
+        .stdout(predicate::str::contains("Features:"))
+        .stdout(predicate::str::contains("Typst:      typst 0.0.0 (fake)"));
+}
+
+#[test]
+fn test_deterministic_regenerate_is_byte_identical() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:2",
+            "--deterministic",
+        ])
+        .assert()
+        .success();
+
+    let year = chrono::Local::now().format("%Y").to_string();
+    let pdf = config_path.join(format!("output/INV-{year}-0001.pdf"));
+    let first = fs::read(&pdf).unwrap();
+
+    // Pinned to midnight UTC of the invoice date
+    let midnight = chrono::Local::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp();
+    assert!(String::from_utf8_lossy(&first).contains(&format!("SOURCE_DATE_EPOCH={midnight}")));
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "regenerate", "1", "--deterministic"])
+        .assert()
+        .success();
+    assert_eq!(first, fs::read(&pdf).unwrap());
+    let state_path = config_path.join("state.toml");
+    assert!(fs::read_to_string(&state_path)
+        .unwrap()
+        .contains("deterministic = true"));
+
+    // Without the flag nothing is pinned, unless [pdf] deterministic is set
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "regenerate", "1"])
+        .assert()
+        .success();
+    assert!(!fs::read_to_string(&pdf)
+        .unwrap()
+        .contains("SOURCE_DATE_EPOCH"));
+    assert!(!fs::read_to_string(&state_path)
+        .unwrap()
+        .contains("deterministic"));
+
+    let config_file = config_path.join("config.toml");
+    let config = fs::read_to_string(&config_file).unwrap().replace(
+        "output_dir = \"./output\"",
+        "output_dir = \"./output\"\ndeterministic = true",
+    );
+    fs::write(&config_file, config).unwrap();
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "regenerate", "1"])
+        .assert()
+        .success();
+    assert_eq!(first, fs::read(&pdf).unwrap());
+}
+
+#[test]
+fn test_verify_files_against_manifest() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    for qty in ["consulting:1", "consulting:2"] {
+        invoice_cmd()
+            .env("PATH", fake_typst_path(temp_dir.path()))
+            .args([
+                "-C",
+                cfg,
+                "generate",
+                "--client",
+                "example-client",
+                "--item",
+                qty,
+            ])
+            .assert()
+            .success();
+    }
+
+    let output_dir = config_path.join("output");
+    let manifest = fs::read_to_string(output_dir.join("manifest.toml")).unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+    assert!(manifest.contains(&format!("[invoices.INV-{year}-0001]")));
+
+    invoice_cmd()
+        .args(["-C", cfg, "verify-files"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All 2 file(s) match the manifest"));
+
+    fs::write(output_dir.join(format!("INV-{year}-0001.pdf")), "tampered").unwrap();
+    fs::remove_file(output_dir.join(format!("INV-{year}-0002.pdf"))).unwrap();
+    fs::write(output_dir.join("stray.pdf"), "stray").unwrap();
+
+    invoice_cmd()
+        .args(["-C", cfg, "verify-files"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(format!(
+            "MODIFIED  INV-{year}-0001.pdf"
+        )))
+        .stdout(predicate::str::contains(format!(
+            "MISSING   INV-{year}-0002.pdf"
+        )))
+        .stdout(predicate::str::contains("ORPHANED  stray.pdf"))
+        .stderr(predicate::str::contains("3 file problem(s) found"));
+
+    fs::remove_file(output_dir.join("stray.pdf")).unwrap();
+    invoice_cmd()
+        .args(["-C", cfg, "verify-files", "--update"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Manifest rebuilt with 1 file(s)"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "verify-files"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_regenerate_all_logs_progress_lines() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "init"])
+        .assert()
+        .success();
+
+    for item in ["consulting:1", "consulting:2"] {
+        invoice_cmd()
+            .env("PATH", &path)
+            .args([
+                "-C",
+                config_path.to_str().unwrap(),
+                "generate",
+                "--client",
+                "example-client",
+                "--item",
+                item,
+            ])
+            .assert()
+            .success();
+    }
+
+    // stderr is not a terminal here, so progress comes out as logfmt lines
+    let year = chrono::Local::now().format("%Y").to_string();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", config_path.to_str().unwrap(), "regenerate", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Regenerated 2 of 2 invoice(s)"))
+        .stderr(predicate::str::contains(format!(
+            "task=regenerate item=INV-{year}-0001 status=ok progress=1/2"
+        )))
+        .stderr(predicate::str::contains(format!(
+            "task=regenerate item=INV-{year}-0002 status=ok progress=2/2"
+        )));
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "regenerate"])
+        .assert()
+        .failure();
+}
+
+#[cfg(feature = "notify")]
+#[test]
+fn test_notify_due_is_quiet_when_nothing_pending() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    // One invoice is paid, the other isn't due for years
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 2
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 500.0
+file = "INV-2026-0001.pdf"
+payments = [{ amount = 500.0, date = "2026-01-20" }]
+
+[[history]]
+number = "INV-2026-0002"
+client = "example-client"
+date = "2099-01-10"
+total = 750.0
+file = "INV-2026-0002.pdf"
+"#,
+    );
+
+    invoice_cmd()
+        .args(["-C", cfg, "notify-due", "--days", "7"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn test_generate_item_description_override() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+
+    invoice_cmd()
+        .args(["-C", config_path.to_str().unwrap(), "init"])
+        .assert()
+        .success();
+
+    let output = temp_dir.path().join("out.pdf");
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            config_path.to_str().unwrap(),
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            r#"consulting:"Audit: phase 1":2"#,
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+
+    let data = fs::read_to_string(&output).unwrap();
+    assert!(data.contains(r#""description":"Audit: phase 1""#));
+    assert!(data.contains(r#""amount":300.0"#));
+
+    // The raw spec is kept so regeneration reproduces the same line
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains(r#"'consulting:"Audit: phase 1":2'"#));
+}
+
+#[test]
+fn test_generate_service_period() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    let output = temp_dir.path().join("feb.pdf");
+    invoice_cmd()
+        .env("PATH", &path)
+        .args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:1",
+            "--period",
+            "2026-02",
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+    let data = fs::read_to_string(&output).unwrap();
+    assert!(data.contains(r#""service_period":"Feb 1–28, 2026""#));
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("from = \"2026-02-01\""));
+    assert!(state.contains("to = \"2026-02-28\""));
+
+    let output = temp_dir.path().join("range.pdf");
+    invoice_cmd()
+        .env("PATH", &path)
+        .args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:1",
+            "--period-from",
+            "2025-12-15",
+            "--period-to",
+            "2026-01-14",
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+    let data = fs::read_to_string(&output).unwrap();
+    assert!(data.contains(r#""service_period":"Dec 15, 2025 – Jan 14, 2026""#));
+
+    invoice_cmd()
+        .args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:1",
+            "--period-from",
+            "2026-02-01",
+            "--period-to",
+            "2026-01-01",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid service period"));
+}
+
+#[test]
+fn test_generate_uses_client_delivery_preferences() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    fs::write(
+        config_path.join("clients.toml"),
+        r#"[acme]
+name = "Acme GmbH"
+email = "ap@acme.example"
+address = "Hauptstrasse 1"
+city = "Berlin"
+state = "BE"
+zip = "10115"
+country = "Germany"
+delivery = "postal"
+formats = ["pdf", "json"]
+language = "de"
+"#,
+    )
+    .unwrap();
+
+    let year = chrono::Local::now().format("%Y").to_string();
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "acme",
+            "--item",
+            "consulting:1",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Deliver: print and mail to Hauptstrasse 1, Berlin, BE 10115, Germany",
+        ));
+
+    // The client's formats apply when --formats isn't given
+    let output_dir = config_path.join("output");
+    let pdf = fs::read_to_string(output_dir.join(format!("INV-{year}-0001.pdf"))).unwrap();
+    assert!(pdf.contains(r#""language":"de""#));
+    assert!(output_dir.join(format!("INV-{year}-0001.json")).exists());
+    assert!(!output_dir.join(format!("INV-{year}-0001.html")).exists());
+}
+
+#[test]
+fn test_invoice_notes_add_list_search() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 2
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 500.0
+file = "INV-2026-0001.pdf"
+items = ["consulting:4"]
+
+[[history]]
+number = "INV-2026-0002"
+client = "example-client"
+date = "2026-01-12"
+total = 750.0
+file = "INV-2026-0002.pdf"
+"#,
+    );
+
+    invoice_cmd()
+        .env("USER", "alice")
+        .args([
+            "-C",
+            cfg,
+            "note",
+            "add",
+            "INV-2026-0001",
+            "Client disputed line 2",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added note to INV-2026-0001"));
+    invoice_cmd()
+        .args(["-C", cfg, "note", "add", "INV-2026-0002", "Paid by check"])
+        .assert()
+        .success();
+
+    invoice_cmd()
+        .args(["-C", cfg, "note", "list", "INV-2026-0001"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("alice  Client disputed line 2"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "show", "INV-2026-0001"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Internal notes (not on the invoice):",
+        ))
+        .stdout(predicate::str::contains("alice  Client disputed line 2"))
+        .stdout(predicate::str::contains("Paid by check").not());
+
+    invoice_cmd()
+        .args(["-C", cfg, "note", "search", "DISPUTED"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("INV-2026-0001"))
+        .stdout(predicate::str::contains("INV-2026-0002").not());
+
+    invoice_cmd()
+        .args(["-C", cfg, "note", "add", "INV-2026-0001", "  "])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Note text cannot be empty"));
+}
+
+#[test]
+fn test_followups_show_in_overdue_list() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 3
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 500.0
+file = "INV-2026-0001.pdf"
+
+[[history]]
+number = "INV-2026-0002"
+client = "example-client"
+date = "2026-01-12"
+total = 750.0
+file = "INV-2026-0002.pdf"
+payments = [{ amount = 750.0, date = "2026-01-20" }]
+
+[[history]]
+number = "INV-2099-0001"
+client = "example-client"
+date = "2099-01-12"
+total = 300.0
+file = "INV-2099-0001.pdf"
+"#,
+    );
+
+    invoice_cmd()
+        .args([
+            "-C",
+            cfg,
+            "followup",
+            "add",
+            "INV-2026-0001",
+            "--via",
+            "phone",
+            "--note",
+            "promised payment Friday",
+            "--date",
+            "2026-02-20",
+            "--next",
+            "2026-02-27",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Next follow-up: 2026-02-27"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "followup", "list", "INV-2026-0001"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "2026-02-20  phone  promised payment Friday  (next: 2026-02-27)",
+        ));
+
+    // Only the unpaid, past-due invoice is listed, keeping its 'list' index
+    invoice_cmd()
+        .args(["-C", cfg, "list", "--overdue"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("LAST FOLLOW-UP"))
+        .stdout(predicate::str::contains("2026-02-20 (phone)"))
+        .stdout(predicate::str::contains("2026-02-27"))
+        .stdout(predicate::str::contains("│ 3 │ INV-2026-0001"))
+        .stdout(predicate::str::contains("INV-2026-0002").not())
+        .stdout(predicate::str::contains("INV-2099-0001").not())
+        .stdout(predicate::str::contains(
+            "Overdue: $500.00 across 1 invoice(s)",
+        ));
+}
+
+#[test]
+fn test_board_groups_invoices_by_status() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    let today = chrono::Local::now().date_naive();
+    write_state(
+        &config_path,
+        &format!(
+            r#"[counter]
+last_number = 5
+last_year = 2026
+
+[[history]]
+number = "INV-OLD-PAID"
+client = "example-client"
+date = "2020-01-10"
+total = 100.0
+file = "a.pdf"
+payments = [{{ amount = 100.0, date = "2020-01-20" }}]
+
+[[history]]
+number = "INV-OVERDUE"
+client = "example-client"
+date = "2020-02-10"
+total = 400.0
+file = "b.pdf"
+payments = [{{ amount = 100.0, date = "2020-02-20" }}]
+
+[[history]]
+number = "INV-PAID-NOW"
+client = "example-client"
+date = "{today}"
+total = 250.0
+file = "c.pdf"
+payments = [{{ amount = 250.0, date = "{today}" }}]
+
+[[history]]
+number = "INV-PARTIAL"
+client = "example-client"
+date = "{today}"
+total = 1000.0
+file = "d.pdf"
+payments = [{{ amount = 400.0, date = "{today}" }}]
+
+[[history]]
+number = "INV-UNPAID"
+client = "example-client"
+date = "{today}"
+total = 750.0
+file = "e.pdf"
+"#
+        ),
+    );
+
+    let output = invoice_cmd()
+        .args(["-C", cfg, "board"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let board = String::from_utf8(output).unwrap();
+
+    assert!(board.contains("UNPAID (1)"));
+    assert!(board.contains("PARTIAL (1)"));
+    assert!(board.contains("OVERDUE (1)"));
+    assert!(board.contains("PAID (THIS MONTH) (1)"));
+    assert!(!board.contains("INV-OLD-PAID"));
+
+    // Cards sit in their status column and show what's still owed
+    let row = board
+        .lines()
+        .find(|line| line.contains("INV-UNPAID"))
+        .unwrap();
+    let cells: Vec<&str> = row.split('│').map(str::trim).collect();
+    assert_eq!(
+        &cells[1..5],
+        [
+            "INV-UNPAID $750.00",
+            "INV-PARTIAL $600.00",
+            "INV-OVERDUE $300.00",
+            "INV-PAID-NOW $250.00"
+        ]
+    );
+}
+
+#[test]
+fn test_monthly_goal_progress() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    // No [goals] section: nothing shown
+    invoice_cmd()
+        .args(["-C", cfg, "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Monthly goal").not());
+
+    let config_file = config_path.join("config.toml");
+    let config = fs::read_to_string(&config_file).unwrap();
+    fs::write(
+        &config_file,
+        format!("{config}\n[goals]\nmonthly_revenue = 4000\n"),
+    )
+    .unwrap();
+
+    let today = chrono::Local::now().date_naive();
+    write_state(
+        &config_path,
+        &format!(
+            r#"[counter]
+last_number = 3
+last_year = 2026
+
+[[history]]
+number = "INV-OLD"
+client = "example-client"
+date = "2020-01-10"
+total = 5000.0
+file = "a.pdf"
+payments = [{{ amount = 5000.0, date = "2020-01-20" }}]
+
+[[history]]
+number = "INV-A"
+client = "example-client"
+date = "{today}"
+total = 1000.0
+file = "b.pdf"
+payments = [{{ amount = 1000.0, date = "{today}" }}]
+
+[[history]]
+number = "INV-B"
+client = "example-client"
+date = "{today}"
+total = 1000.0
+file = "c.pdf"
+"#
+        ),
+    );
+
+    invoice_cmd()
+        .args(["-C", cfg, "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Monthly goal ({}):",
+            today.format("%B %Y")
+        )))
+        .stdout(predicate::str::contains(
+            "Invoiced:  [##########----------]  50%  $2000.00 of $4000.00",
+        ))
+        .stdout(predicate::str::contains(
+            "Collected: [#####---------------]  25%  $1000.00",
+        ))
+        .stdout(predicate::str::contains("Projected: $"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Monthly goal: [##########----------] 50% invoiced, 25% collected",
+        ));
+}
+
+#[test]
+fn test_currency_symbol_suffix_with_space() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    let config_file = config_path.join("config.toml");
+    let config = fs::read_to_string(&config_file).unwrap().replace(
+        "currency_symbol = \"$\"",
+        "currency_symbol = \"€\"\nsymbol_position = \"suffix\"\nsymbol_space = true",
+    );
+    fs::write(&config_file, config).unwrap();
+
+    let output = temp_dir.path().join("out.pdf");
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:8",
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Total:  1200.00 €"));
+
+    // The template reads the placement from the data file
+    let data = fs::read_to_string(&output).unwrap();
+    assert!(data.contains(r#""symbol_position":"suffix""#));
+    assert!(data.contains(r#""symbol_space":true"#));
+
+    invoice_cmd()
+        .args(["-C", cfg, "add-payment", "1", "200"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Recorded 200.00 € payment for INV-{}-0001 (1000.00 € remaining)",
+            chrono::Local::now().format("%Y")
+        )));
+}
+
+#[test]
+fn test_generate_rejects_duplicate_content() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    let generate = |items: &[&str], extra: &[&str]| {
+        let mut cmd = invoice_cmd();
+        cmd.env("PATH", fake_typst_path(temp_dir.path())).args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "example-client",
+        ]);
+        for item in items {
+            cmd.args(["--item", item]);
+        }
+        cmd.args(extra);
+        cmd
+    };
+
+    generate(&["consulting:8", "development:2"], &[])
+        .assert()
+        .success();
+
+    // Same items in another order and spelling are still the same bill
+    generate(&["development: 2", "consulting:4x2"], &[])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Invoice INV-{year}-0001 already bills 'example-client' for the same items this month"
+        )))
+        .stderr(predicate::str::contains("--allow-duplicate"));
+
+    // Different quantities or a different service period are not duplicates
+    generate(&["consulting:8"], &[]).assert().success();
+    generate(&["consulting:8", "development:2"], &["--period", "2020-01"])
+        .assert()
+        .success();
+
+    generate(&["consulting:8", "development:2"], &["--allow-duplicate"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Generated INV-{year}-0004"
+        )));
+
+    // A voided invoice doesn't block issuing the corrected one
+    generate(&["consulting:8"], &[]).assert().failure();
+    invoice_cmd()
+        .args(["-C", cfg, "void", &format!("INV-{year}-0002")])
+        .assert()
+        .success();
+    generate(&["consulting:8"], &[])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Generated INV-{year}-0005"
+        )));
+}
+
+#[test]
+fn test_add_payment_as_credit() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 1
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1000.0
+file = "INV-2026-0001.pdf"
+payments = [{ amount = 900.0, date = "2026-01-20" }]
+"#,
+    );
+
+    // Without --as-credit an overpayment is still rejected
+    invoice_cmd()
+        .args(["-C", cfg, "add-payment", "INV-2026-0001", "150"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--as-credit"));
+
+    invoice_cmd()
+        .args([
+            "-C",
+            cfg,
+            "add-payment",
+            "INV-2026-0001",
+            "150",
+            "--as-credit",
+            "--date",
+            "2026-02-01",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Recorded $150.00 payment for INV-2026-0001 (fully paid, $50.00 kept as client credit)",
+        ));
+
+    invoice_cmd()
+        .args(["-C", cfg, "payments", "INV-2026-0001"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Total paid: $1000.00 / $1000.00 (Status: PAID)",
+        ));
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("[[credits]]"));
+    assert!(state.contains("invoice = \"INV-2026-0001\""));
+
+    invoice_cmd()
+        .args(["-C", cfg, "clients"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("CREDIT"))
+        .stdout(predicate::str::contains("$50.00"));
+
+    // Removing the payment takes its credit back with it
+    invoice_cmd()
+        .args(["-C", cfg, "remove-payment", "INV-2026-0001"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Removed $100.00 payment from INV-2026-0001",
+        ))
+        .stdout(predicate::str::contains(
+            "Withdrew the $50.00 client credit it created",
+        ));
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(!state.contains("[[credits]]"));
+}
+
+#[test]
+fn test_merge_state_unions_histories() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 2
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1000.0
+file = "INV-2026-0001.pdf"
+
+[[history.payments]]
+amount = 400.0
+date = "2026-01-20"
+
+[[history]]
+number = "INV-2026-0002"
+client = "example-client"
+date = "2026-01-15"
+total = 500.0
+file = "INV-2026-0002.pdf"
+"#,
+    );
+    let other = temp_dir.path().join("state (conflicted copy).toml");
+    fs::write(
+        &other,
+        r#"[counter]
+last_number = 3
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1000.0
+file = "INV-2026-0001.pdf"
+
+[[history.payments]]
+amount = 400.0
+date = "2026-01-20"
+
+[[history.payments]]
+amount = 600.0
+date = "2026-02-01"
+
+[[history]]
+number = "INV-2026-0002"
+client = "example-client"
+date = "2026-01-15"
+total = 550.0
+file = "INV-2026-0002.pdf"
+
+[[history]]
+number = "INV-2026-0003"
+client = "example-client"
+date = "2026-01-18"
+total = 300.0
+file = "INV-2026-0003.pdf"
+"#,
+    )
+    .unwrap();
+    let before = fs::read_to_string(config_path.join("state.toml")).unwrap();
+
+    invoice_cmd()
+        .args([
+            "-C",
+            cfg,
+            "merge-state",
+            other.to_str().unwrap(),
+            "--dry-run",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Added:     1 invoice(s) (INV-2026-0003)",
+        ))
+        .stdout(predicate::str::contains(
+            "Updated:   1 invoice(s) (INV-2026-0001)",
+        ))
+        .stdout(predicate::str::contains(
+            "INV-2026-0002 total: '500.00' here, '550.00' in",
+        ))
+        .stdout(predicate::str::contains("INV-2026-0001 2026-02-01 600.00"))
+        .stdout(predicate::str::contains("dry run"));
+    assert_eq!(
+        fs::read_to_string(config_path.join("state.toml")).unwrap(),
+        before
+    );
+
+    invoice_cmd()
+        .args(["-C", cfg, "merge-state", other.to_str().unwrap()])
+        .assert()
+        .success();
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("INV-2026-0003"));
+    assert!(state.contains("last_number = 3"));
+    assert!(state.contains("amount = 600.0"));
+    assert!(state.contains("total = 500.0"));
+
+    // Merging again adds nothing
+    invoice_cmd()
+        .args(["-C", cfg, "merge-state", other.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added:     0 invoice(s)"))
+        .stdout(predicate::str::contains("INV-2026-0002 total"));
+
+    // An invoice trashed here isn't brought back by the other copy
+    invoice_cmd()
+        .args(["-C", cfg, "trash", "add", "INV-2026-0003"])
+        .assert()
+        .success();
+    invoice_cmd()
+        .args(["-C", cfg, "merge-state", other.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added:     0 invoice(s)"))
+        .stdout(predicate::str::contains(
+            "INV-2026-0003 history: 'in the trash' here, 'issued' in",
+        ));
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(!state.contains("INV-2026-0003"));
+}
+
+#[test]
+fn test_compact_normalizes_state() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    write_state(
+        &config_path,
+        r#"# Hand-edited over the years
+[counter]
+last_number = 3
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0003"
+client = "example-client"
+date = "2026-03-01"
+total = 0.30000000000000004
+file = "INV-2026-0003.pdf"
+payments = [
+    { amount = 0.2, date = "2026-03-20" },
+    { amount = 0.10000000000000001, date = "2026-03-10" },
+]
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-05"
+total = 500.0
+file = "INV-2026-0001.pdf"
+paid = true
+"#,
+    );
+
+    invoice_cmd()
+        .args(["-C", cfg, "compact"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Invoices: 2"))
+        .stdout(predicate::str::contains("Size:     465 -> "));
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(!state.contains("0.30000000000000004"));
+    assert!(!state.contains("paid = true"));
+    assert!(state.contains("total = 0.3\n"));
+
+    // Oldest invoice first, legacy `paid` migrated to a payment
+    let first = state.find("INV-2026-0001").unwrap();
+    let second = state.find("INV-2026-0003").unwrap();
+    assert!(first < second);
+    assert!(state.find("2026-03-10").unwrap() < state.find("2026-03-20").unwrap());
+
+    invoice_cmd()
+        .args(["-C", cfg, "payments", "INV-2026-0001"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Status: PAID"));
+
+    // A second run has nothing left to change
+    invoice_cmd()
+        .args(["-C", cfg, "compact"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(unchanged)"));
+}
+
+#[test]
+fn test_clean_output_removes_orphaned_pdfs() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 1
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 100.0
+file = "INV-2026-0001.pdf"
+"#,
+    );
+    let output_dir = config_path.join("output");
+    fs::create_dir_all(&output_dir).unwrap();
+    for name in [
+        "INV-2026-0001.pdf",
+        "INV-2026-0001-draft.pdf",
+        "old-name.pdf",
+        "REPORT-example-client-2026-01-31.pdf",
+        "STATEMENT-example-client-2026-01-31.pdf",
+        "AGING-2026-01-31.pdf",
+        "notes.txt",
+    ] {
+        fs::write(output_dir.join(name), "x").unwrap();
+    }
+
+    invoice_cmd()
+        .args(["-C", cfg, "clean-output", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("INV-2026-0001-draft.pdf"))
+        .stdout(predicate::str::contains("old-name.pdf"))
+        .stdout(predicate::str::contains("REPORT-").not())
+        .stdout(predicate::str::contains("STATEMENT-").not())
+        .stdout(predicate::str::contains("AGING-").not())
+        .stdout(predicate::str::contains("notes.txt").not())
+        .stdout(predicate::str::contains("2 file(s) would be deleted"));
+    assert!(output_dir.join("old-name.pdf").exists());
+
+    // Declining keeps everything
+    assert_cmd::Command::from_std(invoice_cmd())
+        .args(["-C", cfg, "clean-output"])
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing deleted."));
+    assert!(output_dir.join("old-name.pdf").exists());
+
+    assert_cmd::Command::from_std(invoice_cmd())
+        .args(["-C", cfg, "clean-output"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deleted 2 file(s)"));
+    assert!(!output_dir.join("old-name.pdf").exists());
+    assert!(!output_dir.join("INV-2026-0001-draft.pdf").exists());
+    assert!(output_dir.join("INV-2026-0001.pdf").exists());
+    assert!(output_dir
+        .join("REPORT-example-client-2026-01-31.pdf")
+        .exists());
+}
+
+#[test]
+fn test_generate_from_stdin_spec() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    let output = temp_dir.path().join("toml.pdf");
+    assert_cmd::Command::from_std(invoice_cmd())
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            cfg,
+            "generate",
+            "--stdin",
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .write_stdin(
+            r#"client = "example-client"
+items = [
+    "consulting:2x4",
+    { item = "development", description = "API: v2", quantity = 10 },
+]
+period = { from = "2026-01-01", to = "2026-01-31" }
+"#,
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Generated INV-{year}-0001"
+        )))
+        .stdout(predicate::str::contains("Period: Jan 1–31, 2026"));
+
+    let data = fs::read_to_string(&output).unwrap();
+    assert!(data.contains(r#""description":"API: v2""#));
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains(r#"'development:"API: v2":10'"#));
+
+    // JSON works too, and fields outside the data model are rejected
+    let output = temp_dir.path().join("json.pdf");
+    assert_cmd::Command::from_std(invoice_cmd())
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args([
+            "-C",
+            cfg,
+            "generate",
+            "--stdin",
+            "--output",
+            output.to_str().unwrap(),
+        ])
+        .write_stdin(
+            r#"{"client": "example-client", "items": ["consulting:1"], "number": "CUSTOM-1"}"#,
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Generated CUSTOM-1"));
+
+    assert_cmd::Command::from_std(invoice_cmd())
+        .args(["-C", cfg, "generate", "--stdin"])
+        .write_stdin(r#"{"client": "example-client", "items": [], "discount": 5}"#)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid invoice spec"))
+        .stderr(predicate::str::contains("discount"));
+}
+
+#[test]
+fn test_template_check_warns_about_deprecated_fields() {
+    let temp_dir = TempDir::new().unwrap();
+    let template = temp_dir.path().join("cover.typ");
+    fs::write(
+        &template,
+        r#"#let data = json("DATA_JSON_PATH")
+Dear #data.client.name,
+Tax: #data.tax_rate% of #data.at("subtotal")
+#data.colour
+"#,
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .args(["template", "check", template.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "cover.typ:3: data.tax_rate is deprecated since schema 1; use data.tax_percent",
+        ))
+        .stdout(predicate::str::contains(
+            "cover.typ:4: data.colour is not part of the invoice data contract",
+        ))
+        .stdout(predicate::str::contains("subtotal").not())
+        .stdout(predicate::str::contains("2 warning(s)"));
+
+    fs::write(&template, "#data.tax_percent\n").unwrap();
+    invoice_cmd()
+        .args(["template", "check", template.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("compatible with data schema 1"));
+}
+
+#[test]
+fn test_projects_warn_when_fixed_price_exceeded() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let config_file = config_path.join("config.toml");
+    let config = fs::read_to_string(&config_file).unwrap();
+    fs::write(
+        &config_file,
+        format!("{config}\n[projects.website]\ndescription = \"Website redesign\"\nfixed_price = 1000\n"),
+    )
+    .unwrap();
+    let items_file = config_path.join("items.toml");
+    let items = fs::read_to_string(&items_file).unwrap();
+    fs::write(
+        &items_file,
+        format!("{items}\n[milestone]\ndescription = \"Milestone\"\nrate = 600.0\nunit = \"flat\"\nproject = \"website\"\n"),
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "milestone:1"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Warning").not());
+
+    invoice_cmd()
+        .args(["-C", cfg, "projects"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Website redesign"))
+        .stdout(predicate::str::contains("$600.00"))
+        .stdout(predicate::str::contains("$400.00"));
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "milestone:Final milestone:1"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Warning: project 'website' has been invoiced $1200.00, over its fixed price of $1000.00",
+        ));
+
+    invoice_cmd()
+        .args(["-C", cfg, "projects"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("$200.00 over"));
+}
+
+#[test]
+fn test_consolidate_supersedes_unpaid_invoices() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    for quantity in ["1", "2", "3"] {
+        invoice_cmd()
+            .env("PATH", &path)
+            .args(["-C", cfg, "generate", "--client", "example-client"])
+            .args(["--item", &format!("consulting:{quantity}")])
+            .assert()
+            .success();
+    }
+    invoice_cmd()
+        .args(["-C", cfg, "add-payment", &format!("INV-{year}-0003"), "10"])
+        .assert()
+        .success();
+
+    // Invoices with payments can't be merged
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "consolidate", "--client", "example-client"])
+        .args(["--invoices", &format!("INV-{year}-0002,INV-{year}-0003")])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already has payments"));
+
+    // Indexes from 'list': 2 and 3 are the second and first invoices
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "consolidate", "--client", "example-client"])
+        .args(["--invoices", "2,3"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Generated INV-{year}-0004"
+        )))
+        .stdout(predicate::str::contains("$450.00"))
+        .stdout(predicate::str::contains(format!(
+            "Supersedes: INV-{year}-0002, INV-{year}-0001"
+        )));
+
+    invoice_cmd()
+        .args(["-C", cfg, "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("SUPERSEDED"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "add-payment", &format!("INV-{year}-0001"), "150"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "superseded by INV-{year}-0004"
+        )));
+}
+
+#[test]
+fn test_client_display_number_format() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let clients_file = config_path.join("clients.toml");
+    let clients = fs::read_to_string(&clients_file).unwrap();
+    fs::write(
+        &clients_file,
+        format!("{clients}display_number_format = \"EX-{{year}}-{{seq:03}} ({{number}})\"\n"),
+    )
+    .unwrap();
+
+    for quantity in ["1", "2"] {
+        invoice_cmd()
+            .env("PATH", &path)
+            .args(["-C", cfg, "generate", "--client", "example-client"])
+            .args(["--item", &format!("consulting:{quantity}")])
+            .assert()
+            .success();
+    }
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains(&format!(
+        "display_number = \"EX-{year}-002 (INV-{year}-0002)\""
+    )));
+
+    // Regenerating keeps the recorded number even if the format changes
+    fs::write(
+        &clients_file,
+        format!("{clients}display_number_format = \"OTHER-{{seq}}\"\n"),
+    )
+    .unwrap();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "regenerate", &format!("INV-{year}-0001")])
+        .assert()
+        .success();
+    let pdf = fs::read_to_string(
+        config_path
+            .join("output")
+            .join(format!("INV-{year}-0001.pdf")),
+    )
+    .unwrap();
+    assert!(pdf.contains(&format!(
+        "\"display_number\":\"EX-{year}-001 (INV-{year}-0001)\""
+    )));
+}
+
+#[test]
+fn test_item_kinds_group_invoice_lines() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let items_file = config_path.join("items.toml");
+    let items = fs::read_to_string(&items_file).unwrap();
+    fs::write(
+        &items_file,
+        format!("{items}\n[travel]\ndescription = \"Travel\"\nrate = 1.0\nunit = \"dollar\"\nkind = \"expense\"\n"),
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .args(["-C", cfg, "items"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("expense"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "travel:80", "--item", "consulting:2"])
+        .args(["--formats", "html,json"])
+        .assert()
+        .success();
+
+    let output_dir = config_path.join("output");
+    let html = fs::read_to_string(output_dir.join(format!("INV-{year}-0001.html"))).unwrap();
+    let services = html.find(">Services<").expect("services heading");
+    let expenses = html
+        .find(">Reimbursable Expenses<")
+        .expect("expenses heading");
+    assert!(services < html.find("Technical Consulting").unwrap());
+    assert!(expenses > html.find("Technical Consulting").unwrap());
+    assert!(expenses < html.find("Travel").unwrap());
+
+    let json = fs::read_to_string(output_dir.join(format!("INV-{year}-0001.json"))).unwrap();
+    assert!(json.contains("\"kind\": \"expense\""));
+}
+
+#[test]
+fn test_retention_held_and_released() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:10", "--retention", "10%"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Retained: $150.00"));
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("total = 1350.0"));
+    assert!(state.contains("[history.retention]"));
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "release-retention", "--client", "example-client"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Generated INV-{year}-0002"
+        )))
+        .stdout(predicate::str::contains("$150.00"))
+        .stdout(predicate::str::contains(format!(
+            "Releases: INV-{year}-0001"
+        )));
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains(&format!("released_by = \"INV-{year}-0002\"")));
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "release-retention", "--client", "example-client"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No retention held"));
+}
+
+#[test]
+fn test_deposit_deducted_from_later_project_invoice() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let config_file = config_path.join("config.toml");
+    let config = fs::read_to_string(&config_file).unwrap();
+    fs::write(
+        &config_file,
+        format!("{config}\n[projects.website]\ndescription = \"Website redesign\"\nfixed_price = 1000\n"),
+    )
+    .unwrap();
+    let items_file = config_path.join("items.toml");
+    let items = fs::read_to_string(&items_file).unwrap();
+    fs::write(
+        &items_file,
+        format!("{items}\n[milestone]\ndescription = \"Milestone\"\nrate = 600.0\nunit = \"flat\"\nproject = \"website\"\n"),
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "deposit", "--client", "example-client"])
+        .args(["--project", "website", "--percent", "40%"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Total:  $400.00"));
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "milestone:1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Total:  $200.00"))
+        .stdout(predicate::str::contains(format!(
+            "Deducts: INV-{year}-0001"
+        )));
+
+    let pdf = fs::read_to_string(config_path.join(format!("output/INV-{year}-0002.pdf"))).unwrap();
+    assert!(pdf.contains(&format!(
+        "Less deposit invoiced on INV-{year}-0001 (40% of website)"
+    )));
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains(&format!("deducted_by = \"INV-{year}-0002\"")));
+
+    invoice_cmd()
+        .args(["-C", cfg, "deposit", "--client", "example-client"])
+        .args(["--project", "mobile-app", "--percent", "40"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Project 'mobile-app' not found"));
+}
+
+#[test]
+fn test_true_up_bills_hours_beyond_retainer() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let timesheet = temp_dir.path().join("hours.csv");
+    fs::write(
+        &timesheet,
+        "Date,Hours\n2026-02-26,4\n2026-03-02,8\n2026-03-16,4:30\n",
+    )
+    .unwrap();
+    let file = timesheet.to_str().unwrap();
+
+    invoice_cmd()
+        .args(["-C", cfg, "true-up", file, "--client", "example-client"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Client 'example-client' has no retainer",
+        ));
+
+    let clients_file = config_path.join("clients.toml");
+    let clients = fs::read_to_string(&clients_file).unwrap();
+    fs::write(
+        &clients_file,
+        format!("{clients}retainer = {{ included_hours = 10, overage_item = \"consulting\" }}\n"),
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .args(["-C", cfg, "true-up", file, "--client", "example-client"])
+        .args(["--period", "2026-02", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "4 hours tracked, 10 included, 0 over",
+        ))
+        .stdout(predicate::str::contains("Nothing to bill"));
+
+    // March: 12.5 hours, 2.5 of them beyond the retainer
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "true-up", file, "--client", "example-client"])
+        .args(["--period", "2026-03"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "12.5 hours tracked, 10 included, 2.5 over",
+        ))
+        .stdout(predicate::str::contains("Total:  $375.00"));
+
+    let data = fs::read_to_string(
+        config_path
+            .join("output")
+            .join(format!("INV-{year}-0001.pdf")),
+    )
+    .unwrap();
+    assert!(
+        data.contains("Hours beyond the 10 included (12.5 worked)"),
+        "{data}"
+    );
+    assert!(data.contains(r#""Included hours":"10""#), "{data}");
+    assert!(data.contains(r#""Hours worked":"12.5""#), "{data}");
+    assert!(data.contains(r#""Overage hours":"2.5""#), "{data}");
+}
+
+#[test]
+fn test_import_time_with_saved_profile() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let config_file = config_path.join("config.toml");
+    let config = fs::read_to_string(&config_file).unwrap();
+    fs::write(
+        &config_file,
+        format!(
+            "{config}\n[import.profiles.clockify]\nitem = \"consulting\"\n\
+             date_column = \"Start Date\"\ndate_format = \"%m/%d/%Y\"\n\
+             hours_column = \"Duration (h)\"\ndescription_column = \"Description\"\n\
+             round_minutes = 15\n"
+        ),
+    )
+    .unwrap();
+    let timesheet = temp_dir.path().join("clockify.csv");
+    fs::write(
+        &timesheet,
+        "Project,Description,Start Date,Duration (h)\n\
+         Acme,\"Support, email\",03/02/2026,01:10:00\n\
+         Acme,Code review,03/03/2026,02:00:00\n\
+         Acme,\"Support, email\",03/05/2026,00:20:00\n",
+    )
+    .unwrap();
+    let file = timesheet.to_str().unwrap();
+
+    // Entries round up to 15 minutes: 1:15 + 0:30 of support
+    invoice_cmd()
+        .args(["-C", cfg, "import-time", file, "--client", "example-client"])
+        .args(["--profile", "clockify", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 3 entries (3.75 hours)"))
+        .stdout(predicate::str::contains("consulting:Support, email:1.75"))
+        .stdout(predicate::str::contains("consulting:Code review:2"));
+
+    // Flags override the profile
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "import-time", file, "--client", "example-client"])
+        .args(["--profile", "clockify", "--round-minutes", "60"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 3 entries (5.00 hours)"))
+        .stdout(predicate::str::contains("Period: Mar 2–5, 2026"))
+        .stdout(predicate::str::contains("Total:  $750.00"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "import-time", file, "--client", "example-client"])
+        .args(["--profile", "toggl"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Import profile 'toggl' not found"));
+}
+
+#[test]
+fn test_digest_summarizes_week_and_emails() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 3
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1000.0
+file = "INV-2026-0001.pdf"
+
+[[history]]
+number = "INV-2026-0002"
+client = "example-client"
+date = "2026-02-20"
+total = 500.0
+file = "INV-2026-0002.pdf"
+payments = [{ amount = 200.0, date = "2026-03-12" }]
+
+[[history]]
+number = "INV-2026-0003"
+client = "example-client"
+date = "2026-03-14"
+total = 750.0
+file = "INV-2026-0003.pdf"
+"#,
+    );
+
+    invoice_cmd()
+        .args(["-C", cfg, "digest", "--date", "2026-03-16"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Receivables digest for 2026-03-10 to 2026-03-16",
+        ))
+        .stdout(predicate::str::contains("Issued (1): $750.00"))
+        .stdout(predicate::str::contains("Payments received (1): $200.00"))
+        .stdout(predicate::str::contains(
+            "INV-2026-0001  example-client  1-30 days -> 31-60 days  $1000.00 outstanding",
+        ))
+        .stdout(predicate::str::contains(
+            "2026-03-22  INV-2026-0002  example-client  $300.00",
+        ))
+        .stdout(predicate::str::contains(
+            "Outstanding: $2050.00 across 3 invoice(s)",
+        ));
+
+    // A fake sendmail that keeps the message
+    let sendmail = temp_dir.path().join("sendmail");
+    let sent = temp_dir.path().join("sent.eml");
+    fs::write(
+        &sendmail,
+        format!("#!/bin/sh\ncat > '{}'\n", sent.display()),
+    )
+    .unwrap();
+    fs::set_permissions(&sendmail, fs::Permissions::from_mode(0o755)).unwrap();
+    let config_file = config_path.join("config.toml");
+    let config = fs::read_to_string(&config_file).unwrap();
+    fs::write(
+        &config_file,
+        format!(
+            "{config}\n[email]\ncommand = \"{} -t\"\n",
+            sendmail.display()
+        ),
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .args(["-C", cfg, "digest", "--date", "2026-03-16"])
+        .args(["--email", "me@example.com"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Sent digest to me@example.com"));
+
+    let message = fs::read_to_string(&sent).unwrap();
+    assert!(message.contains("To: me@example.com\n"));
+    assert!(message.contains("Subject: Your Company Name: receivables digest for 2026-03-16\n"));
+    assert!(message.contains("Issued (1): $750.00"));
+}
+
+#[test]
+fn test_refresh_status_records_overdue_and_days_late() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 3
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1000.0
+file = "INV-2026-0001.pdf"
+
+[[history]]
+number = "INV-2026-0002"
+client = "example-client"
+date = "2026-02-20"
+total = 500.0
+file = "INV-2026-0002.pdf"
+payments = [{ amount = 500.0, date = "2026-03-25" }]
+
+[[history]]
+number = "INV-2026-0003"
+client = "example-client"
+date = "2026-03-14"
+total = 750.0
+file = "INV-2026-0003.pdf"
+"#,
+    );
+
+    invoice_cmd()
+        .args(["-C", cfg, "refresh-status", "--date", "2026-03-30"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Refreshed the status of 3 invoice(s) as of 2026-03-30: 1 overdue",
+        ));
+
+    // Recorded in state.toml: overdue while owed, paid three days late
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("[history.standing]"), "{state}");
+    assert!(state.contains("status = \"unpaid\""), "{state}");
+    assert!(state.contains("overdue = true\ndays_late = 49"), "{state}");
+    assert!(state.contains("status = \"paid\""), "{state}");
+    assert!(state.contains("overdue = false\ndays_late = 3"), "{state}");
+
+    invoice_cmd()
+        .args([
+            "-C",
+            cfg,
+            "refresh-status",
+            "--date",
+            "2026-03-30",
+            "--json",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""number": "INV-2026-0003""#))
+        .stdout(predicate::str::contains(r#""due": "2026-04-13""#))
+        .stdout(predicate::str::contains(r#""outstanding": 750.0"#));
+}
+
+#[test]
+fn test_overdue_groups_by_client_with_aging_totals() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 4
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1000.0
+file = "INV-2026-0001.pdf"
+
+[[history]]
+number = "INV-2026-0002"
+client = "other-client"
+date = "2026-02-10"
+total = 3000.0
+file = "INV-2026-0002.pdf"
+payments = [{ amount = 500.0, date = "2026-03-01" }]
+
+[[history]]
+number = "INV-2026-0003"
+client = "example-client"
+date = "2026-02-20"
+total = 500.0
+file = "INV-2026-0003.pdf"
+
+[[history]]
+number = "INV-2026-0004"
+client = "example-client"
+date = "2026-03-20"
+total = 750.0
+file = "INV-2026-0004.pdf"
+"#,
+    );
+
+    // Due 30 days after issue: 0001 is 49 days late, 0002 18 and 0003 8;
+    // 0004 isn't due yet
+    invoice_cmd()
+        .args(["-C", cfg, "overdue", "--date", "2026-03-30"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "other-client: $2500.00 overdue across 1 invoice(s), oldest 18 days",
+        ))
+        .stdout(predicate::str::contains(
+            "example-client: $1500.00 overdue across 2 invoice(s), oldest 49 days",
+        ))
+        .stdout(predicate::str::contains("INV-2026-0004").not())
+        .stdout(predicate::str::contains(
+            "1-30 days:  $3000.00 (2 invoice(s))",
+        ))
+        .stdout(predicate::str::contains(
+            "31-60 days: $1000.00 (1 invoice(s))",
+        ))
+        .stdout(predicate::str::contains(
+            "Overdue: $4000.00 across 3 invoice(s) from 2 client(s)",
+        ));
+}
+
+#[test]
+fn test_aging_report_buckets_balances_per_client() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 4
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2025-11-01"
+total = 1000.0
+file = "INV-2026-0001.pdf"
+
+[[history]]
+number = "INV-2026-0002"
+client = "gone-client"
+date = "2026-01-10"
+total = 2000.0
+file = "INV-2026-0002.pdf"
+payments = [{ amount = 500.0, date = "2026-02-01" }]
+
+[[history]]
+number = "INV-2026-0003"
+client = "example-client"
+date = "2026-03-20"
+total = 750.0
+file = "INV-2026-0003.pdf"
+
+[[history]]
+number = "INV-2026-0004"
+client = "example-client"
+date = "2026-03-01"
+total = 300.0
+file = "INV-2026-0004.pdf"
+paid = true
+"#,
+    );
+
+    // Due 30 days after issue: 0001 is 120 days late, 0002 49 and 0003
+    // not due yet
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "aging", "--date", "2026-03-30"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Aging as of 2026-03-30"))
+        .stdout(
+            predicate::str::is_match(
+                r"Example Client Inc\.\s+│ 2\s+│ \$\s+750 │ \$\s+0 │ \$\s+0 │ \$\s+1,000 │ \$\s+1,750",
+            )
+            .unwrap(),
+        )
+        .stdout(predicate::str::is_match(r"gone-client\s+│ 1\s+│ \$\s+0 │ \$\s+1,500 │").unwrap())
+        .stdout(predicate::str::is_match(r"TOTAL\s+│ 3\s+│ .* │ \$\s+3,250").unwrap())
+        .stdout(predicate::str::contains("AGING-2026-03-30.pdf"));
+
+    let data = fs::read_to_string(config_path.join("output").join("AGING-2026-03-30.pdf")).unwrap();
+    assert!(data.contains(r#""client":"Example Client Inc.""#), "{data}");
+    assert!(data.contains(r#""over_90":1000.0"#), "{data}");
+    assert!(data.contains(r#""days_31_60":"$1,500.00""#), "{data}");
+    assert!(!data.contains("INV-2026-0004"));
+}
+
+#[test]
+fn test_sort_items_with_pinned_position() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:1", "--item", "development:4"])
+        .args(["--item", "project-setup:1@1", "--sort-items", "by-amount"])
+        .assert()
+        .success();
+
+    // Largest amount first, except the setup fee pinned to the top
+    let pdf = fs::read_to_string(config_path.join(format!("output/INV-{year}-0001.pdf"))).unwrap();
+    let at = |text: &str| pdf.find(text).unwrap();
+    assert!(at("Project Setup") < at("Software Development"));
+    assert!(at("Software Development") < at("Technical Consulting"));
+
+    // History keeps the sorted order, so regenerating doesn't reshuffle
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    let state_at = |text: &str| state.find(text).unwrap();
+    assert!(state_at("development:4") < state_at("project-setup:1@1"));
+    assert!(state_at("project-setup:1@1") < state_at("consulting:1"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:1", "--sort-items", "by-size"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown item order 'by-size'"));
+}
+
+#[test]
+fn test_report_group_by_month() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 3
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+paid = true
+
+[[history]]
+number = "INV-2026-0002"
+client = "example-client"
+date = "2026-01-20"
+total = 750.0
+file = "INV-2026-0002.pdf"
+
+[[history]]
+number = "INV-2026-0003"
+client = "example-client"
+date = "2026-02-03"
+total = 300.0
+file = "INV-2026-0003.pdf"
+"#,
+    );
+
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "report", "--client", "example-client"])
+        .args(["--group-by", "month"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "January 2026: $1,950.00 (2 invoice(s))",
+        ))
+        .stdout(predicate::str::contains(
+            "February 2026: $300.00 (1 invoice(s))",
+        ))
+        .stdout(predicate::str::contains("Total:    $2,250.00"));
+
+    let report = fs::read_dir(config_path.join("output"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.to_string_lossy().contains("RPT-"))
+        .unwrap();
+    let data = fs::read_to_string(report).unwrap();
+    assert!(data.contains(r#""label":"January 2026""#));
+    assert!(data.contains(r#""outstanding":750.0"#));
+
+    invoice_cmd()
+        .args(["-C", cfg, "report", "--client", "example-client"])
+        .args(["--group-by", "week"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown grouping 'week'"));
+}
+
+#[test]
+fn test_outstanding_converted_to_display_currency() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let history = r#"[counter]
+last_number = 2
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1000.0
+file = "INV-2026-0001.pdf"
+
+[[history]]
+number = "INV-2026-0002"
+client = "example-client"
+date = "2026-01-20"
+total = 200.0
+file = "INV-2026-0002.pdf"
+currency = "EUR"
+"#;
+    write_state(&config_path, history);
+    let config_file = config_path.join("config.toml");
+    let config = fs::read_to_string(&config_file).unwrap();
+    fs::write(
+        &config_file,
+        format!(
+            "{config}\n[currency]\ndisplay = \"BRL\"\nprovider = \"fixed\"\n\
+             [currency.rates]\n\"USD/BRL\" = 5.0\n\"BRL/EUR\" = 0.16\n"
+        ),
+    )
+    .unwrap();
+
+    // Each invoice converts from its own currency; inverse pairs are implied
+    invoice_cmd()
+        .args(["-C", cfg, "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Outstanding in BRL: 6,250 (1 EUR = 6.25 BRL, 1 USD = 5.00 BRL)",
+        ));
+    invoice_cmd()
+        .args(["-C", cfg, "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("6,250 BRL"));
+    invoice_cmd()
+        .env("PATH", fake_typst_path(temp_dir.path()))
+        .args(["-C", cfg, "report", "--client", "example-client"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Outstanding in BRL: 6,250.00"));
+    let year = chrono::Local::now().format("%Y");
+    let report =
+        fs::read_to_string(config_path.join(format!("output/RPT-{year}-0001.pdf"))).unwrap();
+    assert!(report.contains(r#""converted_outstanding":{"currency":"BRL","amount":6250.0"#));
+
+    // Fetched rates are reused from state.toml while fresh
+    fs::write(
+        &config_file,
+        format!("{config}\n[currency]\ndisplay = \"BRL\"\n"),
+    )
+    .unwrap();
+    let fetched = chrono::Local::now()
+        .naive_local()
+        .format("%Y-%m-%dT%H:%M:%S");
+    write_state(
+        &config_path,
+        &format!(
+            "{history}\n[rates.\"USD/BRL\"]\nrate = 4.0\nfetched = \"{fetched}\"\n\
+             \n[rates.\"EUR/BRL\"]\nrate = 6.0\nfetched = \"{fetched}\"\n"
+        ),
+    );
+    invoice_cmd()
+        .args(["-C", cfg, "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Outstanding in BRL: 5,200 (1 EUR = 6.00 BRL, 1 USD = 4.00 BRL)",
+        ));
+}
+
+#[test]
+fn test_report_numbering_and_history() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:1"])
+        .assert()
+        .success();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "report", "--client", "example-client"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Number:   RPT-{year}-0001"
+        )));
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "report", "--client", "example-client"])
+        .args(["--status", "unpaid", "--group-by", "month"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Number:   RPT-{year}-0002"
+        )));
+
+    let output = config_path.join("output");
+    assert!(output.join(format!("RPT-{year}-0001.pdf")).exists());
+    let data = fs::read_to_string(output.join(format!("RPT-{year}-0002.pdf"))).unwrap();
+    assert!(data.contains(&format!(r#""number":"RPT-{year}-0002""#)));
+
+    invoice_cmd()
+        .args(["-C", cfg, "report", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("all invoices"))
+        .stdout(predicate::str::contains("unpaid, by month"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "report", "open", "99"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Report '99' not found"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "clean-output", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("RPT-").not());
+}
+
+#[test]
+fn test_dun_lists_only_unpaid_invoices() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "dun", "example-client"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No unpaid invoices"));
+
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 3
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+paid = true
+
+[[history]]
+number = "INV-2026-0002"
+client = "example-client"
+date = "2026-01-20"
+total = 750.0
+file = "INV-2026-0002.pdf"
+payments = [{ amount = 250.0, date = "2026-02-01" }]
+
+[[history]]
+number = "INV-2099-0001"
+client = "example-client"
+date = "2099-01-05"
+total = 300.0
+file = "INV-2099-0001.pdf"
+"#,
+    );
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "dun", "example-client"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Invoices:  2 unpaid (1 overdue)"))
+        .stdout(predicate::str::contains("Total due: $800.00"));
+
+    let statement = fs::read_dir(config_path.join("output"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.to_string_lossy().contains("STATEMENT-example-client-"))
+        .unwrap();
+    let data = fs::read_to_string(statement).unwrap();
+    assert!(!data.contains("INV-2026-0001"));
+    assert!(data.find("INV-2026-0002").unwrap() < data.find("INV-2099-0001").unwrap());
+    assert!(data.contains(r#""outstanding":500.0"#));
+}
+
+#[test]
+fn test_list_default_limit_and_window() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    let config = fs::read_to_string(config_path.join("config.toml")).unwrap();
+    fs::write(
+        config_path.join("config.toml"),
+        config.replace(
+            "# [display]\n",
+            "[display]\ndefault_list_limit = 2\ndefault_list_window = \"12m\"\n",
+        ),
+    )
+    .unwrap();
+
+    let today = chrono::Local::now().date_naive();
+    let mut state = String::from("[counter]\nlast_number = 5\nlast_year = 2026\n");
+    for (number, date) in [
+        ("INV-OLD-1", "2020-01-10".to_string()),
+        ("INV-OLD-2", "2020-02-10".to_string()),
+        ("INV-NEW-1", today.to_string()),
+        ("INV-NEW-2", today.to_string()),
+        ("INV-NEW-3", today.to_string()),
+    ] {
+        state.push_str(&format!(
+            "\n[[history]]\nnumber = \"{number}\"\nclient = \"example-client\"\ndate = \"{date}\"\ntotal = 100.0\nfile = \"{number}.pdf\"\n"
+        ));
+    }
+    write_state(&config_path, &state);
+
+    invoice_cmd()
+        .args(["-C", cfg, "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("INV-NEW-3"))
+        .stdout(predicate::str::contains("INV-NEW-2"))
+        .stdout(predicate::str::contains("INV-NEW-1").not())
+        .stdout(predicate::str::contains("INV-OLD-2").not())
+        .stdout(predicate::str::contains(
+            "3 older invoice(s) not shown; use --all to list everything",
+        ));
+
+    // An explicit limit replaces both defaults
+    invoice_cmd()
+        .args(["-C", cfg, "list", "--limit", "4"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("INV-OLD-2"))
+        .stdout(predicate::str::contains("INV-OLD-1").not());
+
+    invoice_cmd()
+        .args(["-C", cfg, "list", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("INV-OLD-1"))
+        .stdout(predicate::str::contains("not shown").not());
+}
+
+#[test]
+fn test_list_plain_prints_tab_separated_rows() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 2
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+payments = [{ amount = 200.0, date = "2026-02-01" }]
+
+[[history]]
+number = "INV-2026-0002"
+client = "other-client"
+date = "2026-02-10"
+total = 500.5
+file = "INV-2026-0002.pdf"
+"#,
+    );
+
+    // Newest first, no borders, header or footer
+    invoice_cmd()
+        .args(["-C", cfg, "list", "--plain", "--all"])
+        .assert()
+        .success()
+        .stdout(
+            "1\tINV-2026-0002\t2026-02-10\t500.50\tUNPAID\tother-client\t500.50\n\
+             2\tINV-2026-0001\t2026-01-10\t1200.00\tPARTIAL\texample-client\t1000.00\n",
+        );
+}
+
+#[test]
+fn test_generate_interactive_picks_client_and_items() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    // Stands in for fzf: the client first, then two items, then Esc
+    let picker = temp_dir.path().join("picker");
+    fs::write(
+        &picker,
+        format!(
+            "#!/bin/sh
+count={}/count
+n=$(cat $count 2>/dev/null || echo 0)
+echo $((n + 1)) > $count
+             case $n in
+0) grep '^example-client' ;;
+1) grep '^consulting' ;;
+2) grep '^development' ;;
+*) exit 130 ;;
+esac
+",
+            temp_dir.path().display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&picker, fs::Permissions::from_mode(0o755)).unwrap();
+
+    // An invalid quantity is asked again
+    assert_cmd::Command::from_std(invoice_cmd())
+        .env("INVOICE_PICKER", &picker)
+        .args(["-C", cfg, "generate", "--interactive", "--format", "json"])
+        .write_stdin("lots\n8\n2x3\ny\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Client: Example Client Inc. (example-client)",
+        ))
+        .stderr(predicate::str::contains("Invalid quantity 'lots'"))
+        .stderr(predicate::str::contains(
+            "× 8: $1,200.00  (running total $1,200.00)",
+        ))
+        .stderr(predicate::str::contains(
+            "× 6: $750.00  (running total $1,950.00)",
+        ))
+        .stderr(predicate::str::contains("with 2 line(s)? [Y/n]"));
+
+    let json = fs::read_to_string(
+        config_path
+            .join("output")
+            .join(format!("INV-{year}-0001.json")),
+    )
+    .unwrap();
+    assert!(json.contains(r#""subtotal": 1950.0"#), "{json}");
+
+    // Declining the confirmation generates nothing
+    fs::remove_file(temp_dir.path().join("count")).unwrap();
+    assert_cmd::Command::from_std(invoice_cmd())
+        .env("INVOICE_PICKER", &picker)
+        .args(["-C", cfg, "generate", "--interactive", "--format", "json"])
+        .write_stdin("1\n1\nn\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing generated."));
+    assert!(!config_path
+        .join("output")
+        .join(format!("INV-{year}-0002.json"))
+        .exists());
+}
+
+#[test]
+fn test_pick_prints_the_chosen_invoice() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    write_state(
+        &config_path,
+        r#"[counter]
+last_number = 2
+last_year = 2026
+
+[[history]]
+number = "INV-2026-0001"
+client = "example-client"
+date = "2026-01-10"
+total = 1200.0
+file = "INV-2026-0001.pdf"
+items = ["consulting:8"]
+
+[[history]]
+number = "INV-2026-0002"
+client = "other-client"
+date = "2026-02-10"
+total = 500.0
+file = "INV-2026-0002.pdf"
+"#,
+    );
+
+    // Stands in for fzf: records its options and chooses the second line
+    let picker = temp_dir.path().join("picker");
+    fs::write(
+        &picker,
+        format!(
+            "#!/bin/sh\necho \"$@\" > {}/args\nsed -n 2p\n",
+            temp_dir.path().display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&picker, fs::Permissions::from_mode(0o755)).unwrap();
+
+    invoice_cmd()
+        .env("INVOICE_PICKER", &picker)
+        .args(["-C", cfg, "pick"])
+        .assert()
+        .success()
+        .stdout("INV-2026-0001\n");
+
+    let args = fs::read_to_string(temp_dir.path().join("args")).unwrap();
+    assert!(args.contains("--preview"), "{args}");
+    assert!(args.contains("show {1}"), "{args}");
+
+    // Without an argument, show asks the picker which invoice
+    invoice_cmd()
+        .env("INVOICE_PICKER", &picker)
+        .args(["-C", cfg, "show"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("INV-2026-0001"))
+        .stdout(predicate::str::contains("INV-2026-0002").not());
+}
+
+#[test]
+fn test_net_figures_exclude_tax() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    let config = fs::read_to_string(config_path.join("config.toml")).unwrap();
+    fs::write(
+        config_path.join("config.toml"),
+        config.replace("tax_rate = 0.0", "tax_rate = 0.1"),
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:10",
+        ])
+        .assert()
+        .success();
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("tax = 150.0"), "{state}");
+
+    invoice_cmd()
+        .args(["-C", cfg, "add-payment", "1", "825"])
+        .assert()
+        .success();
+
+    invoice_cmd()
+        .args(["-C", cfg, "list", "--net"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("│ $ 1,500 │ PARTIAL"))
+        .stdout(predicate::str::contains("(-) PAID │ $   750"))
+        .stdout(predicate::str::contains("1,650").not())
+        .stdout(predicate::str::contains("Amounts exclude tax"));
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "report", "--client", "example-client", "--net"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Total:    $1,500.00 (excluding tax)",
+        ));
+}
+
+#[test]
+fn test_due_date_rolls_to_next_business_day() {
+    use chrono::Datelike;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+
+    // Due on the next Saturday; the Monday after is a holiday
+    let today = chrono::Local::now().date_naive();
+    let due_days = 7 - (today.weekday().num_days_from_monday() + 2) % 7;
+    let saturday = today + chrono::Duration::days(due_days.into());
+    let monday = saturday + chrono::Duration::days(2);
+    let tuesday = saturday + chrono::Duration::days(3);
+    assert_eq!(saturday.weekday(), chrono::Weekday::Sat);
+
+    let config = fs::read_to_string(config_path.join("config.toml")).unwrap();
+    fs::write(
+        config_path.join("config.toml"),
+        config.replace(
+            "due_days = 30\n",
+            &format!(
+                "due_days = {due_days}\nbusiness_day_due_dates = true\nholidays = [\"{monday}\"]\n"
+            ),
+        ),
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:1",
+        ])
+        .assert()
+        .success();
+
+    let year = today.format("%Y");
+    let data = fs::read_to_string(config_path.join(format!("output/INV-{year}-0001.pdf"))).unwrap();
+    assert!(
+        data.contains(&format!("\"due_date\":\"{}\"", tuesday.format("%B %d, %Y"))),
+        "{data}"
+    );
+}
+
+#[test]
+fn test_custom_invoice_template_from_config_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    fs::write(
+        config_path.join("templates/invoice.typ"),
+        "#let data = json(\"DATA_JSON_PATH\")\n// CUSTOM TEMPLATE\n#image(\"logo.svg\")\n= #data.number\n",
+    )
+    .unwrap();
+    fs::write(config_path.join("templates/logo.svg"), "<svg/>").unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:1",
+            "--debug",
+        ])
+        .assert()
+        .success();
+
+    let year = chrono::Local::now().format("%Y");
+    let debug = config_path.join(format!("output/debug/INV-{year}-0001"));
+    let template = fs::read_to_string(debug.join("invoice.typ")).unwrap();
+    assert!(template.contains("// CUSTOM TEMPLATE"), "{template}");
+    assert!(template.contains("json(\"data.json\")"), "{template}");
+    assert!(debug.join("logo.svg").exists());
+}
+
+#[test]
+fn test_custom_partial_replaces_only_footer() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    fs::write(
+        config_path.join("templates/footer.typ"),
+        "#let footer(data) = [Bank: Example Bank]\n",
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args([
+            "-C",
+            cfg,
+            "generate",
+            "--client",
+            "example-client",
+            "--item",
+            "consulting:1",
+            "--debug",
+        ])
+        .assert()
+        .success();
+
+    let year = chrono::Local::now().format("%Y");
+    let debug = config_path.join(format!("output/debug/INV-{year}-0001"));
+    let read = |name: &str| fs::read_to_string(debug.join(name)).unwrap();
+    assert!(read("invoice.typ").contains("#import \"footer.typ\": footer"));
+    assert_eq!(
+        read("footer.typ"),
+        "#let footer(data) = [Bank: Example Bank]\n"
+    );
+    assert!(read("header.typ").contains("#let header(data)"));
+    assert!(read("theme.typ").contains("json(\"data.json\")"));
+}
+
+#[test]
+fn test_void_keeps_number_and_trash_reuses_latest() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+    let year = chrono::Local::now().format("%Y");
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    for _ in 0..3 {
+        invoice_cmd()
+            .env("PATH", &path)
+            .args([
+                "-C",
+                cfg,
+                "generate",
+                "--client",
+                "example-client",
+                "--item",
+                "consulting:1",
+                "--allow-duplicate",
+            ])
+            .assert()
+            .success();
+    }
+
+    // Voiding keeps the entry, drops it from the totals and keeps the number
+    invoice_cmd()
+        .args([
+            "-C",
+            cfg,
+            "void",
+            &format!("INV-{year}-0001"),
+            "--reason",
+            "billed twice",
+            "--remove-files",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Voided INV-{year}-0001")));
+    assert!(!config_path
+        .join(format!("output/INV-{year}-0001.pdf"))
+        .exists());
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("reason = \"billed twice\""), "{state}");
+
+    invoice_cmd()
+        .args(["-C", cfg, "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("VOID"))
+        .stdout(predicate::str::contains("TOTAL │ $   300"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "add-payment", &format!("INV-{year}-0001"), "150"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is void"));
+
+    // Trashing an earlier invoice keeps the counter; the latest gives its number back
+    invoice_cmd()
+        .args(["-C", cfg, "trash", "add", &format!("INV-{year}-0002")])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("reuses").not());
+    invoice_cmd()
+        .args(["-C", cfg, "trash", "add", &format!("INV-{year}-0003")])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "the next one reuses INV-{year}-0003"
+        )));
+    invoice_cmd()
+        .args(["-C", cfg, "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Next invoice:     INV-{year}-0003"
+        )));
+
+    // Restoring it takes the number back
+    invoice_cmd()
+        .args(["-C", cfg, "trash", "restore", &format!("INV-{year}-0003")])
+        .assert()
+        .success();
+    invoice_cmd()
+        .args(["-C", cfg, "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Next invoice:     INV-{year}-0004"
+        )));
+}
+
+#[test]
+fn test_payee_directs_payment_and_tags_receivable() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let mut config = fs::read_to_string(config_path.join("config.toml")).unwrap();
+    config.push_str(
+        "\n[payees.factor1]\nname = \"Example Factoring LLC\"\nbank_details = \"Account: 987\"\n",
+    );
+    fs::write(config_path.join("config.toml"), config).unwrap();
+
+    let generate = |payee: &str| {
+        invoice_cmd()
+            .env("PATH", &path)
+            .args([
+                "-C",
+                cfg,
+                "generate",
+                "--client",
+                "example-client",
+                "--item",
+                "consulting:1",
+                "--allow-duplicate",
+                "--payee",
+                payee,
+            ])
+            .assert()
+    };
+    generate("factor2")
+        .failure()
+        .stderr(predicate::str::contains("Payee 'factor2' not found"));
+    generate("factor1")
+        .success()
+        .stdout(predicate::str::contains("Payee:  Example Factoring LLC"));
+
+    let year = chrono::Local::now().format("%Y");
+    let pdf = fs::read_to_string(config_path.join(format!("output/INV-{year}-0001.pdf"))).unwrap();
+    assert!(
+        pdf.contains(r#""payee":{"name":"Example Factoring LLC""#),
+        "{pdf}"
+    );
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("payee = \"factor1\""), "{state}");
+
+    invoice_cmd()
+        .args(["-C", cfg, "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("UNPAID → factor1"))
+        .stdout(predicate::str::contains(
+            "Factored: $150.00 of the outstanding is owed to other payees",
+        ));
+}
+
+#[test]
+fn test_margins_per_item_and_client() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    invoice_cmd()
+        .args(["-C", cfg, "margins"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No item costs configured."));
+
+    let items = fs::read_to_string(config_path.join("items.toml")).unwrap();
+    fs::write(
+        config_path.join("items.toml"),
+        items.replacen("unit = \"hour\"", "unit = \"hour\"\ncost = 90.0", 1),
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:2", "--item", "development:4"])
+        .assert()
+        .success();
+
+    invoice_cmd()
+        .args(["-C", cfg, "items"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("$90.00"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "margins"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "│ consulting  │ $300.00 │ $180.00 │ $120.00 │ 40.0%",
+        ))
+        .stdout(predicate::str::contains(
+            "│ development │ $500.00 │ -       │ -       │ -",
+        ))
+        .stdout(predicate::str::contains(
+            "│ example-client │ $800.00 │ $180.00 │ $120.00 │ 40.0%",
+        ))
+        .stdout(predicate::str::contains(
+            "$500.00 of revenue comes from items without a cost",
+        ));
+}
+
+#[test]
+fn test_generate_converts_foreign_currency_items() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let items_file = config_path.join("items.toml");
+    let mut items = fs::read_to_string(&items_file).unwrap();
+    items.push_str(
+        "\n[subcontractor]\ndescription = \"Design (subcontracted)\"\nrate = 500.00\nunit = \"flat\"\ncurrency = \"eur\"\n",
+    );
+    fs::write(&items_file, items).unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:1", "--item", "subcontractor:1"])
+        .args(["--rate", "EUR=1.085"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("$692.50"));
+
+    let year = chrono::Local::now().format("%Y").to_string();
+    let pdf = config_path.join(format!("output/INV-{year}-0001.pdf"));
+    let data = fs::read_to_string(&pdf).unwrap();
+    assert!(data.contains("\"rate\":542.5,\"amount\":542.5"));
+    assert!(data.contains(
+        "\"original\":{\"currency\":\"EUR\",\"rate\":500.0,\"amount\":500.0,\"exchange_rate\":1.085}"
+    ));
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("EUR = 1.085"));
+
+    // Regenerating converts at the stored rate rather than today's
+    fs::remove_file(&pdf).unwrap();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "regenerate", "1"])
+        .assert()
+        .success();
+    assert!(fs::read_to_string(&pdf)
+        .unwrap()
+        .contains("\"exchange_rate\":1.085"));
+}
+
+#[test]
+fn test_generate_with_custom_fields() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let clients = fs::read_to_string(config_path.join("clients.toml")).unwrap();
+    fs::write(
+        config_path.join("clients.toml"),
+        clients.replace(
+            "# fields = { \"Cost Center\" = \"1234\", \"PO Number\" = \"PO-5678\" }",
+            "fields = { \"Cost Center\" = \"1234\", \"PO Number\" = \"PO-5678\" }",
+        ),
+    )
+    .unwrap();
+
+    // --field overrides a client default, adds a new one or drops one
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:1", "--formats", "pdf,html"])
+        .args(["--field", "Cost Center=9999", "--field", "Project=Apollo"])
+        .args(["--field", "PO Number="])
+        .assert()
+        .success();
+
+    let year = chrono::Local::now().format("%Y").to_string();
+    let pdf = config_path.join(format!("output/INV-{year}-0001.pdf"));
+    let expected = "\"fields\":{\"Cost Center\":\"9999\",\"Project\":\"Apollo\"}";
+    assert!(fs::read_to_string(&pdf).unwrap().contains(expected));
+    let html =
+        fs::read_to_string(config_path.join(format!("output/INV-{year}-0001.html"))).unwrap();
+    assert!(html.contains("<td><strong>Project:</strong></td><td>Apollo</td>"));
+    assert!(!html.contains("PO-5678"));
+
+    // Regenerating keeps the fields the invoice was issued with
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "regenerate", "1"])
+        .assert()
+        .success();
+    assert!(fs::read_to_string(&pdf).unwrap().contains(expected));
+}
+
+#[test]
+fn test_generate_from_spec_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let spec_file = temp_dir.path().join("invoice.toml");
+    fs::write(
+        &spec_file,
+        r#"client = "example-client"
+date = "2026-02-01"
+items = [
+    "consulting:2",
+    { description = "Travel to Denver", quantity = 1, rate = 420.0, unit = "trip", kind = "expense" },
+]
+discounts = [{ description = "Returning client", amount = 50.0 }]
+notes = ["Approved on the kickoff call"]
+fields = { "PO Number" = "PO-77" }
+"#,
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--spec", spec_file.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Generated INV-2026-0001"))
+        .stdout(predicate::str::contains("$670.00"));
+
+    let pdf = config_path.join("output/INV-2026-0001.pdf");
+    let data = fs::read_to_string(&pdf).unwrap();
+    assert!(data.contains("\"date\":\"February 01, 2026\""));
+    assert!(data.contains("\"description\":\"Travel to Denver\""));
+    assert!(data
+        .contains("\"description\":\"Returning client\",\"quantity\":1.0,\"unit\":\"discount\""));
+    assert!(data.contains("\"PO Number\":\"PO-77\""));
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("spec = \"specs/INV-2026-0001.toml\""));
+    assert!(state.contains("Approved on the kickoff call"));
+
+    // The stored spec is the record: edits to it show up on regeneration
+    let stored = config_path.join("specs/INV-2026-0001.toml");
+    let spec = fs::read_to_string(&stored).unwrap();
+    fs::write(&stored, spec.replace("rate = 420.0", "rate = 380.0")).unwrap();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "regenerate", "INV-2026-0001"])
+        .assert()
+        .success();
+    assert!(fs::read_to_string(&pdf)
+        .unwrap()
+        .contains("\"total\":630.0"));
+}
+
+#[test]
+fn test_contract_status_and_overrun_warning() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    invoice_cmd()
+        .args(["-C", cfg, "contract", "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No contracts configured."));
+
+    fs::write(
+        config_path.join("contracts.toml"),
+        "[retainer]\nclient = \"example-client\"\nvalue = 1000.0\nstart = \"2020-01-01\"\n",
+    )
+    .unwrap();
+
+    // The client's only active contract is linked without --contract
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:4"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Contract: retainer"))
+        .stderr(predicate::str::contains("Warning").not());
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:4", "--allow-duplicate"])
+        .args(["--contract", "retainer"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Warning: contract 'retainer' has been billed $1200.00, over its value of $1000.00",
+        ));
+
+    invoice_cmd()
+        .args(["-C", cfg, "contract", "status", "retainer"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("from 2020-01-01"))
+        .stdout(predicate::str::contains("$1200.00 (2 invoices)"))
+        .stdout(predicate::str::contains("$200.00 over"));
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:1", "--contract", "missing"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Contract 'missing' not found"));
+}
+
+#[test]
+fn test_history_survives_deleted_clients_and_items() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+    let year = chrono::Local::now().format("%Y").to_string();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let clients_file = config_path.join("clients.toml");
+    let items_file = config_path.join("items.toml");
+    let clients = fs::read_to_string(&clients_file).unwrap();
+    let items = fs::read_to_string(&items_file).unwrap();
+    fs::write(
+        &clients_file,
+        format!(
+            "{clients}\n[berlin]\nname = \"Berlin GmbH\"\nemail = \"ap@berlin.example\"\n\
+             address = \"Str. 1\"\ncity = \"Berlin\"\nstate = \"BE\"\nzip = \"10115\"\n\
+             \n[gone]\nname = \"Gone Ltd\"\nemail = \"ap@gone.example\"\naddress = \"1 Road\"\n\
+             city = \"Leeds\"\nstate = \"WY\"\nzip = \"LS1\"\n"
+        ),
+    )
+    .unwrap();
+    fs::write(
+        &items_file,
+        format!(
+            "{items}\n[audit]\ndescription = \"Security audit\"\nrate = 900.0\nunit = \"day\"\n"
+        ),
+    )
+    .unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "berlin"])
+        .args(["--item", "audit:1", "--formats", "pdf,json"])
+        .assert()
+        .success();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "gone"])
+        .args(["--item", "consulting:1"])
+        .assert()
+        .success();
+    fs::write(&clients_file, clients).unwrap();
+    fs::write(&items_file, items).unwrap();
+
+    // The JSON artifact stands in for both the client and the item
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "regenerate", &format!("INV-{year}-0001")])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "using the details saved with INV-{year}-0001"
+        )))
+        .stderr(predicate::str::contains(
+            "reusing the lines it was issued with",
+        ));
+    let pdf = fs::read_to_string(config_path.join(format!("output/INV-{year}-0001.pdf"))).unwrap();
+    assert!(pdf.contains("Berlin GmbH"), "{pdf}");
+    assert!(pdf.contains("Security audit"), "{pdf}");
+
+    // Without one, a placeholder client is rendered
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "report", "--client", "gone"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("rendering with a placeholder"));
+    let report =
+        fs::read_to_string(config_path.join(format!("output/RPT-{year}-0001.pdf"))).unwrap();
+    assert!(report.contains("Client record missing (gone)"), "{report}");
+
+    // --strict refuses to render an invoice for the placeholder
+    invoice_cmd()
+        .env("PATH", &path)
+        .args([
+            "-C",
+            cfg,
+            "--strict",
+            "regenerate",
+            &format!("INV-{year}-0002"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Stopped by --strict"))
+        .stderr(predicate::str::contains("rendering with a placeholder"));
+
+    invoice_cmd()
+        .args(["-C", cfg, "report", "--client", "nobody"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Client 'nobody' not found"));
+}
+
+#[test]
+fn test_client_overrides_currency_tax_and_terms() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let mut clients = fs::read_to_string(config_path.join("clients.toml")).unwrap();
+    clients.push_str(
+        "\n[berlin]\nname = \"Berlin GmbH\"\nemail = \"ap@berlin.example\"\naddress = \"Str. 1\"\n\
+         city = \"Berlin\"\nstate = \"BE\"\nzip = \"10115\"\ncurrency = \"EUR\"\n\
+         currency_symbol = \"€\"\ntax_rate = 0.19\ndue_days = 14\n",
+    );
+    fs::write(config_path.join("clients.toml"), clients).unwrap();
+
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "berlin"])
+        .args(["--item", "consulting:1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Total:  €178.50"));
+
+    let year = chrono::Local::now().format("%Y");
+    let pdf = fs::read_to_string(config_path.join(format!("output/INV-{year}-0001.pdf"))).unwrap();
+    assert!(pdf.contains(r#""tax_percent":19.0"#), "{pdf}");
+    assert!(
+        pdf.contains(r#""currency":"EUR","currency_symbol":"€""#),
+        "{pdf}"
+    );
+    assert!(pdf.contains(r#""payment_terms":"Net 14 days""#), "{pdf}");
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(state.contains("due_days = 14"), "{state}");
+    assert!(state.contains("currency = \"EUR\""), "{state}");
+}
+
+#[test]
+fn test_schedule_lists_and_prints_crontab() {
+    use chrono::{Datelike, Timelike};
+
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    let config = fs::read_to_string(config_path.join("config.toml")).unwrap();
+    let with_schedule = |command: &str| {
+        format!("{config}\n[schedule.digest]\ncron = \"30 8 * * mon\"\ncommand = \"{command}\"\n")
+    };
+    fs::write(
+        config_path.join("config.toml"),
+        with_schedule("digest --email me@example.com"),
+    )
+    .unwrap();
+
+    // Next Monday at 08:30, or today if it's Monday before then
+    let now = chrono::Local::now().naive_local();
+    let mut next = now.date();
+    while next.weekday() != chrono::Weekday::Mon
+        || (next == now.date() && (now.hour(), now.minute()) >= (8, 30))
+    {
+        next = next.succ_opt().unwrap();
+    }
+    invoice_cmd()
+        .args(["-C", cfg, "schedule"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("digest --email me@example.com"))
+        .stdout(predicate::str::contains(format!(
+            "{} 08:30",
+            next.format("%Y-%m-%d")
+        )));
+
+    invoice_cmd()
+        .args(["-C", cfg, "schedule", "--crontab"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("30 8 * * mon "))
+        .stdout(predicate::str::contains(format!(
+            " -C {cfg} digest --email me@example.com  # digest"
+        )));
+
+    fs::write(config_path.join("config.toml"), with_schedule("digets")).unwrap();
+    invoice_cmd()
+        .args(["-C", cfg, "schedule"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Scheduled command 'digest' is invalid",
+        ));
+
+    fs::write(
+        config_path.join("config.toml"),
+        with_schedule("digest").replace("30 8 * * mon", "30 25 * * *"),
+    )
+    .unwrap();
+    invoice_cmd()
+        .args(["-C", cfg, "schedule"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'25' is not between 0 and 23"));
+}
+
+#[test]
+fn test_read_only_refuses_changes() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("invoice-config");
+    let cfg = config_path.to_str().unwrap();
+    let path = fake_typst_path(temp_dir.path());
+
+    invoice_cmd().args(["-C", cfg, "init"]).assert().success();
+    invoice_cmd()
+        .env("PATH", &path)
+        .args(["-C", cfg, "generate", "--client", "example-client"])
+        .args(["--item", "consulting:1"])
+        .assert()
+        .success();
+
+    invoice_cmd()
+        .args(["-C", cfg, "--read-only", "add-payment", "1", "150"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "'invoice add-payment' would change invoice data",
+        ));
+    invoice_cmd()
+        .args(["-C", cfg, "list", "--read-only"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("UNPAID"));
+
+    let config = fs::read_to_string(config_path.join("config.toml")).unwrap();
+    fs::write(
+        config_path.join("config.toml"),
+        format!("{config}\n[access]\nread_only = true\n"),
+    )
+    .unwrap();
+    invoice_cmd()
+        .args(["-C", cfg, "trash", "add", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'invoice trash add'"));
+    invoice_cmd()
+        .args(["-C", cfg, "payments", "1"])
+        .assert()
+        .success();
+
+    let state = fs::read_to_string(config_path.join("state.toml")).unwrap();
+    assert!(!state.contains("[[history.payments]]"), "{state}");
+}
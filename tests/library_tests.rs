@@ -0,0 +1,43 @@
+use std::fs;
+
+use invoice::config::{CLIENTS_TEMPLATE, CONFIG_TEMPLATE, ITEMS_TEMPLATE};
+use invoice::pdf::{OutputFormat, RenderOptions};
+use invoice::{generate_invoice, GenerateOptions, Workspace};
+use tempfile::TempDir;
+
+#[test]
+fn test_generate_invoice_returns_what_it_produced() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    fs::write(dir.join("config.toml"), CONFIG_TEMPLATE).unwrap();
+    fs::write(dir.join("clients.toml"), CLIENTS_TEMPLATE).unwrap();
+    fs::write(dir.join("items.toml"), ITEMS_TEMPLATE).unwrap();
+
+    let mut ws = Workspace::load(dir).unwrap();
+    let options = GenerateOptions {
+        render: RenderOptions {
+            formats: vec![OutputFormat::Html],
+            ..RenderOptions::default()
+        },
+        ..GenerateOptions::default()
+    };
+    let generated = generate_invoice(
+        &mut ws,
+        "example-client",
+        &["consulting:8".to_string()],
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(generated.data.number, generated.number);
+    assert_eq!(generated.data.client.name, "Example Client Inc.");
+    assert_eq!(generated.data.total, 1200.0);
+    assert_eq!(generated.amount_due(), 1200.0);
+    assert_eq!(generated.files, vec![generated.path.clone()]);
+    assert!(generated
+        .path
+        .ends_with(format!("{}.html", generated.number)));
+    assert!(generated.path.exists());
+    assert!(generated.warnings.is_empty());
+    assert_eq!(ws.state.history.last().unwrap().number, generated.number);
+}
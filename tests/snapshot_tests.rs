@@ -0,0 +1,13 @@
+use std::path::{Path, PathBuf};
+
+use invoice::testing::{assert_snapshot, data_contract, sample_invoice};
+
+fn snapshot_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots")
+}
+
+#[test]
+fn test_invoice_data_contract_snapshot() {
+    let json = data_contract(&sample_invoice()).unwrap();
+    assert_snapshot(&snapshot_dir(), "invoice_data", &json);
+}